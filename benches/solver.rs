@@ -0,0 +1,164 @@
+//! Benchmarks of the solver's building blocks on a small synthetic system: structure detection,
+//! single Gauss-Newton iterations on 1-, 2- and 4-unknown blocks, and the simulated-annealing
+//! proposal.
+//!
+//! Run with `cargo bench --bench solver`; the full `solve_system` benchmark on the dynamics example
+//! lives in `examples/dynamics/benches`.
+
+use std::hint::black_box;
+
+use argmin::solver::simulatedannealing::Anneal;
+use criterion::{BatchSize, Criterion, criterion_group, criterion_main};
+use field_names_and_counts::FieldNames;
+use struct_to_array::StructToArray;
+use system_solver::{
+    equation_system::{
+        param_traits::{GivenParams, UnknownParams},
+        sub_problem::solve_subproblem::simulated_annealing::SimulatedAnnealingConfig,
+    },
+    prelude::{ad_trait::AD, *},
+    residual_fns_for_generic_params,
+};
+
+#[derive(Debug, Clone, Copy, StructToArray, AdConvert)]
+struct BenchGivens<T> {
+    a: T,
+    b: T,
+    c: T,
+    d: T,
+}
+
+/// One unknown solved on its own, a coupled pair, and a 4-cycle, so that the plan has blocks of 1, 2 and 4 unknowns.
+#[derive(Debug, Clone, Copy, StructToArray, FieldNames, AdConvert)]
+struct BenchUnknowns<T> {
+    x: T,
+    y1: T,
+    y2: T,
+    z1: T,
+    z2: T,
+    z3: T,
+    z4: T,
+}
+
+impl<T: Clone + Copy + std::fmt::Debug + MaybeSendSync> GivenParams for BenchGivens<T> {}
+impl<T: Clone + Copy + std::fmt::Debug + MaybeSendSync> UnknownParams for BenchUnknowns<T> {}
+
+fn x_cubic<T: AD>(g: &BenchGivens<T>, u: &BenchUnknowns<T>) -> T {
+    u.x * u.x * u.x + u.x - g.a
+}
+fn y_circle<T: AD>(g: &BenchGivens<T>, u: &BenchUnknowns<T>) -> T {
+    u.y1 * u.y1 + u.y2 * u.y2 - g.b
+}
+fn y_line<T: AD>(g: &BenchGivens<T>, u: &BenchUnknowns<T>) -> T {
+    u.y1 - u.y2 * g.c
+}
+fn z_12<T: AD>(g: &BenchGivens<T>, u: &BenchUnknowns<T>) -> T {
+    u.z1 * u.z1 + u.z2 - g.d
+}
+fn z_23<T: AD>(g: &BenchGivens<T>, u: &BenchUnknowns<T>) -> T {
+    u.z2 * u.z2 + u.z3 - g.d
+}
+fn z_34<T: AD>(g: &BenchGivens<T>, u: &BenchUnknowns<T>) -> T {
+    u.z3 * u.z3 + u.z4 - g.d
+}
+fn z_41<T: AD>(g: &BenchGivens<T>, u: &BenchUnknowns<T>) -> T {
+    u.z4 * u.z4 + u.z1 - g.d
+}
+
+const GIVENS: BenchGivens<f64> = BenchGivens {
+    a: 10.0,
+    b: 5.0,
+    c: 2.0,
+    d: 2.0,
+};
+
+const UNKNOWNS: BenchUnknowns<f64> = BenchUnknowns {
+    x: 1.5,
+    y1: 1.5,
+    y2: 0.8,
+    z1: 0.7,
+    z2: 1.2,
+    z3: 0.9,
+    z4: 1.1,
+};
+
+fn bench_fns() -> ResidualFnsFor<BenchGivens<f64>, BenchUnknowns<f64>> {
+    residual_fns_for_generic_params!(
+        BenchGivens, BenchUnknowns;
+        x_cubic, y_circle, y_line, z_12, z_23, z_34, z_41
+    )
+}
+
+fn bench_system() -> EquationSystemFor<BenchGivens<f64>, BenchUnknowns<f64>, EqSysStateInit, 7> {
+    EquationSystemBuilder::new_from_f64(GIVENS, bench_fns()).unwrap()
+}
+
+fn structure_detection(c: &mut Criterion) {
+    c.bench_function("structure detection (7 unknowns)", |b| {
+        b.iter_batched(
+            bench_system,
+            |eq_sys| black_box(eq_sys.with_triangularization(&UNKNOWNS).unwrap()),
+            BatchSize::SmallInput,
+        )
+    });
+}
+
+fn gauss_newton_iteration(c: &mut Criterion) {
+    let eq_sys = bench_system().with_triangularization(&UNKNOWNS).unwrap();
+    let options = SolveOptions::default()
+        .with_max_iters(1)
+        .with_verbosity(Verbosity::Quiet);
+
+    let mut group = c.benchmark_group("single Gauss-Newton iteration");
+    for size in [1, 2, 4] {
+        let block_idx = eq_sys
+            .plan()
+            .solution_plan()
+            .blocks
+            .iter()
+            .position(|block| block.unknown_idxs.len() == size)
+            .expect("the bench system has blocks of 1, 2 and 4 unknowns");
+        group.bench_function(format!("{size}-unknown block"), |b| {
+            b.iter(|| {
+                eq_sys.solve_block_with_options(
+                    block_idx,
+                    black_box(&UNKNOWNS),
+                    Some(BlockSolverKind::GaussNewton),
+                    &options,
+                )
+            })
+        });
+    }
+    group.finish();
+}
+
+fn annealing_proposal(c: &mut Criterion) {
+    let sub_problem = SubProblem::new_from_f64(
+        &bench_fns(),
+        &SolutionBlock::new_fullprob(7),
+        &GIVENS,
+        &UNKNOWNS,
+        ResidTransIdentity::new(7),
+        ResidAggSum,
+        &ParamScaling::default(),
+        None,
+        None,
+        None,
+    )
+    .unwrap()
+    .with_simulated_annealing_config(SimulatedAnnealingConfig::default());
+    let p = sub_problem.subprob_initial_params_optspace();
+    let temp = SimulatedAnnealingConfig::default().init_temp / 2.0;
+
+    c.bench_function("simulated annealing proposal", |b| {
+        b.iter(|| sub_problem.anneal(black_box(&p), temp).unwrap())
+    });
+}
+
+criterion_group!(
+    benches,
+    structure_detection,
+    gauss_newton_iteration,
+    annealing_proposal
+);
+criterion_main!(benches);
@@ -0,0 +1,88 @@
+//! Full `solve_system` on the dynamics example, from the same givens and initial guess as `main.rs`.
+//!
+//! Run with `cargo bench -p dynamics_example`.
+
+use std::hint::black_box;
+
+use criterion::{Criterion, criterion_group, criterion_main};
+use dynamics_example::prelude::*;
+use dynamics_example::{
+    constraints::{
+        aerial::{
+            air_no_accel_at_max_air_speed_in_zero_g_residual,
+            air_time_to_95pct_max_air_speed_in_zero_g_residual,
+        },
+        jump::{
+            jump_height_residual, jump_return_to_ground_in_time_down, jump_vel_at_peak_residual,
+        },
+        run::{run_accel_at_max_speed_residual, run_time_to_95pct_max_speed_residual},
+    },
+    dynamics::wall_and_slope::wall_slide_accel_at_wall_terminal_vel_residual,
+};
+use system_solver::{prelude::*, residual_fns_for_generic_params};
+
+fn solve_system(c: &mut Criterion) {
+    let givens_f64 = DynamicsGivenParams {
+        mass: 55.5,
+
+        jump_height: 3.3,
+        jump_time_up: 0.5,
+        jump_time_down: 0.4,
+
+        max_vel_run: 12.2,
+        time_to_95pct_max_vel_run: 0.2,
+        x_stop_speed_threshold: 0.1,
+
+        max_air_speed_x: 15.8,
+        time_to_95pct_max_air_speed_x: 0.3,
+
+        wall_slide_terminal_vel: -4.4,
+        sticky_glove_angle_deg: 25.0,
+    };
+
+    let unknowns = DynamicsDerivedParams {
+        air_drag_coeff: 0.2,
+        air_thrust_max: 2252.1212,
+
+        g: -9.81252,
+        jump_vy_0: 5.235235,
+        jump_boost_force: 50.235235,
+
+        run_force_max: 30.235235,
+        run_drag_coeff: 0.498797,
+
+        sticky_glove_force: 200.986967,
+    };
+
+    let residual_fns = residual_fns_for_generic_params!(
+        DynamicsGivenParams, DynamicsDerivedParams;
+        air_no_accel_at_max_air_speed_in_zero_g_residual,
+        air_time_to_95pct_max_air_speed_in_zero_g_residual,
+        jump_height_residual,
+        jump_vel_at_peak_residual,
+        jump_return_to_ground_in_time_down,
+        run_accel_at_max_speed_residual,
+        run_time_to_95pct_max_speed_residual,
+        wall_slide_accel_at_wall_terminal_vel_residual
+    );
+
+    let eq_sys = EquationSystemBuilder::new_from_f64(givens_f64, residual_fns)
+        .unwrap()
+        .with_triangularization(&unknowns)
+        .unwrap();
+    let options = SolveOptions::default().with_verbosity(Verbosity::Quiet);
+
+    let mut group = c.benchmark_group("dynamics example");
+    group.sample_size(10);
+    group.bench_function("solve_system", |b| {
+        b.iter(|| {
+            eq_sys
+                .solve_system_with_options(black_box(&unknowns), &options)
+                .unwrap()
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, solve_system);
+criterion_main!(benches);
@@ -2,6 +2,7 @@ use crate::prelude::*;
 use system_solver::prelude::{
     ad_trait::AD,
     nalgebra::{ComplexField, RealField, UnitVector2, Vector2},
+    warn_from_residual,
 };
 
 fn normal_from_tan_angle<T: AD>(tangent_angle: T) -> UnitVector2<T> {
@@ -96,7 +97,8 @@ where
     /// # Arguments
     /// * `normal` - Outward unit normal from the surface
     /// * `approx_tangent_vel` - Approximate tangent velocity (will be projected onto tangent)
-    /// * `normal_force_mag` - Magnitude of normal force in Newtons (will be clamped >= 0)
+    /// * `normal_force_mag` - Magnitude of normal force in Newtons (will be clamped >= 0, with a
+    ///   `SolveWarning::Residual` for the running solve)
     ///
     /// # Notes
     /// The `approx_tangent_vel` is projected onto the tangent direction to ensure
@@ -107,14 +109,10 @@ where
         normal_force_mag: T,
         traction_coeff: T,
     ) -> Self {
-        #[cfg(debug_assertions)]
-        {
-            if normal_force_mag < T::constant(0.0) {
-                println!(
-                    "Warning: GroundContact2D created with negative normal_force_mag: {}",
-                    normal_force_mag
-                );
-            }
+        if normal_force_mag < T::constant(0.0) {
+            warn_from_residual(
+                "GroundContact2D created with a negative normal force; clamped to 0",
+            );
         }
         let normal_force_mag = normal_force_mag.max(T::constant(0.0));
 
@@ -1,4 +1,3 @@
-use ad_trait::forward_ad::adfn::adfn;
 use dynamics_example::prelude::*;
 use dynamics_example::{
     constraints::{
@@ -16,18 +15,6 @@ use dynamics_example::{
 
 use system_solver::{prelude::*, residual_fns_for_generic_params};
 
-// Static field names for the unknowns - required for 'static lifetime
-static UNKNOWN_FIELD_NAMES: &[&str] = &[
-    "air_drag_coeff",
-    "air_thrust_max",
-    "g",
-    "jump_vy_0",
-    "jump_boost_force",
-    "run_force_max",
-    "run_drag_coeff",
-    "sticky_glove_force",
-];
-
 fn main() {
     let givens_f64 = DynamicsGivenParams {
         mass: 55.5,
@@ -47,9 +34,6 @@ fn main() {
         sticky_glove_angle_deg: 25.0,
     };
 
-    // Convert givens to adfn<1> version for automatic differentiation
-    let givens_adfn: DynamicsGivenParams<adfn<1>> = givens_f64.to_ad();
-
     let unknowns = DynamicsDerivedParams {
         // analytic solution that we want to converge to: air_drag_coeff=38.509
         air_drag_coeff: 0.2,
@@ -72,19 +56,18 @@ fn main() {
         DynamicsGivenParams, DynamicsDerivedParams;
         air_no_accel_at_max_air_speed_in_zero_g_residual,
         air_time_to_95pct_max_air_speed_in_zero_g_residual,
-        jump_height_residual,
-        jump_vel_at_peak_residual,
-        jump_return_to_ground_in_time_down,
+        jump_height_residual { tag: "jump" },
+        jump_vel_at_peak_residual { tag: "jump" },
+        jump_return_to_ground_in_time_down { tag: "jump" },
         run_accel_at_max_speed_residual,
         run_time_to_95pct_max_speed_residual,
         wall_slide_accel_at_wall_terminal_vel_residual
     );
 
-    let eq_sys =
-        EquationSystemBuilder::new(givens_f64, givens_adfn, residual_fns, UNKNOWN_FIELD_NAMES)
-            .unwrap();
+    let eq_sys = EquationSystemBuilder::new_from_f64(givens_f64, residual_fns).unwrap();
     let eq_sys = eq_sys.with_triangularization(&unknowns).unwrap();
     eq_sys.print_lower_tri_mat();
     eq_sys.print_solution_plan();
-    eq_sys.solve_system(&unknowns).unwrap();
+    let report = eq_sys.solve_system(&unknowns).unwrap();
+    report.print();
 }
@@ -12,7 +12,7 @@ use struct_to_array::StructToArray;
 /// These parameters are meant to be simple to understand and tune, and should
 /// use physical units that are intuitive-- how long a jump lasts, how high
 /// it goes, how fast something travels.
-#[derive(Debug, Clone, Copy, PartialEq, StructToArray)]
+#[derive(Debug, Clone, Copy, PartialEq, StructToArray, AdConvert)]
 pub struct DynamicsGivenParams<T> {
     pub mass: T,
 
@@ -42,7 +42,7 @@ pub struct DynamicsGivenParams<T> {
 /// might not be critical in and of themselves to tune directly, but need to
 /// be set correctly to get the desired dynamics to work out.
 
-#[derive(Copy, Clone, Debug, StructToArray, FieldNames)]
+#[derive(Copy, Clone, Debug, StructToArray, FieldNames, AdConvert)]
 pub struct DynamicsDerivedParams<T> {
     pub air_drag_coeff: T,
     pub air_thrust_max: T,
@@ -68,7 +68,13 @@ pub const N_GIVENS: usize =
     core::mem::size_of::<DynamicsGivenParams<f32>>() / core::mem::size_of::<f32>();
 
 // Implement system_solver traits
-impl<T> GivenParams for DynamicsGivenParams<T> where T: Clone + Copy + std::fmt::Debug {}
-impl<T> UnknownParams for DynamicsDerivedParams<T> where T: Clone + Copy + std::fmt::Debug {}
+impl<T> GivenParams for DynamicsGivenParams<T> where
+    T: Clone + Copy + std::fmt::Debug + MaybeSendSync
+{
+}
+impl<T> UnknownParams for DynamicsDerivedParams<T> where
+    T: Clone + Copy + std::fmt::Debug + MaybeSendSync
+{
+}
 
-// Note: to_ad() conversion methods are defined in dynamics/test_params.rs
+// Note: to_ad()/to_f64() conversion methods are generated by `#[derive(AdConvert)]`
@@ -0,0 +1,289 @@
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+
+use ad_trait::forward_ad::adfn::adfn;
+
+use crate::{
+    equation_system::{EqSysSolutionPlan, EquationSystemBuilder},
+    prelude::*,
+};
+
+type LeastSquaresSubProblem<G64, U64, Gadfn, Uadfn, const N: usize> = SubProblem<
+    G64,
+    U64,
+    Gadfn,
+    Uadfn,
+    ResidTransWeighted<ResidTransIdentity>,
+    ResidNoOpGaussNewton,
+    N,
+>;
+#[cfg(feature = "argmin")]
+type GaussNewtonSubProblem<G64, U64, Gadfn, Uadfn, const N: usize> = SubProblem<
+    G64,
+    U64,
+    Gadfn,
+    Uadfn,
+    ResidTransWeighted<ResidTransUnscaledL2>,
+    ResidNoOpGaussNewton,
+    N,
+>;
+#[cfg(feature = "argmin")]
+type AnnealingSubProblem<G64, U64, Gadfn, Uadfn, const N: usize> =
+    SubProblem<G64, U64, Gadfn, Uadfn, ResidTransWeighted<ResidTransUnscaledL2>, ResidAggSum, N>;
+
+/// The sub-problems of one plan block, each built at `built_at` with the augmented-Lagrangian
+/// `multipliers` the first time a solver needs it.
+pub(super) struct BlockEngines<G64, U64, Gadfn, Uadfn, const N: usize>
+where
+    G64: GivenParamsFor<f64, N>,
+    U64: UnknownParamsFor<f64, N>,
+    Gadfn: GivenParamsFor<adfn<1>, N>,
+    Uadfn: UnknownParamsFor<adfn<1>, N>,
+{
+    block: SolutionBlock,
+    built_at: U64,
+    multipliers: Vec<f64>,
+    least_squares: OnceLock<LeastSquaresSubProblem<G64, U64, Gadfn, Uadfn, N>>,
+    #[cfg(feature = "argmin")]
+    gauss_newton: OnceLock<GaussNewtonSubProblem<G64, U64, Gadfn, Uadfn, N>>,
+    #[cfg(feature = "argmin")]
+    annealing: OnceLock<AnnealingSubProblem<G64, U64, Gadfn, Uadfn, N>>,
+}
+
+impl<G64, U64, Gadfn, Uadfn, const N: usize> BlockEngines<G64, U64, Gadfn, Uadfn, N>
+where
+    G64: GivenParamsFor<f64, N>,
+    U64: UnknownParamsFor<f64, N>,
+    Gadfn: GivenParamsFor<adfn<1>, N>,
+    Uadfn: UnknownParamsFor<adfn<1>, N>,
+{
+    fn new(block: &SolutionBlock, built_at: &U64, multipliers: &[f64]) -> Self {
+        Self {
+            block: block.clone(),
+            built_at: *built_at,
+            multipliers: multipliers.to_vec(),
+            least_squares: OnceLock::new(),
+            #[cfg(feature = "argmin")]
+            gauss_newton: OnceLock::new(),
+            #[cfg(feature = "argmin")]
+            annealing: OnceLock::new(),
+        }
+    }
+
+    /// Whether these sub-problems can serve a solve of `block` from `start` with `multipliers`: the
+    /// block and the multipliers must be the same, and unless the scaling is `start_independent`,
+    /// so must the start, bit for bit.
+    fn serves(
+        &self,
+        block: &SolutionBlock,
+        start: &U64,
+        multipliers: &[f64],
+        start_independent: bool,
+    ) -> bool {
+        let same_block = self.block.unknown_idxs == block.unknown_idxs
+            && self.block.equation_idxs == block.equation_idxs;
+        let same_multipliers = self.multipliers.len() == multipliers.len()
+            && self
+                .multipliers
+                .iter()
+                .zip(multipliers)
+                .all(|(a, b)| a.to_bits() == b.to_bits());
+        let same_start = self
+            .built_at
+            .to_arr()
+            .iter()
+            .zip(start.to_arr())
+            .all(|(a, b)| a.to_bits() == b.to_bits());
+        same_block && same_multipliers && (same_start || start_independent)
+    }
+}
+
+/// Sub-problems of the plan's blocks (keyed by `SolutionBlock::block_idx`), kept across
+/// `solve_system`, `solve_block` and `resolve_with_givens` calls so that re-solving a block doesn't
+/// rebuild its objective functions, param scaler and function engines.
+///
+/// Everything the sub-problems are built from besides the starting point is fixed while an entry
+/// lives: the builder clears the cache when the givens, the plan, the weights, regularization,
+/// scaling or constraints change. The augmented-Lagrangian multipliers baked into the objectives
+/// are part of an entry's key instead, so solves at different outer iterations, possibly running
+/// concurrently, never share sub-problems.
+pub(super) struct BlockEngineCache<G64, U64, Gadfn, Uadfn, const N: usize>
+where
+    G64: GivenParamsFor<f64, N>,
+    U64: UnknownParamsFor<f64, N>,
+    Gadfn: GivenParamsFor<adfn<1>, N>,
+    Uadfn: UnknownParamsFor<adfn<1>, N>,
+{
+    blocks: Mutex<HashMap<usize, Shared<BlockEngines<G64, U64, Gadfn, Uadfn, N>>>>,
+}
+
+impl<G64, U64, Gadfn, Uadfn, const N: usize> BlockEngineCache<G64, U64, Gadfn, Uadfn, N>
+where
+    G64: GivenParamsFor<f64, N>,
+    U64: UnknownParamsFor<f64, N>,
+    Gadfn: GivenParamsFor<adfn<1>, N>,
+    Uadfn: UnknownParamsFor<adfn<1>, N>,
+{
+    pub(super) fn new() -> Self {
+        Self {
+            blocks: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub(super) fn clear(&self) {
+        self.blocks.lock().unwrap().clear();
+    }
+
+    /// The cached sub-problems of `block` if they can serve a solve from `start` with
+    /// `multipliers`, otherwise a fresh (still unbuilt) entry replacing them.
+    fn engines(
+        &self,
+        block: &SolutionBlock,
+        start: &U64,
+        multipliers: &[f64],
+        start_independent: bool,
+    ) -> Shared<BlockEngines<G64, U64, Gadfn, Uadfn, N>> {
+        let mut blocks = self.blocks.lock().unwrap();
+        match blocks.get(&block.block_idx) {
+            Some(engines) if engines.serves(block, start, multipliers, start_independent) => {
+                engines.clone()
+            }
+            _ => {
+                let engines = Shared::new(BlockEngines::new(block, start, multipliers));
+                blocks.insert(block.block_idx, engines.clone());
+                engines
+            }
+        }
+    }
+}
+
+/// The sub-problems of one block solve, shared by every solver attempt on the block (fallbacks,
+/// restarts, and the Gauss-Newton polish after simulated annealing) and, through the builder's
+/// `BlockEngineCache`, by later solves of the same block.
+///
+/// Every attempt gets a copy restarted from its own starting point (see
+/// `SubProblem::starting_from`). The opt-space mapping stays centered on the point the sub-problems
+/// were built at, which only changes where the solver's coordinates are centered, not the solution.
+pub(super) struct BlockSubProblems<'a, G64, U64, Gadfn, Uadfn, const N: usize>
+where
+    G64: GivenParamsFor<f64, N>,
+    U64: UnknownParamsFor<f64, N>,
+    Gadfn: GivenParamsFor<adfn<1>, N>,
+    Uadfn: UnknownParamsFor<adfn<1>, N>,
+{
+    system: &'a EquationSystemBuilder<G64, U64, Gadfn, Uadfn, EqSysSolutionPlan, N>,
+    block: &'a SolutionBlock,
+    engines: Shared<BlockEngines<G64, U64, Gadfn, Uadfn, N>>,
+}
+
+impl<'a, G64, U64, Gadfn, Uadfn, const N: usize> BlockSubProblems<'a, G64, U64, Gadfn, Uadfn, N>
+where
+    G64: GivenParamsFor<f64, N>,
+    U64: UnknownParamsFor<f64, N>,
+    Gadfn: GivenParamsFor<adfn<1>, N>,
+    Uadfn: UnknownParamsFor<adfn<1>, N>,
+{
+    /// The sub-problems for solving `block` from `start` with the augmented-Lagrangian
+    /// `multipliers`, reusing cached ones where possible.
+    pub(super) fn new(
+        system: &'a EquationSystemBuilder<G64, U64, Gadfn, Uadfn, EqSysSolutionPlan, N>,
+        block: &'a SolutionBlock,
+        start: &U64,
+        multipliers: &[f64],
+    ) -> Self {
+        // with fixed priors or a scaling that ignores them, the start doesn't shape the sub-problems
+        let start_independent =
+            system.scaling_priors.is_some() || !system.param_scaling.is_centered_on_start();
+        let engines = system
+            .block_engines
+            .engines(block, start, multipliers, start_independent);
+        Self {
+            system,
+            block,
+            engines,
+        }
+    }
+
+    pub(super) fn block(&self) -> &'a SolutionBlock {
+        self.block
+    }
+
+    /// Domains of the link functions the sub-problems map the unknowns through.
+    pub(super) fn scaling_domains(&self) -> [LinkDomain; N] {
+        self.system
+            .param_scaling
+            .link_domains(&self.system.scaling_center(&self.engines.built_at).to_arr())
+    }
+
+    /// The sub-problem cached in `cell`, building it with `init` on first use. Unlike
+    /// `OnceLock::get_or_init`, a failed build leaves the cell empty.
+    fn get_or_try_init<'c, T>(
+        cell: &'c OnceLock<T>,
+        init: impl FnOnce() -> Result<T, EqSysError>,
+    ) -> Result<&'c T, EqSysError> {
+        if let Some(sub_problem) = cell.get() {
+            return Ok(sub_problem);
+        }
+        let sub_problem = init()?;
+        Ok(cell.get_or_init(|| sub_problem))
+    }
+
+    /// Restarts `sub_problem` from `start`, naming the offending field if `start` is outside the scaling's domain.
+    fn restarted<R, A>(
+        &self,
+        sub_problem: &SubProblem<G64, U64, Gadfn, Uadfn, R, A, N>,
+        start: &U64,
+    ) -> Result<SubProblem<G64, U64, Gadfn, Uadfn, R, A, N>, EqSysError>
+    where
+        R: ResidTransHOF,
+        A: ResidAggHOF,
+    {
+        sub_problem
+            .starting_from(start)
+            .map_err(|e| e.with_unknown_field_names(self.system.unknown_field_names))
+    }
+
+    pub(super) fn least_squares(
+        &self,
+        start: &U64,
+    ) -> Result<LeastSquaresSubProblem<G64, U64, Gadfn, Uadfn, N>, EqSysError> {
+        let sub_problem = Self::get_or_try_init(&self.engines.least_squares, || {
+            self.system.least_squares_sub_problem(
+                self.block,
+                &self.engines.built_at,
+                &self.engines.multipliers,
+            )
+        })?;
+        self.restarted(sub_problem, start)
+    }
+
+    #[cfg(feature = "argmin")]
+    pub(super) fn gauss_newton(
+        &self,
+        start: &U64,
+    ) -> Result<GaussNewtonSubProblem<G64, U64, Gadfn, Uadfn, N>, EqSysError> {
+        let sub_problem = Self::get_or_try_init(&self.engines.gauss_newton, || {
+            self.system.gauss_newton_sub_problem(
+                self.block,
+                &self.engines.built_at,
+                &self.engines.multipliers,
+            )
+        })?;
+        self.restarted(sub_problem, start)
+    }
+
+    #[cfg(feature = "argmin")]
+    pub(super) fn annealing(
+        &self,
+        start: &U64,
+    ) -> Result<AnnealingSubProblem<G64, U64, Gadfn, Uadfn, N>, EqSysError> {
+        let sub_problem = Self::get_or_try_init(&self.engines.annealing, || {
+            self.system.simulated_annealing_sub_problem(
+                self.block,
+                &self.engines.built_at,
+                &self.engines.multipliers,
+            )
+        })?;
+        self.restarted(sub_problem, start)
+    }
+}
@@ -0,0 +1,23 @@
+/// Hard lower/upper bounds on the unknowns, in model space. Use `f64::NEG_INFINITY` /
+/// `f64::INFINITY` for unbounded sides.
+///
+/// Sub-problems evaluate their objective at the projection of each candidate onto the box, and the
+/// native solvers project every iterate, so solutions never leave the bounds (projected
+/// Gauss-Newton).
+#[derive(Clone, Debug)]
+pub struct BoxConstraints<const N: usize> {
+    pub lower: [f64; N],
+    pub upper: [f64; N],
+}
+
+impl<const N: usize> BoxConstraints<N> {
+    /// Clamps `value` into the bounds of unknown `idx`.
+    pub fn clamp(&self, idx: usize, value: f64) -> f64 {
+        value.clamp(self.lower[idx], self.upper[idx])
+    }
+
+    /// Whether model-space `value` lies within the bounds of unknown `idx`.
+    pub fn contains(&self, idx: usize, value: f64) -> bool {
+        self.lower[idx] <= value && value <= self.upper[idx]
+    }
+}
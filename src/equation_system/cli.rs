@@ -0,0 +1,80 @@
+//! Terminal progress bars for interactive solves (`cli` feature), drawn with `indicatif` from the
+//! `SolveOptions::progress` callback.
+
+use std::ops::ControlFlow;
+
+use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
+
+use crate::prelude::*;
+
+/// How often the iteration line is refreshed when `SolveOptions::progress_every_iters` isn't set.
+const DEFAULT_PROGRESS_EVERY_ITERS: u64 = 10;
+
+impl SolveOptions {
+    /// Shows the solve's progress as terminal progress bars: one for the blocks of the plan, and a
+    /// line with the current block's solver, iteration count and best cost so far.
+    ///
+    /// Replaces the per-iteration output, so `verbosity` is set to `Verbosity::Quiet`, and any
+    /// `progress` callback set before is replaced.
+    pub fn with_progress_bars(mut self) -> Self {
+        let bars = MultiProgress::new();
+        let blocks = bars.add(
+            ProgressBar::new(0).with_style(
+                ProgressStyle::with_template("[{elapsed_precise}] {bar:40} {pos}/{len} blocks")
+                    .expect("valid progress bar template"),
+            ),
+        );
+        let current = bars.add(ProgressBar::new_spinner().with_style(
+            ProgressStyle::with_template("{spinner} {msg}").expect("valid spinner template"),
+        ));
+
+        let mut solver = String::new();
+        let mut best_cost = f64::INFINITY;
+        self.verbosity = Verbosity::Quiet;
+        self.progress_every_iters = self
+            .progress_every_iters
+            .or(Some(DEFAULT_PROGRESS_EVERY_ITERS));
+        self.with_progress(move |progress| {
+            match progress {
+                SolveProgress::BlockStarted {
+                    block_idx,
+                    n_blocks,
+                } => {
+                    blocks.set_length(n_blocks as u64);
+                    best_cost = f64::INFINITY;
+                    current.set_message(format!("block {block_idx}"));
+                }
+                SolveProgress::SolverStarted {
+                    block_idx,
+                    solver: started,
+                } => {
+                    solver = started;
+                    current.set_message(format!("block {block_idx}: {solver}"));
+                }
+                SolveProgress::Iteration {
+                    block_idx,
+                    iteration,
+                    cost,
+                } => {
+                    best_cost = best_cost.min(cost);
+                    current.set_message(format!(
+                        "block {block_idx}: {solver}, iteration {iteration}, best cost {best_cost:.3e}"
+                    ));
+                    current.tick();
+                }
+                SolveProgress::BlockFinished {
+                    block_idx,
+                    residual_norm,
+                    ..
+                } => {
+                    // blocks are re-solved by extra sweeps, so track the position rather than counting
+                    blocks.set_position(block_idx as u64 + 1);
+                    current.set_message(format!(
+                        "block {block_idx}: done, residual norm {residual_norm:.3e}"
+                    ));
+                }
+            }
+            ControlFlow::Continue(())
+        })
+    }
+}
@@ -0,0 +1,9 @@
+//! Wall-clock timing for time limits and reports: `std::time::Instant` by default, and
+//! `web_time::Instant` with the `wasm` feature, since `std`'s `Instant::now` panics on
+//! `wasm32-unknown-unknown`.
+
+#[cfg(feature = "wasm")]
+pub use web_time::Instant;
+
+#[cfg(not(feature = "wasm"))]
+pub use std::time::Instant;
@@ -0,0 +1,123 @@
+use ad_trait::forward_ad::adfn::adfn;
+use nalgebra::{Dyn, Matrix, VecStorage};
+use struct_to_array::StructToArray;
+
+use crate::prelude::*;
+
+/// A grouping of the Jacobian's columns into structurally orthogonal colors: no two columns of one
+/// color are nonzero in the same row.
+///
+/// Seeding one tangent slot with every unknown of a color recovers all of their columns from a
+/// single forward-AD pass, so a Jacobian needs as many passes as there are colors rather than
+/// unknowns (divided by the tangent width `K` of `adfn<K>`).
+#[derive(Clone, Debug, PartialEq)]
+pub struct ColumnColoring {
+    /// Color of each unknown, in `StructToArray` order.
+    colors: Vec<usize>,
+    n_colors: usize,
+    /// For each equation, the unknowns it structurally depends on.
+    row_cols: Vec<Vec<usize>>,
+}
+
+impl ColumnColoring {
+    /// Greedy coloring of the columns of `incidence` (nonzero, including NaN, means structurally
+    /// nonzero), visiting the densest columns first.
+    pub fn from_incidence(incidence: &Matrix<f32, Dyn, Dyn, VecStorage<f32, Dyn, Dyn>>) -> Self {
+        let (n_rows, n_cols) = incidence.shape();
+        let row_cols = (0..n_rows)
+            .map(|r| (0..n_cols).filter(|&c| incidence[(r, c)] != 0.0).collect())
+            .collect();
+        Self::from_row_cols(n_cols, row_cols)
+    }
+
+    /// Greedy coloring of `n_cols` columns, where `row_cols` lists for each row the columns it
+    /// structurally depends on, visiting the densest columns first.
+    pub fn from_row_cols(n_cols: usize, row_cols: Vec<Vec<usize>>) -> Self {
+        let mut order: Vec<usize> = (0..n_cols).collect();
+        order.sort_by_key(|&c| {
+            std::cmp::Reverse(row_cols.iter().filter(|cols| cols.contains(&c)).count())
+        });
+
+        let mut colors = vec![usize::MAX; n_cols];
+        let mut n_colors = 0;
+        for c in order {
+            // colors of the columns that share a row with `c`
+            let mut taken = vec![false; n_colors];
+            for cols in row_cols.iter().filter(|cols| cols.contains(&c)) {
+                for &other in cols {
+                    if colors[other] != usize::MAX {
+                        taken[colors[other]] = true;
+                    }
+                }
+            }
+            colors[c] = taken.iter().position(|&t| !t).unwrap_or(n_colors);
+            n_colors = n_colors.max(colors[c] + 1);
+        }
+
+        Self {
+            colors,
+            n_colors,
+            row_cols,
+        }
+    }
+
+    pub fn n_colors(&self) -> usize {
+        self.n_colors
+    }
+
+    /// Color of each unknown, in `StructToArray` order.
+    pub fn colors(&self) -> &[usize] {
+        &self.colors
+    }
+
+    /// The columns of each color, in ascending order.
+    pub fn color_groups(&self) -> Vec<Vec<usize>> {
+        let mut groups = vec![vec![]; self.n_colors];
+        for (c, &color) in self.colors.iter().enumerate() {
+            groups[color].push(c);
+        }
+        groups
+    }
+
+    /// Residuals and Jacobian of `fns` at `unknowns`, with one `adfn<K>` pass per `K` colors.
+    ///
+    /// Jacobian entries outside the coloring's sparsity pattern are left at zero.
+    pub(crate) fn derivative<G, U, const K: usize, const N: usize>(
+        &self,
+        givens: &G,
+        fns: &[ResidualFn<G, U, adfn<K>>],
+        unknowns: &[f64],
+    ) -> (Vec<f64>, Matrix<f64, Dyn, Dyn, VecStorage<f64, Dyn, Dyn>>)
+    where
+        U: StructToArray<adfn<K>, N>,
+    {
+        let mut values = vec![0.0; fns.len()];
+        let mut jacobian = Matrix::<f64, Dyn, Dyn, VecStorage<f64, Dyn, Dyn>>::zeros(fns.len(), N);
+        // at least one pass, so that the values are computed even without unknowns
+        for first_color in (0..self.n_colors.max(1)).step_by(K) {
+            let slot = |c: usize| {
+                (first_color..first_color + K)
+                    .contains(&self.colors[c])
+                    .then(|| self.colors[c] - first_color)
+            };
+            let seeded = U::from_arr(std::array::from_fn(|c| {
+                let mut tangent = [0.0; K];
+                if let Some(s) = slot(c) {
+                    tangent[s] = 1.0;
+                }
+                adfn::new(unknowns[c], tangent)
+            }));
+            for (r, f) in fns.iter().enumerate() {
+                let out = f(givens, &seeded);
+                values[r] = out.value();
+                let tangent = out.tangent();
+                for &c in &self.row_cols[r] {
+                    if let Some(s) = slot(c) {
+                        jacobian[(r, c)] = tangent[s];
+                    }
+                }
+            }
+        }
+        (values, jacobian)
+    }
+}
@@ -0,0 +1,128 @@
+use std::fmt;
+
+use ad_trait::forward_ad::adfn::adfn;
+use nalgebra::{DMatrix, DVector};
+use struct_to_array::StructToArray;
+
+use crate::{equation_system::EquationSystemBuilder, prelude::*};
+
+/// Upper bound on the number of conflicts `EquationSystemBuilder::explain_conflicts` reports.
+pub const MAX_REPORTED_CONFLICTS: usize = 5;
+
+/// A minimal set of equations that can't hold together near a point: dropping any one of them lets
+/// the others be satisfied, to first order.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ConstraintConflict {
+    /// Names of the conflicting residual functions, in registration order.
+    pub fn_names: Vec<&'static str>,
+    /// Unknowns the conflicting equations depend on at the point, in `StructToArray` order.
+    pub unknown_names: Vec<&'static str>,
+    /// Norm of the conflicting equations' residuals that no first-order step of the unknowns removes.
+    pub misfit: f64,
+}
+
+impl fmt::Display for ConstraintConflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let names: Vec<String> = self.fn_names.iter().map(|n| format!("`{n}`")).collect();
+        match names.as_slice() {
+            [single] => write!(f, "{single} can't be satisfied")?,
+            [init @ .., last] => write!(
+                f,
+                "{} and {last} are mutually unsatisfiable",
+                init.join(", ")
+            )?,
+            [] => write!(f, "no equations conflict")?,
+        }
+        if !self.unknown_names.is_empty() {
+            write!(f, " through {}", self.unknown_names.join(", "))?;
+        }
+        write!(f, " (misfit {:.3e})", self.misfit)
+    }
+}
+
+impl<G64, U64, Gadfn, Uadfn, S, const N: usize> EquationSystemBuilder<G64, U64, Gadfn, Uadfn, S, N>
+where
+    G64: GivenParamsFor<f64, N>,
+    U64: UnknownParamsFor<f64, N>,
+    Gadfn: GivenParamsFor<adfn<1>, N>,
+    Uadfn: UnknownParamsFor<adfn<1>, N>,
+{
+    /// Explains why the equations can't all be brought within `tol` of zero near `unknowns`, e.g.
+    /// after a solve that ended with a large residual, as minimal conflicting sets of equations.
+    ///
+    /// Works on the system linearized at `unknowns`: a set of equations conflicts if no step of the
+    /// unknowns brings its linearized residuals within `tol` (in norm). Each conflict is shrunk to
+    /// a minimal set by dropping equations one at a time while the rest still conflicts, then
+    /// removed before looking for the next one, up to `MAX_REPORTED_CONFLICTS`. At a stalled
+    /// least-squares point this names the equations that pull the unknowns in incompatible
+    /// directions; away from one, the linearization may miss conflicts that only show up further
+    /// out. Returns nothing if every residual is within `tol` or some residual or derivative is not
+    /// finite (see `check_finite`).
+    pub fn explain_conflicts(&self, unknowns: &U64, tol: f64) -> Vec<ConstraintConflict> {
+        let (residuals, jacobian) = self.full_derivative(&unknowns.to_arr());
+        if residuals.iter().all(|r| r.abs() <= tol)
+            || residuals.iter().any(|r| !r.is_finite())
+            || jacobian.iter().any(|d| !d.is_finite())
+        {
+            return vec![];
+        }
+
+        let mut active: Vec<usize> = (0..residuals.len()).collect();
+        let mut conflicts = vec![];
+        while conflicts.len() < MAX_REPORTED_CONFLICTS
+            && linearized_misfit(&jacobian, &residuals, &active) > tol
+        {
+            let conflict = minimal_conflict(&jacobian, &residuals, active.clone(), tol);
+            active.retain(|e| !conflict.contains(e));
+            conflicts.push(ConstraintConflict {
+                fn_names: conflict
+                    .iter()
+                    .map(|&e| self.raw_res_fns.fn_names()[e])
+                    .collect(),
+                unknown_names: (0..N)
+                    .filter(|&u| conflict.iter().any(|&e| jacobian[(e, u)] != 0.0))
+                    .map(|u| self.unknown_field_names[u])
+                    .collect(),
+                misfit: linearized_misfit(&jacobian, &residuals, &conflict),
+            });
+        }
+        conflicts
+    }
+}
+
+/// Deletion filter: drops equations from the conflicting set `eqs` one at a time as long as the
+/// rest still conflicts, leaving a minimal conflicting set.
+fn minimal_conflict(
+    jacobian: &DMatrix<f64>,
+    residuals: &[f64],
+    mut eqs: Vec<usize>,
+    tol: f64,
+) -> Vec<usize> {
+    let mut i = 0;
+    while i < eqs.len() {
+        let mut without = eqs.clone();
+        without.remove(i);
+        if linearized_misfit(jacobian, residuals, &without) > tol {
+            eqs = without;
+        } else {
+            i += 1;
+        }
+    }
+    eqs
+}
+
+/// `min_δ ‖J_E δ + r_E‖` over the equations `eqs`: what's left of their residuals after the best
+/// first-order step of the unknowns.
+fn linearized_misfit(jacobian: &DMatrix<f64>, residuals: &[f64], eqs: &[usize]) -> f64 {
+    if eqs.is_empty() {
+        return 0.0;
+    }
+    let j = DMatrix::from_fn(eqs.len(), jacobian.ncols(), |r, c| jacobian[(eqs[r], c)]);
+    let r = DVector::from_iterator(eqs.len(), eqs.iter().map(|&e| residuals[e]));
+    let svd = j.clone().svd(true, true);
+    let eps = 1e-10 * svd.singular_values.max();
+    match svd.solve(&(-&r), eps) {
+        Ok(delta) => (j * delta + r).norm(),
+        Err(_) => r.norm(),
+    }
+}
@@ -0,0 +1,255 @@
+use ad_trait::{AD, forward_ad::adfn::adfn};
+
+use crate::equation_system::shared::{MaybeSendSync, Shared, shared_fn};
+
+/// Whether a constraint function `c(u)` of the unknowns must satisfy `c(u) = 0` or `c(u) >= 0`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ConstraintKind {
+    Equality,
+    NonNegative,
+}
+
+/// Algebraic constraints among the unknowns (e.g. `u1 + u2 - c = 0` or
+/// `run_force_max - air_thrust_max >= 0`), in both f64 and adfn<1> forms.
+///
+/// Unlike residual functions, constraints don't take part in the triangularization; they are
+/// enforced through augmented-Lagrangian penalty terms added to every sub-problem objective (see
+/// `AugmentedLagrangian`).
+#[derive(Clone)]
+pub struct UnknownConstraints<U64, Uadfn> {
+    f64: Vec<shared_fn!(Fn(&U64) -> f64)>,
+    adfn_1: Vec<shared_fn!(Fn(&Uadfn) -> adfn<1>)>,
+    kinds: Vec<ConstraintKind>,
+    names: Vec<&'static str>,
+}
+
+impl<U64, Uadfn> Default for UnknownConstraints<U64, Uadfn> {
+    fn default() -> Self {
+        Self {
+            f64: vec![],
+            adfn_1: vec![],
+            kinds: vec![],
+            names: vec![],
+        }
+    }
+}
+
+impl<U64, Uadfn> UnknownConstraints<U64, Uadfn> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds the constraint `c(u) = 0`. For generic unknown types, pass `c::<f64>` and `c::<adfn<1>>`.
+    pub fn equality(
+        self,
+        name: &'static str,
+        c_f64: impl Fn(&U64) -> f64 + MaybeSendSync + 'static,
+        c_adfn: impl Fn(&Uadfn) -> adfn<1> + MaybeSendSync + 'static,
+    ) -> Self {
+        self.with_constraint(ConstraintKind::Equality, name, c_f64, c_adfn)
+    }
+
+    /// Adds the constraint `c(u) >= 0`.
+    pub fn non_negative(
+        self,
+        name: &'static str,
+        c_f64: impl Fn(&U64) -> f64 + MaybeSendSync + 'static,
+        c_adfn: impl Fn(&Uadfn) -> adfn<1> + MaybeSendSync + 'static,
+    ) -> Self {
+        self.with_constraint(ConstraintKind::NonNegative, name, c_f64, c_adfn)
+    }
+
+    fn with_constraint(
+        mut self,
+        kind: ConstraintKind,
+        name: &'static str,
+        c_f64: impl Fn(&U64) -> f64 + MaybeSendSync + 'static,
+        c_adfn: impl Fn(&Uadfn) -> adfn<1> + MaybeSendSync + 'static,
+    ) -> Self {
+        self.f64.push(Shared::new(c_f64));
+        self.adfn_1.push(Shared::new(c_adfn));
+        self.kinds.push(kind);
+        self.names.push(name);
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.kinds.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.kinds.is_empty()
+    }
+
+    pub fn names(&self) -> &Vec<&'static str> {
+        &self.names
+    }
+
+    pub fn kinds(&self) -> &Vec<ConstraintKind> {
+        &self.kinds
+    }
+
+    /// Evaluates every constraint function at `unknowns`.
+    pub fn values(&self, unknowns: &U64) -> Vec<f64> {
+        self.f64.iter().map(|c| c(unknowns)).collect()
+    }
+
+    /// The largest constraint violation at `unknowns`: `|c|` for equalities, `max(0, -c)` for `c >= 0`.
+    pub fn max_violation(&self, unknowns: &U64) -> f64 {
+        self.kinds
+            .iter()
+            .zip(self.values(unknowns))
+            .map(|(kind, c)| match kind {
+                ConstraintKind::Equality => c.abs(),
+                ConstraintKind::NonNegative => (-c).max(0.0),
+            })
+            .fold(0.0, f64::max)
+    }
+}
+
+/// Augmented-Lagrangian enforcement of `UnknownConstraints`.
+///
+/// Each sub-problem objective gets one extra penalty residual per constraint,
+/// - `sqrt(mu) * (c + lambda / mu)` for `c = 0`,
+/// - `sqrt(mu) * max(0, lambda / mu - c)` for `c >= 0`,
+///
+/// whose squares are, up to constants, the quadratic penalty `mu * c^2` plus the Lagrange term
+/// `2 * lambda * c`. After each full solve the multipliers `lambda` are updated from the constraint
+/// values and the system is solved again, until the constraints hold to within `tol` or
+/// `max_outer_iters` is reached. The multipliers belong to the solve, not to this struct, so
+/// concurrent solves of one builder don't interfere.
+pub struct AugmentedLagrangian<U64, Uadfn> {
+    pub constraints: UnknownConstraints<U64, Uadfn>,
+    /// Penalty weight `mu`; larger values enforce the constraints harder at the cost of conditioning.
+    pub penalty: f64,
+    pub max_outer_iters: usize,
+    /// Largest acceptable constraint violation (see `UnknownConstraints::max_violation`).
+    pub tol: f64,
+}
+
+impl<U64, Uadfn> Clone for AugmentedLagrangian<U64, Uadfn> {
+    fn clone(&self) -> Self {
+        Self {
+            constraints: self.constraints.clone(),
+            penalty: self.penalty,
+            max_outer_iters: self.max_outer_iters,
+            tol: self.tol,
+        }
+    }
+}
+
+impl<U64, Uadfn> AugmentedLagrangian<U64, Uadfn> {
+    pub fn new(constraints: UnknownConstraints<U64, Uadfn>) -> Self {
+        Self {
+            constraints,
+            penalty: 10.0,
+            max_outer_iters: 10,
+            tol: 1e-8,
+        }
+    }
+
+    pub fn with_penalty(mut self, penalty: f64) -> Self {
+        self.penalty = penalty;
+        self
+    }
+
+    pub fn with_max_outer_iters(mut self, max_outer_iters: usize) -> Self {
+        self.max_outer_iters = max_outer_iters;
+        self
+    }
+
+    pub fn with_tol(mut self, tol: f64) -> Self {
+        self.tol = tol;
+        self
+    }
+
+    /// Multipliers a solve starts from: zero for every constraint, i.e. a plain quadratic penalty.
+    pub fn initial_multipliers(&self) -> Vec<f64> {
+        vec![0.0; self.constraints.len()]
+    }
+
+    /// First-order update of `multipliers` from the constraint values at `unknowns`.
+    pub fn updated_multipliers(&self, multipliers: &[f64], unknowns: &U64) -> Vec<f64> {
+        let values = self.constraints.values(unknowns);
+        self.multipliers_per_constraint(multipliers)
+            .into_iter()
+            .zip(&self.constraints.kinds)
+            .zip(values)
+            .map(|((lambda, kind), c)| match kind {
+                ConstraintKind::Equality => lambda + self.penalty * c,
+                ConstraintKind::NonNegative => (lambda - self.penalty * c).max(0.0),
+            })
+            .collect()
+    }
+
+    /// `multipliers` padded with zeros (or truncated) to one per constraint.
+    fn multipliers_per_constraint(&self, multipliers: &[f64]) -> Vec<f64> {
+        (0..self.constraints.len())
+            .map(|i| multipliers.get(i).copied().unwrap_or(0.0))
+            .collect()
+    }
+
+    /// Penalty terms with the given multipliers, for f64 objectives. Missing multipliers count as zero.
+    pub fn penalty_f64(&self, multipliers: &[f64]) -> ConstraintPenalty<f64, U64> {
+        ConstraintPenalty {
+            fns: self.constraints.f64.clone(),
+            kinds: self.constraints.kinds.clone(),
+            multipliers: self.multipliers_per_constraint(multipliers),
+            penalty: self.penalty,
+        }
+    }
+
+    /// Penalty terms with the given multipliers, for adfn<1> objectives. Missing multipliers count as zero.
+    pub fn penalty_adfn(&self, multipliers: &[f64]) -> ConstraintPenalty<adfn<1>, Uadfn> {
+        ConstraintPenalty {
+            fns: self.constraints.adfn_1.clone(),
+            kinds: self.constraints.kinds.clone(),
+            multipliers: self.multipliers_per_constraint(multipliers),
+            penalty: self.penalty,
+        }
+    }
+}
+
+/// Augmented-Lagrangian penalty residuals for one AD type, with frozen multipliers (see `AugmentedLagrangian`).
+#[derive(Clone)]
+pub struct ConstraintPenalty<T: AD, U> {
+    fns: Vec<shared_fn!(Fn(&U) -> T)>,
+    kinds: Vec<ConstraintKind>,
+    multipliers: Vec<f64>,
+    penalty: f64,
+}
+
+impl<T: AD, U> ConstraintPenalty<T, U> {
+    pub fn len(&self) -> usize {
+        self.fns.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.fns.is_empty()
+    }
+
+    /// One penalty residual per constraint, evaluated on model-space unknowns.
+    pub fn penalty_residuals(&self, unknowns: &U) -> Vec<T> {
+        let sqrt_mu = T::constant(self.penalty.sqrt());
+        self.fns
+            .iter()
+            .zip(&self.kinds)
+            .zip(&self.multipliers)
+            .map(|((c, kind), &lambda)| {
+                let shift = T::constant(lambda / self.penalty);
+                let c = c(unknowns);
+                match kind {
+                    ConstraintKind::Equality => sqrt_mu * (c + shift),
+                    ConstraintKind::NonNegative => {
+                        let shortfall = shift - c;
+                        if shortfall > T::zero() {
+                            sqrt_mu * shortfall
+                        } else {
+                            T::zero()
+                        }
+                    }
+                }
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,97 @@
+use std::fmt;
+
+use ad_trait::forward_ad::adfn::adfn;
+use struct_to_array::StructToArray;
+
+use crate::{equation_system::EquationSystemBuilder, prelude::*};
+
+/// A Jacobian entry where the AD derivative and central finite differences disagree (see
+/// `EquationSystemBuilder::check_derivatives`).
+#[derive(Clone, Debug, PartialEq)]
+pub struct DerivativeMismatch {
+    pub fn_name: &'static str,
+    pub unknown: &'static str,
+    pub ad: f64,
+    pub finite_difference: f64,
+}
+
+impl DerivativeMismatch {
+    /// Discrepancy relative to the larger of the two derivatives (absolute below magnitude 1), as
+    /// compared against the tolerance.
+    pub fn error(&self) -> f64 {
+        relative_error(self.ad, self.finite_difference)
+    }
+}
+
+impl fmt::Display for DerivativeMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "d `{}` / d `{}`: AD gives {:.6e}, finite differences give {:.6e} (error {:.2e}); check the residual for conversions to f64 or constants that drop the derivative",
+            self.fn_name,
+            self.unknown,
+            self.ad,
+            self.finite_difference,
+            self.error()
+        )
+    }
+}
+
+fn relative_error(a: f64, b: f64) -> f64 {
+    (a - b).abs() / a.abs().max(b.abs()).max(1.0)
+}
+
+impl<G64, U64, Gadfn, Uadfn, S, const N: usize> EquationSystemBuilder<G64, U64, Gadfn, Uadfn, S, N>
+where
+    G64: GivenParamsFor<f64, N>,
+    U64: UnknownParamsFor<f64, N>,
+    Gadfn: GivenParamsFor<adfn<1>, N>,
+    Uadfn: UnknownParamsFor<adfn<1>, N>,
+{
+    /// Compares the AD Jacobian of the residual functions at `at` against central finite
+    /// differences, and returns the entries whose error (see `DerivativeMismatch::error`) exceeds
+    /// `tol`, in equation then unknown order.
+    ///
+    /// Catches residuals whose `adfn` version loses derivatives, e.g. through a hidden `.into()` to
+    /// f64 or a value read from a lookup table, which otherwise make solvers crawl or stall without
+    /// any error. The differences use steps of `cbrt(eps) * max(|u|, 1)`, so `tol` around `1e-5`
+    /// separates real mismatches from truncation error on smooth residuals. Entries whose finite
+    /// difference isn't finite (e.g. at a domain boundary) are skipped.
+    pub fn check_derivatives(&self, at: &U64, tol: f64) -> Vec<DerivativeMismatch> {
+        let fn_names = self.raw_res_fns.fn_names();
+        let unknowns = at.to_arr();
+        let (_values, jacobian) = self.raw_res_fn_engine.derivative(&unknowns);
+
+        let mut finite_differences = jacobian.clone();
+        for u in 0..N {
+            let h = f64::EPSILON.cbrt() * unknowns[u].abs().max(1.0);
+            let (mut plus, mut minus) = (unknowns, unknowns);
+            plus[u] += h;
+            minus[u] -= h;
+            let (r_plus, r_minus) = (
+                self.raw_res_fn_engine.call(&plus),
+                self.raw_res_fn_engine.call(&minus),
+            );
+            for e in 0..fn_names.len() {
+                finite_differences[(e, u)] = (r_plus[e] - r_minus[e]) / (plus[u] - minus[u]);
+            }
+        }
+
+        let mut mismatches = vec![];
+        for (e, &fn_name) in fn_names.iter().enumerate() {
+            for (u, &unknown) in self.unknown_field_names.iter().enumerate() {
+                let (ad, finite_difference) = (jacobian[(e, u)], finite_differences[(e, u)]);
+                let error = relative_error(ad, finite_difference);
+                if finite_difference.is_finite() && (error > tol || error.is_nan()) {
+                    mismatches.push(DerivativeMismatch {
+                        fn_name,
+                        unknown,
+                        ad,
+                        finite_difference,
+                    });
+                }
+            }
+        }
+        mismatches
+    }
+}
@@ -0,0 +1,228 @@
+use std::fmt;
+
+use nalgebra::{Dyn, Matrix, VecStorage};
+
+/// Coarse Dulmage–Mendelsohn decomposition of an equations × unknowns incidence matrix.
+///
+/// With rows and columns ordered (under, square, over), the incidence matrix is block upper triangular:
+/// - the *overdetermined* equations only involve overdetermined unknowns, and there are more of them than unknowns;
+/// - the *well-determined* (square) equations involve square and overdetermined unknowns only;
+/// - the *underdetermined* unknowns only appear in underdetermined equations, and there are more of
+///   them than equations.
+///
+/// So the overdetermined part is solved first (in a least-squares sense), then the square part
+/// block by block, and the underdetermined part last (in a minimum-norm sense). All indices refer
+/// to the original, unpermuted system.
+#[derive(Clone, Debug, Default)]
+pub struct DmDecomposition {
+    pub over_equations: Vec<usize>,
+    pub over_unknowns: Vec<usize>,
+    pub square_equations: Vec<usize>,
+    pub square_unknowns: Vec<usize>,
+    pub under_equations: Vec<usize>,
+    pub under_unknowns: Vec<usize>,
+}
+
+impl DmDecomposition {
+    /// True if every equation and unknown is in the well-determined part.
+    pub fn is_well_determined(&self) -> bool {
+        self.over_equations.is_empty()
+            && self.over_unknowns.is_empty()
+            && self.under_equations.is_empty()
+            && self.under_unknowns.is_empty()
+    }
+}
+
+/// The over- and underdetermined parts of a `DmDecomposition`, by name: what makes a system
+/// structurally ill-posed, and hence what to fix.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct StructuralDiagnosis {
+    /// Unknowns that the equations don't pin down: they only appear in `underdetermined_equations`,
+    /// which are fewer than them.
+    pub undetermined_unknowns: Vec<&'static str>,
+    pub underdetermined_equations: Vec<&'static str>,
+    /// Equations that compete for too few unknowns (`overdetermined_unknowns`), so that some of
+    /// them are redundant or conflicting.
+    pub over_constraining_equations: Vec<&'static str>,
+    pub overdetermined_unknowns: Vec<&'static str>,
+}
+
+impl StructuralDiagnosis {
+    /// Names the parts of `dm`, with `fn_names` and `unknown_names` indexed like the incidence
+    /// matrix's rows and columns.
+    pub fn from_decomposition(
+        dm: &DmDecomposition,
+        fn_names: &[&'static str],
+        unknown_names: &[&'static str],
+    ) -> Self {
+        let names = |idxs: &[usize], names: &[&'static str]| -> Vec<&'static str> {
+            idxs.iter().map(|&i| names[i]).collect()
+        };
+        Self {
+            undetermined_unknowns: names(&dm.under_unknowns, unknown_names),
+            underdetermined_equations: names(&dm.under_equations, fn_names),
+            over_constraining_equations: names(&dm.over_equations, fn_names),
+            overdetermined_unknowns: names(&dm.over_unknowns, unknown_names),
+        }
+    }
+
+    /// True if every equation and unknown is in the well-determined part.
+    pub fn is_well_posed(&self) -> bool {
+        self.undetermined_unknowns.is_empty() && self.over_constraining_equations.is_empty()
+    }
+
+    /// How many equations the underdetermined part lacks.
+    pub fn n_missing_equations(&self) -> usize {
+        self.undetermined_unknowns
+            .len()
+            .saturating_sub(self.underdetermined_equations.len())
+    }
+
+    /// How many equations of the overdetermined part are redundant (or conflicting).
+    pub fn n_redundant_equations(&self) -> usize {
+        self.over_constraining_equations
+            .len()
+            .saturating_sub(self.overdetermined_unknowns.len())
+    }
+}
+
+impl fmt::Display for StructuralDiagnosis {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_well_posed() {
+            return write!(
+                f,
+                "every equation and unknown is structurally well-determined"
+            );
+        }
+        let mut parts = vec![];
+        if !self.undetermined_unknowns.is_empty() {
+            parts.push(format!(
+                "unknowns {:?} are structurally undetermined: they only appear in {:?}, which is {} equation(s) short. Add residuals that involve them, or make some of them givens",
+                self.undetermined_unknowns,
+                self.underdetermined_equations,
+                self.n_missing_equations()
+            ));
+        }
+        if !self.over_constraining_equations.is_empty() {
+            parts.push(format!(
+                "equations {:?} over-constrain {:?}: {} of them are redundant or conflicting. Remove residuals, or free up givens they involve as unknowns",
+                self.over_constraining_equations,
+                self.overdetermined_unknowns,
+                self.n_redundant_equations()
+            ));
+        }
+        write!(f, "{}", parts.join("; "))
+    }
+}
+
+/// Computes the coarse Dulmage–Mendelsohn decomposition of `incidence`, where any nonzero
+/// (including NaN) entry marks an equation-unknown dependency.
+pub fn dulmage_mendelsohn(
+    incidence: &Matrix<f32, Dyn, Dyn, VecStorage<f32, Dyn, Dyn>>,
+) -> DmDecomposition {
+    let (n_rows, n_cols) = incidence.shape();
+    let row_adj: Vec<Vec<usize>> = (0..n_rows)
+        .map(|r| (0..n_cols).filter(|&c| incidence[(r, c)] != 0.0).collect())
+        .collect();
+    let col_adj: Vec<Vec<usize>> = (0..n_cols)
+        .map(|c| (0..n_rows).filter(|&r| incidence[(r, c)] != 0.0).collect())
+        .collect();
+
+    let (row_match, col_match) = maximum_matching(&row_adj, n_cols);
+
+    // Overdetermined part: everything reachable from an unmatched row by alternating paths
+    // (any edge from a row, matched edges back from a column).
+    let mut over_row = vec![false; n_rows];
+    let mut over_col = vec![false; n_cols];
+    let mut stack: Vec<usize> = (0..n_rows).filter(|&r| row_match[r].is_none()).collect();
+    for &r in &stack {
+        over_row[r] = true;
+    }
+    while let Some(r) = stack.pop() {
+        for &c in &row_adj[r] {
+            if !over_col[c] {
+                over_col[c] = true;
+                if let Some(r2) = col_match[c].filter(|&r2| !over_row[r2]) {
+                    over_row[r2] = true;
+                    stack.push(r2);
+                }
+            }
+        }
+    }
+
+    // Underdetermined part: the same, starting from unmatched columns.
+    let mut under_row = vec![false; n_rows];
+    let mut under_col = vec![false; n_cols];
+    let mut stack: Vec<usize> = (0..n_cols).filter(|&c| col_match[c].is_none()).collect();
+    for &c in &stack {
+        under_col[c] = true;
+    }
+    while let Some(c) = stack.pop() {
+        for &r in &col_adj[c] {
+            if !under_row[r] {
+                under_row[r] = true;
+                if let Some(c2) = row_match[r].filter(|&c2| !under_col[c2]) {
+                    under_col[c2] = true;
+                    stack.push(c2);
+                }
+            }
+        }
+    }
+
+    let mut dm = DmDecomposition::default();
+    for (r, (&over, &under)) in over_row.iter().zip(&under_row).enumerate() {
+        match (over, under) {
+            (true, _) => dm.over_equations.push(r),
+            (false, true) => dm.under_equations.push(r),
+            (false, false) => dm.square_equations.push(r),
+        }
+    }
+    for (c, (&over, &under)) in over_col.iter().zip(&under_col).enumerate() {
+        match (over, under) {
+            (true, _) => dm.over_unknowns.push(c),
+            (false, true) => dm.under_unknowns.push(c),
+            (false, false) => dm.square_unknowns.push(c),
+        }
+    }
+    dm
+}
+
+/// Maximum bipartite matching of rows to columns by augmenting paths. Returns each row's matched
+/// column and each column's matched row.
+fn maximum_matching(
+    row_adj: &[Vec<usize>],
+    n_cols: usize,
+) -> (Vec<Option<usize>>, Vec<Option<usize>>) {
+    fn augment(
+        r: usize,
+        row_adj: &[Vec<usize>],
+        visited: &mut [bool],
+        row_match: &mut [Option<usize>],
+        col_match: &mut [Option<usize>],
+    ) -> bool {
+        for &c in &row_adj[r] {
+            if visited[c] {
+                continue;
+            }
+            visited[c] = true;
+            let free = match col_match[c] {
+                None => true,
+                Some(r2) => augment(r2, row_adj, visited, row_match, col_match),
+            };
+            if free {
+                row_match[r] = Some(c);
+                col_match[c] = Some(r);
+                return true;
+            }
+        }
+        false
+    }
+
+    let mut row_match = vec![None; row_adj.len()];
+    let mut col_match = vec![None; n_cols];
+    for r in 0..row_adj.len() {
+        let mut visited = vec![false; n_cols];
+        augment(r, row_adj, &mut visited, &mut row_match, &mut col_match);
+    }
+    (row_match, col_match)
+}
@@ -0,0 +1,395 @@
+use std::time::Duration;
+
+use ad_trait::{
+    AD,
+    differentiable_function::{DifferentiableFunctionTrait, ForwardAD},
+    forward_ad::adfn::adfn,
+    function_engine::FunctionEngine,
+};
+use nalgebra::{DMatrix, DVector};
+
+use crate::{
+    equation_system::{
+        EqSysSolutionPlan, EqSysStateInit,
+        logging::solver_info,
+        shared::shared_fn,
+        solution_plan::SolutionBlock,
+        solve_options::TimeBudget,
+        sub_problem::solve_subproblem::{
+            block_optimizer::{BlockObjective, BlockOptimizer},
+            convergence::ConvergenceCriteria,
+            external::ExternalOptimizer,
+            levenberg_marquardt::LevenbergMarquardt,
+            min_norm::PseudoInverseGaussNewton,
+            small_newton::SmallNewton,
+        },
+    },
+    prelude::*,
+};
+
+/// A residual function over a runtime-sized unknowns slice, in both f64 and adfn<1> forms.
+///
+/// Givens are captured by the closures. Unknowns are indexed in the order of the builder's `unknown_names`.
+#[derive(Clone)]
+pub struct DynResidualFn {
+    pub name: String,
+    f64: shared_fn!(Fn(&[f64]) -> f64),
+    adfn_1: shared_fn!(Fn(&[adfn<1>]) -> adfn<1>),
+}
+
+impl DynResidualFn {
+    pub fn new(
+        name: impl Into<String>,
+        f64: impl Fn(&[f64]) -> f64 + MaybeSendSync + 'static,
+        adfn_1: impl Fn(&[adfn<1>]) -> adfn<1> + MaybeSendSync + 'static,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            f64: Shared::new(f64),
+            adfn_1: Shared::new(adfn_1),
+        }
+    }
+}
+
+/// All residual functions of a `DynEquationSystemBuilder`, evaluated on the full unknowns vector.
+#[derive(Clone)]
+pub struct DynResiduals<T: AD> {
+    fns: Vec<shared_fn!(Fn(&[T]) -> T)>,
+    n_unknowns: usize,
+}
+
+impl<T: AD> DifferentiableFunctionTrait<T> for DynResiduals<T> {
+    const NAME: &'static str = "DynResiduals";
+
+    fn call(&self, inputs: &[T], _freeze: bool) -> Vec<T> {
+        self.fns.iter().map(|f| f(inputs)).collect()
+    }
+
+    fn num_inputs(&self) -> usize {
+        self.n_unknowns
+    }
+
+    fn num_outputs(&self) -> usize {
+        self.fns.len()
+    }
+}
+
+/// Counterpart of `EquationSystemBuilder` for systems whose number of unknowns is only known at runtime.
+///
+/// Unknowns are `DVector<f64>`s with a runtime list of field names instead of `StructToArray`
+/// structs, and residual functions are closures (see `DynResidualFn`). The system is triangularized
+/// the same way; blocks are solved in model space with the crate's native block optimizers (no
+/// param scaling).
+pub struct DynEquationSystemBuilder<S> {
+    residual_fns: Vec<DynResidualFn>,
+    unknown_names: Vec<String>,
+    res_fn_engine: FunctionEngine<DynResiduals<f64>, DynResiduals<adfn<1>>, ForwardAD>,
+    state: S,
+}
+
+impl DynEquationSystemBuilder<()> {
+    pub fn new(
+        residual_fns: Vec<DynResidualFn>,
+        unknown_names: Vec<String>,
+    ) -> DynEquationSystemBuilder<EqSysStateInit> {
+        let n_unknowns = unknown_names.len();
+        let residuals_f64 = DynResiduals {
+            fns: residual_fns.iter().map(|f| f.f64.clone()).collect(),
+            n_unknowns,
+        };
+        let residuals_adfn = DynResiduals {
+            fns: residual_fns.iter().map(|f| f.adfn_1.clone()).collect(),
+            n_unknowns,
+        };
+
+        DynEquationSystemBuilder {
+            residual_fns,
+            unknown_names,
+            res_fn_engine: FunctionEngine::new(residuals_f64, residuals_adfn, ForwardAD::new()),
+            state: EqSysStateInit {},
+        }
+    }
+}
+
+impl<S> DynEquationSystemBuilder<S> {
+    pub fn unknown_names(&self) -> &[String] {
+        &self.unknown_names
+    }
+
+    pub fn residual_names(&self) -> Vec<&str> {
+        self.residual_fns.iter().map(|f| f.name.as_str()).collect()
+    }
+
+    /// Raw residuals of every equation at `unknowns`, in registration order.
+    pub fn residuals_at(&self, unknowns: &DVector<f64>) -> Result<DVector<f64>, EqSysError> {
+        self.check_unknowns_len(unknowns)?;
+        Ok(DVector::from_vec(
+            self.res_fn_engine.call(unknowns.as_slice()),
+        ))
+    }
+
+    fn check_unknowns_len(&self, unknowns: &DVector<f64>) -> Result<(), EqSysError> {
+        if unknowns.len() != self.unknown_names.len() {
+            return Err(EqSysError::UnknownsLenMismatch {
+                expected: self.unknown_names.len(),
+                got: unknowns.len(),
+            });
+        }
+        Ok(())
+    }
+}
+
+impl DynEquationSystemBuilder<EqSysStateInit> {
+    pub fn with_triangularization(
+        self,
+        initial_unknowns: &DVector<f64>,
+    ) -> Result<DynEquationSystemBuilder<EqSysSolutionPlan>, EqSysError> {
+        self.check_unknowns_len(initial_unknowns)?;
+        let (_val_all, grad_all) = self.res_fn_engine.derivative(initial_unknowns.as_slice());
+
+        Ok(DynEquationSystemBuilder {
+            residual_fns: self.residual_fns,
+            unknown_names: self.unknown_names,
+            res_fn_engine: self.res_fn_engine,
+            state: EqSysSolutionPlan::from_jacobian(grad_all, &ZeroThreshold::default()),
+        })
+    }
+}
+
+impl DynEquationSystemBuilder<EqSysSolutionPlan> {
+    pub fn solution_plan(&self) -> &SolutionPlan {
+        &self.state.solution_plan
+    }
+
+    pub fn print_solution_plan(&self) {
+        let fn_names: Vec<&str> = self.residual_fns.iter().map(|f| f.name.as_str()).collect();
+        print!(
+            "{}",
+            self.state
+                .solution_plan
+                .with_names(&fn_names, &self.unknown_names)
+        );
+    }
+
+    pub fn solve_system(
+        &self,
+        initial_unknowns: &DVector<f64>,
+    ) -> Result<DVector<f64>, EqSysError> {
+        self.solve_system_with_options(initial_unknowns, &SolveOptions::default())
+    }
+
+    /// Solves the system block by block, like `EquationSystemBuilder::solve_system_with_options`.
+    ///
+    /// Honors the time limits, convergence criteria, external solver, `analytic_small_blocks` and
+    /// `max_extra_sweeps` settings of `options`; the remaining settings only apply to the
+    /// const-generic builder.
+    pub fn solve_system_with_options(
+        &self,
+        initial_unknowns: &DVector<f64>,
+        options: &SolveOptions,
+    ) -> Result<DVector<f64>, EqSysError> {
+        self.check_unknowns_len(initial_unknowns)?;
+        let budget = TimeBudget::start(options);
+        let mut current_unknowns = initial_unknowns.clone();
+        let mut prev_residual_norm = f64::INFINITY;
+
+        for sweep in 0..=options.max_extra_sweeps {
+            let sweep_start_unknowns = current_unknowns.clone();
+
+            for block in &self.state.solution_plan.blocks {
+                if budget.is_exhausted() {
+                    solver_info!(
+                        options.verbosity,
+                        ">>>>> Time budget exhausted before sub-problem {}; returning best params so far",
+                        block.block_idx
+                    );
+                    return Ok(current_unknowns);
+                }
+                current_unknowns = self.solve_block(block, &current_unknowns, options, &budget)?;
+            }
+
+            let residual_norm = self.residuals_at(&current_unknowns)?.norm();
+            let improved = residual_norm < prev_residual_norm;
+            if !improved {
+                let got_worse = residual_norm.is_nan() || residual_norm > prev_residual_norm;
+                if sweep > 0 && got_worse {
+                    current_unknowns = sweep_start_unknowns;
+                }
+                break;
+            }
+            prev_residual_norm = residual_norm;
+            if residual_norm == 0.0 {
+                break;
+            }
+        }
+
+        Ok(current_unknowns)
+    }
+
+    /// Solves one block with the other unknowns held at `current_unknowns`, returning the updated full unknowns vector.
+    fn solve_block(
+        &self,
+        block: &SolutionBlock,
+        current_unknowns: &DVector<f64>,
+        options: &SolveOptions,
+        budget: &TimeBudget,
+    ) -> Result<DVector<f64>, EqSysError> {
+        let problem = DynBlockProblem {
+            system: self,
+            block,
+            current_unknowns,
+            convergence: &options.convergence,
+            timeout: budget.block_timeout(),
+        };
+
+        let mut optimizers: Vec<Box<dyn BlockOptimizer + '_>> = vec![];
+        if !block.is_square() {
+            optimizers.push(Box::new(PseudoInverseGaussNewton::default()));
+        } else {
+            if let Some(solver) = &options.external_solver {
+                optimizers.push(Box::new(ExternalOptimizer(solver.as_ref())));
+            }
+            if options.analytic_small_blocks && block.unknown_idxs.len() <= 2 {
+                optimizers.push(Box::new(SmallNewton::default()));
+            }
+            optimizers.push(Box::new(LevenbergMarquardt::default()));
+        }
+
+        let mut last_err = None;
+        for optimizer in &optimizers {
+            match optimizer.optimize(&problem) {
+                Ok(optimum) => {
+                    solver_info!(
+                        options.verbosity,
+                        block_idx = block.block_idx,
+                        solver = optimizer.name(),
+                        iterations = optimum.iterations,
+                        residual_norm = optimum.residual_norm,
+                        "Block {} ({}): {:?} after {} iterations; residual norm {:.6e}",
+                        block.block_idx,
+                        optimizer.name(),
+                        optimum.termination,
+                        optimum.iterations,
+                        optimum.residual_norm
+                    );
+                    return Ok(problem.full_unknowns(&optimum.params));
+                }
+                Err(e) => {
+                    solver_info!(
+                        options.verbosity,
+                        ">>>>> {} failed for block {}: {:?}",
+                        optimizer.name(),
+                        block.block_idx,
+                        e
+                    );
+                    last_err = Some(e);
+                }
+            }
+        }
+        Err(last_err.expect("at least one block optimizer is always tried"))
+    }
+}
+
+/// One block of a `DynEquationSystemBuilder` as a `BlockObjective`, in model space.
+struct DynBlockProblem<'a> {
+    system: &'a DynEquationSystemBuilder<EqSysSolutionPlan>,
+    block: &'a SolutionBlock,
+    current_unknowns: &'a DVector<f64>,
+    convergence: &'a ConvergenceCriteria,
+    timeout: Option<Duration>,
+}
+
+impl DynBlockProblem<'_> {
+    /// Patches block params into the current full unknowns vector.
+    fn full_unknowns(&self, p: &DVector<f64>) -> DVector<f64> {
+        let mut full = self.current_unknowns.clone();
+        for (i, &idx) in self.block.unknown_idxs.iter().enumerate() {
+            full[idx] = p[i];
+        }
+        full
+    }
+
+    fn check_len(&self, p: &DVector<f64>) -> Result<(), EqSysError> {
+        if p.len() != self.block.unknown_idxs.len() {
+            return Err(EqSysError::SubProblemParamLenMismatch {
+                n_params: p.len(),
+                n_unknowns: self.block.unknown_idxs.len(),
+            });
+        }
+        Ok(())
+    }
+}
+
+impl BlockObjective for DynBlockProblem<'_> {
+    fn block_idx(&self) -> usize {
+        self.block.block_idx
+    }
+
+    fn initial_params(&self) -> DVector<f64> {
+        DVector::from_iterator(
+            self.block.unknown_idxs.len(),
+            self.block
+                .unknown_idxs
+                .iter()
+                .map(|&idx| self.current_unknowns[idx]),
+        )
+    }
+
+    fn residuals(&self, p: &DVector<f64>) -> Result<DVector<f64>, EqSysError> {
+        self.check_len(p)?;
+        let all = self
+            .system
+            .res_fn_engine
+            .call(self.full_unknowns(p).as_slice());
+        Ok(DVector::from_iterator(
+            self.block.equation_idxs.len(),
+            self.block.equation_idxs.iter().map(|&e| all[e]),
+        ))
+    }
+
+    fn jacobian(&self, p: &DVector<f64>) -> Result<DMatrix<f64>, EqSysError> {
+        self.check_len(p)?;
+        let (_values, full_jacobian) = self
+            .system
+            .res_fn_engine
+            .derivative(self.full_unknowns(p).as_slice());
+        Ok(DMatrix::from_fn(
+            self.block.equation_idxs.len(),
+            self.block.unknown_idxs.len(),
+            |i, j| full_jacobian[(self.block.equation_idxs[i], self.block.unknown_idxs[j])],
+        ))
+    }
+
+    fn converged(&self, p: &DVector<f64>) -> Result<bool, EqSysError> {
+        let criteria = self.convergence;
+        if criteria.is_empty() {
+            return Ok(false);
+        }
+        let residuals = self.residuals(p)?;
+
+        // Comparisons are written so that NaN residuals never count as converged.
+        if let Some(tol) = criteria.max_abs_residual {
+            if !residuals.iter().all(|r| r.abs() < tol) {
+                return Ok(false);
+            }
+        }
+        if let Some(tol) = criteria.max_cost {
+            let cost = residuals.norm_squared();
+            if cost.is_nan() || cost >= tol {
+                return Ok(false);
+            }
+        }
+        if let Some(tol) = criteria.max_grad_norm {
+            // Gradient of the sum of squared residuals.
+            let grad_norm = (self.jacobian(p)?.transpose() * &residuals * 2.0).norm();
+            if grad_norm.is_nan() || grad_norm >= tol {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    fn timeout(&self) -> Option<Duration> {
+        self.timeout
+    }
+}
@@ -0,0 +1,82 @@
+use std::fmt::Write;
+
+use ad_trait::forward_ad::adfn::adfn;
+
+use crate::{
+    equation_system::{EqSysSolutionPlan, EquationSystemBuilder},
+    prelude::*,
+};
+
+/// `name` as a quoted DOT identifier.
+fn dot_quoted(name: &str) -> String {
+    format!("\"{}\"", name.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+impl<G64, U64, Gadfn, Uadfn, const N: usize>
+    EquationSystemBuilder<G64, U64, Gadfn, Uadfn, EqSysSolutionPlan, N>
+where
+    G64: GivenParamsFor<f64, N>,
+    U64: UnknownParamsFor<f64, N>,
+    Gadfn: GivenParamsFor<adfn<1>, N>,
+    Uadfn: UnknownParamsFor<adfn<1>, N>,
+{
+    /// The equation-unknown dependency graph as a Graphviz DOT document: equations are boxes,
+    /// unknowns ellipses, and each `SolutionBlock` of the plan a cluster, with an edge from each
+    /// equation to each unknown it depends on (dashed where the sampled derivative wasn't finite).
+    ///
+    /// Edges between clusters show why blocks are solved in the order they are; render with e.g. `dot -Tsvg`.
+    pub fn export_dependency_graph_dot(&self) -> String {
+        let fn_names = self.raw_res_fns.fn_names();
+        let mut dot = String::from("digraph dependencies {\n    rankdir=LR;\n");
+
+        let mut placed = vec![false; N];
+        for block in &self.state.solution_plan.blocks {
+            writeln!(dot, "    subgraph cluster_block_{} {{", block.block_idx).unwrap();
+            writeln!(dot, "        label=\"block {}\";", block.block_idx).unwrap();
+            for &e in &block.equation_idxs {
+                writeln!(
+                    dot,
+                    "        e{} [label={}, shape=box];",
+                    e,
+                    dot_quoted(fn_names[e])
+                )
+                .unwrap();
+            }
+            for &u in &block.unknown_idxs {
+                placed[u] = true;
+                writeln!(
+                    dot,
+                    "        u{} [label={}, shape=ellipse];",
+                    u,
+                    dot_quoted(self.unknown_field_names[u])
+                )
+                .unwrap();
+            }
+            dot.push_str("    }\n");
+        }
+        // unknowns that no block solves for (e.g. ones no equation depends on)
+        for (u, _) in placed.iter().enumerate().filter(|(_, placed)| !**placed) {
+            writeln!(
+                dot,
+                "    u{} [label={}, shape=ellipse, style=dotted];",
+                u,
+                dot_quoted(self.unknown_field_names[u])
+            )
+            .unwrap();
+        }
+
+        let binary_matrix = &self.state.binary_matrix;
+        for e in 0..binary_matrix.nrows() {
+            for u in 0..binary_matrix.ncols() {
+                let x = binary_matrix[(e, u)];
+                if x.is_nan() {
+                    writeln!(dot, "    e{} -> u{} [style=dashed];", e, u).unwrap();
+                } else if x != 0.0 {
+                    writeln!(dot, "    e{} -> u{};", e, u).unwrap();
+                }
+            }
+        }
+        dot.push_str("}\n");
+        dot
+    }
+}
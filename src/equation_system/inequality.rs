@@ -0,0 +1,143 @@
+use ad_trait::{AD, forward_ad::adfn::adfn};
+use nalgebra::ComplexField;
+
+use crate::prelude::*;
+
+/// How a violated one-sided residual `g > 0` is turned into a penalty residual, whose square is added to the objective.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum InequalityPenalty {
+    /// `max(0, g)`, i.e. the penalty `max(0, g)^2`. Exactly zero while the inequality holds, and
+    /// continuously differentiable.
+    #[default]
+    ReluSquared,
+    /// `softplus(g) = ln(1 + exp(sharpness * g)) / sharpness`.
+    ///
+    /// Smooth everywhere, but slightly positive even while the inequality holds, which nudges `g` a
+    /// little below 0; larger `sharpness` approaches `ReluSquared`.
+    Softplus { sharpness: f64 },
+}
+
+impl InequalityPenalty {
+    /// The penalty residual for the one-sided residual value `g`.
+    pub fn penalty_residual<T: AD>(&self, g: T) -> T {
+        match *self {
+            InequalityPenalty::ReluSquared => {
+                if g > T::zero() {
+                    g
+                } else {
+                    T::zero()
+                }
+            }
+            InequalityPenalty::Softplus { sharpness } => {
+                let k = T::constant(sharpness);
+                let z = k * g;
+                // ln(1 + e^z) = max(z, 0) + ln(1 + e^-|z|), which doesn't overflow for large z
+                let pos = if z > T::zero() { z } else { T::zero() };
+                (pos + ComplexField::ln(T::one() + ComplexField::exp(-z.abs()))) / k
+            }
+        }
+    }
+}
+
+/// One-sided residuals `g(givens, unknowns) <= 0`, e.g. "the jump apex must be at least 3m" as `3.0 - apex <= 0`.
+///
+/// They don't take part in the triangularization; instead every sub-problem objective gets one
+/// penalty residual `sqrt(weight) * penalty(g)` per inequality (see `InequalityPenalty`). Build
+/// them with the same macros as ordinary residuals, e.g.
+/// `InequalityResiduals::new(residual_fns_for_generic_params!(...))`.
+#[derive(Clone)]
+pub struct InequalityResiduals<G64, U64, Gadfn, Uadfn> {
+    fns: ResidualFns<G64, U64, Gadfn, Uadfn>,
+    pub penalty: InequalityPenalty,
+    /// Weight of the penalty terms relative to the (squared) equality residuals.
+    pub weight: f64,
+}
+
+impl<G64, U64, Gadfn, Uadfn> InequalityResiduals<G64, U64, Gadfn, Uadfn> {
+    pub fn new(fns: ResidualFns<G64, U64, Gadfn, Uadfn>) -> Self {
+        Self {
+            fns,
+            penalty: InequalityPenalty::default(),
+            weight: 1.0,
+        }
+    }
+
+    pub fn with_penalty(mut self, penalty: InequalityPenalty) -> Self {
+        self.penalty = penalty;
+        self
+    }
+
+    pub fn with_weight(mut self, weight: f64) -> Self {
+        self.weight = weight;
+        self
+    }
+
+    pub fn fns(&self) -> &ResidualFns<G64, U64, Gadfn, Uadfn> {
+        &self.fns
+    }
+
+    pub fn len(&self) -> usize {
+        self.fns.f64().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.fns.f64().is_empty()
+    }
+
+    /// Evaluates every one-sided residual `g` at `givens` and `unknowns`.
+    pub fn values(&self, givens: &G64, unknowns: &U64) -> Vec<f64> {
+        self.fns.f64().iter().map(|g| g(givens, unknowns)).collect()
+    }
+
+    /// The largest violation `max(0, g)` at `givens` and `unknowns`.
+    pub fn max_violation(&self, givens: &G64, unknowns: &U64) -> f64 {
+        self.values(givens, unknowns)
+            .into_iter()
+            .fold(0.0, f64::max)
+    }
+
+    /// Penalty terms for f64 objectives.
+    pub fn penalty_f64(&self) -> InequalityPenaltyFns<f64, G64, U64> {
+        InequalityPenaltyFns {
+            fns: self.fns.f64().clone(),
+            penalty: self.penalty,
+            weight: self.weight,
+        }
+    }
+
+    /// Penalty terms for adfn<1> objectives.
+    pub fn penalty_adfn(&self) -> InequalityPenaltyFns<adfn<1>, Gadfn, Uadfn> {
+        InequalityPenaltyFns {
+            fns: self.fns.adfn_1().clone(),
+            penalty: self.penalty,
+            weight: self.weight,
+        }
+    }
+}
+
+/// Penalty residuals of `InequalityResiduals` for one AD type.
+#[derive(Clone)]
+pub struct InequalityPenaltyFns<T: AD, G, U> {
+    fns: Vec<ResidualFn<G, U, T>>,
+    penalty: InequalityPenalty,
+    weight: f64,
+}
+
+impl<T: AD, G, U> InequalityPenaltyFns<T, G, U> {
+    pub fn len(&self) -> usize {
+        self.fns.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.fns.is_empty()
+    }
+
+    /// One penalty residual per inequality, evaluated on model-space unknowns.
+    pub fn penalty_residuals(&self, givens: &G, unknowns: &U) -> Vec<T> {
+        let sqrt_weight = T::constant(self.weight.sqrt());
+        self.fns
+            .iter()
+            .map(|g| sqrt_weight * self.penalty.penalty_residual(g(givens, unknowns)))
+            .collect()
+    }
+}
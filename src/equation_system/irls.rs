@@ -0,0 +1,123 @@
+#[cfg(feature = "argmin")]
+use ad_trait::forward_ad::adfn::adfn;
+#[cfg(feature = "argmin")]
+use struct_to_array::{StructToArray, StructToVec};
+
+#[cfg(feature = "argmin")]
+use crate::{
+    equation_system::{EqSysSolutionPlan, EquationSystemBuilder},
+    prelude::*,
+};
+
+/// Robust loss whose minimization `solve_sub_problem_irls` approximates by reweighted least
+/// squares. `k` and `c` are in the units of the (weighted) residuals.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RobustLoss {
+    /// Quadratic for `|r| <= k`, linear beyond.
+    Huber { k: f64 },
+    /// `c^2 / 2 * ln(1 + (r / c)^2)`, which grows only logarithmically, so gross outliers barely pull on the solution.
+    Cauchy { c: f64 },
+}
+
+impl RobustLoss {
+    /// The IRLS weight `psi(r) / r` of a residual `r`, in `(0, 1]`.
+    pub fn weight(&self, r: f64) -> f64 {
+        match *self {
+            RobustLoss::Huber { k } => {
+                if r.abs() <= k {
+                    1.0
+                } else {
+                    k / r.abs()
+                }
+            }
+            RobustLoss::Cauchy { c } => 1.0 / (1.0 + (r / c).powi(2)),
+        }
+    }
+}
+
+/// Settings of the IRLS driver (see `EquationSystemBuilder::solve_sub_problem_irls`).
+#[derive(Clone, Debug)]
+pub struct IrlsConfig {
+    pub loss: RobustLoss,
+    /// Maximum number of reweighted Gauss-Newton solves.
+    pub max_outer_iters: usize,
+    /// Stop once no unknown of the block moves by more than `tol * max(|u|, 1)` between solves.
+    pub tol: f64,
+}
+
+impl Default for IrlsConfig {
+    fn default() -> Self {
+        Self {
+            loss: RobustLoss::Huber { k: 1.345 },
+            max_outer_iters: 10,
+            tol: 1e-8,
+        }
+    }
+}
+
+#[cfg(feature = "argmin")]
+impl<G64, U64, Gadfn, Uadfn, const N: usize>
+    EquationSystemBuilder<G64, U64, Gadfn, Uadfn, EqSysSolutionPlan, N>
+where
+    G64: GivenParamsFor<f64, N>,
+    U64: UnknownParamsFor<f64, N>,
+    Gadfn: GivenParamsFor<adfn<1>, N>,
+    Uadfn: UnknownParamsFor<adfn<1>, N>,
+{
+    /// Solves a single sub-problem under `cfg.loss` by iteratively reweighted least squares:
+    /// Gauss-Newton solves with `ResidTransScaledL2` losses, whose scales are re-derived from the
+    /// robust weights of the residuals at each solution.
+    ///
+    /// This gives the outlier tolerance of a robust loss while keeping the fast Gauss-Newton inner
+    /// solver. Residuals are weighted by their `with_residual_weights` weights before the robust
+    /// weights are computed.
+    pub fn solve_sub_problem_irls(
+        &self,
+        block: &SolutionBlock,
+        initial_unknowns: &U64,
+        cfg: &IrlsConfig,
+    ) -> Result<U64, EqSysError> {
+        let mut current_unknowns = *initial_unknowns;
+        let mut robust_weights = vec![1.0; block.equation_idxs.len()];
+        for _ in 0..cfg.max_outer_iters {
+            let loss_gen =
+                ResidTransScaledL2::new(robust_weights.iter().map(|w| 1.0 / w).collect());
+            let next_unknowns = self
+                .gauss_newton_sub_problem_with_loss(block, &current_unknowns, &[], loss_gen)?
+                .solve_gauss_newton()?;
+
+            robust_weights = self
+                .block_weighted_residuals(block, &next_unknowns)
+                .iter()
+                .map(|&r| cfg.loss.weight(r))
+                .collect();
+
+            let (prev, next) = (current_unknowns.to_arr(), next_unknowns.to_arr());
+            let converged = block
+                .unknown_idxs
+                .iter()
+                .all(|&u| (next[u] - prev[u]).abs() <= cfg.tol * prev[u].abs().max(1.0));
+            current_unknowns = next_unknowns;
+            if converged {
+                break;
+            }
+        }
+        Ok(current_unknowns)
+    }
+
+    /// The residuals of `block` at `unknowns`, in block order, multiplied by their `with_residual_weights` weights.
+    fn block_weighted_residuals(&self, block: &SolutionBlock, unknowns: &U64) -> Vec<f64> {
+        let residuals = self.raw_res_fn_engine.call(&unknowns.to_vec());
+        block
+            .equation_idxs
+            .iter()
+            .map(|&eq_idx| {
+                let weight = self
+                    .residual_scale_weights
+                    .as_ref()
+                    .map_or(1.0, |weights| weights[eq_idx]);
+                weight * residuals[eq_idx]
+            })
+            .collect()
+    }
+}
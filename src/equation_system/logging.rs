@@ -0,0 +1,59 @@
+//! Progress output of the solvers, gated by `SolveOptions::verbosity`: human-readable lines on
+//! stdout by default, and `tracing` spans and events with the `tracing` feature, so that embedding
+//! applications (e.g. GUIs) can route, filter or drop it instead of getting stdout spam.
+//!
+//! Leading `field = value` pairs of the event macros become structured fields of the `tracing`
+//! event, and are left out of the stdout line.
+
+/// Info-level progress message, emitted unless the verbosity is `Verbosity::Quiet`.
+macro_rules! solver_info {
+    ($verbosity:expr, $($field:ident = $value:expr,)* $fmt:literal $(, $arg:expr)* $(,)?) => {
+        if $verbosity >= $crate::equation_system::solve_options::Verbosity::Normal {
+            #[cfg(feature = "tracing")]
+            ::tracing::info!($($field = $value,)* $fmt $(, $arg)*);
+            #[cfg(not(feature = "tracing"))]
+            {
+                $(let _ = &$value;)*
+                println!($fmt $(, $arg)*);
+            }
+        }
+    };
+}
+
+/// Debug-level progress message (initial params, Jacobians), emitted only at `Verbosity::Debug`.
+macro_rules! solver_debug {
+    ($verbosity:expr, $($field:ident = $value:expr,)* $fmt:literal $(, $arg:expr)* $(,)?) => {
+        if $verbosity >= $crate::equation_system::solve_options::Verbosity::Debug {
+            #[cfg(feature = "tracing")]
+            ::tracing::debug!($($field = $value,)* $fmt $(, $arg)*);
+            #[cfg(not(feature = "tracing"))]
+            {
+                $(let _ = &$value;)*
+                println!($fmt $(, $arg)*);
+            }
+        }
+    };
+}
+
+/// Enters an info-level `tracing` span with the given fields (e.g. the block index) until the
+/// returned guard drops; a no-op without the `tracing` feature.
+macro_rules! solver_span {
+    ($name:literal $(, $field:ident = $value:expr)* $(,)?) => {{
+        #[cfg(feature = "tracing")]
+        let guard = ::tracing::info_span!($name $(, $field = $value)*).entered();
+        #[cfg(not(feature = "tracing"))]
+        let guard = {
+            $(let _ = &$value;)*
+            $crate::equation_system::logging::NoSpan
+        };
+        guard
+    }};
+}
+
+/// Stand-in for the span guard of `solver_span!` without the `tracing` feature.
+#[cfg(not(feature = "tracing"))]
+pub(crate) struct NoSpan;
+
+pub(crate) use solver_debug;
+pub(crate) use solver_info;
+pub(crate) use solver_span;
@@ -1,27 +1,81 @@
+#[cfg(feature = "argmin")]
+use crate::prelude::solve_subproblem::simulated_annealing::SimulatedAnnealingConfig;
+#[cfg(feature = "sparse")]
+use crate::prelude::solve_subproblem::sparse::JacobianSparsity;
 use crate::{
     equation_system::{
+        block_sub_problems::{BlockEngineCache, BlockSubProblems},
+        clock::Instant,
+        logging::{solver_info, solver_span},
         solution_plan::{SolutionBlock, SolutionPlan},
+        solve_options::{SolveContext, TimeBudget},
+        solve_report::SolveLog,
         sub_problem::SubProblem,
     },
-    prelude::{solve_subproblem::simulated_annealing::SimulatedAnnealingConfig, *},
+    prelude::{
+        solve_subproblem::{
+            external::ExternalSolver, restart::RestartPolicy, solver_run_log_data::SolverRunLogData,
+        },
+        *,
+    },
 };
 use ad_trait::{
-    differentiable_function::ForwardAD, forward_ad::adfn::adfn, function_engine::FunctionEngine,
+    differentiable_function::{ForwardAD, ForwardADMulti},
+    forward_ad::adfn::adfn,
+    function_engine::FunctionEngine,
 };
 
+use field_names_and_counts::FieldNames;
+use indexmap::IndexMap;
 use nalgebra::{Dyn, Matrix, PermutationSequence, VecStorage};
 use nalgebra_block_triangularization::{
     LowerBtfStructure, lower_block_triangular_structure, lower_triangular_permutations,
 };
-use struct_to_array::StructToVec;
+use rand::{SeedableRng, rngs::StdRng};
+use std::fmt;
+use struct_to_array::{StructToArray, StructToVec};
 
+mod block_sub_problems;
+pub mod box_constraints;
+#[cfg(feature = "cli")]
+pub mod cli;
+pub(crate) mod clock;
+pub mod coloring;
+pub mod conflicts;
+pub mod constraints;
+pub mod derivative_check;
+pub mod dulmage_mendelsohn;
+pub mod dyn_system;
+pub mod graphviz;
+pub mod inequality;
+pub mod irls;
+pub(crate) mod logging;
+pub mod monte_carlo;
 pub mod objective;
-pub mod opt_tools;
+#[cfg(feature = "argmin")]
+pub(crate) mod opt_tools;
+pub mod optimization_trace;
+#[cfg(feature = "parallel")]
+pub mod parallel;
 pub mod param_scaling;
 pub mod param_traits;
+pub mod plan_overrides;
+#[cfg(feature = "plots")]
+pub mod plots;
 pub mod residuals;
+pub mod role_swap;
+pub mod sensitivity;
+pub mod shared;
 pub mod solution_plan;
+pub mod solve_options;
+pub mod solve_report;
+pub mod solved;
 pub mod sub_problem;
+pub mod sweep;
+pub mod uncertainty;
+pub mod validation;
+pub mod verification;
+pub mod warnings;
 
 #[cfg(test)]
 mod tests;
@@ -51,18 +105,40 @@ where
     raw_res_fns: ResidualFns<G64, U64, Gadfn, Uadfn>,
     /// The function engine to compute residuals and derivatives of the
     /// raw residual functions.
-    raw_res_fn_engine: FunctionEngine<
-        ObjectiveFunction<f64, G64, U64, ResidTransIdentity, ResidNoOpGaussNewton, N>,
-        ObjectiveFunction<adfn<1>, Gadfn, Uadfn, ResidTransIdentity, ResidNoOpGaussNewton, N>,
-        ForwardAD,
-    >,
+    raw_res_fn_engine: RawResFnEngine<G64, U64, Gadfn, Uadfn, N>,
     /// Field names for the unknown parameters (for debugging/logging)
     unknown_field_names: &'static [&'static str],
+    /// Optional per-equation factors applied to every residual before transformation and aggregation.
+    residual_scale_weights: Option<Vec<f64>>,
+    /// Optional per-equation weights used when solving non-square blocks in a (weighted) least-squares sense.
+    residual_weights: Option<Vec<f64>>,
+    /// Optional strength of the Tikhonov regularization toward the priors added to every sub-problem.
+    tikhonov_lambda: Option<f64>,
+    /// How sub-problems map the unknowns to optimization space.
+    param_scaling: ParamScaling<N>,
+    /// Optional fixed priors to center the scaling on, instead of each sub-problem's initial unknowns.
+    scaling_priors: Option<U64>,
+    /// Optional hard model-space bounds on the unknowns.
+    box_constraints: Option<BoxConstraints<N>>,
+    /// Optional constraints among the unknowns, enforced by augmented-Lagrangian penalties in every sub-problem.
+    unknown_constraints: Option<AugmentedLagrangian<U64, Uadfn>>,
+    /// Optional one-sided residuals `g <= 0`, enforced by penalty residuals in every sub-problem.
+    inequality_residuals: Option<InequalityResiduals<G64, U64, Gadfn, Uadfn>>,
+    /// Optional full-system Jacobian with wide `adfn<K>` tangents (see `with_wide_tangents`).
+    wide_jacobian: Option<WideJacobianFn<G64, U64>>,
+    /// Optional column coloring for full-system Jacobians (see `with_jacobian_coloring`).
+    column_coloring: Option<ColumnColoring>,
+    /// Sub-problems of the plan's blocks kept between solves (see `BlockEngineCache`).
+    block_engines: BlockEngineCache<G64, U64, Gadfn, Uadfn, N>,
     state: S,
 }
 
 pub struct EqSysStateInit;
 
+/// `EquationSystemBuilder` named by the f64 versions of the givens and unknowns structs only (see `ParamFamily`).
+pub type EquationSystemFor<G, U, S, const N: usize> =
+    EquationSystemBuilder<G, U, AdfnFor<G>, AdfnFor<U>, S, N>;
+
 impl<G64, U64, Gadfn, Uadfn, const N: usize> EquationSystemBuilder<G64, U64, Gadfn, Uadfn, (), N>
 where
     G64: GivenParamsFor<f64, N> + Clone,
@@ -70,36 +146,34 @@ where
     Gadfn: GivenParamsFor<adfn<1>, N> + Clone,
     Uadfn: UnknownParamsFor<adfn<1>, N>,
 {
+    /// Creates a builder, taking the unknown field names from `U64`'s `FieldNames` derive.
     pub fn new(
         givens_f64: G64,
         givens_adfn: Gadfn,
         raw_residual_fns: ResidualFns<G64, U64, Gadfn, Uadfn>,
+    ) -> Result<EquationSystemBuilder<G64, U64, Gadfn, Uadfn, EqSysStateInit, N>, EqSysError>
+    where
+        U64: FieldNames,
+    {
+        Self::new_with_field_names(givens_f64, givens_adfn, raw_residual_fns, U64::FIELDS)
+    }
+
+    /// Like `new`, with explicit unknown field names (in `StructToArray` order) for unknown types
+    /// that don't derive `FieldNames`, or to override them.
+    pub fn new_with_field_names(
+        givens_f64: G64,
+        givens_adfn: Gadfn,
+        raw_residual_fns: ResidualFns<G64, U64, Gadfn, Uadfn>,
         unknown_field_names: &'static [&'static str],
     ) -> Result<EquationSystemBuilder<G64, U64, Gadfn, Uadfn, EqSysStateInit, N>, EqSysError> {
-        let num_eqs = raw_residual_fns.f64().len();
-        let identity_loss_gen = ResidTransIdentity { n: num_eqs };
-        let resid_pass_through = ResidNoOpGaussNewton::new_fullprob(num_eqs);
-
-        let residuals_f64 = ObjectiveFunction::new(
-            &givens_f64,
-            &raw_residual_fns.f64(),
-            identity_loss_gen.clone(),
-            resid_pass_through.clone(),
-            None,
-        );
-        let residuals_adfn = ObjectiveFunction::new(
-            &givens_adfn,
-            &raw_residual_fns.adfn_1(),
-            identity_loss_gen,
-            resid_pass_through.clone(),
-            None,
-        );
+        if unknown_field_names.len() != N {
+            return Err(EqSysError::UnknownFieldNamesLenMismatch {
+                n_unknowns: N,
+                n_names: unknown_field_names.len(),
+            });
+        }
 
-        let res_fn_engine = FunctionEngine::new(
-            residuals_f64.clone(),
-            residuals_adfn.clone(),
-            ForwardAD::new(),
-        );
+        let res_fn_engine = raw_res_fn_engine(&givens_f64, &givens_adfn, &raw_residual_fns);
 
         Ok(EquationSystemBuilder {
             givens_f64,
@@ -107,85 +181,707 @@ where
             raw_res_fns: raw_residual_fns,
             raw_res_fn_engine: res_fn_engine,
             unknown_field_names,
+            residual_scale_weights: None,
+            residual_weights: None,
+            tikhonov_lambda: None,
+            param_scaling: ParamScaling::default(),
+            scaling_priors: None,
+            box_constraints: None,
+            unknown_constraints: None,
+            inequality_residuals: None,
+            wide_jacobian: None,
+            column_coloring: None,
+            block_engines: BlockEngineCache::new(),
             state: EqSysStateInit {},
         })
     }
 }
 
-impl<G64, U64, Gadfn, Uadfn, const N: usize>
-    EquationSystemBuilder<G64, U64, Gadfn, Uadfn, EqSysStateInit, N>
+impl<G, U, const N: usize> EquationSystemFor<G, U, (), N>
+where
+    G: GivenParamsFor<f64, N> + ToAdParams,
+    U: UnknownParamsFor<f64, N> + ParamFamily,
+    AdfnFor<G>: GivenParamsFor<adfn<1>, N>,
+    AdfnFor<U>: UnknownParamsFor<adfn<1>, N>,
+{
+    /// Like `new`, deriving the `adfn<1>` givens from the f64 ones (see `ToAdParams`).
+    pub fn new_from_f64(
+        givens_f64: G,
+        raw_residual_fns: ResidualFnsFor<G, U>,
+    ) -> Result<EquationSystemFor<G, U, EqSysStateInit, N>, EqSysError>
+    where
+        U: FieldNames,
+    {
+        let givens_adfn = givens_f64.to_ad_params::<adfn<1>>();
+        Self::new(givens_f64, givens_adfn, raw_residual_fns)
+    }
+}
+
+impl<G64, U64, Gadfn, Uadfn, S, const N: usize> EquationSystemBuilder<G64, U64, Gadfn, Uadfn, S, N>
 where
     G64: GivenParamsFor<f64, N>,
     U64: UnknownParamsFor<f64, N>,
     Gadfn: GivenParamsFor<adfn<1>, N>,
     Uadfn: UnknownParamsFor<adfn<1>, N>,
 {
-    pub fn with_triangularization(
-        self,
-        inital_unknowns: &U64,
-    ) -> Result<EquationSystemBuilder<G64, U64, Gadfn, Uadfn, EqSysSolutionPlan, N>, EqSysError>
-    {
-        let unknowns_vec = inital_unknowns.to_arr();
-        let (_val_all, grad_all) = self.raw_res_fn_engine.derivative(&unknowns_vec);
+    /// Sets per-equation weights (one per residual function, in registration order) for least-squares solves.
+    ///
+    /// Systems with more equations than unknowns are solved by minimizing `sum_i w_i * r_i^2`, so a
+    /// larger weight makes an equation win more of the compromise.
+    pub fn with_least_squares_weights(mut self, weights: Vec<f64>) -> Result<Self, EqSysError> {
+        self.validate_residual_weights(&weights)?;
+        self.residual_weights = Some(weights);
+        self.block_engines.clear();
+        Ok(self)
+    }
+
+    /// Multiplies each residual (one weight per residual function, in registration order) by its
+    /// weight in every sub-problem, before the residual transform and aggregation (see
+    /// `ResidTransWeighted`).
+    ///
+    /// Unlike `with_least_squares_weights`, this affects square blocks too: with squared-residual
+    /// losses, an equation with weight 10 costs 100 times as much per unit of error, so it
+    /// dominates where equations compete (e.g. during refinement) while low-weight equations are
+    /// allowed slack.
+    pub fn with_residual_weights(mut self, weights: &[f64]) -> Result<Self, EqSysError> {
+        self.validate_residual_weights(weights)?;
+        self.residual_scale_weights = Some(weights.to_vec());
+        self.block_engines.clear();
+        Ok(self)
+    }
 
-        if grad_all.nrows() != grad_all.ncols() {
-            return Err(EqSysError::NumEquationsNumUnknownsMismatch {
-                n_eqs: grad_all.nrows(),
-                n_unks: grad_all.ncols(),
+    /// Measures each residual in units of its characteristic magnitude (one per residual function,
+    /// in registration order), i.e. sets `with_residual_weights` to `1 / magnitude`.
+    ///
+    /// Residuals in mixed units (meters, m/s, Newtons) otherwise add up to a meaningless aggregate
+    /// cost, in which whichever equation has the largest numbers dominates. Magnitudes can come
+    /// from the problem (e.g. a jump height for a position residual), from
+    /// `residual_magnitudes_at`, or a mix of both.
+    pub fn with_residual_magnitudes(self, magnitudes: &[f64]) -> Result<Self, EqSysError> {
+        let n_eqs = self.raw_res_fns.f64().len();
+        if magnitudes.len() != n_eqs {
+            return Err(EqSysError::ResidualWeightsLenMismatch {
+                n_eqs,
+                n_weights: magnitudes.len(),
             });
         }
+        if let Some((idx, &magnitude)) = magnitudes
+            .iter()
+            .enumerate()
+            .find(|(_, m)| !(m.is_finite() && **m > 0.0))
+        {
+            return Err(EqSysError::InvalidResidualMagnitude {
+                fn_name: self.raw_res_fns.fn_names()[idx],
+                magnitude,
+            });
+        }
+        let weights: Vec<f64> = magnitudes.iter().map(|m| 1.0 / m).collect();
+        self.with_residual_weights(&weights)
+    }
 
-        let binary_matrix = to_binary_matrix(grad_all);
-        let structure = lower_block_triangular_structure(&binary_matrix);
-
-        let (pr, pc) = lower_triangular_permutations(&binary_matrix);
-
-        let mut u = binary_matrix.clone();
-        pr.permute_rows(&mut u);
-        pc.permute_columns(&mut u);
+    /// Scales the residuals by their characteristic magnitudes at `initial_unknowns` (see
+    /// `residual_magnitudes_at` and `with_residual_magnitudes`).
+    pub fn with_auto_residual_scaling(self, initial_unknowns: &U64) -> Result<Self, EqSysError> {
+        let magnitudes = self.residual_magnitudes_at(initial_unknowns);
+        self.with_residual_magnitudes(&magnitudes)
+    }
 
-        let soln_blocks: Vec<SolutionBlock> = structure
-            .block_indices()
+    /// A characteristic magnitude of each residual near `unknowns` (one per residual function, in
+    /// registration order): the larger of `|r_i|` and `sum_j |dr_i/du_j * u_j|`, the size of the
+    /// change in `r_i` if the unknowns moved by their own magnitudes.
+    ///
+    /// The second term keeps the magnitude meaningful for residuals that are (nearly) satisfied at
+    /// `unknowns`. Residuals for which both are zero or not finite get magnitude 1.
+    pub fn residual_magnitudes_at(&self, unknowns: &U64) -> Vec<f64> {
+        let unknowns = unknowns.to_arr();
+        let (residuals, jacobian) = self.full_derivative(&unknowns);
+        residuals
             .iter()
             .enumerate()
-            .map(|(block_num, (row_idxs, col_idxs))| SolutionBlock {
-                block_idx: block_num,
-                equation_idxs: row_idxs.clone(),
-                unknown_idxs: col_idxs.clone(),
+            .map(|(i, r)| {
+                let variation: f64 = (0..N).map(|j| (jacobian[(i, j)] * unknowns[j]).abs()).sum();
+                let magnitude = r.abs().max(variation);
+                if magnitude.is_finite() && magnitude > 0.0 {
+                    magnitude
+                } else {
+                    1.0
+                }
             })
-            .collect();
+            .collect()
+    }
+
+    /// Checks that there is one finite, positive weight per equation.
+    fn validate_residual_weights(&self, weights: &[f64]) -> Result<(), EqSysError> {
+        let n_eqs = self.raw_res_fns.f64().len();
+        if weights.len() != n_eqs {
+            return Err(EqSysError::ResidualWeightsLenMismatch {
+                n_eqs,
+                n_weights: weights.len(),
+            });
+        }
+        if let Some((idx, &weight)) = weights
+            .iter()
+            .enumerate()
+            .find(|(_, w)| !(w.is_finite() && **w > 0.0))
+        {
+            return Err(EqSysError::InvalidResidualWeight {
+                fn_name: self.raw_res_fns.fn_names()[idx],
+                weight,
+            });
+        }
+        Ok(())
+    }
 
-        let solution_plan = SolutionPlan::new(soln_blocks);
+    /// Augments every sub-problem objective with `lambda * ||x - prior||^2` over the block's unknowns in opt space.
+    ///
+    /// This stabilizes near-singular blocks and keeps solutions close to the priors (the initial
+    /// unknowns each sub-problem is scaled around). Small values (e.g. `1e-6`..`1e-2`) bias the
+    /// solution only slightly. Fails with `EqSysError::InvalidTikhonovLambda` unless `lambda` is
+    /// finite and non-negative.
+    pub fn with_tikhonov_regularization(mut self, lambda: f64) -> Result<Self, EqSysError> {
+        if !(lambda.is_finite() && lambda >= 0.0) {
+            return Err(EqSysError::InvalidTikhonovLambda { lambda });
+        }
+        self.tikhonov_lambda = Some(lambda);
+        self.block_engines.clear();
+        Ok(self)
+    }
 
-        Ok(EquationSystemBuilder {
+    /// Sets hard lower/upper bounds (model space) on every unknown; use infinities for unbounded sides.
+    ///
+    /// Solvers only ever evaluate the residuals at params inside the box, and returned solutions lie inside it.
+    pub fn with_box_constraints(mut self, lower: &U64, upper: &U64) -> Result<Self, EqSysError> {
+        let (lower, upper) = (lower.to_arr(), upper.to_arr());
+        for idx in 0..N {
+            if lower[idx].is_nan() || upper[idx].is_nan() || lower[idx] > upper[idx] {
+                return Err(EqSysError::InvalidBoxConstraint {
+                    field: self.unknown_field_names[idx],
+                    lower: lower[idx],
+                    upper: upper[idx],
+                });
+            }
+        }
+        self.box_constraints = Some(BoxConstraints { lower, upper });
+        self.block_engines.clear();
+        Ok(self)
+    }
+
+    /// Sets a lower bound, prior and upper bound for every unknown, e.g. as a `MyUnknowns<ParamBounds>`.
+    ///
+    /// Sub-problems then build their link functions from these bounds and priors (see
+    /// `bounded_link_fns_builder`) instead of the default scaled-log link with a lower bound at 1%
+    /// of the initial value; unknowns bounded on both sides, such as a traction coefficient in
+    /// `(0, 1)`, get a logit link. The bounds are also enforced as box constraints, intersected
+    /// with any set by `with_box_constraints`.
+    pub fn with_param_bounds<B>(mut self, bounds: &B) -> Result<Self, EqSysError>
+    where
+        B: StructToArray<ParamBounds, N>,
+    {
+        let bounds = bounds.to_arr();
+        if let Some((idx, b)) = bounds.iter().enumerate().find(|(_, b)| !b.is_valid()) {
+            return Err(EqSysError::InvalidParamBounds {
+                field: self.unknown_field_names[idx],
+                lb: b.lb,
+                prior: b.prior,
+                ub: b.ub,
+            });
+        }
+
+        let mut box_constraints = self.box_constraints.take().unwrap_or(BoxConstraints {
+            lower: [f64::NEG_INFINITY; N],
+            upper: [f64::INFINITY; N],
+        });
+        for (idx, b) in bounds.iter().enumerate() {
+            box_constraints.lower[idx] = box_constraints.lower[idx].max(b.lb);
+            box_constraints.upper[idx] = box_constraints.upper[idx].min(b.ub);
+            if box_constraints.lower[idx] > box_constraints.upper[idx] {
+                return Err(EqSysError::InvalidBoxConstraint {
+                    field: self.unknown_field_names[idx],
+                    lower: box_constraints.lower[idx],
+                    upper: box_constraints.upper[idx],
+                });
+            }
+        }
+        self.box_constraints = Some(box_constraints);
+        self.param_scaling = ParamScaling::FromBounds(bounds);
+        self.block_engines.clear();
+        Ok(self)
+    }
+
+    /// Chooses the link function of every unknown, e.g. as a `MyUnknowns<ScalingSpec>`: `Asinh` for
+    /// params that may change sign, `Identity` for params that are already O(1), `Affine` for
+    /// well-behaved params of another magnitude, and so on.
+    ///
+    /// Replaces the default sign-aware scaled-log link, and any scaling set by `with_param_bounds`
+    /// (whose box constraints stay in place). Links are centered on each sub-problem's initial
+    /// unknowns.
+    pub fn with_scaling_specs<B>(mut self, specs: &B) -> Result<Self, EqSysError>
+    where
+        B: StructToArray<ScalingSpec, N>,
+    {
+        let specs = specs.to_arr();
+        if let Some((idx, &spec)) = specs.iter().enumerate().find(|(_, s)| !s.is_valid()) {
+            return Err(EqSysError::InvalidScalingSpec {
+                field: self.unknown_field_names[idx],
+                spec,
+            });
+        }
+        self.param_scaling = ParamScaling::PerField(specs);
+        self.block_engines.clear();
+        self.check_scaling_priors()?;
+        Ok(self)
+    }
+
+    /// Keeps the default scaled-log scaling, with `p / scale` as the link of unknowns whose initial
+    /// value is exactly 0 (instead of 1), e.g. 0.1 for an angle in radians that starts at 0.
+    ///
+    /// Such unknowns are free to take either sign, so no fake nonzero prior is needed. Replaces any
+    /// scaling set by `with_param_bounds` or `with_scaling_specs`.
+    pub fn with_zero_prior_scale(mut self, scale: f64) -> Result<Self, EqSysError> {
+        if !(scale.is_finite() && scale > 0.0) {
+            return Err(EqSysError::InvalidZeroPriorScale { scale });
+        }
+        self.block_engines.clear();
+        self.param_scaling = ParamScaling::FromInitialUnknowns {
+            zero_prior_scale: scale,
+        };
+        self.check_scaling_priors()?;
+        Ok(self)
+    }
+
+    /// Centers the scaling of every sub-problem (and the Tikhonov regularization, see
+    /// `with_tikhonov_regularization`) on `priors` instead of on the sub-problem's initial
+    /// unknowns, so that warm-starting from a previous solution doesn't change the scaling.
+    ///
+    /// Doesn't affect `with_param_bounds` scaling, which has its own priors.
+    ///
+    /// Fails with `EqSysError::InvalidParamScaling` if a prior is outside its own link function's
+    /// domain, e.g. non-finite, or negative under `ScalingSpec::LogPositive`; `with_scaling_specs`
+    /// and `with_zero_prior_scale` recheck the priors against the new scaling.
+    pub fn with_scaling_priors(mut self, priors: &U64) -> Result<Self, EqSysError> {
+        self.block_engines.clear();
+        self.scaling_priors = Some(*priors);
+        self.check_scaling_priors()?;
+        Ok(self)
+    }
+
+    /// Checks the priors set by `with_scaling_priors`, if any, against the current scaling.
+    fn check_scaling_priors(&self) -> Result<(), EqSysError> {
+        match &self.scaling_priors {
+            Some(priors) => check_scaling_priors(
+                &self.param_scaling,
+                &priors.to_arr(),
+                self.unknown_field_names,
+            ),
+            None => Ok(()),
+        }
+    }
+
+    /// A starting point built from the priors the builder already knows: those set by
+    /// `with_scaling_priors`, else those of `with_param_bounds`.
+    ///
+    /// Saves hand-maintaining a separate struct of starting values for `with_triangularization` and
+    /// `solve_system`. `None` if neither is set.
+    pub fn prior_unknowns(&self) -> Option<U64> {
+        match (&self.scaling_priors, &self.param_scaling) {
+            (Some(priors), _) => Some(*priors),
+            (None, ParamScaling::FromBounds(bounds)) => {
+                Some(U64::from_arr(bounds.map(|b| b.prior)))
+            }
+            (None, _) => None,
+        }
+    }
+
+    /// The point sub-problems starting at `initial_unknowns` center their scaling on.
+    fn scaling_center<'a>(&'a self, initial_unknowns: &'a U64) -> &'a U64 {
+        self.scaling_priors.as_ref().unwrap_or(initial_unknowns)
+    }
+
+    /// Adds algebraic constraints among the unknowns (e.g. `u1 + u2 = c`), enforced with the
+    /// default `AugmentedLagrangian` settings.
+    ///
+    /// Constraints are not equations of the system: they don't take part in the triangularization,
+    /// so a square system stays square. If a solve ends with a constraint violated by more than the
+    /// tolerance, its report carries a `SolveWarning::ConstraintsViolated`.
+    pub fn with_unknown_constraints(self, constraints: UnknownConstraints<U64, Uadfn>) -> Self {
+        self.with_augmented_lagrangian(AugmentedLagrangian::new(constraints))
+    }
+
+    /// Like `with_unknown_constraints`, with explicit penalty and outer-iteration settings.
+    pub fn with_augmented_lagrangian(
+        mut self,
+        augmented_lagrangian: AugmentedLagrangian<U64, Uadfn>,
+    ) -> Self {
+        self.unknown_constraints = Some(augmented_lagrangian);
+        self.block_engines.clear();
+        self
+    }
+
+    /// Adds one-sided residuals `g <= 0` (e.g. a minimum jump apex), enforced by penalty residuals
+    /// in every sub-problem.
+    ///
+    /// Like constraints among the unknowns, inequalities are not equations of the system and don't
+    /// take part in the triangularization.
+    pub fn with_inequality_residuals(
+        mut self,
+        inequalities: InequalityResiduals<G64, U64, Gadfn, Uadfn>,
+    ) -> Self {
+        self.inequality_residuals = Some(inequalities);
+        self.block_engines.clear();
+        self
+    }
+
+    /// Moves the builder to the typestate `state`, keeping everything else.
+    fn with_state<S2>(self, state: S2) -> EquationSystemBuilder<G64, U64, Gadfn, Uadfn, S2, N> {
+        self.map_state(|_| state)
+    }
+
+    /// Moves the builder to the typestate computed from the current one, keeping everything else.
+    fn map_state<S2>(
+        self,
+        f: impl FnOnce(S) -> S2,
+    ) -> EquationSystemBuilder<G64, U64, Gadfn, Uadfn, S2, N> {
+        EquationSystemBuilder {
             givens_f64: self.givens_f64,
             givens_adfn: self.givens_adfn,
             raw_res_fns: self.raw_res_fns,
             raw_res_fn_engine: self.raw_res_fn_engine,
             unknown_field_names: self.unknown_field_names,
-            state: EqSysSolutionPlan {
-                binary_matrix,
-                lower_tri_mat: u,
-                block_structure: structure,
-                row_permutation: pr,
-                col_permutation: pc,
-                solution_plan,
-            },
+            residual_scale_weights: self.residual_scale_weights,
+            residual_weights: self.residual_weights,
+            tikhonov_lambda: self.tikhonov_lambda,
+            param_scaling: self.param_scaling,
+            scaling_priors: self.scaling_priors,
+            box_constraints: self.box_constraints,
+            unknown_constraints: self.unknown_constraints,
+            inequality_residuals: self.inequality_residuals,
+            wide_jacobian: self.wide_jacobian,
+            column_coloring: self.column_coloring,
+            block_engines: self.block_engines,
+            state: f(self.state),
+        }
+    }
+
+    /// Fails with `EqSysError::NonFiniteResidual` for the first residual function (in registration
+    /// order) that is NaN or infinite at `unknowns`, or has non-finite derivatives there, naming
+    /// the unknowns of those derivatives.
+    pub fn check_finite(&self, unknowns: &U64) -> Result<(), EqSysError> {
+        let (residuals, jacobian) = self.full_derivative(&unknowns.to_arr());
+        for (e, &value) in residuals.iter().enumerate() {
+            let non_finite_unknowns: Vec<&'static str> = (0..N)
+                .filter(|&u| !jacobian[(e, u)].is_finite())
+                .map(|u| self.unknown_field_names[u])
+                .collect();
+            if !value.is_finite() || !non_finite_unknowns.is_empty() {
+                return Err(EqSysError::NonFiniteResidual {
+                    fn_name: self.raw_res_fns.fn_names()[e],
+                    unknowns: non_finite_unknowns,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Residuals and Jacobian of all raw residual functions at `unknowns`, using the wide-tangent
+    /// functions and the column coloring if set.
+    fn full_derivative(
+        &self,
+        unknowns: &[f64; N],
+    ) -> (Vec<f64>, Matrix<f64, Dyn, Dyn, VecStorage<f64, Dyn, Dyn>>) {
+        self.derivative_with_coloring(unknowns, self.column_coloring.as_ref())
+    }
+
+    fn derivative_with_coloring(
+        &self,
+        unknowns: &[f64; N],
+        coloring: Option<&ColumnColoring>,
+    ) -> (Vec<f64>, Matrix<f64, Dyn, Dyn, VecStorage<f64, Dyn, Dyn>>) {
+        match (&self.wide_jacobian, coloring) {
+            (Some(jacobian), _) => {
+                jacobian(&self.givens_f64, self.raw_res_fns.f64(), unknowns, coloring)
+            }
+            (None, Some(coloring)) => coloring.derivative::<_, Uadfn, 1, N>(
+                &self.givens_adfn,
+                self.raw_res_fns.adfn_1(),
+                unknowns,
+            ),
+            (None, None) => self.raw_res_fn_engine.derivative(unknowns),
+        }
+    }
+
+    /// The Jacobian at `unknowns`, with each finite entry replaced by the largest-magnitude finite
+    /// derivative at `sampling`'s perturbed points.
+    ///
+    /// Only its sparsity pattern is meaningful.
+    fn structure_jacobian(
+        &self,
+        unknowns: &U64,
+        sampling: &StructureSampling,
+    ) -> Matrix<f64, Dyn, Dyn, VecStorage<f64, Dyn, Dyn>> {
+        let unknowns_vec = unknowns.to_arr();
+        // no coloring: it would hide nonzeros outside the current pattern
+        let (_val_all, mut grad_all) = self.derivative_with_coloring(&unknowns_vec, None);
+        for point in sampling.perturbed_points(unknowns_vec) {
+            let (_val, grad) = self.derivative_with_coloring(&point, None);
+            grad_all = grad_all.zip_map(&grad, |a, b| {
+                if a.is_finite() && b.is_finite() && b.abs() > a.abs() {
+                    b
+                } else {
+                    a
+                }
+            });
+        }
+        grad_all
+    }
+
+    /// Wraps `inner` so that the residuals of `block` are first multiplied by their
+    /// `with_residual_weights` weights (1 if none were set).
+    fn weighted_resid_trans<R: ResidTransHOF>(
+        &self,
+        block: &SolutionBlock,
+        inner: R,
+    ) -> ResidTransWeighted<R> {
+        let weights = block
+            .equation_idxs
+            .iter()
+            .map(|&eq_idx| {
+                self.residual_scale_weights
+                    .as_ref()
+                    .map_or(1.0, |weights| weights[eq_idx])
+            })
+            .collect();
+        ResidTransWeighted::new(weights, inner)
+    }
+
+    /// The least-squares weights of the equations in `block`, in block order.
+    fn block_residual_weights(&self, block: &SolutionBlock) -> Option<Vec<f64>> {
+        self.residual_weights.as_ref().map(|weights| {
+            block
+                .equation_idxs
+                .iter()
+                .map(|&eq_idx| weights[eq_idx])
+                .collect()
         })
     }
 }
 
+impl<G64, U64, Gadfn, Uadfn, const N: usize>
+    EquationSystemBuilder<G64, U64, Gadfn, Uadfn, EqSysStateInit, N>
+where
+    G64: GivenParamsFor<f64, N>,
+    U64: UnknownParamsFor<f64, N>,
+    Gadfn: GivenParamsFor<adfn<1>, N>,
+    Uadfn: UnknownParamsFor<adfn<1>, N>,
+{
+    /// Appends the residual functions of `more`, e.g. a `residual_fns!` collection from another
+    /// module (see `ResidualFns::concat`).
+    ///
+    /// Residual weights that are already set get weight 1 for the new equations, and wide-tangent
+    /// functions set with `with_wide_tangents` are dropped.
+    pub fn add_residuals(
+        mut self,
+        more: ResidualFns<G64, U64, Gadfn, Uadfn>,
+    ) -> Result<Self, EqSysError> {
+        let n_new = more.f64().len();
+        self.raw_res_fns = self.raw_res_fns.concat(more)?;
+        self.raw_res_fn_engine =
+            raw_res_fn_engine(&self.givens_f64, &self.givens_adfn, &self.raw_res_fns);
+        self.wide_jacobian = None;
+        self.column_coloring = None;
+        for weights in [&mut self.residual_scale_weights, &mut self.residual_weights]
+            .into_iter()
+            .flatten()
+        {
+            weights.extend(std::iter::repeat_n(1.0, n_new));
+        }
+        Ok(self)
+    }
+
+    /// Computes full-system Jacobians (for triangularization, `validate` and the solved state) with
+    /// `adfn<K>` tangents, i.e. `K` Jacobian columns per pass instead of one; an 8-unknown system
+    /// then needs a single pass with `adfn<8>`.
+    ///
+    /// `wide_fns` are the residual functions instantiated at `adfn<K>`, in registration order (see
+    /// `wide_residual_fns!`). Sub-problem solvers still differentiate with `adfn<1>`.
+    pub fn with_wide_tangents<UK, const K: usize>(
+        mut self,
+        wide_fns: Vec<ResidualFn<<G64 as ParamFamily>::For<adfn<K>>, UK, adfn<K>>>,
+    ) -> Result<Self, EqSysError>
+    where
+        G64: ToAdParams + 'static,
+        <G64 as ParamFamily>::For<adfn<K>>: GivenParamsFor<adfn<K>, N> + 'static,
+        U64: 'static,
+        UK: UnknownParamsFor<adfn<K>, N> + 'static,
+    {
+        let n_eqs = self.raw_res_fns.f64().len();
+        if wide_fns.len() != n_eqs {
+            return Err(EqSysError::WideTangentFnsLenMismatch {
+                n_eqs,
+                n_fns: wide_fns.len(),
+            });
+        }
+
+        // The engine is rebuilt per call so that it always sees the current givens.
+        let jacobian = move |givens: &G64,
+                             f64_fns: &Vec<ResidualFn<G64, U64, f64>>,
+                             unknowns: &[f64],
+                             coloring: Option<&ColumnColoring>| {
+            let givens_wide = givens.to_ad_params::<adfn<K>>();
+            if let Some(coloring) = coloring {
+                return coloring.derivative::<_, UK, K, N>(&givens_wide, &wide_fns, unknowns);
+            }
+            let n = f64_fns.len();
+            let residuals_f64 = ObjectiveFunction::<f64, G64, U64, _, _, N>::new(
+                givens,
+                f64_fns,
+                ResidTransIdentity { n },
+                ResidNoOpGaussNewton::new_fullprob(n),
+                None,
+            );
+            let residuals_wide = ObjectiveFunction::<adfn<K>, _, UK, _, _, N>::new(
+                &givens_wide,
+                &wide_fns,
+                ResidTransIdentity { n },
+                ResidNoOpGaussNewton::new_fullprob(n),
+                None,
+            );
+            FunctionEngine::new(
+                residuals_f64,
+                residuals_wide,
+                ForwardADMulti::<adfn<K>>::new(),
+            )
+            .derivative(unknowns)
+        };
+        self.wide_jacobian = Some(Shared::new(jacobian));
+        Ok(self)
+    }
+
+    /// Builds the solution plan from the Jacobian's sparsity pattern around `inital_unknowns`,
+    /// sampled with the default `StructureSampling`.
+    pub fn with_triangularization(
+        self,
+        inital_unknowns: &U64,
+    ) -> Result<EquationSystemBuilder<G64, U64, Gadfn, Uadfn, EqSysSolutionPlan, N>, EqSysError>
+    {
+        self.with_triangularization_sampled(inital_unknowns, &StructureSampling::default())
+    }
+
+    /// Like `with_triangularization`, with explicit control over where the Jacobian is sampled.
+    ///
+    /// Fails with `EqSysError::NonFiniteResidual` if a residual or derivative is not finite at
+    /// `inital_unknowns` (see `check_finite`), instead of planning with an unknown dependency.
+    pub fn with_triangularization_sampled(
+        self,
+        inital_unknowns: &U64,
+        sampling: &StructureSampling,
+    ) -> Result<EquationSystemBuilder<G64, U64, Gadfn, Uadfn, EqSysSolutionPlan, N>, EqSysError>
+    {
+        self.check_finite(inital_unknowns)?;
+        let grad_all = self.structure_jacobian(inital_unknowns, sampling);
+        Ok(self.with_state(EqSysSolutionPlan::from_jacobian(
+            grad_all,
+            &sampling.zero_threshold,
+        )))
+    }
+
+    /// Like `with_triangularization`, but takes the structural sparsity from `incidence` instead of
+    /// evaluating the Jacobian at an initial guess: `incidence[e][u]` says whether equation `e` (in
+    /// registration order) depends on unknown `u` (in `StructToArray` order).
+    ///
+    /// Use this when a clamp or branch makes a true dependency evaluate to exactly zero at every
+    /// convenient initial guess, which would otherwise produce a wrong plan.
+    pub fn with_triangularization_from_incidence(
+        self,
+        incidence: &[[bool; N]],
+    ) -> Result<EquationSystemBuilder<G64, U64, Gadfn, Uadfn, EqSysSolutionPlan, N>, EqSysError>
+    {
+        let n_eqs = self.raw_res_fns.f64().len();
+        if incidence.len() != n_eqs {
+            return Err(EqSysError::IncidenceRowsMismatch {
+                n_eqs,
+                n_rows: incidence.len(),
+            });
+        }
+        let pattern =
+            Matrix::<f64, Dyn, Dyn, VecStorage<f64, Dyn, Dyn>>::from_fn(n_eqs, N, |e, u| {
+                if incidence[e][u] { 1.0 } else { 0.0 }
+            });
+
+        Ok(self.with_state(EqSysSolutionPlan::from_jacobian(
+            pattern,
+            &ZeroThreshold::default(),
+        )))
+    }
+}
+
+/// Full-system residuals and Jacobian from the current givens, f64 residual functions, unknowns and
+/// optional column coloring.
+type WideJacobianFn<G64, U64> = shared::shared_fn!(
+    Fn(
+        &G64,
+        &Vec<ResidualFn<G64, U64, f64>>,
+        &[f64],
+        Option<&ColumnColoring>,
+    ) -> (Vec<f64>, Matrix<f64, Dyn, Dyn, VecStorage<f64, Dyn, Dyn>>)
+);
+
+/// Function engine over the raw residual functions, with the givens baked in.
+type RawResFnEngine<G64, U64, Gadfn, Uadfn, const N: usize> = FunctionEngine<
+    ObjectiveFunction<f64, G64, U64, ResidTransIdentity, ResidNoOpGaussNewton, N>,
+    ObjectiveFunction<adfn<1>, Gadfn, Uadfn, ResidTransIdentity, ResidNoOpGaussNewton, N>,
+    ForwardAD,
+>;
+
+fn raw_res_fn_engine<G64, U64, Gadfn, Uadfn, const N: usize>(
+    givens_f64: &G64,
+    givens_adfn: &Gadfn,
+    raw_res_fns: &ResidualFns<G64, U64, Gadfn, Uadfn>,
+) -> RawResFnEngine<G64, U64, Gadfn, Uadfn, N>
+where
+    G64: GivenParamsFor<f64, N>,
+    U64: UnknownParamsFor<f64, N>,
+    Gadfn: GivenParamsFor<adfn<1>, N>,
+    Uadfn: UnknownParamsFor<adfn<1>, N>,
+{
+    let num_eqs = raw_res_fns.f64().len();
+    let identity_loss_gen = ResidTransIdentity { n: num_eqs };
+    let resid_pass_through = ResidNoOpGaussNewton::new_fullprob(num_eqs);
+
+    let residuals_f64 = ObjectiveFunction::new(
+        givens_f64,
+        &raw_res_fns.f64(),
+        identity_loss_gen.clone(),
+        resid_pass_through.clone(),
+        None,
+    );
+    let residuals_adfn = ObjectiveFunction::new(
+        givens_adfn,
+        &raw_res_fns.adfn_1(),
+        identity_loss_gen,
+        resid_pass_through,
+        None,
+    );
+
+    FunctionEngine::new(residuals_f64, residuals_adfn, ForwardAD::new())
+}
+
 fn to_binary_matrix(
     mat: Matrix<f64, Dyn, Dyn, VecStorage<f64, Dyn, Dyn>>,
+    zero_threshold: &ZeroThreshold,
 ) -> Matrix<f32, Dyn, Dyn, VecStorage<f32, Dyn, Dyn>> {
     let (nrows, ncols) = mat.shape();
     let mut bin_mat = Matrix::<f32, Dyn, Dyn, VecStorage<f32, Dyn, Dyn>>::zeros(nrows, ncols);
     for r in 0..nrows {
+        let row_scale = mat
+            .row(r)
+            .iter()
+            .filter(|x| x.is_finite())
+            .fold(0.0, |max: f64, x| max.max(x.abs()));
         for c in 0..ncols {
             let x = mat[(r, c)];
             let out = if !x.is_finite() {
                 f32::NAN
-            } else if x != 0.0 {
+            } else if zero_threshold.is_dependency(x, row_scale) {
                 1.0
             } else {
                 0.0
@@ -203,6 +899,155 @@ pub struct EqSysSolutionPlan {
     row_permutation: PermutationSequence<Dyn>,
     col_permutation: PermutationSequence<Dyn>,
     solution_plan: SolutionPlan,
+    dm_decomposition: Option<DmDecomposition>,
+    block_conditioning: Option<Vec<BlockConditioning>>,
+}
+
+impl EqSysSolutionPlan {
+    /// Builds the block-triangular solution plan from the sparsity pattern of the full residual
+    /// Jacobian, with entries below `zero_threshold` counting as zero.
+    fn from_jacobian(
+        jacobian: Matrix<f64, Dyn, Dyn, VecStorage<f64, Dyn, Dyn>>,
+        zero_threshold: &ZeroThreshold,
+    ) -> Self {
+        let (n_eqs, n_unks) = jacobian.shape();
+
+        let binary_matrix = to_binary_matrix(jacobian, zero_threshold);
+        let structure = lower_block_triangular_structure(&binary_matrix);
+
+        let (pr, pc) = lower_triangular_permutations(&binary_matrix);
+
+        let mut u = binary_matrix.clone();
+        pr.permute_rows(&mut u);
+        pc.permute_columns(&mut u);
+
+        let dm = dulmage_mendelsohn(&binary_matrix);
+        let (soln_blocks, dm_decomposition) = if n_eqs != n_unks || !dm.is_well_determined() {
+            // Non-square or structurally singular: split off the over- and underdetermined parts,
+            // and triangularize the well-determined part in between.
+            (rectangular_plan_blocks(&binary_matrix, &dm), Some(dm))
+        } else {
+            let blocks = structure
+                .block_indices()
+                .iter()
+                .enumerate()
+                .map(|(block_num, (row_idxs, col_idxs))| SolutionBlock {
+                    block_idx: block_num,
+                    equation_idxs: row_idxs.clone(),
+                    unknown_idxs: col_idxs.clone(),
+                })
+                .collect();
+            (blocks, None)
+        };
+
+        EqSysSolutionPlan {
+            binary_matrix,
+            lower_tri_mat: u,
+            block_structure: structure,
+            row_permutation: pr,
+            col_permutation: pc,
+            solution_plan: SolutionPlan::new(soln_blocks),
+            dm_decomposition,
+            block_conditioning: None,
+        }
+    }
+}
+
+/// `order` followed by the indices below `n` it leaves out, e.g. rows the block-triangular permutation didn't place.
+fn complete_order(order: &[usize], n: usize) -> Vec<usize> {
+    let mut complete = order.to_vec();
+    complete.extend((0..n).filter(|i| !order.contains(i)));
+    complete
+}
+
+/// Solution blocks for a rectangular system: the overdetermined part (solved in least squares),
+/// then the square blocks of the well-determined part, then the underdetermined part (solved for
+/// the minimum-norm update).
+///
+/// Unknowns that appear in no equation get no block.
+fn rectangular_plan_blocks(
+    binary_matrix: &Matrix<f32, Dyn, Dyn, VecStorage<f32, Dyn, Dyn>>,
+    dm: &DmDecomposition,
+) -> Vec<SolutionBlock> {
+    let mut blocks = vec![];
+    let mut push_block = |equation_idxs: Vec<usize>, unknown_idxs: Vec<usize>| {
+        if !equation_idxs.is_empty() {
+            blocks.push(SolutionBlock {
+                block_idx: blocks.len(),
+                equation_idxs,
+                unknown_idxs,
+            });
+        }
+    };
+
+    push_block(dm.over_equations.clone(), dm.over_unknowns.clone());
+
+    if !dm.square_equations.is_empty() {
+        let square = binary_matrix.select_rows(&dm.square_equations);
+        let square = square.select_columns(&dm.square_unknowns);
+        for (row_idxs, col_idxs) in lower_block_triangular_structure(&square).block_indices() {
+            push_block(
+                row_idxs.iter().map(|&r| dm.square_equations[r]).collect(),
+                col_idxs.iter().map(|&c| dm.square_unknowns[c]).collect(),
+            );
+        }
+    }
+
+    push_block(dm.under_equations.clone(), dm.under_unknowns.clone());
+    blocks
+}
+
+/// The block-triangular structure (as printed by `EquationSystemBuilder::print_block_structure`)
+/// followed by the solution plan, by index.
+impl fmt::Display for EqSysSolutionPlan {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let structure = &self.block_structure;
+        writeln!(f, "Lower block triangular structure:")?;
+        writeln!(f, "   matching_size = {}", structure.matching_size)?;
+        writeln!(f, "   block_sizes   = {:?}", structure.block_sizes)?;
+        writeln!(f, "   row_order = {:?}", structure.row_order)?;
+        writeln!(f, "   col_order = {:?}", structure.col_order)?;
+        write!(f, "{}", self.solution_plan)
+    }
+}
+
+impl EqSysSolutionPlan {
+    /// The Dulmage–Mendelsohn decomposition the plan was built from, for rectangular or structurally singular systems.
+    pub fn dm_decomposition(&self) -> Option<&DmDecomposition> {
+        self.dm_decomposition.as_ref()
+    }
+
+    pub fn solution_plan(&self) -> &SolutionPlan {
+        &self.solution_plan
+    }
+
+    /// The incidence matrix permuted to lower block-triangular form, unlabeled (see
+    /// `EquationSystemBuilder::lower_tri_mat_string` for a labeled version).
+    pub fn lower_tri_mat(&self) -> &Matrix<f32, Dyn, Dyn, VecStorage<f32, Dyn, Dyn>> {
+        &self.lower_tri_mat
+    }
+
+    /// Rank and conditioning of each block, if computed with `EquationSystemBuilder::with_block_conditioning`.
+    pub fn block_conditioning(&self) -> Option<&[BlockConditioning]> {
+        self.block_conditioning.as_deref()
+    }
+
+    fn conditioning_of(&self, block_idx: usize) -> Option<&BlockConditioning> {
+        self.block_conditioning()?
+            .iter()
+            .find(|c| c.block_idx == block_idx)
+    }
+
+    /// True if both plans were built from the same equation-by-unknown sparsity pattern, and hence
+    /// have the same blocks.
+    pub fn has_same_structure(&self, other: &Self) -> bool {
+        self.binary_matrix.shape() == other.binary_matrix.shape()
+            && self
+                .binary_matrix
+                .iter()
+                .zip(other.binary_matrix.iter())
+                .all(|(a, b)| a == b || (a.is_nan() && b.is_nan()))
+    }
 }
 
 impl<G64, U64, Gadfn, Uadfn, const N: usize>
@@ -213,37 +1058,216 @@ where
     Gadfn: GivenParamsFor<adfn<1>, N>,
     Uadfn: UnknownParamsFor<adfn<1>, N>,
 {
-    pub fn block_structure(&self) -> &LowerBtfStructure {
-        &self.state.block_structure
-    }
-    pub fn print_lower_tri_mat(&self) {
-        println!(
-            "Lower block triangular matrix:\n{}",
-            self.state.lower_tri_mat
-        );
+    /// Recomputes the sparsity pattern and block structure at `unknowns` (e.g. the current best
+    /// solution), sampled with the default `StructureSampling`.
+    ///
+    /// Dependencies that are only active in part of the parameter space (e.g. a force above an
+    /// angle threshold) can change the structure away from the initial guess. Compare with
+    /// `EqSysSolutionPlan::has_same_structure`, and install the new plan with `with_solution_plan`.
+    pub fn replan_at(&self, unknowns: &U64) -> EqSysSolutionPlan {
+        self.replan_at_sampled(unknowns, &StructureSampling::default())
     }
-    pub fn print_block_structure(&self) {
-        println!("Lower block triangular structure:");
-        println!(
-            "   matching_size = {}",
-            self.state.block_structure.matching_size
-        );
-        println!(
-            "   block_sizes   = {:?}",
-            self.state.block_structure.block_sizes
-        );
-        println!("   row_order = {:?}", self.state.block_structure.row_order);
-        println!("   col_order = {:?}", self.state.block_structure.col_order);
+
+    /// Like `replan_at`, with explicit control over where the Jacobian is sampled and which entries
+    /// count as zero, e.g. the same `StructureSampling` as for `with_triangularization_sampled`.
+    pub fn replan_at_sampled(
+        &self,
+        unknowns: &U64,
+        sampling: &StructureSampling,
+    ) -> EqSysSolutionPlan {
+        EqSysSolutionPlan::from_jacobian(
+            self.structure_jacobian(unknowns, sampling),
+            &sampling.zero_threshold,
+        )
     }
-    pub fn print_permuted_function_names(&self) {
-        println!("Permuted residual function names:");
-        for &r in &self.state.block_structure.row_order {
-            let fn_name = self.raw_res_fns.fn_names()[r];
-            println!("   {}", fn_name);
+
+    /// Replaces the solution plan, e.g. with one from `replan_at`. A column coloring set with
+    /// `with_jacobian_coloring` is recomputed for the new sparsity pattern.
+    pub fn with_solution_plan(mut self, plan: EqSysSolutionPlan) -> Self {
+        if self.column_coloring.is_some() {
+            self.column_coloring = Some(ColumnColoring::from_incidence(&plan.binary_matrix));
         }
+        self.state = plan;
+        self.block_engines.clear();
+        self
     }
-    pub fn print_permuted_unknowns_names(&self) {
-        println!("Permuted unknowns names:");
+
+    /// Computes the numerical rank and condition number of each block's Jacobian at `unknowns`
+    /// (typically the initial guess).
+    ///
+    /// Near-singular blocks are then flagged by `print_solution_plan` and in the solve reports'
+    /// `BlockReport::conditioning`, and every block solve checks the Jacobian again at its
+    /// solution, adding a `SolveWarning::NearSingularJacobian` to the report if it is near-singular
+    /// there.
+    ///
+    /// Ill-conditioned blocks are the usual reason Gauss-Newton fails silently on a block;
+    /// installing another plan with `with_solution_plan` drops the conditioning.
+    pub fn with_block_conditioning(mut self, unknowns: &U64) -> Self {
+        let (_residuals, jacobian) = self.full_derivative(&unknowns.to_arr());
+        let conditioning = self
+            .state
+            .solution_plan
+            .blocks
+            .iter()
+            .map(|block| {
+                let block_jacobian = jacobian
+                    .select_rows(&block.equation_idxs)
+                    .select_columns(&block.unknown_idxs);
+                BlockConditioning::from_jacobian(block.block_idx, &block_jacobian)
+            })
+            .collect();
+        self.state.block_conditioning = Some(conditioning);
+        self
+    }
+
+    /// Colors the Jacobian's columns by the plan's sparsity pattern (see `ColumnColoring`), so that
+    /// full-system Jacobians (for `validate`, block conditioning and the solved state) take one AD
+    /// pass per color instead of per unknown, or per `K` colors with wide tangents (see
+    /// `with_wide_tangents`).
+    ///
+    /// The coloring assumes the sparsity pattern holds everywhere; `replan_at` ignores it, so that
+    /// it can find nonzeros outside the current pattern.
+    pub fn with_jacobian_coloring(mut self) -> Self {
+        self.column_coloring = Some(ColumnColoring::from_incidence(&self.state.binary_matrix));
+        self
+    }
+
+    pub fn jacobian_coloring(&self) -> Option<&ColumnColoring> {
+        self.column_coloring.as_ref()
+    }
+
+    /// The current solution plan state.
+    pub fn plan(&self) -> &EqSysSolutionPlan {
+        &self.state
+    }
+
+    pub fn block_structure(&self) -> &LowerBtfStructure {
+        &self.state.block_structure
+    }
+
+    /// Names of the unknowns in the underdetermined part of a rectangular or structurally singular
+    /// system.
+    ///
+    /// Equations don't pin these down; the solver only makes the minimum-norm change to them.
+    pub fn underdetermined_unknown_names(&self) -> Vec<&'static str> {
+        self.state.dm_decomposition().map_or(vec![], |dm| {
+            dm.under_unknowns
+                .iter()
+                .map(|&u| self.unknown_field_names[u])
+                .collect()
+        })
+    }
+
+    /// Prints the over-, well- and underdetermined parts of a rectangular or structurally singular system.
+    pub fn print_dm_decomposition(&self) {
+        let Some(dm) = self.state.dm_decomposition() else {
+            println!("System is structurally well-determined; no Dulmage-Mendelsohn decomposition");
+            return;
+        };
+        let fn_names = self.raw_res_fns.fn_names();
+        let parts = [
+            ("Overdetermined", &dm.over_equations, &dm.over_unknowns),
+            ("Well-determined", &dm.square_equations, &dm.square_unknowns),
+            ("Underdetermined", &dm.under_equations, &dm.under_unknowns),
+        ];
+        for (label, equation_idxs, unknown_idxs) in parts {
+            println!(
+                "{} part ({} equations, {} unknowns):",
+                label,
+                equation_idxs.len(),
+                unknown_idxs.len()
+            );
+            for &e in equation_idxs {
+                println!("   equation {}: {}", e, fn_names[e]);
+            }
+            for &u in unknown_idxs {
+                println!("   unknown {}: {}", u, self.unknown_field_names[u]);
+            }
+        }
+    }
+    /// Prints the permuted incidence matrix with residual function and unknown names (see `lower_tri_mat_string`).
+    pub fn print_lower_tri_mat(&self) {
+        println!(
+            "Lower block triangular matrix:\n{}",
+            self.lower_tri_mat_string()
+        );
+    }
+
+    /// The incidence matrix in block-triangular order, with residual function names on the rows and
+    /// unknown field names (written top to bottom) on the columns.
+    ///
+    /// Dependencies are marked `X`, non-finite sampled derivatives `?`.
+    pub fn lower_tri_mat_string(&self) -> String {
+        let structure = &self.state.block_structure;
+        let (n_eqs, n_unks) = self.state.binary_matrix.shape();
+        let rows = complete_order(&structure.row_order, n_eqs);
+        let cols = complete_order(&structure.col_order, n_unks);
+
+        let row_labels: Vec<String> = rows.iter().map(|&r| self.raw_res_fns.fn_label(r)).collect();
+        let label_width = row_labels
+            .iter()
+            .map(|label| label.chars().count())
+            .max()
+            .unwrap_or(0);
+        let col_names: Vec<Vec<char>> = cols
+            .iter()
+            .map(|&c| self.unknown_field_names[c].chars().collect())
+            .collect();
+        let header_height = col_names.iter().map(Vec::len).max().unwrap_or(0);
+
+        let mut out = String::new();
+        // column names bottom-aligned, one character per line
+        for line in 0..header_height {
+            out.push_str(&" ".repeat(label_width + 1));
+            for name in &col_names {
+                let offset = header_height - name.len();
+                out.push(' ');
+                out.push(if line >= offset {
+                    name[line - offset]
+                } else {
+                    ' '
+                });
+            }
+            out.push('\n');
+        }
+        for (&r, label) in rows.iter().zip(&row_labels) {
+            out.push_str(&format!("{label:>label_width$} "));
+            for &c in &cols {
+                let x = self.state.binary_matrix[(r, c)];
+                out.push(' ');
+                out.push(if x.is_nan() {
+                    '?'
+                } else if x != 0.0 {
+                    'X'
+                } else {
+                    '.'
+                });
+            }
+            out.push('\n');
+        }
+        out
+    }
+    pub fn print_block_structure(&self) {
+        println!("Lower block triangular structure:");
+        println!(
+            "   matching_size = {}",
+            self.state.block_structure.matching_size
+        );
+        println!(
+            "   block_sizes   = {:?}",
+            self.state.block_structure.block_sizes
+        );
+        println!("   row_order = {:?}", self.state.block_structure.row_order);
+        println!("   col_order = {:?}", self.state.block_structure.col_order);
+    }
+    pub fn print_permuted_function_names(&self) {
+        println!("Permuted residual function names:");
+        for &r in &self.state.block_structure.row_order {
+            println!("   {}", self.raw_res_fns.fn_label(r));
+        }
+    }
+    pub fn print_permuted_unknowns_names(&self) {
+        println!("Permuted unknowns names:");
 
         for &c in &self.state.block_structure.col_order {
             let unk_name = self.unknown_field_names[c];
@@ -251,170 +1275,1549 @@ where
         }
     }
 
+    /// Prints each block's equations and unknowns, and its conditioning if computed with
+    /// `with_block_conditioning` (see `solution_plan_string`).
     pub fn print_solution_plan(&self) {
-        self.state
-            .solution_plan
-            .print_solution_plan(&self.raw_res_fns, self.unknown_field_names);
+        print!("{}", self.solution_plan_string());
+    }
+
+    /// Each block's equations (labelled as in `lower_tri_mat_string`) and unknowns, followed by its
+    /// conditioning if computed with `with_block_conditioning`.
+    ///
+    /// For other layouts, format `SolutionPlan::with_names` yourself.
+    pub fn solution_plan_string(&self) -> String {
+        let labels: Vec<String> = (0..self.raw_res_fns.fn_names().len())
+            .map(|e| self.raw_res_fns.fn_label(e))
+            .collect();
+        let mut out = String::new();
+        for block in &self.state.solution_plan.blocks {
+            out.push_str(
+                &block
+                    .with_names(&labels, self.unknown_field_names)
+                    .to_string(),
+            );
+            if let Some(conditioning) = self.state.conditioning_of(block.block_idx) {
+                out.push_str(&format!("  {conditioning}\n"));
+            }
+        }
+        out
     }
 
     pub fn print_per_fn_residuals_at_params(&self, params: &U64) {
-        let residuals = self.raw_res_fn_engine.call(&params.to_vec());
+        print!("{}", self.per_fn_residuals_string(params));
+    }
 
-        println!("Per-function residuals at given params (plan order):");
+    /// The residual of every equation at `params`, grouped by block in plan order, as printed by
+    /// `print_per_fn_residuals_at_params`.
+    pub fn per_fn_residuals_string(&self, params: &U64) -> String {
+        let residuals = self.raw_res_fn_engine.call(&params.to_vec());
 
+        let mut out = "Per-function residuals at given params (plan order):\n".to_string();
         for block in self.state.solution_plan.blocks.iter() {
-            println!(" Block {}:", block.block_idx);
+            out.push_str(&format!(" Block {}:\n", block.block_idx));
             for &eq_idx in &block.equation_idxs {
-                let fn_name = self.raw_res_fns.fn_names()[eq_idx];
-                let res_val = residuals[eq_idx];
-                println!("   {}: {:.6}", fn_name, res_val);
+                let fn_name = self.raw_res_fns.display_name(eq_idx);
+                let meta = &self.raw_res_fns.meta()[eq_idx];
+                let tag = meta.tag.map(|t| format!(" #{t}")).unwrap_or_default();
+                out.push_str(&format!(
+                    "   {}{}: {}\n",
+                    fn_name,
+                    tag,
+                    meta.format_value(residuals[eq_idx])
+                ));
             }
         }
+        out
     }
 
-    /// Solves a single sub-problem using L-BFGS optimization.
-    pub fn solve_sub_problem_lbfgs(
+    /// The raw residual of every equation at `params`, keyed by residual function name and in
+    /// equation order, so that e.g. `residuals_by_name(&params)["jump_height_residual"]` needs no
+    /// index bookkeeping.
+    pub fn residuals_by_name(&self, params: &U64) -> IndexMap<&'static str, f64> {
+        let residuals = self.raw_res_fn_engine.call(&params.to_vec());
+        self.raw_res_fns
+            .fn_names()
+            .iter()
+            .copied()
+            .zip(residuals)
+            .collect()
+    }
+
+    /// Logs the per-function residuals at `params` (see `per_fn_residuals_string`) at `verbosity`.
+    fn log_per_fn_residuals(&self, params: &U64, verbosity: Verbosity) {
+        solver_info!(
+            verbosity,
+            "{}",
+            self.per_fn_residuals_string(params).trim_end()
+        );
+    }
+
+    #[cfg(feature = "argmin")]
+    fn lbfgs_sub_problem(
         &self,
         block: &SolutionBlock,
         initial_unknowns: &U64,
-    ) -> Result<U64, EqSysError> {
+        multipliers: &[f64],
+    ) -> Result<
+        SubProblem<
+            G64,
+            U64,
+            Gadfn,
+            Uadfn,
+            ResidTransWeighted<ResidTransUnscaledL2>,
+            ResidAggSum,
+            N,
+        >,
+        EqSysError,
+    > {
+        self.lbfgs_sub_problem_with_agg(block, initial_unknowns, multipliers, ResidAggSum {})
+    }
+
+    /// An L-BFGS sub-problem of `block` whose squared (weighted) residuals are combined by `agg`.
+    #[cfg(feature = "argmin")]
+    fn lbfgs_sub_problem_with_agg<A: ResidAggFnToScalarGen>(
+        &self,
+        block: &SolutionBlock,
+        initial_unknowns: &U64,
+        multipliers: &[f64],
+        agg: A,
+    ) -> Result<
+        SubProblem<G64, U64, Gadfn, Uadfn, ResidTransWeighted<ResidTransUnscaledL2>, A, N>,
+        EqSysError,
+    > {
         let l2_loss_gen = ResidTransUnscaledL2 {
             n: self.raw_res_fns.f64().len(),
         };
 
-        let subprob = SubProblem::new(
+        SubProblem::new(
             &self.raw_res_fns,
             &block,
             &self.givens_f64,
             &self.givens_adfn,
-            &initial_unknowns,
-            l2_loss_gen,
-            ResidAggSum {},
-            true,
-        );
+            self.scaling_center(initial_unknowns),
+            self.weighted_resid_trans(block, l2_loss_gen),
+            agg,
+            &self.param_scaling,
+            self.tikhonov_lambda,
+            self.unknown_constraints
+                .as_ref()
+                .map(|al| (al, multipliers)),
+            self.inequality_residuals.as_ref(),
+        )
+        .and_then(|sub_problem| sub_problem.starting_from(initial_unknowns))
+        .map(|sub_problem| sub_problem.with_box_constraints(self.box_constraints.clone()))
+        .map_err(|e| e.with_unknown_field_names(self.unknown_field_names))
+    }
 
-        Ok(subprob.solve_lbfgs()?)
+    #[cfg(feature = "argmin")]
+    fn simulated_annealing_sub_problem(
+        &self,
+        block: &SolutionBlock,
+        initial_unknowns: &U64,
+        multipliers: &[f64],
+    ) -> Result<
+        SubProblem<
+            G64,
+            U64,
+            Gadfn,
+            Uadfn,
+            ResidTransWeighted<ResidTransUnscaledL2>,
+            ResidAggSum,
+            N,
+        >,
+        EqSysError,
+    > {
+        self.lbfgs_sub_problem(block, initial_unknowns, multipliers)
+            .map(|sub_problem| {
+                sub_problem.with_simulated_annealing_config(SimulatedAnnealingConfig::default())
+            })
     }
 
-    pub fn solve_sub_problem_simulated_annealing(
+    #[cfg(feature = "argmin")]
+    fn gauss_newton_sub_problem(
         &self,
         block: &SolutionBlock,
         initial_unknowns: &U64,
-    ) -> Result<U64, EqSysError> {
+        multipliers: &[f64],
+    ) -> Result<
+        SubProblem<
+            G64,
+            U64,
+            Gadfn,
+            Uadfn,
+            ResidTransWeighted<ResidTransUnscaledL2>,
+            ResidNoOpGaussNewton,
+            N,
+        >,
+        EqSysError,
+    > {
         let l2_loss_gen = ResidTransUnscaledL2 {
             n: self.raw_res_fns.f64().len(),
         };
+        self.gauss_newton_sub_problem_with_loss(block, initial_unknowns, multipliers, l2_loss_gen)
+    }
 
-        let subprob = SubProblem::new(
+    /// A Gauss-Newton sub-problem of `block` whose (weighted) residuals go through `loss_gen`.
+    #[cfg(feature = "argmin")]
+    fn gauss_newton_sub_problem_with_loss<R: ResidTransHOF>(
+        &self,
+        block: &SolutionBlock,
+        initial_unknowns: &U64,
+        multipliers: &[f64],
+        loss_gen: R,
+    ) -> Result<
+        SubProblem<G64, U64, Gadfn, Uadfn, ResidTransWeighted<R>, ResidNoOpGaussNewton, N>,
+        EqSysError,
+    > {
+        SubProblem::new(
             &self.raw_res_fns,
             &block,
             &self.givens_f64,
             &self.givens_adfn,
-            &initial_unknowns,
-            l2_loss_gen,
-            ResidAggSum {},
-            true,
+            self.scaling_center(initial_unknowns),
+            self.weighted_resid_trans(block, loss_gen),
+            ResidNoOpGaussNewton::new_subprob(&block),
+            &self.param_scaling,
+            self.tikhonov_lambda,
+            self.unknown_constraints
+                .as_ref()
+                .map(|al| (al, multipliers)),
+            self.inequality_residuals.as_ref(),
         )
-        .with_simulated_annealing_config(SimulatedAnnealingConfig::default());
+        .and_then(|sub_problem| sub_problem.starting_from(initial_unknowns))
+        .map(|sub_problem| sub_problem.with_box_constraints(self.box_constraints.clone()))
+        .map_err(|e| e.with_unknown_field_names(self.unknown_field_names))
+    }
 
-        let best_params = subprob.solve_simulated_annealing()?;
+    fn least_squares_sub_problem(
+        &self,
+        block: &SolutionBlock,
+        initial_unknowns: &U64,
+        multipliers: &[f64],
+    ) -> Result<
+        SubProblem<
+            G64,
+            U64,
+            Gadfn,
+            Uadfn,
+            ResidTransWeighted<ResidTransIdentity>,
+            ResidNoOpGaussNewton,
+            N,
+        >,
+        EqSysError,
+    > {
+        SubProblem::new(
+            &self.raw_res_fns,
+            &block,
+            &self.givens_f64,
+            &self.givens_adfn,
+            self.scaling_center(initial_unknowns),
+            self.weighted_resid_trans(block, ResidTransIdentity::new(self.raw_res_fns.f64().len())),
+            ResidNoOpGaussNewton::new_subprob(&block),
+            &self.param_scaling,
+            self.tikhonov_lambda,
+            self.unknown_constraints
+                .as_ref()
+                .map(|al| (al, multipliers)),
+            self.inequality_residuals.as_ref(),
+        )
+        .and_then(|sub_problem| sub_problem.starting_from(initial_unknowns))
+        .map(|sub_problem| sub_problem.with_box_constraints(self.box_constraints.clone()))
+        .map_err(|e| e.with_unknown_field_names(self.unknown_field_names))
+    }
+
+    /// Solves a single sub-problem as a minimum-norm least-squares problem (see
+    /// `SubProblem::solve_min_norm_least_squares`).
+    ///
+    /// Intended for blocks with more unknowns than equations.
+    pub fn solve_sub_problem_min_norm(
+        &self,
+        block: &SolutionBlock,
+        initial_unknowns: &U64,
+    ) -> Result<U64, EqSysError> {
+        self.least_squares_sub_problem(block, initial_unknowns, &[])?
+            .solve_min_norm_least_squares()
+    }
+
+    /// Solves a single sub-problem in a weighted least-squares sense, using the weights set with
+    /// `with_least_squares_weights` (unweighted if none were set).
+    ///
+    /// Intended for blocks with more equations than unknowns.
+    pub fn solve_sub_problem_weighted_least_squares(
+        &self,
+        block: &SolutionBlock,
+        initial_unknowns: &U64,
+    ) -> Result<U64, EqSysError> {
+        self.least_squares_sub_problem(block, initial_unknowns, &[])?
+            .solve_weighted_least_squares(self.block_residual_weights(block).as_deref())
+    }
+
+    /// Solves a single sub-problem with Levenberg-Marquardt on its raw residuals (see
+    /// `SubProblem::solve_levenberg_marquardt`).
+    pub fn solve_sub_problem_levenberg_marquardt(
+        &self,
+        block: &SolutionBlock,
+        initial_unknowns: &U64,
+    ) -> Result<U64, EqSysError> {
+        self.least_squares_sub_problem(block, initial_unknowns, &[])?
+            .solve_levenberg_marquardt()
+    }
+
+    /// Solves a 1×1 or 2×2 sub-problem with closed-form damped Newton steps (see `SubProblem::solve_small_newton`).
+    pub fn solve_sub_problem_small_newton(
+        &self,
+        block: &SolutionBlock,
+        initial_unknowns: &U64,
+    ) -> Result<U64, EqSysError> {
+        self.least_squares_sub_problem(block, initial_unknowns, &[])?
+            .solve_small_newton()
+    }
+
+    /// Solves a single sub-problem with an external solver backend, which sees the block's raw
+    /// residuals and their Jacobian in opt space.
+    pub fn solve_sub_problem_external(
+        &self,
+        block: &SolutionBlock,
+        initial_unknowns: &U64,
+        solver: &dyn ExternalSolver,
+    ) -> Result<U64, EqSysError> {
+        self.least_squares_sub_problem(block, initial_unknowns, &[])?
+            .solve_external(solver)
+    }
+
+    /// Solves a single sub-problem using L-BFGS optimization.
+    #[cfg(feature = "argmin")]
+    pub fn solve_sub_problem_lbfgs(
+        &self,
+        block: &SolutionBlock,
+        initial_unknowns: &U64,
+    ) -> Result<U64, EqSysError> {
+        Ok(self
+            .lbfgs_sub_problem(block, initial_unknowns, &[])?
+            .solve_lbfgs()?)
+    }
+
+    #[cfg(feature = "argmin")]
+    pub fn solve_sub_problem_simulated_annealing(
+        &self,
+        block: &SolutionBlock,
+        initial_unknowns: &U64,
+    ) -> Result<U64, EqSysError> {
+        let best_params = self
+            .simulated_annealing_sub_problem(block, initial_unknowns, &[])?
+            .solve_simulated_annealing()?;
 
         // self.print_per_fn_residuals_at_params(&best_params);
 
         Ok(best_params)
     }
 
+    #[cfg(feature = "argmin")]
     pub fn solve_sub_problem_gauss_newton(
         &self,
         block: &SolutionBlock,
         initial_unknowns: &U64,
     ) -> Result<U64, EqSysError> {
-        let l2_loss_gen = ResidTransUnscaledL2 {
-            n: self.raw_res_fns.f64().len(),
+        let best_params = self
+            .gauss_newton_sub_problem(block, initial_unknowns, &[])?
+            .solve_gauss_newton()?;
+
+        Ok(best_params)
+    }
+
+    /// The solver `solve_block_default` runs.
+    #[cfg(feature = "argmin")]
+    const DEFAULT_BLOCK_SOLVER: BlockSolverKind = BlockSolverKind::GaussNewton;
+    /// The solver `solve_block_default` runs.
+    #[cfg(not(feature = "argmin"))]
+    const DEFAULT_BLOCK_SOLVER: BlockSolverKind = BlockSolverKind::LevenbergMarquardt;
+
+    /// Solves a square block with the default solver: Gauss-Newton (via argmin) with the `argmin`
+    /// feature, Levenberg-Marquardt otherwise.
+    ///
+    /// Returns the solution and what the solver reported about its run.
+    #[cfg(feature = "argmin")]
+    fn solve_block_default(
+        &self,
+        subs: &BlockSubProblems<'_, G64, U64, Gadfn, Uadfn, N>,
+        start: &U64,
+        options: &SolveOptions,
+        budget: &TimeBudget,
+    ) -> Result<(U64, SolverRunLogData), EqSysError> {
+        let sub_problem = subs
+            .gauss_newton(start)?
+            .with_solve_options(options, budget);
+        let best_params = sub_problem.solve_gauss_newton()?;
+        Ok((best_params, sub_problem.last_run()))
+    }
+
+    /// Solves a square block with the default solver: Gauss-Newton (via argmin) with the `argmin`
+    /// feature, Levenberg-Marquardt otherwise.
+    ///
+    /// Returns the solution and what the solver reported about its run.
+    #[cfg(not(feature = "argmin"))]
+    fn solve_block_default(
+        &self,
+        subs: &BlockSubProblems<'_, G64, U64, Gadfn, Uadfn, N>,
+        start: &U64,
+        options: &SolveOptions,
+        budget: &TimeBudget,
+    ) -> Result<(U64, SolverRunLogData), EqSysError> {
+        let sub_problem = subs
+            .least_squares(start)?
+            .with_solve_options(options, budget);
+        let best_params = sub_problem.solve_levenberg_marquardt()?;
+        Ok((best_params, sub_problem.last_run()))
+    }
+
+    /// Retries the default block solver on `block` from randomly perturbed copies of `start`,
+    /// returning the first successful solution, or the failure of every attempt.
+    fn solve_block_with_restarts(
+        &self,
+        subs: &BlockSubProblems<'_, G64, U64, Gadfn, Uadfn, N>,
+        start: &U64,
+        policy: &RestartPolicy,
+        rng: &mut StdRng,
+        options: &SolveOptions,
+        budget: &TimeBudget,
+    ) -> Result<(U64, SolverRunLogData), Vec<SolverFallback>> {
+        let mut failures = vec![];
+        for attempt in 1..=policy.max_restarts {
+            if budget.is_exhausted() {
+                break;
+            }
+
+            let perturbed_start = match subs.least_squares(start).and_then(|sub_problem| {
+                sub_problem.perturbed_initial_params(policy.perturbation_scale, rng)
+            }) {
+                Ok(perturbed_start) => perturbed_start,
+                Err(e) => {
+                    // the start or the policy is unusable, so every restart would fail the same way
+                    failures.push(SolverFallback {
+                        solver: BlockSolverKind::Restart,
+                        error: e.to_string(),
+                    });
+                    break;
+                }
+            };
+
+            match self.solve_block_default(subs, &perturbed_start, options, budget) {
+                Ok(soln) => return Ok(soln),
+                Err(e) => {
+                    solver_info!(
+                        options.verbosity,
+                        block_idx = subs.block().block_idx,
+                        attempt = attempt,
+                        ">>>>> Restart {}/{} failed for block {}: {:?}",
+                        attempt,
+                        policy.max_restarts,
+                        subs.block().block_idx,
+                        e
+                    );
+                    failures.push(SolverFallback {
+                        solver: BlockSolverKind::Restart,
+                        error: e.to_string(),
+                    });
+                }
+            }
+        }
+        Err(failures)
+    }
+
+    /// Replaces the givens, keeping the triangularization and solution plan.
+    ///
+    /// The plan only depends on which residuals involve which unknowns, so it stays valid as long
+    /// as the new givens don't switch residual functions onto different branches.
+    pub fn set_givens(&mut self, givens_f64: G64, givens_adfn: Gadfn) {
+        self.raw_res_fn_engine = raw_res_fn_engine(&givens_f64, &givens_adfn, &self.raw_res_fns);
+        self.givens_f64 = givens_f64;
+        self.givens_adfn = givens_adfn;
+        self.block_engines.clear();
+    }
+
+    /// Swaps in new givens (see `set_givens`) and re-runs `solve_system` warm-started from
+    /// `prev_solution`, without rebuilding the builder or re-triangularizing.
+    ///
+    /// Meant for interactive tweaking, where each small change of the givens moves the solution only a little.
+    pub fn resolve_with_givens(
+        &mut self,
+        new_givens_f64: G64,
+        new_givens_adfn: Gadfn,
+        prev_solution: &U64,
+    ) -> Result<SolveReport<U64>, EqSysError> {
+        self.resolve_with_givens_with_options(
+            new_givens_f64,
+            new_givens_adfn,
+            prev_solution,
+            &SolveOptions::default(),
+        )
+    }
+
+    /// Like `resolve_with_givens`, but solving with `solve_system_with_options`.
+    pub fn resolve_with_givens_with_options(
+        &mut self,
+        new_givens_f64: G64,
+        new_givens_adfn: Gadfn,
+        prev_solution: &U64,
+        options: &SolveOptions,
+    ) -> Result<SolveReport<U64>, EqSysError> {
+        self.set_givens(new_givens_f64, new_givens_adfn);
+        self.solve_system_with_options(prev_solution, options)
+    }
+
+    /// Like `resolve_with_givens`, but re-solves only the blocks the change of givens affects, with
+    /// default options; see `resolve_changed_givens_with_options`.
+    pub fn resolve_changed_givens(
+        &mut self,
+        new_givens_f64: G64,
+        new_givens_adfn: Gadfn,
+        prev_solution: &U64,
+    ) -> Result<SolveReport<U64>, EqSysError> {
+        self.resolve_changed_givens_with_options(
+            new_givens_f64,
+            new_givens_adfn,
+            prev_solution,
+            &SolveOptions::default(),
+        )
+    }
+
+    /// Like `resolve_with_givens_with_options`, but re-solves only the blocks the change of givens affects.
+    ///
+    /// An equation counts as affected when its residual at `prev_solution` changes with the new
+    /// givens. A block is re-solved when one of its equations is affected, or depends on an unknown
+    /// that an earlier re-solved block moved; every other block keeps its values from
+    /// `prev_solution`, which is assumed to solve the system for the previous givens. The
+    /// full-problem refinement and extra sweeps are skipped, and the report only lists the
+    /// re-solved blocks. With constraints among the unknowns (see `with_unknown_constraints`),
+    /// later augmented-Lagrangian iterations re-solve the whole plan.
+    ///
+    /// `prev_solution` is checked like any initial guess (see `solve_system_with_options`).
+    pub fn resolve_changed_givens_with_options(
+        &mut self,
+        new_givens_f64: G64,
+        new_givens_adfn: Gadfn,
+        prev_solution: &U64,
+        options: &SolveOptions,
+    ) -> Result<SolveReport<U64>, EqSysError> {
+        let prev_params = prev_solution.to_vec();
+        let old_residuals = self.raw_res_fn_engine.call(&prev_params);
+        self.set_givens(new_givens_f64, new_givens_adfn);
+        let new_residuals = self.raw_res_fn_engine.call(&prev_params);
+        let changed_eqs: Vec<bool> = old_residuals
+            .iter()
+            .zip(&new_residuals)
+            .map(|(old, new)| old != new)
+            .collect();
+        self.solve_system_with(prev_solution, options, Some(&changed_eqs))
+    }
+
+    /// Re-solves block `block_idx` of the solution plan starting from `unknowns`, leaving the
+    /// unknowns of every other block as they are, with default options; see
+    /// `solve_block_with_options`.
+    ///
+    /// Useful after tweaking a given that only feeds later blocks, or to retry a badly converged
+    /// block with another solver, without rerunning the entire plan.
+    pub fn solve_block(
+        &self,
+        block_idx: usize,
+        unknowns: &U64,
+        solver: Option<BlockSolverKind>,
+    ) -> Result<(U64, BlockReport), EqSysError> {
+        self.solve_block_with_options(block_idx, unknowns, solver, &SolveOptions::default())
+    }
+
+    /// Like `solve_block`, but with run limits and solver settings taken from `options`.
+    ///
+    /// With `solver: None` the block goes through the same escalation as in `solve_system`
+    /// (external solver, analytic Newton, default solver, restarts, simulated annealing). With
+    /// `Some(kind)` only that solver runs and its error is returned as-is; `External` uses
+    /// `options.external_solver`, and `Restart` uses `options.restart_policy` (or the default
+    /// policy).
+    pub fn solve_block_with_options(
+        &self,
+        block_idx: usize,
+        unknowns: &U64,
+        solver: Option<BlockSolverKind>,
+        options: &SolveOptions,
+    ) -> Result<(U64, BlockReport), EqSysError> {
+        let blocks = &self.state.solution_plan.blocks;
+        let block = blocks
+            .get(block_idx)
+            .ok_or(EqSysError::BlockIdxOutOfRange {
+                block_idx,
+                n_blocks: blocks.len(),
+            })?;
+        let budget = TimeBudget::start(options);
+        let _warnings = budget.warnings().collect_on_this_thread();
+
+        let Some(solver) = solver else {
+            let mut restart_rng =
+                StdRng::seed_from_u64(options.restart_policy.as_ref().map_or(0, |p| p.seed));
+            let ctx = SolveContext {
+                options,
+                budget: &budget,
+                multipliers: &[],
+            };
+            return self.solve_plan_block(block_idx, block, unknowns, ctx, &mut restart_rng);
         };
 
-        let subprob = SubProblem::new(
-            &self.raw_res_fns,
-            &block,
-            &self.givens_f64,
-            &self.givens_adfn,
-            &initial_unknowns,
-            l2_loss_gen,
-            ResidNoOpGaussNewton::new_subprob(&block),
-            true,
-        );
+        let subs = BlockSubProblems::new(self, block, unknowns, &[]);
+        let start_time = Instant::now();
+        let (best_params, run) =
+            self.run_block_solver(&subs, unknowns, solver, options, &budget)?;
+        self.warn_about_block_solution(&subs, &best_params, &budget);
+        let report = self.block_report(block, solver, vec![], run, &best_params, start_time);
+        Ok((best_params, report))
+    }
 
-        let best_params = subprob.solve_gauss_newton()?;
+    /// Runs exactly one solver on `block`, without falling back to any other. Returns the solution
+    /// and what the solver reported about its run.
+    fn run_block_solver(
+        &self,
+        subs: &BlockSubProblems<'_, G64, U64, Gadfn, Uadfn, N>,
+        start: &U64,
+        solver: BlockSolverKind,
+        options: &SolveOptions,
+        budget: &TimeBudget,
+    ) -> Result<(U64, SolverRunLogData), EqSysError> {
+        let block = subs.block();
+        match solver {
+            BlockSolverKind::WeightedLeastSquares => {
+                let sub_problem = subs
+                    .least_squares(start)?
+                    .with_solve_options(options, budget);
+                let best_params = sub_problem
+                    .solve_weighted_least_squares(self.block_residual_weights(block).as_deref())?;
+                Ok((best_params, sub_problem.last_run()))
+            }
+            BlockSolverKind::External => {
+                let external = options
+                    .external_solver
+                    .as_ref()
+                    .ok_or(EqSysError::BlockSolverUnavailable { solver })?;
+                let sub_problem = subs
+                    .least_squares(start)?
+                    .with_solve_options(options, budget);
+                let best_params = sub_problem.solve_external(external.as_ref())?;
+                Ok((best_params, sub_problem.last_run()))
+            }
+            BlockSolverKind::SmallNewton => {
+                let sub_problem = subs
+                    .least_squares(start)?
+                    .with_solve_options(options, budget);
+                let best_params = sub_problem.solve_small_newton()?;
+                Ok((best_params, sub_problem.last_run()))
+            }
+            BlockSolverKind::LevenbergMarquardt => {
+                let sub_problem = subs
+                    .least_squares(start)?
+                    .with_solve_options(options, budget);
+                let best_params = sub_problem.solve_levenberg_marquardt()?;
+                Ok((best_params, sub_problem.last_run()))
+            }
+            #[cfg(feature = "sparse")]
+            BlockSolverKind::SparseLevenbergMarquardt => {
+                let sub_problem = subs
+                    .least_squares(start)?
+                    .with_jacobian_sparsity(Some(JacobianSparsity::from_incidence(
+                        &self.state.binary_matrix,
+                        block,
+                    )))
+                    .with_solve_options(options, budget);
+                let best_params = sub_problem.solve_sparse_levenberg_marquardt()?;
+                Ok((best_params, sub_problem.last_run()))
+            }
+            #[cfg(not(feature = "sparse"))]
+            BlockSolverKind::SparseLevenbergMarquardt => {
+                Err(EqSysError::BlockSolverUnavailable { solver })
+            }
+            #[cfg(feature = "argmin")]
+            BlockSolverKind::GaussNewton => {
+                let sub_problem = subs
+                    .gauss_newton(start)?
+                    .with_solve_options(options, budget);
+                let best_params = sub_problem.solve_gauss_newton()?;
+                Ok((best_params, sub_problem.last_run()))
+            }
+            #[cfg(feature = "argmin")]
+            BlockSolverKind::SimulatedAnnealing => {
+                self.solve_block_annealed(subs, start, options, budget)
+            }
+            #[cfg(not(feature = "argmin"))]
+            BlockSolverKind::GaussNewton | BlockSolverKind::SimulatedAnnealing => {
+                Err(EqSysError::BlockSolverUnavailable { solver })
+            }
+            BlockSolverKind::Restart => {
+                let policy = options.restart_policy.clone().unwrap_or_default();
+                let mut rng = StdRng::seed_from_u64(policy.seed);
+                self.solve_block_with_restarts(subs, start, &policy, &mut rng, options, budget)
+                    .map_err(|failures| EqSysError::RestartsFailed {
+                        block_idx: block.block_idx,
+                        attempts: failures.len(),
+                    })
+            }
+        }
+    }
 
-        Ok(best_params)
+    /// Solves the system with default options; see `solve_system_with_options`.
+    pub fn solve_system(&self, initial_unknowns: &U64) -> Result<SolveReport<U64>, EqSysError> {
+        self.solve_system_with_options(initial_unknowns, &SolveOptions::default())
     }
 
-    pub fn solve_system(&self, initial_unknowns: &U64) -> Result<U64, EqSysError> {
-        let mut current_unknowns = initial_unknowns.clone();
+    /// Raw residuals of `block`'s equations at `params`, packaged with how the block was solved.
+    fn block_report(
+        &self,
+        block: &SolutionBlock,
+        solver: BlockSolverKind,
+        fallbacks: Vec<SolverFallback>,
+        run: SolverRunLogData,
+        params: &U64,
+        start_time: Instant,
+    ) -> BlockReport {
+        let residuals = self.raw_res_fn_engine.call(&params.to_vec());
+        BlockReport {
+            block_idx: block.block_idx,
+            sweep: 0,
+            solver,
+            fallbacks,
+            iterations: run.iterations,
+            termination: run.termination,
+            residuals: block.equation_idxs.iter().map(|&e| residuals[e]).collect(),
+            elapsed: start_time.elapsed(),
+            conditioning: self.state.conditioning_of(block.block_idx).cloned(),
+        }
+    }
 
-        for (i, block) in self.state.solution_plan.blocks.iter().enumerate() {
-            println!(
-                "\n\n################## Solving sub-problem {} ##################",
-                i
-            );
+    /// Warns about the accepted solution `params` of `subs`' block: unknowns pinned at a bound of
+    /// their scaling, and, if the builder computes block conditioning (see
+    /// `with_block_conditioning`), a near-singular Jacobian there.
+    fn warn_about_block_solution(
+        &self,
+        subs: &BlockSubProblems<'_, G64, U64, Gadfn, Uadfn, N>,
+        params: &U64,
+        budget: &TimeBudget,
+    ) {
+        let block = subs.block();
+        let warnings = budget.warnings();
+        let values = params.to_arr();
+        let domains = subs.scaling_domains();
+        for &idx in &block.unknown_idxs {
+            let LinkDomain { prior, lb, ub } = domains[idx];
+            let pinned_at = [lb, ub].into_iter().find(|&bound| {
+                bound.is_finite()
+                    && (values[idx] - bound).abs() <= PINNED_REL_TOL * (prior - bound).abs()
+            });
+            if let Some(bound) = pinned_at {
+                warnings.push(SolveWarning::PinnedAtScalingBound {
+                    block_idx: block.block_idx,
+                    unknown: self.unknown_field_names[idx],
+                    value: values[idx],
+                    bound,
+                });
+            }
+        }
 
-            self.state.solution_plan.print_solution_block(
+        if self.state.block_conditioning.is_some() {
+            let (_residuals, jacobian) = self.full_derivative(&values);
+            let block_jacobian = jacobian
+                .select_rows(&block.equation_idxs)
+                .select_columns(&block.unknown_idxs);
+            let conditioning = BlockConditioning::from_jacobian(block.block_idx, &block_jacobian);
+            if conditioning.is_near_singular() {
+                warnings.push(SolveWarning::NearSingularJacobian { conditioning });
+            }
+        }
+    }
+
+    /// Solves one block of the plan starting from `current_unknowns`, escalating through the
+    /// configured solvers (external, Gauss-Newton, restarts, simulated annealing) until one
+    /// succeeds.
+    fn solve_plan_block(
+        &self,
+        i: usize,
+        block: &SolutionBlock,
+        current_unknowns: &U64,
+        ctx: SolveContext<'_>,
+        restart_rng: &mut StdRng,
+    ) -> Result<(U64, BlockReport), EqSysError> {
+        let SolveContext {
+            options,
+            budget,
+            multipliers,
+        } = ctx;
+        let _span = solver_span!("block", block_idx = i);
+        solver_info!(
+            options.verbosity,
+            "\n\n################## Solving sub-problem {} ##################\n{}",
+            i,
+            self.state
+                .solution_plan
+                .solution_block_string(block, &self.raw_res_fns, self.unknown_field_names)
+                .trim_end()
+        );
+
+        let _warnings = budget.warnings().collect_on_this_thread();
+        let subs = BlockSubProblems::new(self, block, current_unknowns, multipliers);
+        let (best_params, report) =
+            self.escalate_block_solvers(i, &subs, current_unknowns, options, budget, restart_rng)?;
+        self.warn_about_block_solution(&subs, &best_params, budget);
+        Ok((best_params, report))
+    }
+
+    /// Solves `subs`' block starting from `current_unknowns` with the first of the configured
+    /// solvers (external, Gauss-Newton, restarts, simulated annealing) that succeeds.
+    fn escalate_block_solvers(
+        &self,
+        i: usize,
+        subs: &BlockSubProblems<'_, G64, U64, Gadfn, Uadfn, N>,
+        current_unknowns: &U64,
+        options: &SolveOptions,
+        budget: &TimeBudget,
+        restart_rng: &mut StdRng,
+    ) -> Result<(U64, BlockReport), EqSysError> {
+        let start_time = Instant::now();
+        let block = subs.block();
+
+        if !block.is_square() {
+            let sub_problem = subs
+                .least_squares(current_unknowns)?
+                .with_solve_options(options, budget);
+            let best_params = sub_problem
+                .solve_weighted_least_squares(self.block_residual_weights(block).as_deref())?;
+            self.log_per_fn_residuals(&best_params, options.verbosity);
+            let report = self.block_report(
                 block,
-                &self.raw_res_fns,
-                self.unknown_field_names,
+                BlockSolverKind::WeightedLeastSquares,
+                vec![],
+                sub_problem.last_run(),
+                &best_params,
+                start_time,
             );
+            return Ok((best_params, report));
+        }
 
-            let gn_soln = self.solve_sub_problem_gauss_newton(block, &current_unknowns);
+        let mut fallbacks = vec![];
 
-            if let Ok(best_params) = gn_soln {
-                current_unknowns = best_params;
-                continue;
-            } else if let Err(e) = &gn_soln {
-                println!(
-                    ">>>>> Gauss-Newton failed for sub-problem {}: {:?}. Trying Simulated Annealing",
-                    i, e
-                );
+        if let Some(solver) = &options.external_solver {
+            let sub_problem = subs
+                .least_squares(current_unknowns)?
+                .with_solve_options(options, budget);
+
+            match sub_problem.solve_external(solver.as_ref()) {
+                Ok(best_params) => {
+                    self.log_per_fn_residuals(&best_params, options.verbosity);
+                    let report = self.block_report(
+                        block,
+                        BlockSolverKind::External,
+                        fallbacks,
+                        sub_problem.last_run(),
+                        &best_params,
+                        start_time,
+                    );
+                    return Ok((best_params, report));
+                }
+                Err(e) => {
+                    solver_info!(
+                        options.verbosity,
+                        ">>>>> External solver {} failed for sub-problem {}: {:?}. Trying built-in solvers",
+                        solver.name(),
+                        i,
+                        e
+                    );
+                    fallbacks.push(SolverFallback {
+                        solver: BlockSolverKind::External,
+                        error: e.to_string(),
+                    });
+                }
             }
+        }
 
-            let sa_soln = self.solve_sub_problem_simulated_annealing(block, &current_unknowns);
+        let gn_start = match &options.grid_search {
+            Some(cfg) => subs.least_squares(current_unknowns)?.grid_search(cfg)?,
+            None => current_unknowns.clone(),
+        };
 
-            let sa_soln = match sa_soln {
-                Ok(best_params) => best_params,
+        if options.analytic_small_blocks && block.unknown_idxs.len() <= 2 {
+            let sub_problem = subs
+                .least_squares(&gn_start)?
+                .with_solve_options(options, budget);
+            match sub_problem.solve_small_newton() {
+                Ok(best_params) => {
+                    let report = self.block_report(
+                        block,
+                        BlockSolverKind::SmallNewton,
+                        fallbacks,
+                        sub_problem.last_run(),
+                        &best_params,
+                        start_time,
+                    );
+                    return Ok((best_params, report));
+                }
                 Err(e) => {
-                    println!(
-                        "    >>>>> Simulated Annealing also failed for sub-problem {}: {:?}",
-                        i, e
+                    solver_info!(
+                        options.verbosity,
+                        ">>>>> Analytic Newton failed for sub-problem {}: {:?}. Trying default block solver",
+                        i,
+                        e
                     );
-                    return Err(e);
+                    fallbacks.push(SolverFallback {
+                        solver: BlockSolverKind::SmallNewton,
+                        error: e.to_string(),
+                    });
                 }
-            };
-
-            // If we got an SA solution, refine it with Gauss-Newton
-            let refined_gn_soln = self.solve_sub_problem_gauss_newton(block, &sa_soln);
+            }
+        }
 
-            current_unknowns = match refined_gn_soln {
-                Ok(best_params) => best_params,
+        if options
+            .sparse_min_block_size
+            .is_some_and(|min_size| block.unknown_idxs.len() >= min_size)
+        {
+            let solver = BlockSolverKind::SparseLevenbergMarquardt;
+            match self.run_block_solver(subs, &gn_start, solver, options, budget) {
+                Ok((best_params, run)) => {
+                    let report =
+                        self.block_report(block, solver, fallbacks, run, &best_params, start_time);
+                    return Ok((best_params, report));
+                }
                 Err(e) => {
-                    panic!(
-                        "\n    >>>>> Gauss-Newton refinement after SA also failed for sub-problem {}: {:?}.",
-                        i, e
+                    solver_info!(
+                        options.verbosity,
+                        ">>>>> Sparse Levenberg-Marquardt failed for sub-problem {}: {:?}. Trying default block solver",
+                        i,
+                        e
                     );
-                    // sa_soln
+                    fallbacks.push(SolverFallback {
+                        solver,
+                        error: e.to_string(),
+                    });
                 }
+            }
+        }
+
+        let default_err = match self.solve_block_default(subs, &gn_start, options, budget) {
+            Ok((best_params, run)) => {
+                let report = self.block_report(
+                    block,
+                    Self::DEFAULT_BLOCK_SOLVER,
+                    fallbacks,
+                    run,
+                    &best_params,
+                    start_time,
+                );
+                return Ok((best_params, report));
+            }
+            Err(e) => {
+                solver_info!(
+                    options.verbosity,
+                    ">>>>> Block solver failed for sub-problem {}: {:?}",
+                    i,
+                    e
+                );
+                fallbacks.push(SolverFallback {
+                    solver: Self::DEFAULT_BLOCK_SOLVER,
+                    error: e.to_string(),
+                });
+                e
+            }
+        };
+
+        if let Some(policy) = &options.restart_policy {
+            let restart_soln = self.solve_block_with_restarts(
+                subs,
+                &gn_start,
+                policy,
+                restart_rng,
+                options,
+                budget,
+            );
+            match restart_soln {
+                Ok((best_params, run)) => {
+                    let report = self.block_report(
+                        block,
+                        BlockSolverKind::Restart,
+                        fallbacks,
+                        run,
+                        &best_params,
+                        start_time,
+                    );
+                    return Ok((best_params, report));
+                }
+                Err(restart_failures) => fallbacks.extend(restart_failures),
+            }
+        }
+
+        let (best_params, run) =
+            self.solve_block_last_resort(i, subs, current_unknowns, options, budget, default_err)?;
+        let report = self.block_report(
+            block,
+            BlockSolverKind::SimulatedAnnealing,
+            fallbacks,
+            run,
+            &best_params,
+            start_time,
+        );
+        Ok((best_params, report))
+    }
+
+    /// Last-resort global search for a block the local solvers failed on: simulated annealing
+    /// followed by a Gauss-Newton polish.
+    ///
+    /// Returns the solution, the total iterations of both runs, and how the polish ended.
+    #[cfg(feature = "argmin")]
+    fn solve_block_last_resort(
+        &self,
+        i: usize,
+        subs: &BlockSubProblems<'_, G64, U64, Gadfn, Uadfn, N>,
+        current_unknowns: &U64,
+        options: &SolveOptions,
+        budget: &TimeBudget,
+        _local_err: EqSysError,
+    ) -> Result<(U64, SolverRunLogData), EqSysError> {
+        solver_info!(
+            options.verbosity,
+            ">>>>> Trying Simulated Annealing for sub-problem {}",
+            i
+        );
+        self.solve_block_annealed(subs, current_unknowns, options, budget)
+    }
+
+    /// Simulated annealing on `block` followed by a Gauss-Newton polish.
+    ///
+    /// Returns the solution, the total iterations of both runs, and how the polish ended. If the
+    /// polish fails, `SolveOptions::polish_failure` decides between keeping the annealed solution
+    /// (with a warning) and failing the block.
+    #[cfg(feature = "argmin")]
+    fn solve_block_annealed(
+        &self,
+        subs: &BlockSubProblems<'_, G64, U64, Gadfn, Uadfn, N>,
+        start: &U64,
+        options: &SolveOptions,
+        budget: &TimeBudget,
+    ) -> Result<(U64, SolverRunLogData), EqSysError> {
+        let block = subs.block();
+        let sa_sub_problem = subs.annealing(start)?.with_solve_options(options, budget);
+
+        let sa_soln = match sa_sub_problem.solve_simulated_annealing() {
+            Ok(best_params) => best_params,
+            Err(e) => {
+                solver_info!(
+                    options.verbosity,
+                    "    >>>>> Simulated Annealing also failed for sub-problem {}: {:?}",
+                    block.block_idx,
+                    e
+                );
+                return Err(e);
+            }
+        };
+
+        // If we got an SA solution, refine it with Gauss-Newton
+        let gn_sub_problem = subs
+            .gauss_newton(&sa_soln)?
+            .with_solve_options(options, budget);
+
+        let sa_run = sa_sub_problem.last_run();
+        let (best_params, run) = match gn_sub_problem.solve_gauss_newton() {
+            Ok(best_params) => {
+                // the polish decides how the block ended; the iterations of both runs count
+                let gn_run = gn_sub_problem.last_run();
+                let run = SolverRunLogData {
+                    iterations: sa_run
+                        .iterations
+                        .zip(gn_run.iterations)
+                        .map(|(sa, gn)| sa + gn),
+                    termination: gn_run.termination,
+                };
+                (best_params, run)
+            }
+            Err(e) if options.polish_failure == PolishFailurePolicy::Fail => {
+                return Err(EqSysError::AnnealingPolishFailed {
+                    block_idx: block.block_idx,
+                    source: Box::new(e),
+                });
+            }
+            Err(e) => {
+                solver_info!(
+                    options.verbosity,
+                    "    >>>>> Gauss-Newton refinement after SA failed for sub-problem {}: {:?}. Keeping the SA solution",
+                    block.block_idx,
+                    e
+                );
+                budget.warnings().push(SolveWarning::AnnealingPolishFailed {
+                    block_idx: block.block_idx,
+                    error: e.to_string(),
+                });
+                (sa_soln, sa_run)
+            }
+        };
+
+        self.log_per_fn_residuals(&best_params, options.verbosity);
+        Ok((best_params, run))
+    }
+
+    /// Without the `argmin` feature there is no global fallback; the local solver's error is returned.
+    #[cfg(not(feature = "argmin"))]
+    fn solve_block_last_resort(
+        &self,
+        _i: usize,
+        _subs: &BlockSubProblems<'_, G64, U64, Gadfn, Uadfn, N>,
+        _current_unknowns: &U64,
+        _options: &SolveOptions,
+        _budget: &TimeBudget,
+        local_err: EqSysError,
+    ) -> Result<(U64, SolverRunLogData), EqSysError> {
+        Err(local_err)
+    }
+
+    /// Euclidean norm of all raw residuals at `params`.
+    fn full_residual_norm(&self, params: &U64) -> f64 {
+        self.raw_res_fn_engine
+            .call(&params.to_vec())
+            .iter()
+            .map(|r| r * r)
+            .sum::<f64>()
+            .sqrt()
+    }
+
+    /// Like `solve_system`, but with run limits taken from `options`.
+    ///
+    /// If `options.max_total_time` runs out partway through the plan, the params solved so far are
+    /// returned as-is (later blocks keep their initial values and the full-problem refinement is
+    /// skipped), and the report's `time_budget_exhausted` is set. The same happens when the
+    /// `options.progress` callback cancels the solve, which also sets the report's `cancelled`.
+    /// Fails with `EqSysError::InvalidInitialGuess` if `initial_unknowns` is non-finite, outside
+    /// its scaling's domain or box constraints, or gives a non-finite residual (see
+    /// `initial_guess_violations`), and with `EqSysError::NonFiniteResidual` if a derivative is
+    /// non-finite there.
+    ///
+    /// If a block fails after others were solved, the error is an `EqSysError::PartialSolution`
+    /// carrying the solved blocks' reports and the params solved so far, rather than the block's
+    /// bare error.
+    pub fn solve_system_with_options(
+        &self,
+        initial_unknowns: &U64,
+        options: &SolveOptions,
+    ) -> Result<SolveReport<U64>, EqSysError> {
+        self.solve_system_with(initial_unknowns, options, None)
+    }
+
+    /// `solve_system_with_options`, except that if `changed_eqs` is set, the first pass only
+    /// re-solves the blocks it affects (see `solve_changed_blocks`) instead of sweeping the whole
+    /// plan.
+    fn solve_system_with(
+        &self,
+        initial_unknowns: &U64,
+        options: &SolveOptions,
+        changed_eqs: Option<&[bool]>,
+    ) -> Result<SolveReport<U64>, EqSysError> {
+        self.check_initial_guess(initial_unknowns)?;
+        self.check_finite(initial_unknowns)?;
+        if let Some(cfg) = &options.grid_search {
+            cfg.validate()?;
+        }
+        if let Some(policy) = &options.restart_policy {
+            policy.validate()?;
+        }
+        let budget = TimeBudget::start(options);
+        let _warnings = budget.warnings().collect_on_this_thread();
+        let mut log = SolveLog::default();
+        let Some(al) = &self.unknown_constraints else {
+            log.outer_iterations = 1;
+            let ctx = SolveContext {
+                options,
+                budget: &budget,
+                multipliers: &[],
+            };
+            let solution = self.solve_pass(initial_unknowns, changed_eqs, ctx, &mut log)?;
+            return Ok(self.solve_report(solution, log, &budget));
+        };
+
+        // Augmented-Lagrangian outer loop: re-solve with updated multipliers until the
+        // constraints among the unknowns hold. New multipliers can move any block, so only
+        // the first pass is limited to `changed_eqs`.
+        let mut multipliers = al.initial_multipliers();
+        let mut current_unknowns = initial_unknowns.clone();
+        let mut violation = f64::INFINITY;
+        for outer in 0..al.max_outer_iters.max(1) {
+            let _span = solver_span!("outer_iteration", outer = outer);
+            log.outer_iterations += 1;
+            let changed_eqs = changed_eqs.filter(|_| outer == 0);
+            let ctx = SolveContext {
+                options,
+                budget: &budget,
+                multipliers: &multipliers,
+            };
+            current_unknowns = self.solve_pass(&current_unknowns, changed_eqs, ctx, &mut log)?;
+            violation = al.constraints.max_violation(&current_unknowns);
+            solver_info!(
+                options.verbosity,
+                violation = violation,
+                ">>>>> Augmented Lagrangian iteration {}: max constraint violation {:.6e}",
+                outer,
+                violation
+            );
+            if violation <= al.tol || budget.is_exhausted() {
+                break;
+            }
+            multipliers = al.updated_multipliers(&multipliers, &current_unknowns);
+        }
+        if violation.is_nan() || violation > al.tol {
+            budget.warnings().push(SolveWarning::ConstraintsViolated {
+                max_violation: violation,
+                tol: al.tol,
+                outer_iterations: log.outer_iterations,
+            });
+        }
+        Ok(self.solve_report(current_unknowns, log, &budget))
+    }
+
+    /// Packages a finished solve: the final residuals of every equation at `solution`, plus
+    /// everything accumulated in `log`.
+    fn solve_report(&self, solution: U64, log: SolveLog, budget: &TimeBudget) -> SolveReport<U64> {
+        SolveReport {
+            residuals: self.raw_res_fn_engine.call(&solution.to_vec()),
+            residual_names: self.raw_res_fns.fn_names().clone(),
+            solution,
+            blocks: log.blocks,
+            refinement_passes: log.refinement_passes,
+            outer_iterations: log.outer_iterations,
+            total_time: budget.elapsed(),
+            time_budget_exhausted: log.time_budget_exhausted,
+            cancelled: budget.is_cancelled(),
+            eval_cache: budget.eval_cache_stats(),
+            uncertainty: None,
+            conflicts: log.conflicts,
+            traces: budget.traces(),
+            warnings: budget.collected_warnings(),
+        }
+    }
+
+    /// One pass over the plan: `solve_system_once`, or `solve_changed_blocks` if `changed_eqs` is set.
+    fn solve_pass(
+        &self,
+        initial_unknowns: &U64,
+        changed_eqs: Option<&[bool]>,
+        ctx: SolveContext<'_>,
+        log: &mut SolveLog,
+    ) -> Result<U64, EqSysError> {
+        match changed_eqs {
+            Some(changed_eqs) => self.solve_changed_blocks(initial_unknowns, changed_eqs, ctx, log),
+            None => self.solve_system_once(initial_unknowns, ctx, log),
+        }
+    }
+
+    /// Re-solves, in plan order, the blocks with an equation in `changed_eqs` or depending on an
+    /// unknown that an earlier re-solved block moved, keeping every other block's values from
+    /// `initial_unknowns`.
+    ///
+    /// No extra sweeps or full-problem refinement.
+    fn solve_changed_blocks(
+        &self,
+        initial_unknowns: &U64,
+        changed_eqs: &[bool],
+        ctx: SolveContext<'_>,
+        log: &mut SolveLog,
+    ) -> Result<U64, EqSysError> {
+        let SolveContext {
+            options, budget, ..
+        } = ctx;
+        let mut restart_rng =
+            StdRng::seed_from_u64(options.restart_policy.as_ref().map_or(0, |p| p.seed));
+        let mut moved_unknowns = [false; N];
+        let mut current_unknowns = *initial_unknowns;
+        let n_blocks = self.state.solution_plan.blocks.len();
+
+        for (i, block) in self.state.solution_plan.blocks.iter().enumerate() {
+            let dirty = block.equation_idxs.iter().any(|&e| {
+                changed_eqs[e]
+                    || moved_unknowns
+                        .iter()
+                        .enumerate()
+                        .any(|(u, &moved)| moved && self.state.binary_matrix[(e, u)] != 0.0)
+            });
+            if !dirty {
+                continue;
+            }
+            if budget.is_exhausted() {
+                solver_info!(
+                    options.verbosity,
+                    ">>>>> Time budget exhausted before sub-problem {}; returning best params so far",
+                    i
+                );
+                log.time_budget_exhausted = true;
+                break;
+            }
+
+            budget.report_progress(SolveProgress::BlockStarted {
+                block_idx: i,
+                n_blocks,
+            });
+            let (best_params, report) = self
+                .solve_plan_block(i, block, &current_unknowns, ctx, &mut restart_rng)
+                .map_err(|e| log.block_failed(i, current_unknowns.to_vec(), e))?;
+            budget.report_progress(SolveProgress::BlockFinished {
+                block_idx: i,
+                n_blocks,
+                residual_norm: report.residual_norm(),
+                elapsed: report.elapsed,
+            });
+            log.blocks.push(report);
+            for (moved, (before, after)) in moved_unknowns.iter_mut().zip(
+                current_unknowns
+                    .to_arr()
+                    .into_iter()
+                    .zip(best_params.to_arr()),
+            ) {
+                *moved |= before != after;
+            }
+            current_unknowns = best_params;
+        }
+        Ok(current_unknowns)
+    }
+
+    /// One pass of block sweeps followed by full-problem refinement, with the constraint
+    /// multipliers (if any) held fixed.
+    fn solve_system_once(
+        &self,
+        initial_unknowns: &U64,
+        ctx: SolveContext<'_>,
+        log: &mut SolveLog,
+    ) -> Result<U64, EqSysError> {
+        let SolveContext {
+            options, budget, ..
+        } = ctx;
+        let mut current_unknowns = initial_unknowns.clone();
+        let mut restart_rng =
+            StdRng::seed_from_u64(options.restart_policy.as_ref().map_or(0, |p| p.seed));
+        let mut prev_residual_norm = f64::INFINITY;
+
+        for sweep in 0..=options.max_extra_sweeps {
+            let _span = solver_span!("sweep", sweep = sweep);
+            if sweep > 0 {
+                solver_info!(
+                    options.verbosity,
+                    residual_norm = prev_residual_norm,
+                    "\n\n################## Block sweep {} (residual norm {:.6e}) ##################",
+                    sweep,
+                    prev_residual_norm
+                );
+            }
+            let sweep_start_unknowns = current_unknowns.clone();
+
+            #[cfg(feature = "parallel")]
+            let completed = if options.parallel_blocks {
+                self.sweep_blocks_parallel(sweep, &mut current_unknowns, ctx, log)?
+            } else {
+                self.sweep_blocks(sweep, &mut current_unknowns, ctx, &mut restart_rng, log)?
             };
+            #[cfg(not(feature = "parallel"))]
+            let completed =
+                self.sweep_blocks(sweep, &mut current_unknowns, ctx, &mut restart_rng, log)?;
+            if !completed {
+                return Ok(current_unknowns);
+            }
 
-            self.print_per_fn_residuals_at_params(&current_unknowns);
+            // Stop sweeping once a sweep no longer improves the full-system residual norm,
+            // keeping the better of the last two results.
+            let residual_norm = self.full_residual_norm(&current_unknowns);
+            let improved = residual_norm < prev_residual_norm;
+            if !improved {
+                let got_worse = residual_norm.is_nan() || residual_norm > prev_residual_norm;
+                if sweep > 0 && got_worse {
+                    current_unknowns = sweep_start_unknowns;
+                }
+                break;
+            }
+            prev_residual_norm = residual_norm;
+            if residual_norm == 0.0 {
+                break;
+            }
         }
 
-        // Do a final fine-tuning pass over the full problem
-        println!("\n\n################## full-problem refinement ##################");
+        if budget.is_exhausted() {
+            solver_info!(
+                options.verbosity,
+                ">>>>> Time budget exhausted before full-problem refinement; skipping it"
+            );
+            log.time_budget_exhausted = true;
+            return Ok(current_unknowns);
+        }
 
-        let full_prob_block = SolutionBlock::new_fullprob(self.raw_res_fns.f64().len());
+        // Non-square blocks are solved in (weighted) least squares already; an unweighted
+        // full-problem pass would only undo their weighting.
+        if self
+            .state
+            .solution_plan
+            .blocks
+            .iter()
+            .any(|b| !b.is_square())
+        {
+            return Ok(current_unknowns);
+        }
 
-        current_unknowns = self.solve_sub_problem_lbfgs(&full_prob_block, &current_unknowns)?;
+        let refined = self.refine_full_problem(current_unknowns, ctx, log)?;
+        if let Some(tol) = options.refinement.conflict_tol {
+            log.conflicts = self.explain_conflicts(&refined, tol);
+        }
+        Ok(refined)
+    }
 
-        self.print_per_fn_residuals_at_params(&current_unknowns);
+    /// Solves every block of the plan once, in order, updating `current_unknowns` in place.
+    ///
+    /// Returns false if the time budget ran out first. If a block fails after others were solved,
+    /// the error is an `EqSysError::PartialSolution` with what was solved so far.
+    fn sweep_blocks(
+        &self,
+        sweep: usize,
+        current_unknowns: &mut U64,
+        ctx: SolveContext<'_>,
+        restart_rng: &mut StdRng,
+        log: &mut SolveLog,
+    ) -> Result<bool, EqSysError> {
+        let SolveContext {
+            options, budget, ..
+        } = ctx;
+        let n_blocks = self.state.solution_plan.blocks.len();
+        for (i, block) in self.state.solution_plan.blocks.iter().enumerate() {
+            if budget.is_exhausted() {
+                solver_info!(
+                    options.verbosity,
+                    ">>>>> Time budget exhausted before sub-problem {}; returning best params so far",
+                    i
+                );
+                log.time_budget_exhausted = true;
+                return Ok(false);
+            }
+
+            budget.report_progress(SolveProgress::BlockStarted {
+                block_idx: i,
+                n_blocks,
+            });
+            let (best_params, mut report) = self
+                .solve_plan_block(i, block, current_unknowns, ctx, restart_rng)
+                .map_err(|e| log.block_failed(i, current_unknowns.to_vec(), e))?;
+            budget.report_progress(SolveProgress::BlockFinished {
+                block_idx: i,
+                n_blocks,
+                residual_norm: report.residual_norm(),
+                elapsed: report.elapsed,
+            });
+            report.sweep = sweep;
+            log.blocks.push(report);
+            *current_unknowns = best_params;
+        }
+        Ok(true)
+    }
+
+    /// Runs the full-problem fine-tuning passes configured in `options.refinement`.
+    ///
+    /// A failed pass is reported like a failed block, with `n_blocks` as its index.
+    fn refine_full_problem(
+        &self,
+        mut current_unknowns: U64,
+        ctx: SolveContext<'_>,
+        log: &mut SolveLog,
+    ) -> Result<U64, EqSysError> {
+        let SolveContext {
+            options, budget, ..
+        } = ctx;
+        let cfg = &options.refinement;
+        if cfg.skip {
+            return Ok(current_unknowns);
+        }
+
+        let full_prob_block = SolutionBlock::new_fullprob_rect(self.raw_res_fns.f64().len(), N);
+        let n_blocks = self.state.solution_plan.blocks.len();
+
+        for pass in 0..cfg.passes {
+            if let Some(tol) = cfg.residual_tol {
+                let residuals = self.raw_res_fn_engine.call(&current_unknowns.to_vec());
+                if residuals.iter().all(|r| r.abs() < tol) {
+                    solver_info!(
+                        options.verbosity,
+                        ">>>>> All residuals within {:.1e}; refinement done",
+                        tol
+                    );
+                    break;
+                }
+            }
+            if budget.is_exhausted() {
+                solver_info!(
+                    options.verbosity,
+                    ">>>>> Time budget exhausted during full-problem refinement"
+                );
+                log.time_budget_exhausted = true;
+                break;
+            }
+
+            let _span = solver_span!("refinement", pass = pass);
+            solver_info!(
+                options.verbosity,
+                "\n\n################## full-problem refinement ({:?}, pass {}) ##################",
+                cfg.solver,
+                pass
+            );
+
+            current_unknowns = self
+                .refinement_pass(&full_prob_block, &current_unknowns, ctx)
+                .map_err(|e| log.block_failed(n_blocks, current_unknowns.to_vec(), e))?;
+            log.refinement_passes += 1;
+
+            self.log_per_fn_residuals(&current_unknowns, options.verbosity);
+        }
 
         Ok(current_unknowns)
     }
+
+    /// One full-problem refinement pass with `options.refinement.solver`, starting from `current_unknowns`.
+    fn refinement_pass(
+        &self,
+        full_prob_block: &SolutionBlock,
+        current_unknowns: &U64,
+        ctx: SolveContext<'_>,
+    ) -> Result<U64, EqSysError> {
+        let SolveContext {
+            options,
+            budget,
+            multipliers,
+        } = ctx;
+        let cfg = &options.refinement;
+        match cfg.solver {
+            #[cfg(feature = "argmin")]
+            RefinementSolver::Lbfgs => self
+                .lbfgs_sub_problem(full_prob_block, current_unknowns, multipliers)?
+                .with_solve_options(options, budget)
+                .with_root_check(false)
+                .solve_lbfgs(),
+            #[cfg(feature = "argmin")]
+            RefinementSolver::SmoothMaxLbfgs => self
+                .lbfgs_sub_problem_with_agg(
+                    full_prob_block,
+                    current_unknowns,
+                    multipliers,
+                    ResidAggSmoothMax::new(cfg.smooth_max_sharpness),
+                )?
+                .with_solve_options(options, budget)
+                .with_root_check(false)
+                .solve_lbfgs(),
+            #[cfg(not(feature = "argmin"))]
+            RefinementSolver::Lbfgs
+            | RefinementSolver::GaussNewton
+            | RefinementSolver::SmoothMaxLbfgs => self
+                .least_squares_sub_problem(full_prob_block, current_unknowns, multipliers)?
+                .with_solve_options(options, budget)
+                .with_root_check(false)
+                .solve_levenberg_marquardt(),
+            RefinementSolver::LevenbergMarquardt => self
+                .least_squares_sub_problem(full_prob_block, current_unknowns, multipliers)?
+                .with_solve_options(options, budget)
+                .with_root_check(false)
+                .solve_levenberg_marquardt(),
+            #[cfg(feature = "argmin")]
+            RefinementSolver::GaussNewton => self
+                .gauss_newton_sub_problem(full_prob_block, current_unknowns, multipliers)?
+                .with_solve_options(options, budget)
+                .with_root_check(false)
+                .solve_gauss_newton(),
+        }
+    }
 }
@@ -0,0 +1,212 @@
+use ad_trait::forward_ad::adfn::adfn;
+use rand::{SeedableRng, rngs::StdRng};
+use struct_to_array::StructToArray;
+
+use crate::{
+    equation_system::{EqSysSolutionPlan, EquationSystemBuilder},
+    prelude::*,
+};
+
+/// Settings of `EquationSystemBuilder::solve_monte_carlo`; the per-solve settings go in a separate `SolveOptions`.
+#[derive(Clone, Debug)]
+pub struct MonteCarloOptions {
+    /// Seed for the RNG handed to the given sampler, so runs are reproducible.
+    pub seed: u64,
+    /// A sample whose solve ends with a raw residual above this in absolute value counts as a
+    /// failure, since the system is then (locally) infeasible for its givens.
+    pub residual_tol: f64,
+}
+
+impl Default for MonteCarloOptions {
+    fn default() -> Self {
+        Self {
+            seed: 0,
+            residual_tol: 1e-6,
+        }
+    }
+}
+
+/// One successfully solved sample of a Monte Carlo run.
+#[derive(Clone, Debug)]
+pub struct MonteCarloSample<G, U> {
+    pub givens: G,
+    pub solution: U,
+    pub residual_norm: f64,
+}
+
+/// One sample of a Monte Carlo run whose solve failed, or ended outside
+/// `MonteCarloOptions::residual_tol` (`EqSysError::ResidualsBeyondTol`).
+#[derive(Debug)]
+pub struct MonteCarloFailure<G> {
+    pub givens: G,
+    pub error: EqSysError,
+}
+
+/// Distribution of one unknown over the solved samples of a Monte Carlo run.
+#[derive(Clone, Debug, PartialEq)]
+pub struct UnknownDistribution {
+    pub name: &'static str,
+    pub mean: f64,
+    pub std_dev: f64,
+    pub min: f64,
+    pub max: f64,
+}
+
+/// Result of `EquationSystemBuilder::solve_monte_carlo`.
+#[derive(Debug)]
+pub struct MonteCarloResult<G, U> {
+    /// The solution at the nominal givens, which every sample was warm-started from.
+    pub nominal: U,
+    pub samples: Vec<MonteCarloSample<G, U>>,
+    pub failures: Vec<MonteCarloFailure<G>>,
+    /// Unknown field names, in `StructToArray` order.
+    pub unknown_names: Vec<&'static str>,
+}
+
+impl<G, U> MonteCarloResult<G, U> {
+    pub fn n_runs(&self) -> usize {
+        self.samples.len() + self.failures.len()
+    }
+
+    /// Fraction of the samples that failed to solve.
+    pub fn failure_rate(&self) -> f64 {
+        if self.n_runs() == 0 {
+            0.0
+        } else {
+            self.failures.len() as f64 / self.n_runs() as f64
+        }
+    }
+
+    /// Mean, (sample) standard deviation and range of each unknown over the solved samples, in
+    /// `StructToArray` order. Empty if no sample solved.
+    pub fn distributions<const N: usize>(&self) -> Vec<UnknownDistribution>
+    where
+        U: StructToArray<f64, N>,
+    {
+        if self.samples.is_empty() {
+            return vec![];
+        }
+        let values: Vec<[f64; N]> = self.samples.iter().map(|s| s.solution.to_arr()).collect();
+        let n = values.len() as f64;
+        self.unknown_names
+            .iter()
+            .enumerate()
+            .map(|(u, &name)| {
+                let mean = values.iter().map(|v| v[u]).sum::<f64>() / n;
+                let sum_sq = values.iter().map(|v| (v[u] - mean).powi(2)).sum::<f64>();
+                UnknownDistribution {
+                    name,
+                    mean,
+                    std_dev: if values.len() > 1 {
+                        (sum_sq / (n - 1.0)).sqrt()
+                    } else {
+                        0.0
+                    },
+                    min: values.iter().map(|v| v[u]).fold(f64::INFINITY, f64::min),
+                    max: values
+                        .iter()
+                        .map(|v| v[u])
+                        .fold(f64::NEG_INFINITY, f64::max),
+                }
+            })
+            .collect()
+    }
+}
+
+impl<G64, U64, Gadfn, Uadfn, const N: usize>
+    EquationSystemBuilder<G64, U64, Gadfn, Uadfn, EqSysSolutionPlan, N>
+where
+    G64: GivenParamsFor<f64, N>,
+    U64: UnknownParamsFor<f64, N>,
+    Gadfn: GivenParamsFor<adfn<1>, N>,
+    Uadfn: UnknownParamsFor<adfn<1>, N>,
+{
+    /// Solves the system at the current (nominal) givens from `initial_unknowns`, then `n` times at
+    /// givens drawn by `given_sampler` from the nominal givens and a seeded RNG, each warm-started
+    /// from the nominal solution.
+    ///
+    /// Returns the solved samples, the failed ones (solve errors, or residuals beyond
+    /// `options.residual_tol`), and through `MonteCarloResult::distributions` the spread of each
+    /// unknown. A high failure rate marks a region of the design space where the system becomes
+    /// infeasible. The plan is reused throughout, and the nominal givens are restored afterwards;
+    /// only a failure of the nominal solve is returned as an error.
+    pub fn solve_monte_carlo<const NG: usize>(
+        &mut self,
+        n: usize,
+        given_sampler: impl FnMut(&G64, &mut StdRng) -> G64,
+        initial_unknowns: &U64,
+        options: &MonteCarloOptions,
+    ) -> Result<MonteCarloResult<G64, U64>, EqSysError>
+    where
+        G64: StructToArray<f64, NG>,
+        Gadfn: StructToArray<adfn<1>, NG>,
+    {
+        self.solve_monte_carlo_with_options(
+            n,
+            given_sampler,
+            initial_unknowns,
+            options,
+            &SolveOptions::default(),
+        )
+    }
+
+    /// `solve_monte_carlo`, running the nominal solve and every sample's solve with `solve_options`.
+    pub fn solve_monte_carlo_with_options<const NG: usize>(
+        &mut self,
+        n: usize,
+        mut given_sampler: impl FnMut(&G64, &mut StdRng) -> G64,
+        initial_unknowns: &U64,
+        options: &MonteCarloOptions,
+        solve_options: &SolveOptions,
+    ) -> Result<MonteCarloResult<G64, U64>, EqSysError>
+    where
+        G64: StructToArray<f64, NG>,
+        Gadfn: StructToArray<adfn<1>, NG>,
+    {
+        let nominal = self
+            .solve_system_with_options(initial_unknowns, solve_options)?
+            .solution;
+        let original_givens = (self.givens_f64, self.givens_adfn);
+        let mut rng = StdRng::seed_from_u64(options.seed);
+
+        let mut samples = vec![];
+        let mut failures = vec![];
+        for _ in 0..n {
+            let givens = given_sampler(&original_givens.0, &mut rng);
+            self.set_givens(
+                givens,
+                Gadfn::from_arr(givens.to_arr().map(adfn::<1>::constant)),
+            );
+            match self.solve_system_with_options(&nominal, solve_options) {
+                Ok(report)
+                    if report
+                        .residuals
+                        .iter()
+                        .all(|r| r.abs() <= options.residual_tol) =>
+                {
+                    samples.push(MonteCarloSample {
+                        givens,
+                        solution: report.solution,
+                        residual_norm: report.residual_norm(),
+                    });
+                }
+                Ok(report) => failures.push(MonteCarloFailure {
+                    givens,
+                    error: EqSysError::ResidualsBeyondTol {
+                        residual_norm: report.residual_norm(),
+                        tol: options.residual_tol,
+                    },
+                }),
+                Err(e) => failures.push(MonteCarloFailure { givens, error: e }),
+            }
+        }
+
+        self.set_givens(original_givens.0, original_givens.1);
+        Ok(MonteCarloResult {
+            nominal,
+            samples,
+            failures,
+            unknown_names: self.unknown_field_names.to_vec(),
+        })
+    }
+}
@@ -1,11 +1,11 @@
-use std::rc::Rc;
+use std::marker::PhantomData;
 
 use ad_trait::{AD, differentiable_function::DifferentiableFunctionTrait};
 
-use crate::prelude::*;
+use crate::{equation_system::shared::shared_fn, prelude::*};
 
-pub fn l2_loss_fns<T: AD>(n: usize) -> Vec<Rc<dyn Fn(T) -> T>> {
-    let f: Rc<dyn Fn(T) -> T> = Rc::new(|r: T| r * r);
+pub fn l2_loss_fns<T: AD>(n: usize) -> Vec<shared_fn!(Fn(T) -> T)> {
+    let f: shared_fn!(Fn(T) -> T) = Shared::new(|r: T| r * r);
     (0..n).map(|_| f.clone()).collect()
 }
 
@@ -19,14 +19,15 @@ pub fn sum_loss<T: AD>(residuals: Vec<T>) -> T {
 /// Forward and inverse parameter scaling functions between (constrained)model space and optimization (unconstrained) parameter space.
 #[derive(Clone)]
 pub struct ParamScaler<T: AD, const N: usize> {
-    model_to_opt: Rc<dyn Fn([T; N]) -> [T; N]>,
-    opt_to_model: Rc<dyn Fn([T; N]) -> [T; N]>,
+    model_to_opt: shared_fn!(Fn([T; N]) -> [T; N]),
+    opt_to_model: shared_fn!(Fn([T; N]) -> [T; N]),
 }
 
 impl<T: AD, const N: usize> ParamScaler<T, N> {
     /// Creates a ParamScaler from priors. The priors provide the f64 values which are
-    /// converted to the target AD type T for use in the link functions.
-    pub fn new_link_fns_from_priors<U>(priors: &U) -> Self
+    /// converted to the target AD type T for use in the link functions; zero priors get
+    /// the affine link with `zero_prior_scale` (see `default_link_fns_builder`).
+    pub fn new_link_fns_from_priors<U>(priors: &U, zero_prior_scale: f64) -> Self
     where
         U: UnknownParamsFor<f64, N>,
     {
@@ -38,12 +39,67 @@ impl<T: AD, const N: usize> ParamScaler<T, N> {
                 .collect::<Vec<_>>()
                 .try_into()
                 .unwrap(),
+            zero_prior_scale,
         );
         Self {
-            model_to_opt: Rc::new(model_to_opt),
-            opt_to_model: Rc::new(opt_to_model),
+            model_to_opt: Shared::new(model_to_opt),
+            opt_to_model: Shared::new(opt_to_model),
         }
     }
+    /// Creates a ParamScaler from per-field bounds and priors (see `bounded_link_fns_builder`).
+    pub fn new_link_fns_from_bounds(bounds: &[ParamBounds; N]) -> Self {
+        let (opt_to_model, model_to_opt) = bounded_link_fns_builder::<T, N>(*bounds);
+        Self {
+            model_to_opt: Shared::new(model_to_opt),
+            opt_to_model: Shared::new(opt_to_model),
+        }
+    }
+
+    /// Creates a ParamScaler with a per-field link function, centered on `priors` (see `spec_link_fns_builder`).
+    pub fn new_link_fns_from_specs<U>(specs: &[ScalingSpec; N], priors: &U) -> Self
+    where
+        U: UnknownParamsFor<f64, N>,
+    {
+        let priors = priors.to_arr().map(T::constant);
+        let (opt_to_model, model_to_opt) = spec_link_fns_builder::<T, N>(*specs, priors);
+        Self {
+            model_to_opt: Shared::new(model_to_opt),
+            opt_to_model: Shared::new(opt_to_model),
+        }
+    }
+
+    /// The scaler selected by `scaling`, or `None` for `ParamScaling::Unscaled`.
+    pub fn from_scaling<U>(scaling: &ParamScaling<N>, initial_unknowns: &U) -> Option<Self>
+    where
+        U: UnknownParamsFor<f64, N>,
+    {
+        match scaling {
+            ParamScaling::Unscaled => None,
+            ParamScaling::FromInitialUnknowns { zero_prior_scale } => Some(
+                Self::new_link_fns_from_priors(initial_unknowns, *zero_prior_scale),
+            ),
+            ParamScaling::FromBounds(bounds) => Some(Self::new_link_fns_from_bounds(bounds)),
+            ParamScaling::PerField(specs) => {
+                Some(Self::new_link_fns_from_specs(specs, initial_unknowns))
+            }
+        }
+    }
+
+    /// Like `from_scaling`, but fails with `EqSysError::InvalidScaling` if an initial unknown is
+    /// outside its link function's domain (see `check_link_domains`), instead of building links
+    /// that produce NaNs.
+    pub fn try_from_scaling<U>(
+        scaling: &ParamScaling<N>,
+        initial_unknowns: &U,
+    ) -> Result<Option<Self>, EqSysError>
+    where
+        U: UnknownParamsFor<f64, N>,
+    {
+        let priors = initial_unknowns.to_arr();
+        check_link_domains(&scaling.link_domains(&priors), &priors)?;
+        Ok(Self::from_scaling(scaling, initial_unknowns))
+    }
+
     pub fn model_to_opt(&self, model_params: [T; N]) -> [T; N] {
         (self.model_to_opt)(model_params)
     }
@@ -52,18 +108,42 @@ impl<T: AD, const N: usize> ParamScaler<T, N> {
     }
 }
 
+/// Tikhonov regularization toward the priors: adds `lambda * ||x - prior||^2` to the objective,
+/// where `x` are the opt-space values of `unknown_idxs` and `prior` their priors' opt-space images.
+///
+/// The default link functions map each prior to 0 in opt space, but identity, off-center affine and
+/// unscaled unknowns keep their priors where they are.
+#[derive(Clone, Debug)]
+pub struct TikhonovRegularization {
+    pub lambda: f64,
+    /// Full-problem indices of the unknowns to regularize (typically the free unknowns of one block).
+    pub unknown_idxs: Vec<usize>,
+    /// Opt-space images of the priors of `unknown_idxs`, in the same order.
+    pub prior_opt: Vec<f64>,
+}
+
 /// Container for objective function computing residuals from given residual functions. May or may not include residual transforms and residuals-to-loss functions.
 #[derive(Clone)]
 pub struct ObjectiveFunction<T: AD, G, U, R: ResidTransHOF, A: ResidAggHOF, const N: usize> {
     givens: G,
-    fns: Vec<Rc<fn(&G, &U) -> T>>,
+    fns: Vec<ResidualFn<G, U, T>>,
 
-    /// Optional vector of functions to transform each residual before computing loss. This is applied element-wise to the residuals vector, and is where weighting, scaling, loss transforms (L1, L2, etc) can be applied.
-    residual_transforms_gen: R,
+    /// Functions to transform each residual before computing loss, made once from the `R` generator
+    /// at construction.
+    ///
+    /// This is applied element-wise to the residuals vector, and is where weighting, scaling, loss
+    /// transforms (L1, L2, etc) can be applied.
+    residual_transforms: Vec<shared_fn!(Fn(T) -> T)>,
 
     /// Optional function to convert residuals vector to a single loss value. Typically this should probably be a summation or norm?
     residual_agg_gen: A,
+    /// The `A` generator's residual operator, made once at construction.
+    residual_operator: shared_fn!(Fn(Vec<T>) -> Vec<T>),
+    _resid_trans: PhantomData<R>,
     param_scaling: Option<ParamScaler<T, N>>,
+    regularization: Option<TikhonovRegularization>,
+    constraint_penalty: Option<ConstraintPenalty<T, U>>,
+    inequality_penalty: Option<InequalityPenaltyFns<T, G, U>>,
 }
 
 impl<T, G, U, R, A, const N: usize> ObjectiveFunction<T, G, U, R, A, N>
@@ -76,7 +156,7 @@ where
 {
     pub fn new(
         givens: &G,
-        fns: &Vec<Rc<fn(&G, &U) -> T>>,
+        fns: &Vec<ResidualFn<G, U, T>>,
         residual_transforms_gen: R,
         residual_agg_gen: A,
         param_scaling: Option<ParamScaler<T, N>>,
@@ -87,11 +167,37 @@ where
         Self {
             givens: givens.clone(),
             fns: fns.clone(),
-            residual_transforms_gen,
+            residual_transforms: residual_transforms_gen.make_loss_fns::<T>(),
+            residual_operator: residual_agg_gen.make_residual_operator_fn::<T>(),
             residual_agg_gen,
+            _resid_trans: PhantomData,
             param_scaling,
+            regularization: None,
+            constraint_penalty: None,
+            inequality_penalty: None,
         }
     }
+
+    /// Adds Tikhonov regularization toward the priors to this objective.
+    pub fn with_regularization(mut self, regularization: TikhonovRegularization) -> Self {
+        self.regularization = Some(regularization);
+        self
+    }
+
+    /// Adds augmented-Lagrangian penalty residuals for constraints among the unknowns to this objective.
+    pub fn with_constraint_penalty(mut self, constraint_penalty: ConstraintPenalty<T, U>) -> Self {
+        self.constraint_penalty = Some(constraint_penalty);
+        self
+    }
+
+    /// Adds penalty residuals for one-sided residuals `g <= 0` to this objective.
+    pub fn with_inequality_penalty(
+        mut self,
+        inequality_penalty: InequalityPenaltyFns<T, G, U>,
+    ) -> Self {
+        self.inequality_penalty = Some(inequality_penalty);
+        self
+    }
 }
 
 impl<T, G, U, R, A, const N: usize> DifferentiableFunctionTrait<T>
@@ -123,23 +229,38 @@ where
         // generate unknowns from model-space vector
         let unknowns = U::from_arr(p_model);
 
-        let residuals = self.fns.iter().map(|f| f(&self.givens, &unknowns));
-
-        let residual_transforms = self.residual_transforms_gen.make_loss_fns::<T>();
+        // transformed residuals, with room for the penalties so that a pass-through operator
+        // hands back the only allocation of the call
+        let mut residuals = Vec::with_capacity(self.num_outputs());
+        residuals.extend(
+            self.residual_transforms
+                .iter()
+                .zip(&self.fns)
+                .map(|(transform_fn, f)| transform_fn(f(&self.givens, &unknowns))),
+        );
+        let mut outputs = (self.residual_operator)(residuals);
 
-        let scaled_residuals: Vec<T> = residual_transforms
+        // Penalty residuals sqrt(lambda) * (x - prior), on the opt-space inputs
+        let regularization = self.regularization.iter().flat_map(|reg| {
+            let sqrt_lambda = T::constant(reg.lambda.sqrt());
+            reg.unknown_idxs
+                .iter()
+                .zip(&reg.prior_opt)
+                .map(move |(&idx, &prior)| sqrt_lambda * (inputs[idx] - T::constant(prior)))
+        });
+        let constraints = self
+            .constraint_penalty
             .iter()
-            .zip(residuals)
-            .map(|(transform_fn, r)| transform_fn(r).clone())
-            .collect();
-
-        self.residual_agg_gen.make_residual_operator_fn::<T>()(scaled_residuals)
-
-        // if let Some(loss_total_fn) = self.residual_agg_gen {
-        //     vec![loss_total_fn(scaled_residuals)]
-        // } else {
-        //     scaled_residuals.to_vec()
-        // }
+            .flat_map(|penalty| penalty.penalty_residuals(&unknowns));
+        let inequalities = self
+            .inequality_penalty
+            .iter()
+            .flat_map(|penalty| penalty.penalty_residuals(&self.givens, &unknowns));
+        self.residual_agg_gen.append_penalty_residuals(
+            &mut outputs,
+            regularization.chain(constraints).chain(inequalities),
+        );
+        outputs
     }
 
     fn num_inputs(&self) -> usize {
@@ -147,6 +268,18 @@ where
     }
 
     fn num_outputs(&self) -> usize {
-        self.residual_agg_gen.num_outputs()
+        let n_penalty = self
+            .regularization
+            .as_ref()
+            .map_or(0, |reg| reg.unknown_idxs.len())
+            + self
+                .constraint_penalty
+                .as_ref()
+                .map_or(0, ConstraintPenalty::len)
+            + self
+                .inequality_penalty
+                .as_ref()
+                .map_or(0, InequalityPenaltyFns::len);
+        self.residual_agg_gen.num_outputs() + self.residual_agg_gen.num_penalty_outputs(n_penalty)
     }
 }
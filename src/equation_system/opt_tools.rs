@@ -1,25 +1,74 @@
 use std::{cell::RefCell, rc::Rc};
 
-use argmin::core::{Error, IterState, KV, OptimizationResult, State, observers::Observe};
+use ad_trait::forward_ad::adfn::adfn;
+use argmin::core::{
+    Error, IterState, KV, OptimizationResult, State, TerminationReason, TerminationStatus,
+    observers::Observe,
+};
+use nalgebra::DVector;
 
-use crate::prelude::SubProblem;
+use crate::equation_system::clock::Instant;
+use crate::equation_system::optimization_trace::{IterationRecord, OptimizationTrace, TraceSink};
+use crate::equation_system::sub_problem::solve_subproblem::block_optimizer::BlockTermination;
+use crate::prelude::*;
 
-pub type OptRes<S, G64, U64, Gadfn, Uadfn, R, A, const N: usize, GR = (), J = ()> =
+pub(crate) type OptRes<S, G64, U64, Gadfn, Uadfn, R, A, const N: usize, GR = (), J = ()> =
     OptimizationResult<
         SubProblem<G64, U64, Gadfn, Uadfn, R, A, N>,
         S,
         IterState<nalgebra::DVector<f64>, GR, J, (), (), f64>,
     >;
 
+/// Why an argmin run stopped, in the solver-independent terms of `BlockReport`.
+pub(crate) fn block_termination(status: &TerminationStatus) -> BlockTermination {
+    match status {
+        TerminationStatus::Terminated(
+            TerminationReason::SolverConverged | TerminationReason::TargetCostReached,
+        ) => BlockTermination::Converged,
+        TerminationStatus::Terminated(TerminationReason::MaxItersReached) => {
+            BlockTermination::MaxIters
+        }
+        TerminationStatus::Terminated(TerminationReason::Timeout) => BlockTermination::Timeout,
+        TerminationStatus::Terminated(_) => BlockTermination::Stalled,
+        TerminationStatus::NotTerminated => BlockTermination::Unreported,
+    }
+}
+
 #[derive(Clone)]
-pub struct MyObserver {
+pub(crate) struct MyObserver {
     cost_history: Rc<RefCell<Vec<f64>>>,
+    trace: Option<Rc<RefCell<TraceRecorder>>>,
+}
+
+/// The in-progress trace of one solver run. Params are always kept until `MyObserver::finish`,
+/// since gradient norms are computed from them afterwards.
+struct TraceRecorder {
+    sink: TraceSink,
+    start: Instant,
+    trace: OptimizationTrace,
 }
 
 impl MyObserver {
     pub fn new() -> Self {
         Self {
             cost_history: Rc::new(RefCell::new(Vec::new())),
+            trace: None,
+        }
+    }
+
+    /// An observer that also records a trace of the run of `solver` on block `block_idx`, handed to `sink` by `finish`.
+    pub fn with_trace(sink: TraceSink, block_idx: usize, solver: &str) -> Self {
+        Self {
+            trace: Some(Rc::new(RefCell::new(TraceRecorder {
+                sink,
+                start: Instant::now(),
+                trace: OptimizationTrace {
+                    block_idx,
+                    solver: solver.to_string(),
+                    iterations: vec![],
+                },
+            }))),
+            ..Self::new()
         }
     }
 
@@ -30,14 +79,33 @@ impl MyObserver {
     pub fn observe_cost(&self, cost: f64) {
         self.cost_history.borrow_mut().push(cost);
     }
+
+    /// Completes the trace, if any, with gradient norms from `grad_norm` (of opt-space sub-problem
+    /// params) and hands it to the sink.
+    pub fn finish(&self, grad_norm: impl Fn(&DVector<f64>) -> f64) {
+        let Some(recorder) = &self.trace else {
+            return;
+        };
+        let mut recorder = recorder.borrow_mut();
+        let config = recorder.sink.config;
+        let mut trace = std::mem::take(&mut recorder.trace);
+        for record in &mut trace.iterations {
+            if config.grad_norms {
+                record.grad_norm = record.params.as_ref().map(&grad_norm);
+            }
+            if !config.params {
+                record.params = None;
+            }
+        }
+        recorder.sink.push(trace);
+    }
 }
 
 impl<I> Observe<I> for MyObserver
 where
     // Optional constraint on `I`. The `State` trait, which every state used in argmin needs to
     // implement, offers a range of methods which can be useful.
-    I: State,
-    I: State<Float = f64>,
+    I: State<Param = DVector<f64>, Float = f64>,
 {
     fn observe_init(&mut self, _name: &str, _state: &I, _kv: &KV) -> Result<(), Error> {
         Ok(())
@@ -46,6 +114,50 @@ where
     fn observe_iter(&mut self, state: &I, _kv: &KV) -> Result<(), Error> {
         self.observe_cost(state.get_cost());
 
+        if let Some(recorder) = &self.trace {
+            let mut recorder = recorder.borrow_mut();
+            let params = state.get_param().cloned();
+            let step_size = match (recorder.trace.iterations.last(), &params) {
+                (Some(prev), Some(params)) if recorder.sink.config.step_sizes => prev
+                    .params
+                    .as_ref()
+                    .map(|prev_params| (params - prev_params).norm()),
+                _ => None,
+            };
+            let elapsed = recorder.start.elapsed();
+            recorder.trace.iterations.push(IterationRecord {
+                iter: state.get_iter(),
+                cost: state.get_cost(),
+                params,
+                grad_norm: None,
+                step_size,
+                elapsed,
+            });
+        }
+
         Ok(())
     }
 }
+
+impl<G64, U64, Gadfn, Uadfn, R, A, const N: usize> SubProblem<G64, U64, Gadfn, Uadfn, R, A, N>
+where
+    G64: GivenParamsFor<f64, N>,
+    U64: UnknownParamsFor<f64, N>,
+    Gadfn: GivenParamsFor<adfn<1>, N>,
+    Uadfn: UnknownParamsFor<adfn<1>, N>,
+    R: ResidTransHOF,
+    A: ResidAggHOF,
+{
+    /// An observer for a run of `solver` on this sub-problem, tracing it if `SolveOptions::trace` is set.
+    pub(crate) fn observer(&self, solver: &str) -> MyObserver {
+        match &self.trace {
+            Some(sink) => MyObserver::with_trace(sink.clone(), self.block.block_idx, solver),
+            None => MyObserver::new(),
+        }
+    }
+
+    /// Hands the trace recorded by `observer`, if any, to the solve's trace sink.
+    pub(crate) fn finish_trace(&self, observer: &MyObserver) {
+        observer.finish(|p| self.subprob_grad_norm(p));
+    }
+}
@@ -0,0 +1,260 @@
+use std::borrow::Cow;
+use std::fmt::Write;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use nalgebra::DVector;
+
+/// What gets recorded per iteration of the argmin block solvers when `SolveOptions::trace` is set,
+/// on top of the cost and timestamp.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TraceConfig {
+    /// The sub-problem's opt-space param vector.
+    pub params: bool,
+    /// Norm of the opt-space gradient of the block objective, computed after the run.
+    pub grad_norms: bool,
+    /// Length of the opt-space step from the previous recorded iteration.
+    pub step_sizes: bool,
+}
+
+impl TraceConfig {
+    /// Records everything.
+    pub fn all() -> Self {
+        Self {
+            params: true,
+            grad_norms: true,
+            step_sizes: true,
+        }
+    }
+
+    pub fn with_params(mut self) -> Self {
+        self.params = true;
+        self
+    }
+
+    pub fn with_grad_norms(mut self) -> Self {
+        self.grad_norms = true;
+        self
+    }
+
+    pub fn with_step_sizes(mut self) -> Self {
+        self.step_sizes = true;
+        self
+    }
+}
+
+/// One recorded iteration of a block solver run. Fields not enabled in the `TraceConfig` are `None`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct IterationRecord {
+    pub iter: u64,
+    pub cost: f64,
+    pub params: Option<DVector<f64>>,
+    pub grad_norm: Option<f64>,
+    pub step_size: Option<f64>,
+    /// Wall-clock time since the solver run started.
+    pub elapsed: Duration,
+}
+
+/// Per-iteration history of one argmin solver run on a block.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct OptimizationTrace {
+    pub block_idx: usize,
+    pub solver: String,
+    pub iterations: Vec<IterationRecord>,
+}
+
+impl OptimizationTrace {
+    pub fn costs(&self) -> Vec<f64> {
+        self.iterations.iter().map(|r| r.cost).collect()
+    }
+
+    /// Recorded param vectors; empty unless `TraceConfig::params` was set.
+    pub fn params(&self) -> Vec<&DVector<f64>> {
+        self.iterations
+            .iter()
+            .filter_map(|r| r.params.as_ref())
+            .collect()
+    }
+
+    /// Recorded gradient norms; empty unless `TraceConfig::grad_norms` was set.
+    pub fn grad_norms(&self) -> Vec<f64> {
+        self.iterations.iter().filter_map(|r| r.grad_norm).collect()
+    }
+
+    /// Recorded step sizes, from the second iteration on; empty unless `TraceConfig::step_sizes` was set.
+    pub fn step_sizes(&self) -> Vec<f64> {
+        self.iterations.iter().filter_map(|r| r.step_size).collect()
+    }
+
+    pub fn timestamps(&self) -> Vec<Duration> {
+        self.iterations.iter().map(|r| r.elapsed).collect()
+    }
+
+    /// This run's history as CSV; see `traces_to_csv`.
+    pub fn to_csv(&self) -> String {
+        traces_to_csv(std::slice::from_ref(self))
+    }
+
+    /// This run's history as JSON; see `traces_to_json`.
+    pub fn to_json(&self) -> String {
+        traces_to_json(std::slice::from_ref(self))
+    }
+}
+
+/// Iteration histories as CSV with one row per recorded iteration: `run` (index into `traces`),
+/// `block_idx`, `solver`, `iter`, `elapsed_s`, `cost`, `grad_norm`, `step_size`, then `param_0`,
+/// `param_1`, ... up to the largest block.
+///
+/// Solver names with commas, quotes or line breaks are quoted, fields that weren't recorded are
+/// left empty, and non-finite values are written as `NaN` / `inf` / `-inf`, so the file loads
+/// straight into e.g. `pandas.read_csv`.
+pub fn traces_to_csv(traces: &[OptimizationTrace]) -> String {
+    let n_params = traces
+        .iter()
+        .flat_map(|t| &t.iterations)
+        .filter_map(|r| r.params.as_ref().map(|p| p.len()))
+        .max()
+        .unwrap_or(0);
+
+    let mut csv = String::from("run,block_idx,solver,iter,elapsed_s,cost,grad_norm,step_size");
+    for i in 0..n_params {
+        write!(csv, ",param_{i}").unwrap();
+    }
+    csv.push('\n');
+
+    let opt = |x: Option<f64>| x.map_or_else(String::new, |x| format!("{x:?}"));
+    for (run, trace) in traces.iter().enumerate() {
+        for record in &trace.iterations {
+            write!(
+                csv,
+                "{run},{},{},{},{:?},{:?},{},{}",
+                trace.block_idx,
+                csv_field(&trace.solver),
+                record.iter,
+                record.elapsed.as_secs_f64(),
+                record.cost,
+                opt(record.grad_norm),
+                opt(record.step_size)
+            )
+            .unwrap();
+            for i in 0..n_params {
+                let param = record.params.as_ref().and_then(|p| p.get(i).copied());
+                write!(csv, ",{}", opt(param)).unwrap();
+            }
+            csv.push('\n');
+        }
+    }
+    csv
+}
+
+/// Iteration histories as a JSON array with one object per solver run (`block_idx`, `solver`,
+/// `iterations`), each iteration an object with `iter`, `elapsed_s`, `cost`, `grad_norm`,
+/// `step_size` and `params`.
+///
+/// Fields that weren't recorded, and non-finite values, are `null`.
+pub fn traces_to_json(traces: &[OptimizationTrace]) -> String {
+    let mut json = String::from("[");
+    for (run, trace) in traces.iter().enumerate() {
+        if run > 0 {
+            json.push(',');
+        }
+        write!(
+            json,
+            "\n  {{\"block_idx\": {}, \"solver\": {}, \"iterations\": [",
+            trace.block_idx,
+            json_string(&trace.solver)
+        )
+        .unwrap();
+        for (i, record) in trace.iterations.iter().enumerate() {
+            if i > 0 {
+                json.push(',');
+            }
+            let params = record.params.as_ref().map_or_else(
+                || "null".to_string(),
+                |p| {
+                    let values: Vec<String> = p.iter().map(|&x| json_number(Some(x))).collect();
+                    format!("[{}]", values.join(", "))
+                },
+            );
+            write!(
+                json,
+                "\n    {{\"iter\": {}, \"elapsed_s\": {}, \"cost\": {}, \"grad_norm\": {}, \"step_size\": {}, \"params\": {}}}",
+                record.iter,
+                json_number(Some(record.elapsed.as_secs_f64())),
+                json_number(Some(record.cost)),
+                json_number(record.grad_norm),
+                json_number(record.step_size),
+                params
+            )
+            .unwrap();
+        }
+        json.push_str(if trace.iterations.is_empty() {
+            "]}"
+        } else {
+            "\n  ]}"
+        });
+    }
+    json.push_str(if traces.is_empty() { "]" } else { "\n]" });
+    json
+}
+
+/// `field` as a CSV field (RFC 4180): quoted, with its quotes doubled, if it contains a comma, quote or line break.
+fn csv_field(field: &str) -> Cow<'_, str> {
+    if field.contains([',', '"', '\n', '\r']) {
+        Cow::Owned(format!("\"{}\"", field.replace('"', "\"\"")))
+    } else {
+        Cow::Borrowed(field)
+    }
+}
+
+/// `s` as a quoted JSON string, with quotes, backslashes and control characters escaped.
+fn json_string(s: &str) -> String {
+    let mut json = String::with_capacity(s.len() + 2);
+    json.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            c if c.is_control() => write!(json, "\\u{:04x}", c as u32).unwrap(),
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+    json
+}
+
+/// `x` as a JSON number, or `null` if it is missing or not finite (JSON has no NaN or infinity).
+fn json_number(x: Option<f64>) -> String {
+    match x {
+        Some(x) if x.is_finite() => format!("{x:?}"),
+        _ => "null".to_string(),
+    }
+}
+
+/// Collects the traces of every solver run of one `solve_system` call, shared between its sub-problems.
+#[derive(Clone)]
+pub(crate) struct TraceSink {
+    pub(crate) config: TraceConfig,
+    traces: Arc<Mutex<Vec<OptimizationTrace>>>,
+}
+
+impl TraceSink {
+    pub(crate) fn new(config: TraceConfig) -> Self {
+        Self {
+            config,
+            traces: Arc::default(),
+        }
+    }
+
+    pub(crate) fn push(&self, trace: OptimizationTrace) {
+        self.traces.lock().unwrap().push(trace);
+    }
+
+    /// The traces collected so far, in the order the runs finished.
+    pub(crate) fn traces(&self) -> Vec<OptimizationTrace> {
+        self.traces.lock().unwrap().clone()
+    }
+}
@@ -0,0 +1,123 @@
+use ad_trait::forward_ad::adfn::adfn;
+use rand::{SeedableRng, rngs::StdRng};
+use rayon::prelude::*;
+use struct_to_array::StructToArray;
+
+use crate::{
+    equation_system::{
+        EqSysSolutionPlan, EquationSystemBuilder, logging::solver_info,
+        solve_options::SolveContext, solve_report::SolveLog,
+    },
+    prelude::*,
+};
+
+/// Concurrent solving of independent blocks of the plan (`parallel` feature).
+impl<G64, U64, Gadfn, Uadfn, const N: usize>
+    EquationSystemBuilder<G64, U64, Gadfn, Uadfn, EqSysSolutionPlan, N>
+where
+    G64: GivenParamsFor<f64, N>,
+    U64: UnknownParamsFor<f64, N>,
+    Gadfn: GivenParamsFor<adfn<1>, N>,
+    Uadfn: UnknownParamsFor<adfn<1>, N>,
+{
+    /// Groups the plan's blocks (by index) into levels of their dependency DAG: a block's level is
+    /// one more than the highest level of the blocks whose unknowns its equations use, so blocks of
+    /// the same level don't depend on each other and can be solved concurrently.
+    pub fn block_levels(&self) -> Vec<Vec<usize>> {
+        let blocks = &self.state.solution_plan.blocks;
+        let mut block_level = vec![0; blocks.len()];
+        let mut levels: Vec<Vec<usize>> = vec![];
+        for (i, block) in blocks.iter().enumerate() {
+            let level = (0..i)
+                .filter(|&j| self.block_uses(block, &blocks[j]))
+                .map(|j| block_level[j] + 1)
+                .max()
+                .unwrap_or(0);
+            block_level[i] = level;
+            if level == levels.len() {
+                levels.push(vec![]);
+            }
+            levels[level].push(i);
+        }
+        levels
+    }
+
+    /// Like `sweep_blocks`, but solves the blocks of each level (see `block_levels`) concurrently
+    /// on the rayon thread pool.
+    ///
+    /// Restarts use one RNG per block, seeded from the restart policy's seed and the block index,
+    /// so that the result doesn't depend on thread scheduling.
+    pub(super) fn sweep_blocks_parallel(
+        &self,
+        sweep: usize,
+        current_unknowns: &mut U64,
+        ctx: SolveContext<'_>,
+        log: &mut SolveLog,
+    ) -> Result<bool, EqSysError> {
+        let SolveContext {
+            options, budget, ..
+        } = ctx;
+        let blocks = &self.state.solution_plan.blocks;
+        let seed = options.restart_policy.as_ref().map_or(0, |p| p.seed);
+        for level in self.block_levels() {
+            if budget.is_exhausted() {
+                solver_info!(
+                    options.verbosity,
+                    ">>>>> Time budget exhausted before sub-problems {:?}; returning best params so far",
+                    level
+                );
+                log.time_budget_exhausted = true;
+                return Ok(false);
+            }
+
+            let start = *current_unknowns;
+            let solved = level
+                .par_iter()
+                .map(|&i| {
+                    // residual functions evaluated on this worker thread report to this solve
+                    let _warnings = budget.warnings().collect_on_this_thread();
+                    let mut restart_rng = StdRng::seed_from_u64(seed.wrapping_add(i as u64));
+                    budget.report_progress(SolveProgress::BlockStarted {
+                        block_idx: i,
+                        n_blocks: blocks.len(),
+                    });
+                    let (best_params, report) = self
+                        .solve_plan_block(i, &blocks[i], &start, ctx, &mut restart_rng)
+                        .map_err(|e| (i, e))?;
+                    budget.report_progress(SolveProgress::BlockFinished {
+                        block_idx: i,
+                        n_blocks: blocks.len(),
+                        residual_norm: report.residual_norm(),
+                        elapsed: report.elapsed,
+                    });
+                    Ok((best_params, report))
+                })
+                .collect::<Vec<Result<_, (usize, EqSysError)>>>();
+
+            // each block only moves its own unknowns; blocks of the level that succeeded are kept
+            // even if another one failed
+            let mut merged = current_unknowns.to_arr();
+            let mut failure = None;
+            for (&i, result) in level.iter().zip(solved) {
+                match result {
+                    Ok((best_params, mut report)) => {
+                        let best = best_params.to_arr();
+                        for &u in &blocks[i].unknown_idxs {
+                            merged[u] = best[u];
+                        }
+                        report.sweep = sweep;
+                        log.blocks.push(report);
+                    }
+                    Err(failed) => {
+                        failure.get_or_insert(failed);
+                    }
+                }
+            }
+            *current_unknowns = U64::from_arr(merged);
+            if let Some((i, e)) = failure {
+                return Err(log.block_failed(i, current_unknowns.to_arr().to_vec(), e));
+            }
+        }
+        Ok(true)
+    }
+}
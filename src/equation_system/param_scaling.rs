@@ -1,5 +1,228 @@
 use ad_trait::AD;
 use nalgebra::ComplexField;
+use struct_to_array::StructToArray;
+
+use crate::error::EqSysError;
+
+/// Lower bound, prior and upper bound of one unknown, in model space. Either bound may be infinite.
+///
+/// Used per field as `U<ParamBounds>` (e.g. `MyUnknowns<ParamBounds>`) with `EquationSystemBuilder::with_param_bounds`.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ParamBounds {
+    pub lb: f64,
+    pub prior: f64,
+    pub ub: f64,
+}
+
+impl ParamBounds {
+    pub fn new(lb: f64, prior: f64, ub: f64) -> Self {
+        Self { lb, prior, ub }
+    }
+
+    /// True if `lb < prior < ub` (bounds may be infinite, the prior must be finite).
+    pub fn is_valid(&self) -> bool {
+        self.prior.is_finite() && self.lb < self.prior && self.prior < self.ub
+    }
+}
+
+/// Unknowns set to the priors of `bounds` (e.g. a `MyUnknowns<ParamBounds>`), as a starting point
+/// that lies inside the bounds by construction.
+pub fn unknowns_from_priors<U, B, const N: usize>(bounds: &B) -> U
+where
+    U: StructToArray<f64, N>,
+    B: StructToArray<ParamBounds, N>,
+{
+    U::from_arr(bounds.to_arr().map(|b| b.prior))
+}
+
+/// How sub-problems map the unknowns between model space and optimization space.
+#[derive(Clone, Debug)]
+pub enum ParamScaling<const N: usize> {
+    /// Optimize directly in model space.
+    Unscaled,
+    /// Scaled-log links centered on each sub-problem's initial unknowns, with lower bounds at 1% of
+    /// the priors' magnitudes, and `p / zero_prior_scale` for unknowns whose initial value is
+    /// exactly 0 (see `default_link_fns_builder`).
+    FromInitialUnknowns { zero_prior_scale: f64 },
+    /// Per-field links from explicit bounds and priors (see `bounded_link_fns_builder`).
+    FromBounds([ParamBounds; N]),
+    /// Per-field link functions centered on each sub-problem's initial unknowns (see `spec_link_fns_builder`).
+    PerField([ScalingSpec; N]),
+}
+
+impl<const N: usize> Default for ParamScaling<N> {
+    fn default() -> Self {
+        ParamScaling::FromInitialUnknowns {
+            zero_prior_scale: 1.0,
+        }
+    }
+}
+
+impl<const N: usize> ParamScaling<N> {
+    /// The domain of unknown `idx`'s link function when centered on `prior` (for `FromBounds`, the
+    /// bounds' own prior is used instead).
+    pub fn link_domain(&self, idx: usize, prior: f64) -> LinkDomain {
+        let (lb, ub) = match self {
+            ParamScaling::FromInitialUnknowns { .. } => log_link_interval(prior),
+            ParamScaling::FromBounds(bounds) => {
+                let ParamBounds { lb, prior, ub } = bounds[idx];
+                return LinkDomain { prior, lb, ub };
+            }
+            ParamScaling::PerField(specs) => match specs[idx] {
+                ScalingSpec::LogPositive => (prior * 0.01, f64::INFINITY),
+                ScalingSpec::LogNegative => (f64::NEG_INFINITY, prior * 0.01),
+                ScalingSpec::Logit { lb, ub } => (lb, ub),
+                _ => (f64::NEG_INFINITY, f64::INFINITY),
+            },
+            ParamScaling::Unscaled => (f64::NEG_INFINITY, f64::INFINITY),
+        };
+        LinkDomain { prior, lb, ub }
+    }
+
+    /// `link_domain` of every unknown, centered on `priors`.
+    pub fn link_domains(&self, priors: &[f64; N]) -> [LinkDomain; N] {
+        std::array::from_fn(|idx| self.link_domain(idx, priors[idx]))
+    }
+
+    /// Whether the link functions depend on the sub-problem's initial unknowns, i.e. a sub-problem
+    /// built at one starting point isn't centered the same way as one built at another.
+    pub fn is_centered_on_start(&self) -> bool {
+        match self {
+            ParamScaling::Unscaled | ParamScaling::FromBounds(_) => false,
+            ParamScaling::FromInitialUnknowns { .. } => true,
+            ParamScaling::PerField(specs) => specs.iter().any(|s| *s != ScalingSpec::Identity),
+        }
+    }
+}
+
+/// The prior a link function is centered on, and the open model-space interval `(lb, ub)` it is defined on.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct LinkDomain {
+    pub prior: f64,
+    pub lb: f64,
+    pub ub: f64,
+}
+
+impl LinkDomain {
+    pub fn contains(&self, value: f64) -> bool {
+        value.is_finite() && self.lb < value && value < self.ub
+    }
+
+    /// `value` if the domain contains it, otherwise the edge it is on or past, moved a small
+    /// relative step inside the domain (e.g. for a box bound of 0 on a log-scaled unknown).
+    pub fn nudged_inside(&self, value: f64) -> f64 {
+        const REL_STEP: f64 = 1e-12;
+        if self.contains(value) {
+            value
+        } else if value <= self.lb {
+            self.lb + REL_STEP * self.lb.abs().max(1.0)
+        } else {
+            self.ub - REL_STEP * self.ub.abs().max(1.0)
+        }
+    }
+}
+
+/// Checks that every value lies in its unknown's link domain, and that every prior does (so that
+/// the links are well-defined at all).
+///
+/// Without this, link functions only `debug_assert!` their domains, and silently produce NaNs in release builds.
+pub fn check_link_domains<const N: usize>(
+    domains: &[LinkDomain; N],
+    values: &[f64; N],
+) -> Result<(), EqSysError> {
+    let invalid = domains
+        .iter()
+        .zip(values)
+        .enumerate()
+        .find(|(_, (domain, &value))| !(domain.contains(domain.prior) && domain.contains(value)));
+    match invalid {
+        Some((unknown_idx, (domain, &value))) => Err(EqSysError::InvalidScaling {
+            unknown_idx,
+            field: None,
+            value,
+            prior: domain.prior,
+            lb: domain.lb,
+            ub: domain.ub,
+        }),
+        None => Ok(()),
+    }
+}
+
+/// Checks that every prior lies in its own link function's domain under `scaling` (e.g. that
+/// `ScalingSpec::LogPositive` priors are positive), so that the links are well-defined at all;
+/// `scaled_log_link` and friends only `debug_assert!` this.
+///
+/// Fails with `EqSysError::InvalidParamScaling` naming the first offending field in `field_names`.
+pub fn check_scaling_priors<const N: usize>(
+    scaling: &ParamScaling<N>,
+    priors: &[f64; N],
+    field_names: &[&'static str],
+) -> Result<(), EqSysError> {
+    let degenerate = scaling
+        .link_domains(priors)
+        .into_iter()
+        .enumerate()
+        .find(|(_, domain)| !domain.contains(domain.prior));
+    match degenerate {
+        Some((idx, LinkDomain { prior, lb, ub })) => Err(EqSysError::InvalidParamScaling {
+            field_name: field_names.get(idx).copied().unwrap_or("?"),
+            prior,
+            lb,
+            ub,
+        }),
+        None => Ok(()),
+    }
+}
+
+/// Domain of the default sign-aware scaled-log link centered on `prior`: beyond 1% of the prior, on
+/// its side of zero, or anywhere for a zero prior.
+fn log_link_interval(prior: f64) -> (f64, f64) {
+    if prior > 0.0 {
+        (prior * 0.01, f64::INFINITY)
+    } else if prior < 0.0 {
+        (f64::NEG_INFINITY, prior * 0.01)
+    } else {
+        (f64::NEG_INFINITY, f64::INFINITY)
+    }
+}
+
+/// Link function for one unknown. `prior` is the value that maps to 0 in opt space.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum ScalingSpec {
+    /// `scaled_log_link` for positive params, with a lower bound at 1% of the prior.
+    LogPositive,
+    /// `scaled_log_link` on `-p` for negative params, with an upper bound at 1% of the prior.
+    LogNegative,
+    /// `scaled_logit_link` for params constrained to `(lb, ub)`.
+    Logit { lb: f64, ub: f64 },
+    /// No scaling; opt space is model space.
+    Identity,
+    /// `(p - prior) / scale`: no distortion, but centered on the prior and measured in units of
+    /// `scale`, for params that are already well-behaved but not O(1).
+    Affine { scale: f64 },
+    /// `scaled_asinh_link` for params that may change sign, with `|prior|` (or 1 for a zero prior) as the scale.
+    Asinh,
+    /// `scaled_asinh_link` with an explicit scale: roughly linear within `±scale` and logarithmic
+    /// beyond.
+    ///
+    /// For signed params whose priors are near zero, where `Asinh` would make the linear region
+    /// vanishingly small.
+    AsinhScaled { scale: f64 },
+}
+
+impl ScalingSpec {
+    /// False for `Logit` specs without finite bounds `lb < ub`, and `Affine` or `AsinhScaled` specs
+    /// without a finite, positive scale.
+    pub fn is_valid(&self) -> bool {
+        match *self {
+            ScalingSpec::Logit { lb, ub } => lb.is_finite() && ub.is_finite() && lb < ub,
+            ScalingSpec::Affine { scale } | ScalingSpec::AsinhScaled { scale } => {
+                scale.is_finite() && scale > 0.0
+            }
+            _ => true,
+        }
+    }
+}
 
 /// Logarithmic mapping from constrained model space (lb, +inf) to unconstrained optimization space (-inf, +inf).
 ///
@@ -7,7 +230,6 @@ use nalgebra::ComplexField;
 /// (1) the output is shifted by `lb` to ensure positivity
 /// (2) the logarithm is normalized by the prior to center the mapping around the prior (so that an input near the prior will be near 0.0 in the unconstrained space)
 pub fn scaled_log_link<T: AD>(p: T, prior: T, lb: T) -> T {
-    debug_assert!(lb >= T::zero(), "lb must be non-negative, got {}", lb);
     debug_assert!(p > lb, "p must be greater than lb, got p={} lb={}", p, lb);
     debug_assert!(
         prior > lb,
@@ -20,7 +242,6 @@ pub fn scaled_log_link<T: AD>(p: T, prior: T, lb: T) -> T {
 
 /// Inverse of `scaled_log_link`, mapping from unconstrained optimization space (-inf, +inf) to constrained model space (lb, +inf).
 pub fn scaled_log_link_inv<T: AD>(x: T, prior: T, lb: T) -> T {
-    debug_assert!(lb >= T::zero(), "lb must be non-negative, got {}", lb);
     debug_assert!(
         prior > lb,
         "prior must be greater than lb, got prior={} lb={}",
@@ -31,14 +252,21 @@ pub fn scaled_log_link_inv<T: AD>(x: T, prior: T, lb: T) -> T {
 }
 
 /// Builds model_to_opt and opt_to_model functions using default_exp_link and its inverse.
-/// This assumes all priors are non-zero. If any priors can be zero, a different scaling strategy is needed.
+/// A log link can't be centered on 0, so priors of exactly 0 get the affine link
+/// `p / zero_prior_scale` instead, leaving such params free to take either sign.
 ///
-/// For negative priors, signs are flipped appropriately.
+/// For negative priors, signs are flipped appropriately. A param can never cross zero under this
+/// link; for params that legitimately change sign, select `ScalingSpec::Asinh` or
+/// `ScalingSpec::AsinhScaled` with `EquationSystemBuilder::with_scaling_specs`.
 pub fn default_link_fns_builder<T: AD, const N: usize>(
     priors_vec: [T; N],
+    zero_prior_scale: f64,
 ) -> (impl Fn([T; N]) -> [T; N], impl Fn([T; N]) -> [T; N]) {
     let model_to_opt = move |p_model: [T; N]| {
         std::array::from_fn(|i| {
+            if priors_vec[i] == T::zero() {
+                return p_model[i] / T::constant(zero_prior_scale);
+            }
             // signs of p and prior must match
             debug_assert!(
                 p_model[i].signum() == priors_vec[i].signum(),
@@ -55,6 +283,9 @@ pub fn default_link_fns_builder<T: AD, const N: usize>(
     };
     let opt_to_model = move |p_opt: [T; N]| {
         std::array::from_fn(|i| {
+            if priors_vec[i] == T::zero() {
+                return p_opt[i] * T::constant(zero_prior_scale);
+            }
             // p_opt can be any real number.
             // if the prior is negative, we need to:
             // (a) use its absolute value to compute the softplus_default
@@ -67,3 +298,141 @@ pub fn default_link_fns_builder<T: AD, const N: usize>(
     };
     (opt_to_model, model_to_opt)
 }
+
+/// Builds model_to_opt and opt_to_model functions from per-field `ParamBounds`. Each prior maps to 0 in opt space.
+///
+/// - both finite: `scaled_logit_link` on `(lb, ub)`, so that the optimizer can't step past either bound;
+/// - finite `lb` only: `scaled_log_link` from `lb`;
+/// - finite `ub` only: the mirrored log link `-ln((ub - p) / (ub - prior))`;
+/// - both infinite: the shift `p - prior`.
+pub fn bounded_link_fns_builder<T: AD, const N: usize>(
+    bounds: [ParamBounds; N],
+) -> (impl Fn([T; N]) -> [T; N], impl Fn([T; N]) -> [T; N]) {
+    let model_to_opt = move |p_model: [T; N]| {
+        std::array::from_fn(|i| {
+            let ParamBounds { lb, prior, ub } = bounds[i];
+            let (p, prior) = (p_model[i], T::constant(prior));
+            if lb.is_finite() && ub.is_finite() {
+                scaled_logit_link(p, prior, T::constant(lb), T::constant(ub))
+            } else if lb.is_finite() {
+                scaled_log_link(p, prior, T::constant(lb))
+            } else if ub.is_finite() {
+                let ub = T::constant(ub);
+                -ComplexField::ln((ub - p) / (ub - prior))
+            } else {
+                p - prior
+            }
+        })
+    };
+    let opt_to_model = move |p_opt: [T; N]| {
+        std::array::from_fn(|i| {
+            let ParamBounds { lb, prior, ub } = bounds[i];
+            let (x, prior) = (p_opt[i], T::constant(prior));
+            if lb.is_finite() && ub.is_finite() {
+                scaled_logit_link_inv(x, prior, T::constant(lb), T::constant(ub))
+            } else if lb.is_finite() {
+                scaled_log_link_inv(x, prior, T::constant(lb))
+            } else if ub.is_finite() {
+                let ub = T::constant(ub);
+                ub - ComplexField::exp(-x) * (ub - prior)
+            } else {
+                x + prior
+            }
+        })
+    };
+    (opt_to_model, model_to_opt)
+}
+
+/// Logit mapping from model space (lb, ub) to unconstrained optimization space, shifted so that `prior` maps to 0.
+pub fn scaled_logit_link<T: AD>(p: T, prior: T, lb: T, ub: T) -> T {
+    debug_assert!(lb < ub, "lb must be less than ub, got lb={} ub={}", lb, ub);
+    debug_assert!(
+        p > lb && p < ub,
+        "p must be within (lb, ub), got p={} lb={} ub={}",
+        p,
+        lb,
+        ub
+    );
+    logit((p - lb) / (ub - lb)) - logit((prior - lb) / (ub - lb))
+}
+
+/// Inverse of `scaled_logit_link`.
+pub fn scaled_logit_link_inv<T: AD>(x: T, prior: T, lb: T, ub: T) -> T {
+    debug_assert!(lb < ub, "lb must be less than ub, got lb={} ub={}", lb, ub);
+    let z = x + logit((prior - lb) / (ub - lb));
+    lb + (ub - lb) / (T::one() + ComplexField::exp(-z))
+}
+
+fn logit<T: AD>(u: T) -> T {
+    ComplexField::ln(u / (T::one() - u))
+}
+
+/// Asinh mapping for params that may change sign: linear near the prior's scale, logarithmic for
+/// `|p| >> scale`. Shifted so that `prior` maps to 0.
+pub fn scaled_asinh_link<T: AD>(p: T, prior: T, scale: T) -> T {
+    debug_assert!(scale > T::zero(), "scale must be positive, got {}", scale);
+    ComplexField::asinh(p / scale) - ComplexField::asinh(prior / scale)
+}
+
+/// Inverse of `scaled_asinh_link`.
+pub fn scaled_asinh_link_inv<T: AD>(x: T, prior: T, scale: T) -> T {
+    debug_assert!(scale > T::zero(), "scale must be positive, got {}", scale);
+    ComplexField::sinh(x + ComplexField::asinh(prior / scale)) * scale
+}
+
+/// Builds model_to_opt and opt_to_model functions applying `specs[i]` to unknown `i`, centered on `priors_vec`.
+///
+/// The `Asinh` link uses `|prior|` as its scale, or 1 for a zero prior.
+pub fn spec_link_fns_builder<T: AD, const N: usize>(
+    specs: [ScalingSpec; N],
+    priors_vec: [T; N],
+) -> (impl Fn([T; N]) -> [T; N], impl Fn([T; N]) -> [T; N]) {
+    let asinh_scale = move |prior: T| {
+        if prior == T::zero() {
+            T::one()
+        } else {
+            prior.abs()
+        }
+    };
+    let model_to_opt = move |p_model: [T; N]| {
+        std::array::from_fn(|i| {
+            let (p, prior) = (p_model[i], priors_vec[i]);
+            match specs[i] {
+                ScalingSpec::LogPositive => scaled_log_link(p, prior, prior * T::constant(0.01)),
+                ScalingSpec::LogNegative => scaled_log_link(-p, -prior, -prior * T::constant(0.01)),
+                ScalingSpec::Logit { lb, ub } => {
+                    scaled_logit_link(p, prior, T::constant(lb), T::constant(ub))
+                }
+                ScalingSpec::Identity => p,
+                ScalingSpec::Affine { scale } => (p - prior) / T::constant(scale),
+                ScalingSpec::Asinh => scaled_asinh_link(p, prior, asinh_scale(prior)),
+                ScalingSpec::AsinhScaled { scale } => {
+                    scaled_asinh_link(p, prior, T::constant(scale))
+                }
+            }
+        })
+    };
+    let opt_to_model = move |p_opt: [T; N]| {
+        std::array::from_fn(|i| {
+            let (x, prior) = (p_opt[i], priors_vec[i]);
+            match specs[i] {
+                ScalingSpec::LogPositive => {
+                    scaled_log_link_inv(x, prior, prior * T::constant(0.01))
+                }
+                ScalingSpec::LogNegative => {
+                    -scaled_log_link_inv(x, -prior, -prior * T::constant(0.01))
+                }
+                ScalingSpec::Logit { lb, ub } => {
+                    scaled_logit_link_inv(x, prior, T::constant(lb), T::constant(ub))
+                }
+                ScalingSpec::Identity => x,
+                ScalingSpec::Affine { scale } => prior + x * T::constant(scale),
+                ScalingSpec::Asinh => scaled_asinh_link_inv(x, prior, asinh_scale(prior)),
+                ScalingSpec::AsinhScaled { scale } => {
+                    scaled_asinh_link_inv(x, prior, T::constant(scale))
+                }
+            }
+        })
+    };
+    (opt_to_model, model_to_opt)
+}
@@ -1,64 +1,78 @@
-use ad_trait::AD;
+use std::collections::HashMap;
+
+use ad_trait::{AD, forward_ad::adfn::adfn};
+use field_names_and_counts::FieldNames;
 use struct_to_array::StructToArray;
 
+use crate::{equation_system::shared::MaybeSendSync, error::EqSysError};
+
 /// Trait for "Given" parameters - the fixed parameters that define a problem instance.
 /// These are the design parameters that are chosen manually.
 ///
 /// Implementors must be generic over a numeric type `T: AD` and provide conversions
-/// between the f64 and generic AD versions.
+/// between the f64 and generic AD versions (e.g. with `#[derive(AdConvert)]`).
+/// With the `parallel` feature they must also be `Send + Sync` (see `MaybeSendSync`).
+/// `#[derive(SolverParams)]` implements this (and `UnknownParams`) along with the conversions.
 ///
 /// # Example
 /// ```ignore
-/// #[derive(Clone, Copy, Debug, StructToArray)]
+/// #[derive(Clone, Copy, Debug, StructToArray, AdConvert)]
 /// struct MyGivens<T> {
 ///     mass: T,
 ///     max_speed: T,
 /// }
-///
-/// impl MyGivens<f64> {
-///     pub fn to_ad<T: AD>(self) -> MyGivens<T> {
-///         MyGivens {
-///             mass: T::constant(self.mass),
-///             max_speed: T::constant(self.max_speed),
-///         }
-///     }
-/// }
 /// ```
-pub trait GivenParams: Clone + Copy + std::fmt::Debug {}
+pub trait GivenParams: Clone + Copy + std::fmt::Debug + MaybeSendSync {}
+
+/// The family of instantiations of a param struct over scalar types, represented by its f64
+/// version: `Foo<f64>::For<T> = Foo<T>`. Implemented by `#[derive(AdConvert)]`.
+///
+/// Lets signatures name one type per param struct instead of separate f64 and `adfn<1>` versions
+/// (see `EquationSystemFor`, `SubProblemFor`, `ResidualFnsFor`). The API that needs to convert
+/// between the versions lives in impls over those families, e.g. `EquationSystemFor::new_from_f64`,
+/// `SubProblemFor::new_from_f64` and `ResidualFnsFor::with_complex_step_residual`.
+pub trait ParamFamily {
+    type For<T: AD>;
+}
+
+/// The `adfn<1>` version of a param family's f64 representative.
+pub type AdfnFor<P> = <P as ParamFamily>::For<adfn<1>>;
+
+/// Conversion of an f64 param struct to its counterpart over any AD type; implemented by `#[derive(AdConvert)]`.
+///
+/// Lets `EquationSystemBuilder::new_from_f64` derive the `adfn<1>` givens itself instead of taking a second copy.
+pub trait ToAdParams: ParamFamily {
+    fn to_ad_params<T: AD>(&self) -> Self::For<T>;
+}
+
+/// Conversion of a param struct over any AD type (e.g. `f32`) back to its f64 version; implemented
+/// by `#[derive(AdConvert)]`.
+///
+/// Lets `ResidualFnsFor::with_complex_step_residual` recover the f64 givens from the `adfn<1>` ones.
+pub trait ToF64Params {
+    type F64;
+    fn to_f64_params(&self) -> Self::F64;
+}
 
 /// Trait for "Unknown" parameters - the parameters that will be solved for.
 /// These are typically low-level parameters derived from the given parameters.
 ///
 /// Implementors must be generic over a numeric type `T: AD` and provide:
-/// - The number of parameters (N)
-/// - Field names for debugging
-/// - Conversions between f64 and generic AD versions
+/// - The number of parameters (N), via `StructToArray`
+/// - Field names, via `FieldNames` (used by `EquationSystemBuilder::new` for logging)
+/// - Conversions between f64 and generic AD versions, e.g. via `AdConvert`
+///
+/// `#[solver_params]` derives all of these, and this trait, at once.
 ///
 /// # Example
 /// ```ignore
-/// #[derive(Clone, Copy, Debug, StructToArray, FieldNames)]
+/// #[derive(Clone, Copy, Debug, StructToArray, FieldNames, AdConvert)]
 /// struct MyUnknowns<T> {
 ///     drag_coeff: T,
 ///     thrust_max: T,
 /// }
-///
-/// impl MyUnknowns<f64> {
-///     pub fn to_ad<T: AD>(&self) -> MyUnknowns<T> {
-///         MyUnknowns {
-///             drag_coeff: T::constant(self.drag_coeff),
-///             thrust_max: T::constant(self.thrust_max),
-///         }
-///     }
-/// }
-///
-/// impl<T> MyUnknowns<T> {
-///     pub const N: usize = 2;
-///     pub fn field_names() -> &'static [&'static str] {
-///         &["drag_coeff", "thrust_max"]
-///     }
-/// }
 /// ```
-pub trait UnknownParams: Clone + Copy + std::fmt::Debug {}
+pub trait UnknownParams: Clone + Copy + std::fmt::Debug + MaybeSendSync {}
 
 /// Marker trait to ensure that types implementing GivenParams are properly
 /// bounded. Note that GivenParams do NOT need StructToArray - they are just
@@ -92,3 +106,63 @@ where
     U: UnknownParams + StructToArray<T, N>,
 {
 }
+
+/// Builds a param struct (givens or unknowns) from values keyed by field name, e.g. as read from a
+/// config file, without compile-time knowledge of the struct.
+///
+/// Every field needs a value: fails with `EqSysError::UnknownParamField` for a key that names no
+/// field, and with `EqSysError::MissingParamField` for a field without one. To set only some
+/// fields, use `params_updated_from_map`.
+pub fn params_from_map<P, K, const N: usize>(values: &HashMap<K, f64>) -> Result<P, EqSysError>
+where
+    P: FieldNames + StructToArray<f64, N>,
+    K: AsRef<str>,
+{
+    let mut arr = [f64::NAN; N];
+    let mut set = [false; N];
+    for (field, &value) in values {
+        let idx = param_field_idx::<P>(field.as_ref())?;
+        arr[idx] = value;
+        set[idx] = true;
+    }
+    if let Some(idx) = set.iter().position(|&set| !set) {
+        return Err(EqSysError::MissingParamField {
+            field: P::FIELDS[idx],
+        });
+    }
+    Ok(P::from_arr(arr))
+}
+
+/// A copy of `params` with the fields named in `values` overwritten. Fails with
+/// `EqSysError::UnknownParamField` for a key that names no field.
+pub fn params_updated_from_map<P, K, const N: usize>(
+    params: &P,
+    values: &HashMap<K, f64>,
+) -> Result<P, EqSysError>
+where
+    P: FieldNames + StructToArray<f64, N>,
+    K: AsRef<str>,
+{
+    let mut arr = params.to_arr();
+    for (field, &value) in values {
+        arr[param_field_idx::<P>(field.as_ref())?] = value;
+    }
+    Ok(P::from_arr(arr))
+}
+
+/// Every field of a param struct (e.g. a solution) keyed by its name; the inverse of `params_from_map`.
+pub fn params_to_map<P, const N: usize>(params: &P) -> HashMap<&'static str, f64>
+where
+    P: FieldNames + StructToArray<f64, N>,
+{
+    P::FIELDS.iter().copied().zip(params.to_arr()).collect()
+}
+
+fn param_field_idx<P: FieldNames>(field: &str) -> Result<usize, EqSysError> {
+    P::FIELDS
+        .iter()
+        .position(|&name| name == field)
+        .ok_or_else(|| EqSysError::UnknownParamField {
+            field: field.to_string(),
+        })
+}
@@ -0,0 +1,199 @@
+use ad_trait::forward_ad::adfn::adfn;
+
+use crate::{
+    equation_system::{EqSysSolutionPlan, EquationSystemBuilder},
+    prelude::*,
+};
+
+/// Manual edits of the automatic solution plan.
+///
+/// The automatic decomposition sometimes separates weakly coupled unknowns that converge better
+/// when solved jointly. Every edit keeps the plan valid: blocks are (re)ordered so that each
+/// block's equations only use its own unknowns and those of earlier blocks, and an edit that can't
+/// satisfy this fails with `EqSysError::InvalidPlanOverride`. The edits only change the blocks that
+/// are solved; `print_lower_tri_mat` and `block_structure` still show the automatic structure.
+impl<G64, U64, Gadfn, Uadfn, const N: usize>
+    EquationSystemBuilder<G64, U64, Gadfn, Uadfn, EqSysSolutionPlan, N>
+where
+    G64: GivenParamsFor<f64, N>,
+    U64: UnknownParamsFor<f64, N>,
+    Gadfn: GivenParamsFor<adfn<1>, N>,
+    Uadfn: UnknownParamsFor<adfn<1>, N>,
+{
+    /// Merges the blocks `block_idxs` into one block, solved jointly in place of the earliest of
+    /// them.
+    ///
+    /// Blocks in between that both depend on and feed into the merged block make the merge fail;
+    /// merge them as well.
+    pub fn with_merged_blocks(mut self, block_idxs: &[usize]) -> Result<Self, EqSysError> {
+        let blocks = std::mem::take(&mut self.state.solution_plan.blocks);
+        let n_blocks = blocks.len();
+        if let Some(&block_idx) = block_idxs.iter().find(|&&idx| idx >= n_blocks) {
+            return Err(EqSysError::BlockIdxOutOfRange {
+                block_idx,
+                n_blocks,
+            });
+        }
+        let Some(&first) = block_idxs.iter().min() else {
+            self.state.solution_plan.blocks = blocks;
+            return Ok(self);
+        };
+
+        let mut merged = SolutionBlock {
+            block_idx: first,
+            equation_idxs: vec![],
+            unknown_idxs: vec![],
+        };
+        let mut rest = vec![];
+        for (idx, block) in blocks.into_iter().enumerate() {
+            if block_idxs.contains(&idx) {
+                merged.equation_idxs.extend(block.equation_idxs);
+                merged.unknown_idxs.extend(block.unknown_idxs);
+            } else {
+                rest.push(block);
+            }
+        }
+        rest.insert(first, merged);
+
+        self.state.solution_plan.blocks = self.dependency_ordered(rest)?;
+        Ok(self)
+    }
+
+    /// Merges the blocks containing the named equations and unknowns, so that they are all solved
+    /// in one block (see `with_merged_blocks`).
+    pub fn with_joint_block(
+        self,
+        fn_names: &[&str],
+        unknown_names: &[&str],
+    ) -> Result<Self, EqSysError> {
+        let blocks = &self.state.solution_plan.blocks;
+        let mut block_idxs = vec![];
+        for &fn_name in fn_names {
+            let eq_idx = self
+                .raw_res_fns
+                .fn_names()
+                .iter()
+                .position(|&name| name == fn_name)
+                .ok_or_else(|| EqSysError::UnknownResidualName {
+                    fn_name: fn_name.to_string(),
+                })?;
+            block_idxs.extend(
+                blocks
+                    .iter()
+                    .position(|b| b.equation_idxs.contains(&eq_idx)),
+            );
+        }
+        for &unknown_name in unknown_names {
+            let unknown_idx = self
+                .unknown_field_names
+                .iter()
+                .position(|&name| name == unknown_name)
+                .ok_or_else(|| EqSysError::UnknownParamField {
+                    field: unknown_name.to_string(),
+                })?;
+            let block_idx = blocks
+                .iter()
+                .position(|b| b.unknown_idxs.contains(&unknown_idx))
+                .ok_or_else(|| EqSysError::InvalidPlanOverride {
+                    reason: format!("unknown `{unknown_name}` appears in no equation"),
+                })?;
+            block_idxs.push(block_idx);
+        }
+        block_idxs.sort_unstable();
+        block_idxs.dedup();
+
+        self.with_merged_blocks(&block_idxs)
+    }
+
+    /// Reorders the blocks: `order[i]` is the current index of the block to solve `i`-th.
+    ///
+    /// Fails if `order` is not a permutation of the blocks, or if a block would be solved before a
+    /// block whose unknowns its equations use.
+    pub fn with_block_order(mut self, order: &[usize]) -> Result<Self, EqSysError> {
+        let n_blocks = self.state.solution_plan.blocks.len();
+        let mut seen = vec![false; n_blocks];
+        for &idx in order {
+            if idx >= n_blocks || seen[idx] {
+                return Err(EqSysError::InvalidPlanOverride {
+                    reason: format!("{order:?} is not a permutation of the {n_blocks} blocks"),
+                });
+            }
+            seen[idx] = true;
+        }
+        if order.len() != n_blocks {
+            return Err(EqSysError::InvalidPlanOverride {
+                reason: format!("{order:?} is not a permutation of the {n_blocks} blocks"),
+            });
+        }
+
+        let mut blocks: Vec<_> = order
+            .iter()
+            .map(|&idx| self.state.solution_plan.blocks[idx].clone())
+            .collect();
+        for (pos, block) in blocks.iter().enumerate() {
+            if let Some(later) = blocks[pos + 1..].iter().find(|b| self.block_uses(block, b)) {
+                return Err(EqSysError::InvalidPlanOverride {
+                    reason: format!(
+                        "block {} uses unknowns {:?} of block {}, which would be solved later",
+                        block.block_idx,
+                        self.unknown_names(later),
+                        later.block_idx
+                    ),
+                });
+            }
+        }
+        for (pos, block) in blocks.iter_mut().enumerate() {
+            block.block_idx = pos;
+        }
+
+        self.state.solution_plan.blocks = blocks;
+        Ok(self)
+    }
+
+    /// True if an equation of `block` depends on an unknown of `other`.
+    pub(super) fn block_uses(&self, block: &SolutionBlock, other: &SolutionBlock) -> bool {
+        block.equation_idxs.iter().any(|&e| {
+            other
+                .unknown_idxs
+                .iter()
+                .any(|&u| self.state.binary_matrix[(e, u)] != 0.0)
+        })
+    }
+
+    fn unknown_names(&self, block: &SolutionBlock) -> Vec<&'static str> {
+        block
+            .unknown_idxs
+            .iter()
+            .map(|&u| self.unknown_field_names[u])
+            .collect()
+    }
+
+    /// Orders `blocks` so that every block comes after the blocks whose unknowns it uses, keeping
+    /// the given order where possible, and renumbers them.
+    fn dependency_ordered(
+        &self,
+        mut blocks: Vec<SolutionBlock>,
+    ) -> Result<Vec<SolutionBlock>, EqSysError> {
+        let mut ordered = Vec::with_capacity(blocks.len());
+        while !blocks.is_empty() {
+            let ready = blocks.iter().position(|block| {
+                blocks
+                    .iter()
+                    .all(|other| std::ptr::eq(block, other) || !self.block_uses(block, other))
+            });
+            let Some(ready) = ready else {
+                let cycle: Vec<_> = blocks.iter().map(|b| self.unknown_names(b)).collect();
+                return Err(EqSysError::InvalidPlanOverride {
+                    reason: format!(
+                        "the blocks solving {cycle:?} depend on each other; merge them as well"
+                    ),
+                });
+            };
+            ordered.push(blocks.remove(ready));
+        }
+        for (pos, block) in ordered.iter_mut().enumerate() {
+            block.block_idx = pos;
+        }
+        Ok(ordered)
+    }
+}
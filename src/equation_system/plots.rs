@@ -0,0 +1,187 @@
+//! Convergence and residual plots (`plots` feature), rendered with `plotters`: SVG if the path ends
+//! in `.svg`, PNG otherwise.
+
+use std::path::Path;
+
+use plotters::coord::Shift;
+use plotters::prelude::*;
+
+use crate::prelude::*;
+
+const CONVERGENCE_PLOT_SIZE: (u32, u32) = (1024, 768);
+
+/// Plots cost against iteration on a log scale, one line per traced solver run (see
+/// `SolveOptions::trace`).
+///
+/// Iterations with a zero or non-finite cost are left out, since the log scale can't show them.
+pub fn plot_convergence(
+    traces: &[OptimizationTrace],
+    path: impl AsRef<Path>,
+) -> Result<(), EqSysError> {
+    let path = path.as_ref();
+    if is_svg(path) {
+        draw_convergence(
+            &SVGBackend::new(path, CONVERGENCE_PLOT_SIZE).into_drawing_area(),
+            traces,
+        )
+    } else {
+        draw_convergence(
+            &BitMapBackend::new(path, CONVERGENCE_PLOT_SIZE).into_drawing_area(),
+            traces,
+        )
+    }
+}
+
+/// Plots `|r|` of every equation as a horizontal bar on a log scale, labelled with `names`. Zero
+/// residuals get no bar, and non-finite ones are left out.
+pub fn plot_residuals(
+    names: &[&str],
+    residuals: &[f64],
+    path: impl AsRef<Path>,
+) -> Result<(), EqSysError> {
+    let path = path.as_ref();
+    let size = (1024, 100 + 24 * residuals.len() as u32);
+    if is_svg(path) {
+        draw_residuals(
+            &SVGBackend::new(path, size).into_drawing_area(),
+            names,
+            residuals,
+        )
+    } else {
+        draw_residuals(
+            &BitMapBackend::new(path, size).into_drawing_area(),
+            names,
+            residuals,
+        )
+    }
+}
+
+impl<U> SolveReport<U> {
+    /// The report's traces as a cost-per-iteration plot; see `plot_convergence`.
+    pub fn plot_convergence(&self, path: impl AsRef<Path>) -> Result<(), EqSysError> {
+        plot_convergence(&self.traces, path)
+    }
+
+    /// The final per-equation residuals as a bar chart; see `plot_residuals`.
+    pub fn plot_residuals(&self, path: impl AsRef<Path>) -> Result<(), EqSysError> {
+        plot_residuals(&self.residual_names, &self.residuals, path)
+    }
+}
+
+fn is_svg(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("svg"))
+}
+
+fn plot_error(e: impl std::error::Error) -> EqSysError {
+    EqSysError::PlotFailed(e.to_string())
+}
+
+/// Smallest and largest of the positive, finite `values`, widened to a range a log axis can show.
+fn log_range(values: impl Iterator<Item = f64>) -> (f64, f64) {
+    let (min, max) = values
+        .filter(|v| v.is_finite() && *v > 0.0)
+        .fold((f64::INFINITY, 0.0f64), |(min, max), v| {
+            (min.min(v), max.max(v))
+        });
+    if max == 0.0 {
+        (1e-16, 1.0)
+    } else {
+        (min / 10.0, max * 10.0)
+    }
+}
+
+fn draw_convergence<DB: DrawingBackend>(
+    root: &DrawingArea<DB, Shift>,
+    traces: &[OptimizationTrace],
+) -> Result<(), EqSysError> {
+    root.fill(&WHITE).map_err(plot_error)?;
+    let records = || traces.iter().flat_map(|t| &t.iterations);
+    let max_iter = records().map(|r| r.iter).max().unwrap_or(0).max(1);
+    let (min_cost, max_cost) = log_range(records().map(|r| r.cost));
+
+    let mut chart = ChartBuilder::on(root)
+        .caption("Cost per iteration", ("sans-serif", 24))
+        .margin(10)
+        .x_label_area_size(40)
+        .y_label_area_size(80)
+        .build_cartesian_2d(0..max_iter, (min_cost..max_cost).log_scale())
+        .map_err(plot_error)?;
+    chart
+        .configure_mesh()
+        .x_desc("iteration")
+        .y_desc("cost")
+        .draw()
+        .map_err(plot_error)?;
+
+    for (run, trace) in traces.iter().enumerate() {
+        let color = Palette99::pick(run).to_rgba();
+        let points = trace
+            .iterations
+            .iter()
+            .filter(|r| r.cost.is_finite() && r.cost > 0.0)
+            .map(|r| (r.iter, r.cost));
+        chart
+            .draw_series(LineSeries::new(points, color))
+            .map_err(plot_error)?
+            .label(format!("block {} ({})", trace.block_idx, trace.solver))
+            .legend(move |(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], color));
+    }
+    if !traces.is_empty() {
+        chart
+            .configure_series_labels()
+            .background_style(WHITE.mix(0.8))
+            .border_style(BLACK)
+            .draw()
+            .map_err(plot_error)?;
+    }
+    root.present().map_err(plot_error)
+}
+
+fn draw_residuals<DB: DrawingBackend>(
+    root: &DrawingArea<DB, Shift>,
+    names: &[&str],
+    residuals: &[f64],
+) -> Result<(), EqSysError> {
+    root.fill(&WHITE).map_err(plot_error)?;
+    let (min_abs, max_abs) = log_range(residuals.iter().map(|r| r.abs()));
+    let longest_name = names.iter().map(|n| n.len()).max().unwrap_or(0) as u32;
+
+    let mut chart = ChartBuilder::on(root)
+        .caption("Final residuals", ("sans-serif", 24))
+        .margin(10)
+        .x_label_area_size(40)
+        .y_label_area_size(20 + 8 * longest_name)
+        .build_cartesian_2d(
+            (min_abs..max_abs).log_scale(),
+            (0..residuals.len()).into_segmented(),
+        )
+        .map_err(plot_error)?;
+    chart
+        .configure_mesh()
+        .x_desc("|residual|")
+        .y_labels(residuals.len())
+        .y_label_formatter(&|y| match y {
+            SegmentValue::CenterOf(e) => names.get(*e).map_or_else(String::new, |n| n.to_string()),
+            _ => String::new(),
+        })
+        .draw()
+        .map_err(plot_error)?;
+
+    chart
+        .draw_series(
+            Histogram::horizontal(&chart)
+                .style(BLUE.filled())
+                .margin(4)
+                .baseline(min_abs)
+                .data(
+                    residuals
+                        .iter()
+                        .enumerate()
+                        .filter(|(_, r)| r.is_finite())
+                        .map(|(e, r)| (e, r.abs().max(min_abs))),
+                ),
+        )
+        .map_err(plot_error)?;
+    root.present().map_err(plot_error)
+}
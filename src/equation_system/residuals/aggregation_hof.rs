@@ -1,8 +1,7 @@
-use std::rc::Rc;
-
 use ad_trait::AD;
+use nalgebra::{ComplexField, DMatrix, DVector};
 
-use crate::prelude::*;
+use crate::{equation_system::shared::shared_fn, prelude::*};
 
 /// Trait for specifying a higher-order-function that can generate *generic* residual aggregation functions for vecs of residuals of any type `T:AD`.
 ///
@@ -10,19 +9,32 @@ use crate::prelude::*;
 ///
 /// We do this as a trait rather than a normal struct or function so that we can more easily specify the HOF in one place, and then pladd that around to the locations where `ad_trait` needs concrete `f64`` and `adfn<1>` versions.es, it's kind of a pain to need this much abstraction, but it's seems better than passing around multiple copies of functions specified for different types all over the place.
 pub trait ResidAggHOF: Clone {
-    fn make_residual_operator_fn<T: AD>(&self) -> Rc<dyn Fn(Vec<T>) -> Vec<T>>;
+    fn make_residual_operator_fn<T: AD>(&self) -> shared_fn!(Fn(Vec<T>) -> Vec<T>);
     fn scalar_cost_f64(&self, residuals: Vec<f64>) -> f64;
     fn num_outputs(&self) -> usize;
+    /// Folds extra penalty residuals (e.g. from regularization) into already-aggregated outputs.
+    ///
+    /// Scalar aggregations add the sum of squared penalties; pass-through aggregations append them
+    /// as extra residuals.
+    fn append_penalty_residuals<T: AD>(
+        &self,
+        outputs: &mut Vec<T>,
+        penalty_residuals: impl IntoIterator<Item = T>,
+    );
+    /// Number of extra outputs that `append_penalty_residuals` adds for `n_penalty` penalty residuals.
+    fn num_penalty_outputs(&self, n_penalty: usize) -> usize;
+    /// Gradient of the objective minimized over `outputs`, given their Jacobian.
+    fn objective_gradient_f64(&self, outputs: &[f64], jacobian: &DMatrix<f64>) -> DVector<f64>;
 }
 
 pub trait ResidAggFnToScalarGen: Clone {
-    fn make_residuals_to_scalar_fn<T: AD>(&self) -> Rc<dyn Fn(Vec<T>) -> T>;
+    fn make_residuals_to_scalar_fn<T: AD>(&self) -> shared_fn!(Fn(Vec<T>) -> T);
 }
 
 impl<R: ResidAggFnToScalarGen> ResidAggHOF for R {
-    fn make_residual_operator_fn<T: AD>(&self) -> Rc<dyn Fn(Vec<T>) -> Vec<T>> {
+    fn make_residual_operator_fn<T: AD>(&self) -> shared_fn!(Fn(Vec<T>) -> Vec<T>) {
         let to_scalar_fn = self.make_residuals_to_scalar_fn::<T>();
-        Rc::new(move |residuals: Vec<T>| vec![to_scalar_fn(residuals)])
+        Shared::new(move |residuals: Vec<T>| vec![to_scalar_fn(residuals)])
     }
     fn scalar_cost_f64(&self, residuals: Vec<f64>) -> f64 {
         let to_scalar_fn = self.make_residuals_to_scalar_fn::<f64>();
@@ -31,13 +43,28 @@ impl<R: ResidAggFnToScalarGen> ResidAggHOF for R {
     fn num_outputs(&self) -> usize {
         1
     }
+    fn append_penalty_residuals<T: AD>(
+        &self,
+        outputs: &mut Vec<T>,
+        penalty_residuals: impl IntoIterator<Item = T>,
+    ) {
+        outputs[0] = penalty_residuals
+            .into_iter()
+            .fold(outputs[0], |acc, p| acc + p * p);
+    }
+    fn num_penalty_outputs(&self, _n_penalty: usize) -> usize {
+        0
+    }
+    fn objective_gradient_f64(&self, _outputs: &[f64], jacobian: &DMatrix<f64>) -> DVector<f64> {
+        jacobian.row_sum().transpose()
+    }
 }
 
 #[derive(Clone)]
 pub struct ResidAggSum;
 impl ResidAggFnToScalarGen for ResidAggSum {
-    fn make_residuals_to_scalar_fn<T: AD>(&self) -> Rc<dyn Fn(Vec<T>) -> T> {
-        Rc::new(|residuals: Vec<T>| {
+    fn make_residuals_to_scalar_fn<T: AD>(&self) -> shared_fn!(Fn(Vec<T>) -> T) {
+        Shared::new(|residuals: Vec<T>| {
             residuals
                 .iter()
                 .cloned()
@@ -46,6 +73,97 @@ impl ResidAggFnToScalarGen for ResidAggSum {
     }
 }
 
+/// p-norm of the (transformed) residuals, `(sum_i |x_i|^p)^(1/p)`, for `p >= 1`.
+///
+/// `p = 1` is the sum of magnitudes; larger `p` put more of the cost on the largest residuals,
+/// approaching the max as `p` grows (see also `ResidAggSmoothMax`).
+#[derive(Clone)]
+pub struct ResidAggPNorm {
+    pub p: f64,
+}
+impl ResidAggPNorm {
+    pub fn new(p: f64) -> Self {
+        Self { p }
+    }
+}
+impl ResidAggFnToScalarGen for ResidAggPNorm {
+    fn make_residuals_to_scalar_fn<T: AD>(&self) -> shared_fn!(Fn(Vec<T>) -> T) {
+        let p = self.p;
+        Shared::new(move |residuals: Vec<T>| {
+            let sum = residuals.iter().fold(T::constant(0.0), |acc, &x| {
+                acc + ComplexField::powf(ComplexField::abs(x), T::constant(p))
+            });
+            // the root isn't differentiable at zero; the cost is at its minimum there anyway
+            if sum > T::zero() {
+                ComplexField::powf(sum, T::constant(1.0 / p))
+            } else {
+                sum
+            }
+        })
+    }
+}
+
+/// Weighted sum of the (transformed) residuals, `sum_i w_i * x_i`.
+///
+/// Weights are in the order of the residuals being aggregated (e.g. block equation order for a
+/// sub-problem). To weight residuals before a nonlinear transform instead, use
+/// `ResidTransWeighted`.
+#[derive(Clone)]
+pub struct ResidAggWeightedSum {
+    pub weights: Vec<f64>,
+}
+impl ResidAggWeightedSum {
+    pub fn new(weights: Vec<f64>) -> Self {
+        Self { weights }
+    }
+}
+impl ResidAggFnToScalarGen for ResidAggWeightedSum {
+    fn make_residuals_to_scalar_fn<T: AD>(&self) -> shared_fn!(Fn(Vec<T>) -> T) {
+        let weights = self.weights.clone();
+        Shared::new(move |residuals: Vec<T>| {
+            residuals
+                .iter()
+                .zip(&weights)
+                .fold(T::constant(0.0), |acc, (&x, &w)| acc + x * T::constant(w))
+        })
+    }
+}
+
+/// Smooth maximum of the (transformed) residuals,
+/// `m + ln(sum_i exp(sharpness * (x_i - m))) / sharpness` with `m = max_i x_i`.
+///
+/// Minimizing it minimizes the worst-case residual (Chebyshev / minimax) instead of the sum. It
+/// overestimates the max by at most `ln(n) / sharpness`, so a larger `sharpness` tracks the max
+/// more closely at the cost of a less smooth cost surface.
+#[derive(Clone)]
+pub struct ResidAggSmoothMax {
+    pub sharpness: f64,
+}
+impl ResidAggSmoothMax {
+    pub fn new(sharpness: f64) -> Self {
+        Self { sharpness }
+    }
+}
+impl ResidAggFnToScalarGen for ResidAggSmoothMax {
+    fn make_residuals_to_scalar_fn<T: AD>(&self) -> shared_fn!(Fn(Vec<T>) -> T) {
+        let sharpness = self.sharpness;
+        Shared::new(move |residuals: Vec<T>| {
+            let Some(&first) = residuals.first() else {
+                return T::constant(0.0);
+            };
+            // shift by the max so that exp can't overflow
+            let max = residuals
+                .iter()
+                .fold(first, |m, &x| if x > m { x } else { m });
+            let beta = T::constant(sharpness);
+            let sum_exp = residuals.iter().fold(T::constant(0.0), |acc, &x| {
+                acc + ComplexField::exp((x - max) * beta)
+            });
+            max + ComplexField::ln(sum_exp) / beta
+        })
+    }
+}
+
 #[derive(Clone)]
 pub struct ResidNoOpGaussNewton {
     n: usize,
@@ -61,8 +179,8 @@ impl ResidNoOpGaussNewton {
     }
 }
 impl ResidAggHOF for ResidNoOpGaussNewton {
-    fn make_residual_operator_fn<T: AD>(&self) -> Rc<dyn Fn(Vec<T>) -> Vec<T>> {
-        Rc::new(|residuals: Vec<T>| residuals)
+    fn make_residual_operator_fn<T: AD>(&self) -> shared_fn!(Fn(Vec<T>) -> Vec<T>) {
+        Shared::new(|residuals: Vec<T>| residuals)
     }
     fn num_outputs(&self) -> usize {
         self.n
@@ -70,4 +188,18 @@ impl ResidAggHOF for ResidNoOpGaussNewton {
     fn scalar_cost_f64(&self, residuals: Vec<f64>) -> f64 {
         residuals.iter().fold(0.0, |acc, &x| acc + x)
     }
+    fn append_penalty_residuals<T: AD>(
+        &self,
+        outputs: &mut Vec<T>,
+        penalty_residuals: impl IntoIterator<Item = T>,
+    ) {
+        outputs.extend(penalty_residuals);
+    }
+    fn num_penalty_outputs(&self, n_penalty: usize) -> usize {
+        n_penalty
+    }
+    /// `2 Jᵀ r`, the gradient of the sum of squared outputs that Gauss-Newton minimizes.
+    fn objective_gradient_f64(&self, outputs: &[f64], jacobian: &DMatrix<f64>) -> DVector<f64> {
+        jacobian.tr_mul(&DVector::from_column_slice(outputs)) * 2.0
+    }
 }
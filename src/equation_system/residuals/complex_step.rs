@@ -0,0 +1,76 @@
+use ad_trait::forward_ad::adfn::adfn;
+use nalgebra::Complex;
+use struct_to_array::StructToArray;
+
+use crate::prelude::*;
+
+/// Imaginary step of the complex-step derivatives.
+///
+/// No difference is taken, so there is no cancellation error and the step can be far below machine
+/// epsilon: the truncation error `O(h^2)` vanishes and the derivatives are accurate to machine
+/// precision.
+pub const COMPLEX_STEP: f64 = 1e-20;
+
+impl<G, U> ResidualFnsFor<G, U>
+where
+    G: ParamFamily,
+    U: ParamFamily,
+{
+    /// Appends a residual function of the f64 givens and the unknowns as complex numbers (in
+    /// `StructToArray` order), differentiated by complex step instead of AD: the derivative along a
+    /// tangent `v` is `Im f(u + i h v) / h` (see `COMPLEX_STEP`).
+    ///
+    /// For wrapping legacy residual code that isn't written against `ad_trait` but can be run on
+    /// complex numbers (e.g. code generic over `nalgebra::ComplexField`). The function must be
+    /// complex-analytic in the unknowns: `abs`, `max`, comparisons and branches on the real part
+    /// give wrong derivatives where they switch, and anything that drops the imaginary part (such
+    /// as `.re` or casting to f64 midway) gives zero ones. Only the unknowns are differentiated, so
+    /// `sensitivities`, uncertainty propagation and promoting givens to unknowns fail with
+    /// `EqSysError::GivensDerivativesUnavailable` on systems with such residuals. See
+    /// `complex_step_residual_fns!`.
+    pub fn with_complex_step_residual<const N: usize>(
+        self,
+        name: &'static str,
+        res_fn: impl Fn(&G, &[Complex<f64>; N]) -> Complex<f64> + MaybeSendSync + 'static,
+    ) -> Self
+    where
+        U: StructToArray<f64, N>,
+        AdfnFor<G>: ToF64Params<F64 = G>,
+        AdfnFor<U>: StructToArray<adfn<1>, N>,
+    {
+        let res_fn = Shared::new(res_fn);
+        let res_fn_f64 = res_fn.clone();
+        let mut res_fns = self.with_residual(
+            name,
+            move |givens: &G, unknowns: &U| {
+                let unknowns = unknowns.to_arr().map(|u| Complex::new(u, 0.0));
+                res_fn_f64(givens, &unknowns).re
+            },
+            move |givens: &AdfnFor<G>, unknowns: &AdfnFor<U>| {
+                let unknowns = unknowns
+                    .to_arr()
+                    .map(|u| Complex::new(u.value(), COMPLEX_STEP * u.tangent()[0]));
+                let out = res_fn(&givens.to_f64_params(), &unknowns);
+                adfn::new(out.re, [out.im / COMPLEX_STEP])
+            },
+        );
+        res_fns.complex_step_fns.push(name);
+        res_fns
+    }
+}
+
+/// Create ResidualFns from residual functions of the f64 givens and complex unknowns,
+/// differentiated by complex step (see `ResidualFns::with_complex_step_residual`).
+/// Usage: `complex_step_residual_fns!(GivenType, UnknownType; fn1, fn2, ...)`
+/// where each function has the signature `fn(&GivenType<f64>, &[Complex<f64>; N]) -> Complex<f64>`.
+#[macro_export]
+macro_rules! complex_step_residual_fns {
+    ($g:ident, $u:ident; $($fn_name:ident),* $(,)?) => {
+        $crate::equation_system::residuals::residuals::ResidualFnsFor::<$g<f64>, $u<f64>>::new(
+            vec![],
+            vec![],
+            vec![],
+        )
+        $(.with_complex_step_residual(stringify!($fn_name), $fn_name))*
+    };
+}
@@ -1,5 +1,7 @@
 pub mod aggregation_hof;
+pub mod complex_step;
 pub mod residuals;
 pub mod transformation_hof;
 
+pub use complex_step::*;
 pub use residuals::*;
@@ -1,13 +1,38 @@
-use std::rc::Rc;
-
 use ad_trait::{AD, forward_ad::adfn::adfn};
 
-use crate::prelude::*;
+use crate::{equation_system::shared::shared_fn, prelude::*};
+
+/// A residual function of the givens and unknowns.
+///
+/// Any closure works, including ones that capture data (e.g. a lookup table of measurements); with
+/// the `parallel` feature it must be `Send + Sync`.
+pub type ResidualFn<G, U, T> = shared_fn!(Fn(&G, &U) -> T);
+
+/// Wraps a function or closure as a `ResidualFn`.
+pub fn residual_fn<G, U, T>(
+    f: impl Fn(&G, &U) -> T + MaybeSendSync + 'static,
+) -> ResidualFn<G, U, T> {
+    Shared::new(f)
+}
+
+/// Like `residual_fn`, but multiplies the residual by `scale` if one is given, e.g. `1.0 / 3.3` to
+/// measure a residual in units of its typical magnitude.
+///
+/// Used by `residual_fns_for_generic_params!` for entries with a `* scale`.
+pub fn scaled_residual_fn<G, U, T: AD>(
+    f: impl Fn(&G, &U) -> T + MaybeSendSync + 'static,
+    scale: Option<f64>,
+) -> ResidualFn<G, U, T> {
+    match scale {
+        Some(scale) => Shared::new(move |g: &G, u: &U| f(g, u) * T::constant(scale)),
+        None => Shared::new(f),
+    }
+}
 
 #[derive(Clone)]
 pub struct ResidualsFn<T: AD, G, U> {
     pub residual_scale: T,
-    pub res_fn: Rc<fn(&G, &U) -> T>,
+    pub res_fn: ResidualFn<G, U, T>,
 }
 
 pub struct ResidualFns2<G, U> {
@@ -20,17 +45,17 @@ pub struct ResidualFns2<G, U> {
 #[macro_export]
 macro_rules! residual_fns_2 {
     ($($fn_name:expr),* $(,)?) => {
-        ResidualFns {
-            f64: vec![
-                $(Rc::new($fn_name)),*
+        $crate::equation_system::residuals::residuals::ResidualFns::new(
+            vec![
+                $($crate::equation_system::residuals::residuals::residual_fn($fn_name)),*
             ],
-            adfn_1: vec![
-                $(Rc::new($fn_name)),*
+            vec![
+                $($crate::equation_system::residuals::residuals::residual_fn($fn_name)),*
             ],
-            fn_names: vec![
+            vec![
                 $(stringify!($fn_name)),*
             ],
-        }
+        )
     };
 }
 
@@ -38,17 +63,17 @@ macro_rules! residual_fns_2 {
 #[macro_export]
 macro_rules! residual_fns {
     ($($fn_name:expr),* $(,)?) => {
-        ResidualFns {
-            f64: vec![
-                $(Rc::new($fn_name)),*
+        $crate::equation_system::residuals::residuals::ResidualFns::new(
+            vec![
+                $($crate::equation_system::residuals::residuals::residual_fn($fn_name)),*
             ],
-            adfn_1: vec![
-                $(Rc::new($fn_name)),*
+            vec![
+                $($crate::equation_system::residuals::residuals::residual_fn($fn_name)),*
             ],
-            fn_names: vec![
+            vec![
                 $(stringify!($fn_name)),*
             ],
-        }
+        )
     };
 }
 
@@ -56,32 +81,152 @@ macro_rules! residual_fns {
 /// Separate type parameters allow the givens/unknowns types to be parameterized by the AD type.
 #[derive(Clone)]
 pub struct ResidualFns<G64, U64, Gadfn, Uadfn> {
-    f64: Vec<Rc<fn(&G64, &U64) -> f64>>,
-    adfn_1: Vec<Rc<fn(&Gadfn, &Uadfn) -> adfn<1>>>,
+    f64: Vec<ResidualFn<G64, U64, f64>>,
+    adfn_1: Vec<ResidualFn<Gadfn, Uadfn, adfn<1>>>,
     fn_names: Vec<&'static str>,
+    meta: Vec<ResidualMeta>,
+    /// Names of the functions added by `with_complex_step_residual`, whose adfn versions only carry
+    /// the unknowns' tangents.
+    complex_step_fns: Vec<&'static str>,
+}
+
+/// Optional human-readable metadata for a residual function, used when printing plans and residuals.
+#[derive(Clone, Debug, Default)]
+pub struct ResidualMeta {
+    /// What a nonzero residual means, e.g. "jump apex too low" for a positive value.
+    pub description: Option<&'static str>,
+    /// Physical units of the residual value, e.g. "m".
+    pub units: Option<&'static str>,
+    /// Group the residual belongs to, e.g. "jump".
+    pub tag: Option<&'static str>,
+    /// Shown instead of the function's name when printing, e.g. "jump apex height".
+    ///
+    /// Lookups by name (`ResidualFns::with_meta`, plan overrides, ...) still use the function's
+    /// name.
+    pub display_name: Option<&'static str>,
+}
+
+impl ResidualMeta {
+    pub fn with_description(mut self, description: &'static str) -> Self {
+        self.description = Some(description);
+        self
+    }
+
+    pub fn with_units(mut self, units: &'static str) -> Self {
+        self.units = Some(units);
+        self
+    }
+
+    pub fn with_tag(mut self, tag: &'static str) -> Self {
+        self.tag = Some(tag);
+        self
+    }
+
+    pub fn with_display_name(mut self, display_name: &'static str) -> Self {
+        self.display_name = Some(display_name);
+        self
+    }
+
+    /// Formats a residual value with its units and description, e.g. `-0.300000 m (jump apex too low)`.
+    pub fn format_value(&self, value: f64) -> String {
+        let mut out = format!("{:.6}", value);
+        if let Some(units) = self.units {
+            out.push_str(&format!(" {}", units));
+        }
+        if let Some(description) = self.description {
+            out.push_str(&format!(" ({})", description));
+        }
+        out
+    }
+
+    /// Formats the metadata as a suffix for a residual function's name, e.g.
+    /// ` [m] #jump: jump apex too low`. Empty if no metadata is set.
+    pub fn label_suffix(&self) -> String {
+        let mut out = String::new();
+        if let Some(units) = self.units {
+            out.push_str(&format!(" [{}]", units));
+        }
+        if let Some(tag) = self.tag {
+            out.push_str(&format!(" #{}", tag));
+        }
+        if let Some(description) = self.description {
+            out.push_str(&format!(": {}", description));
+        }
+        out
+    }
 }
 
+/// `ResidualFns` named by the f64 versions of the givens and unknowns structs only (see `ParamFamily`).
+pub type ResidualFnsFor<G, U> = ResidualFns<G, U, AdfnFor<G>, AdfnFor<U>>;
+
 /// Create ResidualFns for types that are generic over T: AD.
 /// Usage: `residual_fns_for_generic_params!(GivenType, UnknownType; fn1, fn2, ...)`
 /// where GivenType<T> and UnknownType<T> are the parameter types.
+///
+/// Each function may be followed, in this order, by a display name (see
+/// `ResidualMeta::display_name`), `ResidualMeta` fields in braces, and a factor the residual is
+/// multiplied by:
+/// `jump_height_residual as "jump apex height" { units: "m", description: "jump apex too low", tag: "jump" } * 1.0 / 3.3`.
 #[macro_export]
 macro_rules! residual_fns_for_generic_params {
-    ($g:ident, $u:ident; $($fn_name:ident),* $(,)?) => {
+    ($g:ident, $u:ident; $(
+        $fn_name:ident
+        $(as $display:literal)?
+        $({ $($key:ident : $val:expr),* $(,)? })?
+        $(* $scale:expr)?
+    ),* $(,)?) => {
         $crate::equation_system::residuals::residuals::ResidualFns::<
             $g<f64>, $u<f64>,
             $g<ad_trait::forward_ad::adfn::adfn<1>>, $u<ad_trait::forward_ad::adfn::adfn<1>>
-        >::new(
-            vec![$(std::rc::Rc::new($fn_name::<f64>)),*],
-            vec![$(std::rc::Rc::new($fn_name::<ad_trait::forward_ad::adfn::adfn<1>>)),*],
+        >::new_with_meta(
+            vec![$($crate::equation_system::residuals::residuals::scaled_residual_fn(
+                $fn_name::<f64>,
+                ::core::option::Option::<f64>::None $(.or(Some($scale)))?,
+            )),*],
+            vec![$($crate::equation_system::residuals::residuals::scaled_residual_fn(
+                $fn_name::<ad_trait::forward_ad::adfn::adfn<1>>,
+                ::core::option::Option::<f64>::None $(.or(Some($scale)))?,
+            )),*],
             vec![$(stringify!($fn_name)),*],
+            vec![$(
+                $crate::equation_system::residuals::residuals::ResidualMeta {
+                    $(display_name: Some($display),)?
+                    $($($key: Some($val),)*)?
+                    ..Default::default()
+                }
+            ),*],
         )
     };
 }
 
+/// Residual functions for types that are generic over T: AD, instantiated at `adfn<K>` for
+/// `EquationSystemBuilder::with_wide_tangents`.
+/// Usage: `wide_residual_fns!(GivenType, UnknownType, K; fn1, fn2, ...)`, listing the functions in
+/// the same order, and with the same factors, as for `residual_fns_for_generic_params!`. Display
+/// names and metadata are accepted and ignored.
+#[macro_export]
+macro_rules! wide_residual_fns {
+    ($g:ident, $u:ident, $k:expr; $(
+        $fn_name:ident
+        $(as $display:literal)?
+        $({ $($key:ident : $val:expr),* $(,)? })?
+        $(* $scale:expr)?
+    ),* $(,)?) => {
+        vec![$($crate::equation_system::residuals::residuals::scaled_residual_fn::<
+            $g<ad_trait::forward_ad::adfn::adfn<{ $k }>>,
+            $u<ad_trait::forward_ad::adfn::adfn<{ $k }>>,
+            ad_trait::forward_ad::adfn::adfn<{ $k }>,
+        >(
+            $fn_name::<ad_trait::forward_ad::adfn::adfn<{ $k }>>,
+            ::core::option::Option::<f64>::None $(.or(Some($scale)))?,
+        )),*]
+    };
+}
+
 fn filter_res_fns_to_block<T, G, U>(
-    fns: Vec<Rc<fn(&G, &U) -> T>>,
+    fns: Vec<ResidualFn<G, U, T>>,
     solution_block: &SolutionBlock,
-) -> Vec<Rc<fn(&G, &U) -> T>> {
+) -> Vec<ResidualFn<G, U, T>> {
     fns.iter()
         .enumerate()
         .filter_map(|(i, f)| {
@@ -94,27 +239,129 @@ fn filter_res_fns_to_block<T, G, U>(
         .collect::<Vec<_>>()
 }
 
+/// An empty collection, to be filled with `push` when the residual functions are only known at runtime.
+impl<G64, U64, Gadfn, Uadfn> Default for ResidualFns<G64, U64, Gadfn, Uadfn> {
+    fn default() -> Self {
+        Self::new(vec![], vec![], vec![])
+    }
+}
+
 impl<G64, U64, Gadfn, Uadfn> ResidualFns<G64, U64, Gadfn, Uadfn> {
     /// Creates a new ResidualFns instance with the given function vectors.
     pub fn new(
-        f64: Vec<Rc<fn(&G64, &U64) -> f64>>,
-        adfn_1: Vec<Rc<fn(&Gadfn, &Uadfn) -> adfn<1>>>,
+        f64: Vec<ResidualFn<G64, U64, f64>>,
+        adfn_1: Vec<ResidualFn<Gadfn, Uadfn, adfn<1>>>,
+        fn_names: Vec<&'static str>,
+    ) -> Self {
+        let meta = vec![ResidualMeta::default(); fn_names.len()];
+        Self::new_with_meta(f64, adfn_1, fn_names, meta)
+    }
+
+    /// Like `new`, with per-function metadata (one entry per function).
+    pub fn new_with_meta(
+        f64: Vec<ResidualFn<G64, U64, f64>>,
+        adfn_1: Vec<ResidualFn<Gadfn, Uadfn, adfn<1>>>,
         fn_names: Vec<&'static str>,
+        meta: Vec<ResidualMeta>,
     ) -> Self {
+        debug_assert!(
+            f64.len() == fn_names.len()
+                && adfn_1.len() == fn_names.len()
+                && meta.len() == fn_names.len(),
+            "ResidualFns: expected one f64 fn, adfn fn and metadata entry per name"
+        );
         Self {
             f64,
             adfn_1,
             fn_names,
+            meta,
+            complex_step_fns: vec![],
+        }
+    }
+
+    /// Sets the metadata of the residual function named `fn_name`.
+    pub fn with_meta(mut self, fn_name: &str, meta: ResidualMeta) -> Result<Self, EqSysError> {
+        let idx = self
+            .fn_names
+            .iter()
+            .position(|&name| name == fn_name)
+            .ok_or_else(|| EqSysError::UnknownResidualName {
+                fn_name: fn_name.to_string(),
+            })?;
+        self.meta[idx] = meta;
+        Ok(self)
+    }
+
+    /// Appends a residual function given as separate f64 and adfn<1> closures, e.g. closures
+    /// capturing measured data that can't be written as one function generic over `T: AD`.
+    pub fn with_residual(
+        mut self,
+        name: &'static str,
+        res_fn_f64: impl Fn(&G64, &U64) -> f64 + MaybeSendSync + 'static,
+        res_fn_adfn: impl Fn(&Gadfn, &Uadfn) -> adfn<1> + MaybeSendSync + 'static,
+    ) -> Self {
+        self.f64.push(residual_fn(res_fn_f64));
+        self.adfn_1.push(residual_fn(res_fn_adfn));
+        self.fn_names.push(name);
+        self.meta.push(ResidualMeta::default());
+        self
+    }
+
+    /// Registers a residual function given as separate f64 and adfn<1> closures, for systems
+    /// assembled at runtime, e.g. by looping over constraint templates or collecting
+    /// plugin-provided constraints into a `ResidualFns::default()`. Fails if `name` is already
+    /// registered.
+    ///
+    /// Names are `&'static str` like everywhere else; a name built at runtime can be made one with `String::leak`.
+    pub fn push(
+        &mut self,
+        name: &'static str,
+        res_fn_f64: impl Fn(&G64, &U64) -> f64 + MaybeSendSync + 'static,
+        res_fn_adfn: impl Fn(&Gadfn, &Uadfn) -> adfn<1> + MaybeSendSync + 'static,
+    ) -> Result<(), EqSysError> {
+        if self.fn_names.contains(&name) {
+            return Err(EqSysError::DuplicateResidualName { fn_name: name });
+        }
+        self.f64.push(residual_fn(res_fn_f64));
+        self.adfn_1.push(residual_fn(res_fn_adfn));
+        self.fn_names.push(name);
+        self.meta.push(ResidualMeta::default());
+        Ok(())
+    }
+
+    /// Number of registered residual functions.
+    pub fn len(&self) -> usize {
+        self.fn_names.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.fn_names.is_empty()
+    }
+
+    /// Appends the functions of `other` after this collection's, e.g. to combine `residual_fns!`
+    /// collections from different modules. Fails if a name appears in both.
+    pub fn concat(mut self, other: Self) -> Result<Self, EqSysError> {
+        if let Some(&fn_name) = other
+            .fn_names
+            .iter()
+            .find(|name| self.fn_names.contains(name))
+        {
+            return Err(EqSysError::DuplicateResidualName { fn_name });
         }
+        self.f64.extend(other.f64);
+        self.adfn_1.extend(other.adfn_1);
+        self.fn_names.extend(other.fn_names);
+        self.meta.extend(other.meta);
+        Ok(self)
     }
 
     /// Returns a reference to the f64 residual functions.
-    pub fn f64(&self) -> &Vec<Rc<fn(&G64, &U64) -> f64>> {
+    pub fn f64(&self) -> &Vec<ResidualFn<G64, U64, f64>> {
         &self.f64
     }
 
     /// Returns a reference to the adfn<1> residual functions.
-    pub fn adfn_1(&self) -> &Vec<Rc<fn(&Gadfn, &Uadfn) -> adfn<1>>> {
+    pub fn adfn_1(&self) -> &Vec<ResidualFn<Gadfn, Uadfn, adfn<1>>> {
         &self.adfn_1
     }
 
@@ -123,6 +370,31 @@ impl<G64, U64, Gadfn, Uadfn> ResidualFns<G64, U64, Gadfn, Uadfn> {
         &self.fn_names
     }
 
+    /// Names of the functions differentiated by complex step (see `with_complex_step_residual`),
+    /// which have no derivatives with respect to the givens.
+    pub fn complex_step_fn_names(&self) -> &[&'static str] {
+        &self.complex_step_fns
+    }
+
+    /// Returns a reference to the per-function metadata.
+    pub fn meta(&self) -> &Vec<ResidualMeta> {
+        &self.meta
+    }
+
+    /// The function's display name if it has one (see `ResidualMeta::display_name`), its name otherwise.
+    pub fn display_name(&self, idx: usize) -> &'static str {
+        self.meta[idx].display_name.unwrap_or(self.fn_names[idx])
+    }
+
+    /// The function's display name followed by its metadata, e.g. `jump_height_residual [m] #jump: jump apex too low`.
+    pub fn fn_label(&self, idx: usize) -> String {
+        format!(
+            "{}{}",
+            self.display_name(idx),
+            self.meta[idx].label_suffix()
+        )
+    }
+
     /// Filters the residual functions to only those in the given solution block.
     pub fn filter_res_fns_to_block(
         &self,
@@ -136,11 +408,25 @@ impl<G64, U64, Gadfn, Uadfn> ResidualFns<G64, U64, Gadfn, Uadfn> {
             .iter()
             .map(|&i| self.fn_names[i])
             .collect::<Vec<_>>();
+        let meta = solution_block
+            .equation_idxs
+            .iter()
+            .map(|&i| self.meta[i].clone())
+            .collect::<Vec<_>>();
+
+        let complex_step_fns = self
+            .complex_step_fns
+            .iter()
+            .filter(|name| fn_names.contains(name))
+            .copied()
+            .collect();
 
         ResidualFns {
             f64: res_fns_64,
             adfn_1: res_fns_adfn1,
             fn_names,
+            meta,
+            complex_step_fns,
         }
     }
 }
@@ -1,14 +1,14 @@
-use std::rc::Rc;
-
 use ad_trait::AD;
 
+use crate::{equation_system::shared::shared_fn, prelude::*};
+
 /// Trait for specifying a higher-order-function that can generate *generic* vectors of residual transformation functions for residuals of any type `T:AD`.
 ///
 /// These functions are applied element-wise to the residuals vector, and is where weighting, scaling, loss transforms (L1, L2, etc) can be applied.
 ///
 /// We do this as a trait rather than a normal struct or function so that we can more easily specify the HOF in one place, and then pladd that around to the locations where `ad_trait` needs concrete `f64`` and `adfn<1>` versions.es, it's kind of a pain to need this much abstraction, but it's seems better than passing around multiple copies of functions specified for different types all over the place.
 pub trait ResidTransHOF: Clone {
-    fn make_loss_fns<T: AD>(&self) -> Vec<Rc<dyn Fn(T) -> T>>;
+    fn make_loss_fns<T: AD>(&self) -> Vec<shared_fn!(Fn(T) -> T)>;
 }
 
 #[derive(Clone)]
@@ -22,8 +22,8 @@ impl ResidTransIdentity {
 }
 
 impl ResidTransHOF for ResidTransIdentity {
-    fn make_loss_fns<T: AD>(&self) -> Vec<Rc<dyn Fn(T) -> T>> {
-        let f: Rc<dyn Fn(T) -> T> = Rc::new(|r: T| r);
+    fn make_loss_fns<T: AD>(&self) -> Vec<shared_fn!(Fn(T) -> T)> {
+        let f: shared_fn!(Fn(T) -> T) = Shared::new(|r: T| r);
         (0..self.n).map(|_| f.clone()).collect()
     }
 }
@@ -35,22 +35,61 @@ pub struct ResidTransUnscaledL2 {
     pub n: usize,
 }
 impl ResidTransHOF for ResidTransUnscaledL2 {
-    fn make_loss_fns<T: AD>(&self) -> Vec<Rc<dyn Fn(T) -> T>> {
-        let f: Rc<dyn Fn(T) -> T> = Rc::new(|r: T| r * r);
+    fn make_loss_fns<T: AD>(&self) -> Vec<shared_fn!(Fn(T) -> T)> {
+        let f: shared_fn!(Fn(T) -> T) = Shared::new(|r: T| r * r);
         (0..self.n).map(|_| f.clone()).collect()
     }
 }
 
+/// Multiplies each residual by a fixed weight before applying the inner transform: `r_i -> inner_i(w_i * r_i)`.
+///
+/// Weights are in the order of the residuals being transformed (e.g. block equation order for a sub-problem).
+#[derive(Clone)]
+pub struct ResidTransWeighted<R: ResidTransHOF> {
+    pub weights: Vec<f64>,
+    pub inner: R,
+}
+impl<R: ResidTransHOF> ResidTransWeighted<R> {
+    pub fn new(weights: Vec<f64>, inner: R) -> Self {
+        Self { weights, inner }
+    }
+}
+impl<R: ResidTransHOF> ResidTransHOF for ResidTransWeighted<R> {
+    fn make_loss_fns<T: AD>(&self) -> Vec<shared_fn!(Fn(T) -> T)> {
+        self.inner
+            .make_loss_fns::<T>()
+            .into_iter()
+            .zip(&self.weights)
+            .map(|(inner, &w)| {
+                let f: shared_fn!(Fn(T) -> T) = Shared::new(move |r: T| inner(r * T::constant(w)));
+                f
+            })
+            .collect()
+    }
+}
+
+/// Scaled L2 loss functions (r^2 / s) for each residual.
 #[derive(Clone)]
 pub struct ResidTransScaledL2 {
-    scales: Vec<f64>,
+    pub scales: Vec<f64>,
+}
+impl ResidTransScaledL2 {
+    pub fn new(scales: Vec<f64>) -> Self {
+        Self { scales }
+    }
+
+    /// Scales that make each loss `(r_i / m_i)^2`, i.e. the residuals measured in units of their
+    /// characteristic magnitudes (see `EquationSystemBuilder::residual_magnitudes_at`).
+    pub fn from_magnitudes(magnitudes: &[f64]) -> Self {
+        Self::new(magnitudes.iter().map(|m| m * m).collect())
+    }
 }
 impl ResidTransHOF for ResidTransScaledL2 {
-    fn make_loss_fns<T: AD>(&self) -> Vec<Rc<dyn Fn(T) -> T>> {
+    fn make_loss_fns<T: AD>(&self) -> Vec<shared_fn!(Fn(T) -> T)> {
         self.scales
             .iter()
             .map(|&s| {
-                let f: Rc<dyn Fn(T) -> T> = Rc::new(move |r: T| r * r / T::constant(s));
+                let f: shared_fn!(Fn(T) -> T) = Shared::new(move |r: T| r * r / T::constant(s));
                 f
             })
             .collect()
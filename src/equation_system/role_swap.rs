@@ -0,0 +1,176 @@
+use ad_trait::{AD, forward_ad::adfn::adfn};
+use field_names_and_counts::FieldNames;
+use nalgebra::DVector;
+use struct_to_array::StructToArray;
+
+use crate::{equation_system::EquationSystemBuilder, prelude::*};
+
+/// Where an unknown of a role-swapped system lives in the original param structs.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Slot {
+    Given(usize),
+    Unknown(usize),
+}
+
+/// A system with some givens promoted to unknowns and some unknowns fixed, built by
+/// `EquationSystemBuilder::with_swapped_roles`.
+///
+/// The swapped system is a `DynEquationSystemBuilder` over the remaining original unknowns (in
+/// `StructToArray` order) followed by the promoted givens. The residual functions are the original
+/// ones; they are still called with the original givens and unknowns structs.
+pub struct SwappedRoles<G64, U64, const NG: usize, const N: usize> {
+    pub system: DynEquationSystemBuilder<EqSysStateInit>,
+    /// Starting point for the swapped system: the current values of its unknowns.
+    pub initial_unknowns: DVector<f64>,
+    slots: Vec<Slot>,
+    givens: G64,
+    unknowns: U64,
+}
+
+impl<G64, U64, const NG: usize, const N: usize> SwappedRoles<G64, U64, NG, N>
+where
+    G64: StructToArray<f64, NG>,
+    U64: StructToArray<f64, N>,
+{
+    /// Patches a solution of the swapped system back into the original givens and unknowns structs.
+    pub fn split_solution(&self, solution: &DVector<f64>) -> Result<(G64, U64), EqSysError> {
+        if solution.len() != self.slots.len() {
+            return Err(EqSysError::UnknownsLenMismatch {
+                expected: self.slots.len(),
+                got: solution.len(),
+            });
+        }
+        let (givens, unknowns) = patch_slots(
+            self.givens.to_arr(),
+            self.unknowns.to_arr(),
+            &self.slots,
+            solution.as_slice(),
+        );
+        Ok((G64::from_arr(givens), U64::from_arr(unknowns)))
+    }
+}
+
+/// Overwrites the slots of `givens` and `unknowns` with `values`, in slot order.
+fn patch_slots<T: Copy, const NG: usize, const N: usize>(
+    mut givens: [T; NG],
+    mut unknowns: [T; N],
+    slots: &[Slot],
+    values: &[T],
+) -> ([T; NG], [T; N]) {
+    for (slot, &v) in slots.iter().zip(values) {
+        match *slot {
+            Slot::Given(idx) => givens[idx] = v,
+            Slot::Unknown(idx) => unknowns[idx] = v,
+        }
+    }
+    (givens, unknowns)
+}
+
+fn field_idx(names: &[&str], field: &str) -> Result<usize, EqSysError> {
+    names
+        .iter()
+        .position(|&name| name == field)
+        .ok_or_else(|| EqSysError::UnknownParamField {
+            field: field.to_string(),
+        })
+}
+
+impl<G64, U64, Gadfn, Uadfn, S, const N: usize> EquationSystemBuilder<G64, U64, Gadfn, Uadfn, S, N>
+where
+    G64: GivenParamsFor<f64, N> + FieldNames + 'static,
+    U64: UnknownParamsFor<f64, N> + 'static,
+    Gadfn: GivenParamsFor<adfn<1>, N> + 'static,
+    Uadfn: UnknownParamsFor<adfn<1>, N> + 'static,
+{
+    /// Builds the inverse problem: the given fields named in `promote_givens` become unknowns, and
+    /// the unknown fields named in `fix_unknowns` are held at their values in `unknowns`.
+    ///
+    /// E.g. fixing `air_drag_coeff` and promoting `max_air_speed_x` solves for the top speed a
+    /// given drag coefficient produces, without restructuring the param structs or residual
+    /// signatures. Use `SwappedRoles::split_solution` to map the solution back. Other builder
+    /// settings (weights, regularization, bounds, constraints) are not carried over. Fails with
+    /// `EqSysError::DuplicateParamField` if a given is promoted twice, and with
+    /// `EqSysError::GivensDerivativesUnavailable` if givens are promoted in a system with
+    /// complex-step residuals, which can't differentiate them.
+    pub fn with_swapped_roles<const NG: usize>(
+        &self,
+        promote_givens: &[&str],
+        fix_unknowns: &[&str],
+        unknowns: &U64,
+    ) -> Result<SwappedRoles<G64, U64, NG, N>, EqSysError>
+    where
+        G64: StructToArray<f64, NG>,
+        Gadfn: StructToArray<adfn<1>, NG>,
+    {
+        let mut fixed = [false; N];
+        for field in fix_unknowns {
+            fixed[field_idx(self.unknown_field_names, field)?] = true;
+        }
+        let mut slots = vec![];
+        let mut names = vec![];
+        for idx in (0..N).filter(|&idx| !fixed[idx]) {
+            slots.push(Slot::Unknown(idx));
+            names.push(self.unknown_field_names[idx].to_string());
+        }
+        if let (Some(_), Some(&fn_name)) = (
+            promote_givens.first(),
+            self.raw_res_fns.complex_step_fn_names().first(),
+        ) {
+            return Err(EqSysError::GivensDerivativesUnavailable { fn_name });
+        }
+        for field in promote_givens {
+            let idx = field_idx(G64::FIELDS, field)?;
+            if slots.contains(&Slot::Given(idx)) {
+                return Err(EqSysError::DuplicateParamField {
+                    field: field.to_string(),
+                });
+            }
+            slots.push(Slot::Given(idx));
+            names.push(G64::FIELDS[idx].to_string());
+        }
+
+        let givens_arr = self.givens_f64.to_arr();
+        let unknowns_arr = unknowns.to_arr();
+        let initial_unknowns = DVector::from_iterator(
+            slots.len(),
+            slots.iter().map(|slot| match *slot {
+                Slot::Given(idx) => givens_arr[idx],
+                Slot::Unknown(idx) => unknowns_arr[idx],
+            }),
+        );
+        let givens_adfn_arr = givens_arr.map(adfn::<1>::constant);
+        let unknowns_adfn_arr = unknowns_arr.map(adfn::<1>::constant);
+
+        let residual_fns = self
+            .raw_res_fns
+            .f64()
+            .iter()
+            .zip(self.raw_res_fns.adfn_1())
+            .zip(self.raw_res_fns.fn_names())
+            .map(|((res_fn_f64, res_fn_adfn), &name)| {
+                let (res_fn_f64, res_fn_adfn) = (res_fn_f64.clone(), res_fn_adfn.clone());
+                let (slots_f64, slots_adfn) = (slots.clone(), slots.clone());
+                DynResidualFn::new(
+                    name,
+                    move |x: &[f64]| {
+                        let (g, u) = patch_slots(givens_arr, unknowns_arr, &slots_f64, x);
+                        res_fn_f64(&G64::from_arr(g), &U64::from_arr(u))
+                    },
+                    move |x: &[adfn<1>]| {
+                        let (g, u) =
+                            patch_slots(givens_adfn_arr, unknowns_adfn_arr, &slots_adfn, x);
+                        res_fn_adfn(&Gadfn::from_arr(g), &Uadfn::from_arr(u))
+                    },
+                )
+            })
+            .collect();
+
+        Ok(SwappedRoles {
+            system: DynEquationSystemBuilder::new(residual_fns, names),
+            initial_unknowns,
+            slots,
+            givens: self.givens_f64.clone(),
+            unknowns: *unknowns,
+        })
+    }
+}
@@ -0,0 +1,125 @@
+use ad_trait::{AD, forward_ad::adfn::adfn};
+use field_names_and_counts::FieldNames;
+use nalgebra::DMatrix;
+use struct_to_array::StructToArray;
+
+use crate::{equation_system::EquationSystemBuilder, prelude::*};
+
+/// First-order sensitivities of the unknowns to the givens at a solution, computed by
+/// `EquationSystemBuilder::sensitivities`.
+#[derive(Clone, Debug)]
+pub struct Sensitivities {
+    pub unknown_names: Vec<&'static str>,
+    pub given_names: Vec<&'static str>,
+    /// `matrix[(u, g)]` is d(unknown `u`) / d(given `g`), in `StructToArray` order.
+    pub matrix: DMatrix<f64>,
+}
+
+impl Sensitivities {
+    /// d(`unknown`) / d(`given`), or `None` if either name is unknown.
+    pub fn get(&self, unknown: &str, given: &str) -> Option<f64> {
+        let u = self
+            .unknown_names
+            .iter()
+            .position(|&name| name == unknown)?;
+        let g = self.given_names.iter().position(|&name| name == given)?;
+        Some(self.matrix[(u, g)])
+    }
+
+    /// The first-order change of each unknown (in `StructToArray` order) when the givens change by
+    /// `given_deltas`, e.g. "raising `jump_height` by 0.1 raises `jump_vy_0` by about 0.3".
+    pub fn predicted_change(&self, given_deltas: &[f64]) -> Vec<f64> {
+        (0..self.matrix.nrows())
+            .map(|u| {
+                self.matrix
+                    .row(u)
+                    .iter()
+                    .zip(given_deltas)
+                    .map(|(s, dg)| s * dg)
+                    .sum()
+            })
+            .collect()
+    }
+}
+
+impl<G64, U64, Gadfn, Uadfn, S, const N: usize> EquationSystemBuilder<G64, U64, Gadfn, Uadfn, S, N>
+where
+    G64: GivenParamsFor<f64, N> + FieldNames,
+    U64: UnknownParamsFor<f64, N>,
+    Gadfn: GivenParamsFor<adfn<1>, N>,
+    Uadfn: UnknownParamsFor<adfn<1>, N>,
+{
+    /// d(unknowns)/d(givens) at `at_solution` by the implicit function theorem: with `r(u, g) = 0`
+    /// holding along the solution path, `J_u du = -J_g dg`, where both Jacobians come from forward
+    /// AD.
+    ///
+    /// This replaces finite-difference re-solves for questions like "how much does each unknown
+    /// move per unit of this given". Overdetermined systems get the least-squares sensitivities.
+    /// Fails with `EqSysError::SingularSensitivityJacobian` if `J_u` has rank below the number of
+    /// unknowns, since some unknowns then aren't locally determined by the givens. Fails with
+    /// `EqSysError::GivensDerivativesUnavailable` if the system has complex-step residuals, whose
+    /// `J_g` rows would silently be zero.
+    pub fn sensitivities<const NG: usize>(
+        &self,
+        at_solution: &U64,
+    ) -> Result<Sensitivities, EqSysError>
+    where
+        G64: StructToArray<f64, NG>,
+        Gadfn: StructToArray<adfn<1>, NG>,
+    {
+        let unknowns = at_solution.to_arr();
+        let (_residuals, jacobian_u) = self.full_derivative(&unknowns);
+        let rank = BlockConditioning::from_jacobian(0, &jacobian_u).rank;
+        if rank < N {
+            return Err(EqSysError::SingularSensitivityJacobian {
+                rank,
+                n_unknowns: N,
+            });
+        }
+
+        let jacobian_g = self.givens_jacobian::<NG>(&unknowns)?;
+        let matrix = jacobian_u
+            .svd(true, true)
+            .solve(&(-jacobian_g), f64::EPSILON)
+            .map_err(|_| EqSysError::SingularSensitivityJacobian {
+                rank,
+                n_unknowns: N,
+            })?;
+
+        Ok(Sensitivities {
+            unknown_names: self.unknown_field_names.to_vec(),
+            given_names: G64::FIELDS.to_vec(),
+            matrix,
+        })
+    }
+
+    /// Jacobian of all raw residuals with respect to the givens at `unknowns`, one forward-AD pass
+    /// per given.
+    ///
+    /// Fails for complex-step residuals, which drop the givens' tangents.
+    fn givens_jacobian<const NG: usize>(
+        &self,
+        unknowns: &[f64; N],
+    ) -> Result<DMatrix<f64>, EqSysError>
+    where
+        G64: StructToArray<f64, NG>,
+        Gadfn: StructToArray<adfn<1>, NG>,
+    {
+        if let Some(&fn_name) = self.raw_res_fns.complex_step_fn_names().first() {
+            return Err(EqSysError::GivensDerivativesUnavailable { fn_name });
+        }
+        let fns = self.raw_res_fns.adfn_1();
+        let givens = self.givens_f64.to_arr();
+        let unknowns_adfn = Uadfn::from_arr(unknowns.map(adfn::<1>::constant));
+        let mut jacobian = DMatrix::zeros(fns.len(), NG);
+        for g in 0..NG {
+            let seeded = Gadfn::from_arr(std::array::from_fn(|i| {
+                adfn::new(givens[i], [if i == g { 1.0 } else { 0.0 }])
+            }));
+            for (r, f) in fns.iter().enumerate() {
+                jacobian[(r, g)] = f(&seeded, &unknowns_adfn).tangent()[0];
+            }
+        }
+        Ok(jacobian)
+    }
+}
@@ -0,0 +1,42 @@
+//! Shared ownership of stored closures: `Rc` by default, `Arc` with `Send + Sync` closures with the
+//! `parallel` feature, so that builders and sub-problems can be used from several threads.
+
+#[cfg(feature = "parallel")]
+pub use std::sync::Arc as Shared;
+
+#[cfg(not(feature = "parallel"))]
+pub use std::rc::Rc as Shared;
+
+/// `Send + Sync` with the `parallel` feature, and no bound without it. Closures and params that end
+/// up in shared closures must satisfy it.
+#[cfg(feature = "parallel")]
+pub trait MaybeSendSync: Send + Sync {}
+#[cfg(feature = "parallel")]
+impl<T: Send + Sync + ?Sized> MaybeSendSync for T {}
+
+/// `Send + Sync` with the `parallel` feature, and no bound without it. Closures and params that end
+/// up in shared closures must satisfy it.
+#[cfg(not(feature = "parallel"))]
+pub trait MaybeSendSync {}
+#[cfg(not(feature = "parallel"))]
+impl<T: ?Sized> MaybeSendSync for T {}
+
+/// A shared closure type: `shared_fn!(Fn(&U) -> T)` is `Rc<dyn Fn(&U) -> T>`, or
+/// `Arc<dyn Fn(&U) -> T + Send + Sync>` with the `parallel` feature.
+#[cfg(feature = "parallel")]
+macro_rules! shared_fn {
+    ($($sig:tt)*) => {
+        $crate::equation_system::shared::Shared<dyn $($sig)* + Send + Sync>
+    };
+}
+
+/// A shared closure type: `shared_fn!(Fn(&U) -> T)` is `Rc<dyn Fn(&U) -> T>`, or
+/// `Arc<dyn Fn(&U) -> T + Send + Sync>` with the `parallel` feature.
+#[cfg(not(feature = "parallel"))]
+macro_rules! shared_fn {
+    ($($sig:tt)*) => {
+        $crate::equation_system::shared::Shared<dyn $($sig)*>
+    };
+}
+
+pub(crate) use shared_fn;
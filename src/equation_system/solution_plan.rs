@@ -1,3 +1,8 @@
+use std::fmt;
+
+use nalgebra::DMatrix;
+use rand::{Rng, SeedableRng, rngs::StdRng};
+
 use crate::prelude::*;
 
 #[derive(Debug)]
@@ -17,10 +22,8 @@ impl SolutionPlan {
         res_fns: &ResidualFns<G64, U64, Gadfn, Uadfn>,
         field_names: &[&str],
     ) {
-        for block in self.blocks.iter() {
-            println!("Solution Block {}:", block.block_idx);
-            self.print_solution_block(block, res_fns, field_names);
-        }
+        let labels = equation_labels(res_fns);
+        print!("{}", self.with_names(&labels, field_names));
     }
 
     pub fn print_solution_block<G64, U64, Gadfn, Uadfn>(
@@ -29,19 +32,231 @@ impl SolutionPlan {
         res_fns: &ResidualFns<G64, U64, Gadfn, Uadfn>,
         field_names: &[&str],
     ) {
-        println!("  equations:");
-        // print the name of each equation
-        for e in &block.equation_idxs {
-            let fn_name = res_fns.fn_names()[*e];
-            println!("    {e}: {}", fn_name);
+        print!(
+            "{}",
+            self.solution_block_string(block, res_fns, field_names)
+        );
+    }
+
+    /// The names of `block`'s equations and unknowns, as printed by `print_solution_block`.
+    pub fn solution_block_string<G64, U64, Gadfn, Uadfn>(
+        &self,
+        block: &SolutionBlock,
+        res_fns: &ResidualFns<G64, U64, Gadfn, Uadfn>,
+        field_names: &[&str],
+    ) -> String {
+        let labels = equation_labels(res_fns);
+        block.with_names(&labels, field_names).to_string()
+    }
+
+    /// Displays every block with its equations and unknowns named by `equation_names` and
+    /// `unknown_names`, indexed like the full system.
+    pub fn with_names<'a, E: AsRef<str>, U: AsRef<str>>(
+        &'a self,
+        equation_names: &'a [E],
+        unknown_names: &'a [U],
+    ) -> NamedSolutionPlan<'a, E, U> {
+        NamedSolutionPlan {
+            plan: self,
+            equation_names,
+            unknown_names,
+        }
+    }
+}
+
+/// Every equation's label (`ResidualFns::fn_label`), indexed like the full system.
+fn equation_labels<G64, U64, Gadfn, Uadfn>(
+    res_fns: &ResidualFns<G64, U64, Gadfn, Uadfn>,
+) -> Vec<String> {
+    (0..res_fns.fn_names().len())
+        .map(|e| res_fns.fn_label(e))
+        .collect()
+}
+
+/// One line per block, with its equation and unknown indices; see `SolutionPlan::with_names` for a listing by name.
+impl fmt::Display for SolutionPlan {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for block in &self.blocks {
+            writeln!(f, "{block}")?;
         }
-        println!("  unknowns:");
+        Ok(())
+    }
+}
+
+/// A `SolutionPlan` displayed by name, from `SolutionPlan::with_names`.
+pub struct NamedSolutionPlan<'a, E, U> {
+    plan: &'a SolutionPlan,
+    equation_names: &'a [E],
+    unknown_names: &'a [U],
+}
+
+impl<E: AsRef<str>, U: AsRef<str>> fmt::Display for NamedSolutionPlan<'_, E, U> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for block in &self.plan.blocks {
+            write!(
+                f,
+                "{}",
+                block.with_names(self.equation_names, self.unknown_names)
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// How `with_triangularization` samples the Jacobian to find which unknowns each equation depends on.
+///
+/// The sparsity patterns at the initial unknowns and at `n_extra_points` randomly perturbed copies
+/// of them are OR-ed together, so a dependency that happens to evaluate to exactly zero at one
+/// point still shows up. Non-finite derivatives at the perturbed points are ignored.
+#[derive(Clone, Debug)]
+pub struct StructureSampling {
+    pub n_extra_points: usize,
+    /// Each unknown `u` is moved to `u * (1 + d)`, or to `d` if `u` is 0, with `d` uniform in `±rel_perturbation`.
+    pub rel_perturbation: f64,
+    /// Seed for the perturbations, so plans are reproducible.
+    pub seed: u64,
+    /// Which sampled derivatives count as dependencies.
+    pub zero_threshold: ZeroThreshold,
+}
+
+impl Default for StructureSampling {
+    fn default() -> Self {
+        Self {
+            n_extra_points: 4,
+            rel_perturbation: 0.1,
+            seed: 0,
+            zero_threshold: ZeroThreshold::default(),
+        }
+    }
+}
+
+impl StructureSampling {
+    /// Only the Jacobian at the initial unknowns, as in a single-sample structure detection.
+    pub fn single_point() -> Self {
+        Self {
+            n_extra_points: 0,
+            ..Default::default()
+        }
+    }
+
+    /// The perturbed copies of `unknowns` to sample the Jacobian at.
+    pub fn perturbed_points<const N: usize>(&self, unknowns: [f64; N]) -> Vec<[f64; N]> {
+        let mut rng = StdRng::seed_from_u64(self.seed);
+        let scale = self.rel_perturbation;
+        (0..self.n_extra_points)
+            .map(|_| {
+                unknowns.map(|u| {
+                    let d = rng.random_range(-scale..=scale);
+                    if u == 0.0 { d } else { u * (1.0 + d) }
+                })
+            })
+            .collect()
+    }
+}
 
-        for u in &block.unknown_idxs {
-            let unk_name = field_names[*u];
-            println!("    {u}: {}", unk_name);
+/// When a Jacobian entry counts as structurally zero, i.e. its equation doesn't depend on its unknown.
+///
+/// An entry is a dependency if its magnitude exceeds both `abs` and `rel` times the largest finite
+/// magnitude in its row. The default (both 0) only treats exact zeros as zero. Raise `abs` or `rel`
+/// when round-off leaves entries like `1e-17` where the equation doesn't really depend on the
+/// unknown, which would otherwise couple blocks; prefer `rel` for equations of very different
+/// scales, so that small but real derivatives of small-scale equations aren't dropped.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ZeroThreshold {
+    pub abs: f64,
+    pub rel: f64,
+}
+
+impl ZeroThreshold {
+    /// Whether `derivative` is a dependency in a row whose largest finite magnitude is `row_scale`.
+    pub fn is_dependency(&self, derivative: f64, row_scale: f64) -> bool {
+        derivative.abs() > self.abs.max(self.rel * row_scale)
+    }
+}
+
+/// Condition number above which a block counts as near-singular (see `BlockConditioning::is_near_singular`).
+pub const NEAR_SINGULAR_CONDITION: f64 = 1e10;
+
+/// Numerical rank and condition number of one block's Jacobian, computed by
+/// `EquationSystemBuilder::with_block_conditioning`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BlockConditioning {
+    pub block_idx: usize,
+    /// Number of singular values above `max(m, n) * eps * sigma_max`.
+    pub rank: usize,
+    /// The rank of a block without linearly dependent equations or unknowns: `min(m, n)`.
+    pub full_rank: usize,
+    /// Ratio of the largest to the smallest of the `full_rank` singular values; infinite if the
+    /// smallest is zero or the Jacobian isn't finite.
+    pub condition_number: f64,
+}
+
+impl BlockConditioning {
+    /// Conditioning of block `block_idx` with (model-space) Jacobian `jacobian`.
+    pub fn from_jacobian(block_idx: usize, jacobian: &DMatrix<f64>) -> Self {
+        let (m, n) = jacobian.shape();
+        let full_rank = m.min(n);
+        if full_rank == 0 {
+            return Self {
+                block_idx,
+                rank: 0,
+                full_rank,
+                condition_number: 1.0,
+            };
+        }
+        let singular_values = if jacobian.iter().all(|d| d.is_finite()) {
+            jacobian.clone().try_svd(false, false, f64::EPSILON, 1000)
+        } else {
+            None
+        };
+        let Some(svd) = singular_values else {
+            return Self {
+                block_idx,
+                rank: 0,
+                full_rank,
+                condition_number: f64::INFINITY,
+            };
+        };
+        let sigma = &svd.singular_values;
+        let sigma_max = sigma.max();
+        let sigma_min = sigma.min();
+        let tol = m.max(n) as f64 * f64::EPSILON * sigma_max;
+        Self {
+            block_idx,
+            rank: sigma.iter().filter(|&&s| s > tol).count(),
+            full_rank,
+            condition_number: if sigma_min > 0.0 {
+                sigma_max / sigma_min
+            } else {
+                f64::INFINITY
+            },
         }
     }
+
+    /// Rank-deficient, or with a condition number above `NEAR_SINGULAR_CONDITION`. Newton-type
+    /// solvers take unreliable steps on such blocks and may fail silently.
+    pub fn is_near_singular(&self) -> bool {
+        self.rank < self.full_rank
+            || self.condition_number > NEAR_SINGULAR_CONDITION
+            || self.condition_number.is_nan()
+    }
+}
+
+impl fmt::Display for BlockConditioning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "rank {}/{}, condition number {:.3e}{}",
+            self.rank,
+            self.full_rank,
+            self.condition_number,
+            if self.is_near_singular() {
+                " (NEAR-SINGULAR)"
+            } else {
+                ""
+            }
+        )
+    }
 }
 
 /// A block in the solution plan, representing a subset of equations and unknowns. The indices refer to the positions in the original, unpermuted system.
@@ -55,10 +270,81 @@ pub struct SolutionBlock {
 impl SolutionBlock {
     /// Creates a new SolutionBlock.
     pub fn new_fullprob(size: usize) -> Self {
+        Self::new_fullprob_rect(size, size)
+    }
+
+    /// Creates a SolutionBlock covering every equation and every unknown of a (possibly non-square) system.
+    pub fn new_fullprob_rect(n_eqs: usize, n_unks: usize) -> Self {
         Self {
             block_idx: 0,
-            equation_idxs: (0..size).collect(),
-            unknown_idxs: (0..size).collect(),
+            equation_idxs: (0..n_eqs).collect(),
+            unknown_idxs: (0..n_unks).collect(),
+        }
+    }
+
+    /// True if the block has as many equations as unknowns.
+    pub fn is_square(&self) -> bool {
+        self.equation_idxs.len() == self.unknown_idxs.len()
+    }
+
+    /// True if the block has fewer equations than unknowns, so it has to be solved in a minimum-norm sense.
+    pub fn is_underdetermined(&self) -> bool {
+        self.equation_idxs.len() < self.unknown_idxs.len()
+    }
+
+    /// True if the block has more equations than unknowns, so it can only be solved in a least-squares sense.
+    pub fn is_overdetermined(&self) -> bool {
+        self.equation_idxs.len() > self.unknown_idxs.len()
+    }
+
+    /// Displays the block with its equations and unknowns named by `equation_names` and
+    /// `unknown_names`, indexed like the full system.
+    pub fn with_names<'a, E: AsRef<str>, U: AsRef<str>>(
+        &'a self,
+        equation_names: &'a [E],
+        unknown_names: &'a [U],
+    ) -> NamedSolutionBlock<'a, E, U> {
+        NamedSolutionBlock {
+            block: self,
+            equation_names,
+            unknown_names,
+        }
+    }
+}
+
+impl fmt::Display for SolutionBlock {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Solution Block {}: equations {:?}, unknowns {:?}",
+            self.block_idx, self.equation_idxs, self.unknown_idxs
+        )
+    }
+}
+
+/// A `SolutionBlock` displayed by name, from `SolutionBlock::with_names`: a header line, then one
+/// line per equation and unknown with its index and name (`?` if the name is missing).
+pub struct NamedSolutionBlock<'a, E, U> {
+    block: &'a SolutionBlock,
+    equation_names: &'a [E],
+    unknown_names: &'a [U],
+}
+
+impl<E: AsRef<str>, U: AsRef<str>> fmt::Display for NamedSolutionBlock<'_, E, U> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fn name<S: AsRef<str>>(names: &[S], idx: usize) -> &str {
+            names.get(idx).map_or("?", AsRef::as_ref)
+        }
+
+        writeln!(f, "Solution Block {}:", self.block.block_idx)?;
+        writeln!(f, "  equations:")?;
+        for &e in &self.block.equation_idxs {
+            writeln!(f, "    {e}: {}", name(self.equation_names, e))?;
+        }
+        writeln!(f, "  unknowns:")?;
+        for &u in &self.block.unknown_idxs {
+            writeln!(f, "    {u}: {}", name(self.unknown_names, u))?;
         }
+        Ok(())
     }
 }
@@ -0,0 +1,524 @@
+use std::fmt;
+use std::ops::ControlFlow;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::equation_system::clock::Instant;
+use crate::equation_system::optimization_trace::{OptimizationTrace, TraceConfig, TraceSink};
+use crate::equation_system::warnings::{SolveWarning, Warnings};
+
+use crate::equation_system::sub_problem::{
+    eval_cache::{EvalCache, EvalCacheCounters, EvalCacheStats},
+    solve_subproblem::{
+        broyden::BroydenConfig, convergence::ConvergenceCriteria, external::ExternalSolver,
+        grid_search::GridSearchConfig, restart::RestartPolicy,
+    },
+};
+
+/// Options controlling how `solve_system` runs.
+#[derive(Clone, Debug, Default)]
+pub struct SolveOptions {
+    /// Wall-clock budget for the whole `solve_system` call. When it runs out, the best params found
+    /// so far are returned instead of continuing through the plan.
+    pub max_total_time: Option<Duration>,
+    /// Wall-clock budget for each individual sub-problem solver run (GN, SA, L-BFGS). A solver that
+    /// hits this limit stops and hands back its best params.
+    pub max_block_time: Option<Duration>,
+    /// Criteria that end a block's solver run early once met.
+    pub convergence: ConvergenceCriteria,
+    /// Backend tried first on every square block. If it fails, the built-in Gauss-Newton /
+    /// simulated annealing chain runs as usual.
+    pub external_solver: Option<Arc<dyn ExternalSolver>>,
+    /// If set, each square block first runs a coarse grid search around the prior and starts
+    /// Gauss-Newton from the best grid point.
+    pub grid_search: Option<GridSearchConfig>,
+    /// If set, a block whose Gauss-Newton solve fails is retried from perturbed starting points
+    /// before falling back to simulated annealing.
+    pub restart_policy: Option<RestartPolicy>,
+    /// Try a closed-form damped Newton solve on 1×1 and 2×2 blocks before Gauss-Newton.
+    ///
+    /// This skips the argmin executor setup, which dominates run time for small blocks.
+    pub analytic_small_blocks: bool,
+    /// If set, square blocks with at least this many unknowns are first solved with
+    /// Levenberg-Marquardt on their sparse Jacobian (see `SparseLevenbergMarquardt`), falling back
+    /// to the default block solver if that fails.
+    ///
+    /// Needs the `sparse` feature; without it the sparse attempt always fails.
+    pub sparse_min_block_size: Option<usize>,
+    /// Solve blocks that don't depend on each other (the same level of the plan's dependency DAG,
+    /// see `block_levels`) concurrently on the rayon thread pool. Per-block log output interleaves.
+    #[cfg(feature = "parallel")]
+    pub parallel_blocks: bool,
+    /// If set, Gauss-Newton block solves reuse the Jacobian across iterations with Broyden rank-one
+    /// updates, recomputing the AD Jacobian only when progress stalls (see `BroydenConfig`).
+    pub broyden: Option<BroydenConfig>,
+    /// Extra Gauss-Seidel sweeps over all plan blocks after the first forward pass.
+    ///
+    /// Sweeping stops early once a sweep no longer lowers the full-system residual norm, which
+    /// picks up weak couplings the block structure misses.
+    pub max_extra_sweeps: usize,
+    /// How the final full-problem pass runs after all blocks are solved.
+    pub refinement: RefinementConfig,
+    /// Iteration cap for every sub-problem solver run. If `None`, each solver uses its own default
+    /// (`DEFAULT_MAX_ITERS` for the argmin solvers).
+    pub max_iters: Option<u64>,
+    /// How much progress output the solvers print (or emit as `tracing` events, with the `tracing` feature).
+    pub verbosity: Verbosity,
+    /// Seed for the random number generators used by simulated annealing (both its proposals and
+    /// argmin's acceptance draws) and parameter perturbations, so that solves are reproducible.
+    ///
+    /// Every sub-problem is reseeded with it when it's built for a run; change it to deliberately
+    /// explore a different random path.
+    pub rng_seed: u64,
+    /// If set, each sub-problem memoizes up to this many objective evaluations and Jacobians, keyed
+    /// by the exact opt-space params, so that repeated evaluations at the same point (line
+    /// searches, convergence checks, the post-optimization summary) don't re-run the residual
+    /// functions.
+    ///
+    /// Worth it when residuals are expensive (e.g. integrations); hit counts end up in
+    /// `SolveReport::eval_cache`.
+    pub memoize_evaluations: Option<usize>,
+    /// Called before and after every block solve, and every `progress_every_iters` solver
+    /// iterations.
+    ///
+    /// Returning `ControlFlow::Break` cancels the solve cooperatively: the running solver stops at
+    /// its next iteration, and `solve_system` returns the best params so far with
+    /// `SolveReport::cancelled` set.
+    pub progress: Option<ProgressCallback>,
+    /// If set, `progress` is also called every this many iterations of each block solver run.
+    pub progress_every_iters: Option<u64>,
+    /// If set, the argmin block solvers (Gauss-Newton, L-BFGS, simulated annealing) record a
+    /// per-iteration trace with what the config asks for, collected in `SolveReport::traces`.
+    ///
+    /// For debugging stalls that the cost history alone doesn't explain, such as line searches
+    /// taking tiny steps.
+    pub trace: Option<TraceConfig>,
+    /// What to do when the Gauss-Newton polish after a successful simulated-annealing run on a block fails.
+    pub polish_failure: PolishFailurePolicy,
+}
+
+/// What `solve_system` does with a block whose simulated-annealing run succeeded but whose
+/// Gauss-Newton polish then failed.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PolishFailurePolicy {
+    /// Keep the annealed solution, and add a `SolveWarning::AnnealingPolishFailed` to the report.
+    #[default]
+    AcceptAnnealed,
+    /// Fail the block with `EqSysError::AnnealingPolishFailed`.
+    Fail,
+}
+
+/// Iteration cap of the argmin sub-problem solvers (Gauss-Newton, L-BFGS, simulated annealing) when
+/// `SolveOptions::max_iters` is not set.
+pub const DEFAULT_MAX_ITERS: u64 = 10_000;
+
+/// How much progress output the solvers produce while running.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Verbosity {
+    /// No progress output at all.
+    Quiet,
+    /// Pre/post optimization summaries for every solver run.
+    #[default]
+    Normal,
+    /// Also initial params and Jacobians before each run.
+    Debug,
+}
+
+impl SolveOptions {
+    pub fn with_max_total_time(mut self, max_total_time: Duration) -> Self {
+        self.max_total_time = Some(max_total_time);
+        self
+    }
+
+    pub fn with_max_block_time(mut self, max_block_time: Duration) -> Self {
+        self.max_block_time = Some(max_block_time);
+        self
+    }
+
+    pub fn with_convergence_criteria(mut self, convergence: ConvergenceCriteria) -> Self {
+        self.convergence = convergence;
+        self
+    }
+
+    pub fn with_external_solver(mut self, solver: Arc<dyn ExternalSolver>) -> Self {
+        self.external_solver = Some(solver);
+        self
+    }
+
+    pub fn with_grid_search(mut self, cfg: GridSearchConfig) -> Self {
+        self.grid_search = Some(cfg);
+        self
+    }
+
+    pub fn with_restart_policy(mut self, policy: RestartPolicy) -> Self {
+        self.restart_policy = Some(policy);
+        self
+    }
+
+    pub fn with_analytic_small_blocks(mut self, analytic_small_blocks: bool) -> Self {
+        self.analytic_small_blocks = analytic_small_blocks;
+        self
+    }
+
+    pub fn with_sparse_min_block_size(mut self, sparse_min_block_size: usize) -> Self {
+        self.sparse_min_block_size = Some(sparse_min_block_size);
+        self
+    }
+
+    #[cfg(feature = "parallel")]
+    pub fn with_parallel_blocks(mut self, parallel_blocks: bool) -> Self {
+        self.parallel_blocks = parallel_blocks;
+        self
+    }
+
+    pub fn with_broyden(mut self, broyden: BroydenConfig) -> Self {
+        self.broyden = Some(broyden);
+        self
+    }
+
+    pub fn with_max_extra_sweeps(mut self, max_extra_sweeps: usize) -> Self {
+        self.max_extra_sweeps = max_extra_sweeps;
+        self
+    }
+
+    pub fn with_refinement(mut self, refinement: RefinementConfig) -> Self {
+        self.refinement = refinement;
+        self
+    }
+
+    pub fn with_max_iters(mut self, max_iters: u64) -> Self {
+        self.max_iters = Some(max_iters);
+        self
+    }
+
+    pub fn with_verbosity(mut self, verbosity: Verbosity) -> Self {
+        self.verbosity = verbosity;
+        self
+    }
+
+    pub fn with_rng_seed(mut self, rng_seed: u64) -> Self {
+        self.rng_seed = rng_seed;
+        self
+    }
+
+    pub fn with_memoize_evaluations(mut self, capacity: usize) -> Self {
+        self.memoize_evaluations = Some(capacity);
+        self
+    }
+
+    pub fn with_progress(
+        mut self,
+        progress: impl FnMut(SolveProgress) -> ControlFlow<()> + Send + 'static,
+    ) -> Self {
+        self.progress = Some(ProgressCallback::new(progress));
+        self
+    }
+
+    pub fn with_progress_every_iters(mut self, every_iters: u64) -> Self {
+        self.progress_every_iters = Some(every_iters);
+        self
+    }
+
+    pub fn with_trace(mut self, trace: TraceConfig) -> Self {
+        self.trace = Some(trace);
+        self
+    }
+
+    pub fn with_polish_failure(mut self, policy: PolishFailurePolicy) -> Self {
+        self.polish_failure = policy;
+        self
+    }
+}
+
+/// What a `SolveOptions::progress` callback is told about a running solve.
+#[derive(Clone, Debug, PartialEq)]
+pub enum SolveProgress {
+    /// Block `block_idx` of the plan's `n_blocks` is about to be solved.
+    BlockStarted { block_idx: usize, n_blocks: usize },
+    /// A solver run on block `block_idx` is starting; there can be several per block as solvers
+    /// fall back on each other.
+    SolverStarted { block_idx: usize, solver: String },
+    /// Block `block_idx` of the plan's `n_blocks` was solved, with this residual norm.
+    BlockFinished {
+        block_idx: usize,
+        n_blocks: usize,
+        residual_norm: f64,
+        elapsed: Duration,
+    },
+    /// A block solver run reached `iteration`, with this sum of squared raw block residuals.
+    Iteration {
+        block_idx: usize,
+        iteration: u64,
+        cost: f64,
+    },
+}
+
+/// A `SolveOptions::progress` callback, shared between clones of the options and (with the
+/// `parallel` feature) between threads.
+#[derive(Clone)]
+pub struct ProgressCallback(Arc<Mutex<dyn FnMut(SolveProgress) -> ControlFlow<()> + Send>>);
+
+impl ProgressCallback {
+    pub fn new(callback: impl FnMut(SolveProgress) -> ControlFlow<()> + Send + 'static) -> Self {
+        Self(Arc::new(Mutex::new(callback)))
+    }
+
+    fn call(&self, progress: SolveProgress) -> ControlFlow<()> {
+        (self.0.lock().unwrap())(progress)
+    }
+}
+
+impl fmt::Debug for ProgressCallback {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("ProgressCallback")
+    }
+}
+
+/// The progress callback as seen by one sub-problem's solver runs: counts iterations, reports every
+/// `every_iters` of them, and tells the solver when the solve was cancelled.
+#[derive(Clone)]
+pub(crate) struct ProgressHook {
+    callback: ProgressCallback,
+    every_iters: Option<u64>,
+    cancelled: Arc<AtomicBool>,
+    iterations: Arc<AtomicU64>,
+}
+
+impl ProgressHook {
+    /// Reports that `solver` starts on block `block_idx`, cancelling the solve if the callback asks to.
+    pub(crate) fn solver_started(&self, block_idx: usize, solver: &str) {
+        let progress = SolveProgress::SolverStarted {
+            block_idx,
+            solver: solver.to_string(),
+        };
+        if self.callback.call(progress).is_break() {
+            self.cancelled.store(true, Ordering::Relaxed);
+        }
+    }
+
+    /// Counts one solver iteration on block `block_idx`, reporting it with the cost from `cost` if
+    /// it is due. Returns true once the solve is cancelled.
+    pub(crate) fn tick(&self, block_idx: usize, cost: impl FnOnce() -> f64) -> bool {
+        if let Some(every) = self.every_iters.filter(|&every| every > 0) {
+            let iteration = self.iterations.fetch_add(1, Ordering::Relaxed) + 1;
+            if iteration % every == 0 {
+                let progress = SolveProgress::Iteration {
+                    block_idx,
+                    iteration,
+                    cost: cost(),
+                };
+                if self.callback.call(progress).is_break() {
+                    self.cancelled.store(true, Ordering::Relaxed);
+                }
+            }
+        }
+        self.cancelled.load(Ordering::Relaxed)
+    }
+}
+
+/// Solver used for the full-problem refinement pass.
+///
+/// L-BFGS and Gauss-Newton run through argmin; without the `argmin` feature they fall back to Levenberg-Marquardt.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum RefinementSolver {
+    #[default]
+    Lbfgs,
+    LevenbergMarquardt,
+    GaussNewton,
+    /// L-BFGS on the smooth maximum of the squared residuals (see `ResidAggSmoothMax` and
+    /// `RefinementConfig::smooth_max_sharpness`), i.e. minimizing the worst-case violation ("no
+    /// equation off by more than X") rather than the sum.
+    SmoothMaxLbfgs,
+}
+
+/// Configuration of the full-problem refinement that `solve_system` runs after the block-by-block solve.
+#[derive(Clone, Debug)]
+pub struct RefinementConfig {
+    pub solver: RefinementSolver,
+    /// Number of refinement passes; each pass restarts the solver from the previous pass's result.
+    pub passes: usize,
+    /// Skip refinement entirely and return the block-by-block solution.
+    pub skip: bool,
+    /// If set, passes stop early (or don't start) once every raw residual satisfies
+    /// `|r| < residual_tol`; `passes` then acts as an upper bound.
+    pub residual_tol: Option<f64>,
+    /// Sharpness of the smooth max minimized by `RefinementSolver::SmoothMaxLbfgs`, in inverse
+    /// units of the squared (weighted) residuals.
+    pub smooth_max_sharpness: f64,
+    /// If set and some raw residual still exceeds this after refinement, the solve report names the
+    /// conflicting equations (see `EquationSystemBuilder::explain_conflicts`).
+    pub conflict_tol: Option<f64>,
+}
+
+impl Default for RefinementConfig {
+    fn default() -> Self {
+        Self {
+            solver: RefinementSolver::Lbfgs,
+            passes: 1,
+            skip: false,
+            residual_tol: None,
+            smooth_max_sharpness: 100.0,
+            conflict_tol: None,
+        }
+    }
+}
+
+impl RefinementConfig {
+    pub fn skipped() -> Self {
+        Self {
+            skip: true,
+            ..Self::default()
+        }
+    }
+
+    pub fn with_solver(mut self, solver: RefinementSolver) -> Self {
+        self.solver = solver;
+        self
+    }
+
+    pub fn with_passes(mut self, passes: usize) -> Self {
+        self.passes = passes;
+        self
+    }
+
+    pub fn with_residual_tol(mut self, residual_tol: f64) -> Self {
+        self.residual_tol = Some(residual_tol);
+        self
+    }
+
+    pub fn with_smooth_max_sharpness(mut self, sharpness: f64) -> Self {
+        self.smooth_max_sharpness = sharpness;
+        self
+    }
+
+    pub fn with_conflict_tol(mut self, conflict_tol: f64) -> Self {
+        self.conflict_tol = Some(conflict_tol);
+        self
+    }
+}
+
+/// Tracks wall-clock time spent in a `solve_system` call against the limits in `SolveOptions`, the
+/// run's evaluation-cache counters, optimization traces and warnings, and whether the progress
+/// callback cancelled it.
+pub(crate) struct TimeBudget {
+    start: Instant,
+    max_total_time: Option<Duration>,
+    max_block_time: Option<Duration>,
+    memoize_evaluations: Option<usize>,
+    eval_cache_counters: Arc<EvalCacheCounters>,
+    progress: Option<ProgressCallback>,
+    progress_every_iters: Option<u64>,
+    cancelled: Arc<AtomicBool>,
+    trace_sink: Option<TraceSink>,
+    warnings: Warnings,
+}
+
+impl TimeBudget {
+    pub(crate) fn start(options: &SolveOptions) -> Self {
+        Self {
+            start: Instant::now(),
+            max_total_time: options.max_total_time,
+            max_block_time: options.max_block_time,
+            memoize_evaluations: options.memoize_evaluations,
+            eval_cache_counters: Arc::default(),
+            progress: options.progress.clone(),
+            progress_every_iters: options.progress_every_iters,
+            cancelled: Arc::default(),
+            trace_sink: options.trace.map(TraceSink::new),
+            warnings: Warnings::default(),
+        }
+    }
+
+    pub(crate) fn trace_sink(&self) -> Option<TraceSink> {
+        self.trace_sink.clone()
+    }
+
+    /// Traces of the solver runs so far, if tracing is on.
+    pub(crate) fn traces(&self) -> Vec<OptimizationTrace> {
+        self.trace_sink
+            .as_ref()
+            .map_or_else(Vec::new, TraceSink::traces)
+    }
+
+    pub(crate) fn warnings(&self) -> Warnings {
+        self.warnings.clone()
+    }
+
+    /// Warnings raised so far.
+    pub(crate) fn collected_warnings(&self) -> Vec<SolveWarning> {
+        self.warnings.warnings()
+    }
+
+    /// Passes `progress` to the progress callback, if any, and cancels the run if it asks to.
+    pub(crate) fn report_progress(&self, progress: SolveProgress) {
+        if let Some(callback) = &self.progress {
+            if callback.call(progress).is_break() {
+                self.cancelled.store(true, Ordering::Relaxed);
+            }
+        }
+    }
+
+    /// A fresh iteration counter for one sub-problem's solver runs, reporting to the progress callback, if any.
+    pub(crate) fn progress_hook(&self) -> Option<ProgressHook> {
+        self.progress.as_ref().map(|callback| ProgressHook {
+            callback: callback.clone(),
+            every_iters: self.progress_every_iters,
+            cancelled: self.cancelled.clone(),
+            iterations: Arc::default(),
+        })
+    }
+
+    pub(crate) fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+
+    /// A fresh evaluation cache for one sub-problem, counting into this run's totals, if memoization is on.
+    pub(crate) fn eval_cache<const N: usize>(&self) -> Option<EvalCache<N>> {
+        self.memoize_evaluations
+            .map(|capacity| EvalCache::new(capacity, self.eval_cache_counters.clone()))
+    }
+
+    /// Cache hits and misses of this run so far, if memoization is on.
+    pub(crate) fn eval_cache_stats(&self) -> Option<EvalCacheStats> {
+        self.memoize_evaluations
+            .map(|_| self.eval_cache_counters.stats())
+    }
+
+    /// Time left in the total budget, or `None` if there is no total limit.
+    pub(crate) fn remaining(&self) -> Option<Duration> {
+        self.max_total_time
+            .map(|total| total.saturating_sub(self.start.elapsed()))
+    }
+
+    pub(crate) fn elapsed(&self) -> Duration {
+        self.start.elapsed()
+    }
+
+    /// True once the total time ran out or the run was cancelled.
+    pub(crate) fn is_exhausted(&self) -> bool {
+        self.is_cancelled() || self.remaining().is_some_and(|r| r.is_zero())
+    }
+
+    /// Timeout for the next solver run: the smaller of the per-block limit and whatever is left of
+    /// the total budget (none at all once cancelled).
+    pub(crate) fn block_timeout(&self) -> Option<Duration> {
+        if self.is_cancelled() {
+            return Some(Duration::ZERO);
+        }
+        match (self.max_block_time, self.remaining()) {
+            (Some(block), Some(remaining)) => Some(block.min(remaining)),
+            (block, remaining) => block.or(remaining),
+        }
+    }
+}
+
+/// What the solver runs of one pass over the plan are built from.
+#[derive(Clone, Copy)]
+pub(crate) struct SolveContext<'a> {
+    pub(crate) options: &'a SolveOptions,
+    pub(crate) budget: &'a TimeBudget,
+    /// Augmented-Lagrangian multipliers of the current outer iteration (see `AugmentedLagrangian`);
+    /// empty, i.e. all zero, without constraints among the unknowns.
+    pub(crate) multipliers: &'a [f64],
+}
@@ -0,0 +1,267 @@
+use std::fmt::Debug;
+use std::path::Path;
+use std::time::Duration;
+use std::{fs, io};
+
+use crate::equation_system::{
+    conflicts::ConstraintConflict,
+    optimization_trace::{OptimizationTrace, traces_to_csv, traces_to_json},
+    solution_plan::BlockConditioning,
+    sub_problem::{EvalCacheStats, solve_subproblem::block_optimizer::BlockTermination},
+    uncertainty::UncertaintyReport,
+    warnings::SolveWarning,
+};
+use crate::error::EqSysError;
+
+/// A solver that can produce a block's accepted solution in `solve_system`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BlockSolverKind {
+    /// (Weighted) minimum-norm least squares, used for non-square blocks.
+    WeightedLeastSquares,
+    External,
+    SmallNewton,
+    GaussNewton,
+    LevenbergMarquardt,
+    /// Levenberg-Marquardt on the block's sparse Jacobian (needs the `sparse` feature).
+    SparseLevenbergMarquardt,
+    /// The default block solver, started from a randomly perturbed point (see `RestartPolicy`).
+    Restart,
+    /// Simulated annealing followed by a Gauss-Newton polish.
+    SimulatedAnnealing,
+}
+
+/// A solver that was tried on a block and failed before another one succeeded.
+#[derive(Clone, Debug)]
+pub struct SolverFallback {
+    pub solver: BlockSolverKind,
+    pub error: String,
+}
+
+/// How one block of the plan was solved, in one sweep.
+#[derive(Clone, Debug)]
+pub struct BlockReport {
+    pub block_idx: usize,
+    /// 0 for the first pass over the plan, then 1, 2, ... for extra sweeps.
+    pub sweep: usize,
+    /// The solver whose solution was accepted.
+    pub solver: BlockSolverKind,
+    /// Solvers that failed on this block before `solver`, in the order they were tried.
+    pub fallbacks: Vec<SolverFallback>,
+    /// Iterations of the accepted solver run, if it reports them.
+    pub iterations: Option<u64>,
+    /// Why the accepted solver run stopped, if it reports it. A run that hit its iteration cap or
+    /// timeout is still accepted; check `residuals` to see how far it got.
+    pub termination: Option<BlockTermination>,
+    /// Raw residuals of the block's equations at the accepted solution, in block order.
+    pub residuals: Vec<f64>,
+    pub elapsed: Duration,
+    /// The block's conditioning at the initial guess, if computed with
+    /// `EquationSystemBuilder::with_block_conditioning`.
+    pub conditioning: Option<BlockConditioning>,
+}
+
+impl BlockReport {
+    pub fn residual_norm(&self) -> f64 {
+        self.residuals.iter().map(|r| r * r).sum::<f64>().sqrt()
+    }
+}
+
+/// Everything `solve_system` found out while solving: the solution, how each block was solved, and the final residuals.
+#[derive(Clone, Debug)]
+pub struct SolveReport<U> {
+    pub solution: U,
+    /// One entry per block solve, in the order they ran (all sweeps, all augmented-Lagrangian iterations).
+    pub blocks: Vec<BlockReport>,
+    /// Number of full-problem refinement passes that ran.
+    pub refinement_passes: usize,
+    /// Number of augmented-Lagrangian outer iterations (1 without constraints among the unknowns).
+    pub outer_iterations: usize,
+    /// Raw residuals of every equation at `solution`, in registration order.
+    pub residuals: Vec<f64>,
+    /// Name of each residual function, matching `residuals`.
+    pub residual_names: Vec<&'static str>,
+    pub total_time: Duration,
+    /// True if `SolveOptions::max_total_time` ran out or the solve was cancelled, so that later
+    /// blocks or refinement were skipped.
+    pub time_budget_exhausted: bool,
+    /// True if the `SolveOptions::progress` callback cancelled the solve; `solution` holds the best
+    /// params found until then.
+    pub cancelled: bool,
+    /// Evaluation-cache hits and misses over all sub-problems, if `SolveOptions::memoize_evaluations` was set.
+    pub eval_cache: Option<EvalCacheStats>,
+    /// Intervals on the unknowns from uncertain givens, if solved with
+    /// `EquationSystemBuilder::solve_system_with_uncertainty`.
+    pub uncertainty: Option<UncertaintyReport>,
+    /// Minimal sets of conflicting equations, if `RefinementConfig::conflict_tol` was set and the
+    /// refined solution still misses it.
+    pub conflicts: Vec<ConstraintConflict>,
+    /// One entry per argmin solver run, in the order they finished, if `SolveOptions::trace` was set.
+    pub traces: Vec<OptimizationTrace>,
+    /// Non-fatal issues noticed along the way, in the order they were first raised.
+    pub warnings: Vec<SolveWarning>,
+}
+
+impl<U> SolveReport<U> {
+    /// How block `block_idx` was solved the last time it ran (the last sweep or
+    /// augmented-Lagrangian iteration), if it ran at all.
+    pub fn last_block_report(&self, block_idx: usize) -> Option<&BlockReport> {
+        self.blocks.iter().rev().find(|b| b.block_idx == block_idx)
+    }
+}
+
+impl<U> SolveReport<U> {
+    /// The per-iteration histories in `traces` as CSV (see `traces_to_csv`). Only has a header row
+    /// unless solved with `SolveOptions::trace`.
+    pub fn convergence_csv(&self) -> String {
+        traces_to_csv(&self.traces)
+    }
+
+    /// The per-iteration histories in `traces` as JSON (see `traces_to_json`).
+    pub fn convergence_json(&self) -> String {
+        traces_to_json(&self.traces)
+    }
+
+    pub fn write_convergence_csv(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        fs::write(path, self.convergence_csv())
+    }
+
+    pub fn write_convergence_json(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        fs::write(path, self.convergence_json())
+    }
+}
+
+impl<U: Debug> SolveReport<U> {
+    /// Euclidean norm of all raw residuals at the solution.
+    pub fn residual_norm(&self) -> f64 {
+        self.residuals.iter().map(|r| r * r).sum::<f64>().sqrt()
+    }
+
+    pub fn max_abs_residual(&self) -> f64 {
+        self.residuals.iter().fold(0.0, |acc, r| acc.max(r.abs()))
+    }
+
+    /// Prints the solution, a line per block solve, and the final per-equation residuals.
+    pub fn print(&self) {
+        println!("Solve report ({:.3?}):", self.total_time);
+        for block in &self.blocks {
+            let fallbacks = block
+                .fallbacks
+                .iter()
+                .map(|f| format!("{:?}", f.solver))
+                .collect::<Vec<_>>();
+            println!(
+                "  sweep {} block {}: {:?} ({}{} iterations, {:.3?}); residual norm {:.6e}{}",
+                block.sweep,
+                block.block_idx,
+                block.solver,
+                block
+                    .termination
+                    .map_or_else(String::new, |t| format!("{t:?} after ")),
+                block
+                    .iterations
+                    .map_or_else(|| "?".to_string(), |i| i.to_string()),
+                block.elapsed,
+                block.residual_norm(),
+                if fallbacks.is_empty() {
+                    String::new()
+                } else {
+                    format!(" after {} failed", fallbacks.join(", "))
+                }
+            );
+            for fallback in &block.fallbacks {
+                println!("    {:?} failed: {}", fallback.solver, fallback.error);
+            }
+            if let Some(conditioning) = block.conditioning.as_ref().filter(|c| c.is_near_singular())
+            {
+                println!("    near-singular at the initial guess: {}", conditioning);
+            }
+        }
+        if self.refinement_passes > 0 {
+            println!("  refinement passes: {}", self.refinement_passes);
+        }
+        if self.outer_iterations > 1 {
+            println!(
+                "  augmented-Lagrangian iterations: {}",
+                self.outer_iterations
+            );
+        }
+        if let Some(cache) = &self.eval_cache {
+            println!(
+                "  evaluation cache: {} hits, {} misses ({:.1}% hit rate)",
+                cache.hits,
+                cache.misses,
+                100.0 * cache.hit_rate()
+            );
+        }
+        if self.cancelled {
+            println!("  cancelled; the solution may be incomplete");
+        } else if self.time_budget_exhausted {
+            println!("  time budget exhausted; the solution may be incomplete");
+        }
+        if !self.traces.is_empty() {
+            println!("  traced solver runs: {}", self.traces.len());
+        }
+        for warning in &self.warnings {
+            println!("  warning: {}", warning);
+        }
+        for conflict in &self.conflicts {
+            println!("  conflict: {}", conflict);
+        }
+        println!("  residuals (norm {:.6e}):", self.residual_norm());
+        for (name, r) in self.residual_names.iter().zip(&self.residuals) {
+            println!("    {}: {:.6e}", name, r);
+        }
+        println!("  solution: {:#?}", self.solution);
+        if let Some(uncertainty) = &self.uncertainty {
+            print!("  {}", uncertainty);
+        }
+    }
+}
+
+/// What a `solve_system` run had solved when a block failed, carried by `EqSysError::PartialSolution`.
+#[derive(Debug)]
+pub struct PartialSolution {
+    /// Reports of the block solves that succeeded before the failure, in the order they ran.
+    pub solved_blocks: Vec<BlockReport>,
+    /// Index of the block that failed, or the number of plan blocks if full-problem refinement failed.
+    pub failed_block: usize,
+    /// Model-space unknowns after the last successful block solve, in field order;
+    /// `StructToArray::from_arr` turns them back into the unknowns struct.
+    ///
+    /// Unknowns of the failed block and of the blocks after it still hold their initial guesses (or
+    /// earlier sweeps' values).
+    pub best_params_so_far: Vec<f64>,
+    /// Why the block failed.
+    pub error: EqSysError,
+}
+
+/// Block reports and flags accumulated during a `solve_system` run, before the final solution is known.
+#[derive(Default)]
+pub(crate) struct SolveLog {
+    pub(crate) blocks: Vec<BlockReport>,
+    pub(crate) refinement_passes: usize,
+    pub(crate) outer_iterations: usize,
+    pub(crate) time_budget_exhausted: bool,
+    pub(crate) conflicts: Vec<ConstraintConflict>,
+}
+
+impl SolveLog {
+    /// `error` of block `failed_block`, wrapped in `EqSysError::PartialSolution` with the blocks
+    /// solved so far and `best_params_so_far`, unless no block was solved yet.
+    pub(crate) fn block_failed(
+        &mut self,
+        failed_block: usize,
+        best_params_so_far: Vec<f64>,
+        error: EqSysError,
+    ) -> EqSysError {
+        if self.blocks.is_empty() {
+            return error;
+        }
+        EqSysError::PartialSolution(Box::new(PartialSolution {
+            solved_blocks: std::mem::take(&mut self.blocks),
+            failed_block,
+            best_params_so_far,
+            error,
+        }))
+    }
+}
@@ -0,0 +1,105 @@
+use ad_trait::forward_ad::adfn::adfn;
+use nalgebra::{Dyn, Matrix, VecStorage};
+
+use crate::{
+    equation_system::{EqSysSolutionPlan, EquationSystemBuilder},
+    prelude::*,
+};
+
+/// Typestate of a solved system: the plan it was solved with, the `SolveReport`, and the Jacobian
+/// of the raw residuals at the solution.
+pub struct EqSysSolved<U64> {
+    plan: EqSysSolutionPlan,
+    report: SolveReport<U64>,
+    jacobian: Matrix<f64, Dyn, Dyn, VecStorage<f64, Dyn, Dyn>>,
+}
+
+impl<G64, U64, Gadfn, Uadfn, const N: usize>
+    EquationSystemBuilder<G64, U64, Gadfn, Uadfn, EqSysSolutionPlan, N>
+where
+    G64: GivenParamsFor<f64, N>,
+    U64: UnknownParamsFor<f64, N>,
+    Gadfn: GivenParamsFor<adfn<1>, N>,
+    Uadfn: UnknownParamsFor<adfn<1>, N>,
+{
+    /// Solves the system with default options and moves the builder to the solved state, which
+    /// keeps the results around (see `solve_system` to only get the report).
+    pub fn solve(
+        self,
+        initial_unknowns: &U64,
+    ) -> Result<EquationSystemBuilder<G64, U64, Gadfn, Uadfn, EqSysSolved<U64>, N>, EqSysError>
+    {
+        self.solve_with_options(initial_unknowns, &SolveOptions::default())
+    }
+
+    /// Like `solve`, with run limits taken from `options` (see `solve_system_with_options`).
+    pub fn solve_with_options(
+        self,
+        initial_unknowns: &U64,
+        options: &SolveOptions,
+    ) -> Result<EquationSystemBuilder<G64, U64, Gadfn, Uadfn, EqSysSolved<U64>, N>, EqSysError>
+    {
+        let report = self.solve_system_with_options(initial_unknowns, options)?;
+        let (_residuals, jacobian) = self.full_derivative(&report.solution.to_arr());
+        Ok(self.map_state(|plan| EqSysSolved {
+            plan,
+            report,
+            jacobian,
+        }))
+    }
+}
+
+impl<G64, U64, Gadfn, Uadfn, const N: usize>
+    EquationSystemBuilder<G64, U64, Gadfn, Uadfn, EqSysSolved<U64>, N>
+where
+    G64: GivenParamsFor<f64, N>,
+    U64: UnknownParamsFor<f64, N>,
+    Gadfn: GivenParamsFor<adfn<1>, N>,
+    Uadfn: UnknownParamsFor<adfn<1>, N>,
+{
+    pub fn solution(&self) -> &U64 {
+        &self.state.report.solution
+    }
+
+    /// Raw residual of every equation at the solution, in registration order.
+    pub fn residuals(&self) -> &[f64] {
+        &self.state.report.residuals
+    }
+
+    /// Raw residual of the equation named `fn_name` at the solution.
+    pub fn residual(&self, fn_name: &str) -> Option<f64> {
+        self.state
+            .report
+            .residual_names
+            .iter()
+            .position(|&name| name == fn_name)
+            .map(|idx| self.state.report.residuals[idx])
+    }
+
+    /// How each block was solved, in the order the solves ran.
+    pub fn block_reports(&self) -> &[BlockReport] {
+        &self.state.report.blocks
+    }
+
+    pub fn report(&self) -> &SolveReport<U64> {
+        &self.state.report
+    }
+
+    /// Jacobian of the raw residuals (rows, in registration order) with respect to the unknowns
+    /// (columns, in `StructToArray` order) at the solution.
+    pub fn jacobian(&self) -> &Matrix<f64, Dyn, Dyn, VecStorage<f64, Dyn, Dyn>> {
+        &self.state.jacobian
+    }
+
+    /// The solution plan the system was solved with.
+    pub fn plan(&self) -> &EqSysSolutionPlan {
+        &self.state.plan
+    }
+
+    /// Goes back to the planned state, e.g. to re-solve with other givens. Clone the `report` first to keep it.
+    pub fn into_planned(
+        self,
+    ) -> EquationSystemBuilder<G64, U64, Gadfn, Uadfn, EqSysSolutionPlan, N> {
+        self.map_state(|solved| solved.plan)
+    }
+}
@@ -1,5 +1,4 @@
 use ad_trait::forward_ad::adfn::adfn;
-use anyhow::bail;
 use argmin::{
     core::{CostFunction, Error as ArgminError, Gradient, Jacobian, Operator},
     solver::simulatedannealing::Anneal,
@@ -45,22 +44,7 @@ where
     type Output = nalgebra::DVector<f64>;
 
     fn apply(&self, p: &Self::Param) -> Result<Self::Output, ArgminError> {
-        if p.len() != self.block.unknown_idxs.len() {
-            bail!(
-                "Parameter vector length ({}) for subproblem cost function did not match number subproblem unknowns ({})",
-                p.len(),
-                self.block.unknown_idxs.len()
-            );
-        }
-
-        let p_vec: Vec<f64> = p.as_slice().to_vec();
-        let p_opt = self.optspace_fullprob_input_from_subprob_input(&p_vec);
-        // println!(
-        //     "SubProblem::cost called with full opt space params: {:?}",
-        //     p_opt
-        // );
-        let result = self.loss_fn_engine.call(&p_opt);
-        Ok(nalgebra::DVector::from_vec(result))
+        Ok(self.outputs_optspace(p)?)
     }
 }
 
@@ -78,26 +62,19 @@ where
     type Gradient = nalgebra::DVector<f64>;
 
     fn gradient(&self, p: &Self::Param) -> Result<Self::Gradient, ArgminError> {
-        if p.len() != self.block.unknown_idxs.len() {
-            bail!(
-                "Parameter vector length ({}) for subproblem gradient function did not match number subproblem unknowns ({})",
-                p.len(),
-                self.block.unknown_idxs.len()
-            );
-        }
+        self.check_subprob_param_len(p)?;
 
-        let p_vec: Vec<f64> = p.as_slice().to_vec();
-        let p_full = self.optspace_fullprob_input_from_subprob_input(&p_vec);
+        let p_full = self.optspace_fullprob_input_from_subprob_input(p.as_slice());
 
-        let (_values, full_jacobian) = self.loss_fn_engine.derivative(&p_full);
+        let (_values, full_jacobian) = self.derivative_fullprob_optspace(&p_full);
 
         // Select columns, then convert 1×N matrix to N×1 vector
         let gradient_matrix = self.select_subprob_jacobian(&full_jacobian);
         if gradient_matrix.nrows() != 1 {
-            bail!(
-                "Expected gradient to have 1 row (scalar function output), but got {} rows",
-                gradient_matrix.nrows()
-            );
+            return Err(EqSysError::NonScalarGradientOutput {
+                n_outputs: gradient_matrix.nrows(),
+            }
+            .into());
         }
         Ok(gradient_matrix.row(0).transpose())
     }
@@ -116,20 +93,7 @@ where
     type Jacobian = nalgebra::DMatrix<f64>;
 
     fn jacobian(&self, p: &Self::Param) -> Result<Self::Jacobian, ArgminError> {
-        if p.len() != self.block.unknown_idxs.len() {
-            bail!(
-                "Parameter vector length ({}) for subproblem jacobian function did not match number subproblem unknowns ({})",
-                p.len(),
-                self.block.unknown_idxs.len()
-            );
-        }
-
-        let p_vec: Vec<f64> = p.as_slice().to_vec();
-        let p_full = self.optspace_fullprob_input_from_subprob_input(&p_vec);
-
-        let (_values, full_jacobian) = self.loss_fn_engine.derivative(&p_full);
-
-        Ok(self.select_subprob_jacobian(&full_jacobian))
+        Ok(self.gauss_newton_jacobian(p)?)
     }
 }
 
@@ -154,18 +118,12 @@ where
     ///   - occasional Cauchy (heavy-tailed) big jump (order-of-magnitude moves in log-space)
     /// - Persists RNG state across calls (required for meaningful SA behavior).
     fn anneal(&self, p: &Self::Param, temp: Self::Float) -> Result<Self::Output, ArgminError> {
-        if p.len() != self.block.unknown_idxs.len() {
-            bail!(
-                "Parameter vector length ({}) for subproblem anneal function did not match number subproblem unknowns ({})",
-                p.len(),
-                self.block.unknown_idxs.len()
-            );
-        }
+        self.check_subprob_param_len(p)?;
 
         let sa_cfg = self
             .sa_cfg
             .as_ref()
-            .expect("Simulated annealing config (sa_cfg) not set on annealing SubProblem");
+            .ok_or(EqSysError::AnnealingConfigMissing)?;
 
         // Normalize temperature into [0, 1] fraction of initial temp.
         // tau ~ 1 => "hot" => larger steps & more frequent big jumps
@@ -207,7 +165,9 @@ where
         };
 
         // Safety clamp (limits extreme tails causing overflow / NaN in downstream exp/link funcs)
-        delta = delta.clamp(-sa_cfg.max_abs_step, sa_cfg.max_abs_step);
+        let clamped = delta.clamp(-sa_cfg.max_abs_step, sa_cfg.max_abs_step);
+        self.annealing_steps.record(clamped != delta);
+        delta = clamped;
 
         // // --- Optional: gradient-biased drift (compile-time gated) -----------------------------
         // //
@@ -0,0 +1,122 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use nalgebra::{Dyn, Matrix, VecStorage};
+
+/// Hit and miss counts of the sub-problem evaluation caches of one `solve_system` run (see
+/// `SolveOptions::memoize_evaluations`).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct EvalCacheStats {
+    pub hits: u64,
+    pub misses: u64,
+}
+
+impl EvalCacheStats {
+    /// Fraction of lookups answered from the cache, or 0 if there were none.
+    pub fn hit_rate(&self) -> f64 {
+        let lookups = self.hits + self.misses;
+        if lookups == 0 {
+            0.0
+        } else {
+            self.hits as f64 / lookups as f64
+        }
+    }
+}
+
+/// Run-wide cache counters, shared by the caches of every sub-problem of a run.
+#[derive(Debug, Default)]
+pub(crate) struct EvalCacheCounters {
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl EvalCacheCounters {
+    pub(crate) fn stats(&self) -> EvalCacheStats {
+        EvalCacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+        }
+    }
+
+    fn record(&self, hit: bool) {
+        let counter = if hit { &self.hits } else { &self.misses };
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+type Jacobian = Matrix<f64, Dyn, Dyn, VecStorage<f64, Dyn, Dyn>>;
+
+/// Memoized objective outputs and Jacobians of one sub-problem, keyed by the bit pattern of the
+/// full opt-space parameter vector.
+///
+/// Only exactly repeated points hit (a line search re-evaluating its accepted point, observers, the
+/// post-optimization summary). Once `capacity` points are stored the cache is cleared rather than
+/// evicting entry by entry, since solvers rarely revisit old points.
+#[derive(Debug)]
+pub(crate) struct EvalCache<const N: usize> {
+    capacity: usize,
+    outputs: Mutex<HashMap<[u64; N], Vec<f64>>>,
+    derivatives: Mutex<HashMap<[u64; N], (Vec<f64>, Jacobian)>>,
+    counters: Arc<EvalCacheCounters>,
+}
+
+impl<const N: usize> EvalCache<N> {
+    pub(crate) fn new(capacity: usize, counters: Arc<EvalCacheCounters>) -> Self {
+        Self {
+            capacity,
+            outputs: Mutex::new(HashMap::new()),
+            derivatives: Mutex::new(HashMap::new()),
+            counters,
+        }
+    }
+
+    fn key(p: &[f64; N]) -> [u64; N] {
+        p.map(f64::to_bits)
+    }
+
+    /// The objective outputs at `p`, from the cache if `p` was evaluated (or differentiated)
+    /// before, otherwise from `eval`.
+    pub(crate) fn outputs(&self, p: &[f64; N], eval: impl FnOnce() -> Vec<f64>) -> Vec<f64> {
+        let key = Self::key(p);
+        let cached = self.outputs.lock().unwrap().get(&key).cloned().or_else(|| {
+            let derivatives = self.derivatives.lock().unwrap();
+            derivatives.get(&key).map(|(values, _)| values.clone())
+        });
+        self.counters.record(cached.is_some());
+        if let Some(values) = cached {
+            return values;
+        }
+
+        let values = eval();
+        self.insert(&self.outputs, key, values.clone());
+        values
+    }
+
+    /// The objective outputs and Jacobian at `p`, from the cache if `p` was differentiated before,
+    /// otherwise from `eval`.
+    pub(crate) fn derivative(
+        &self,
+        p: &[f64; N],
+        eval: impl FnOnce() -> (Vec<f64>, Jacobian),
+    ) -> (Vec<f64>, Jacobian) {
+        let key = Self::key(p);
+        let cached = self.derivatives.lock().unwrap().get(&key).cloned();
+        self.counters.record(cached.is_some());
+        if let Some(derivative) = cached {
+            return derivative;
+        }
+
+        let derivative = eval();
+        self.insert(&self.derivatives, key, derivative.clone());
+        derivative
+    }
+
+    fn insert<V>(&self, map: &Mutex<HashMap<[u64; N], V>>, key: [u64; N], value: V) {
+        let mut map = map.lock().unwrap();
+        if map.len() >= self.capacity {
+            map.clear();
+        }
+        map.insert(key, value);
+    }
+}
@@ -1,5 +1,8 @@
+#[cfg(feature = "argmin")]
 mod argmin_impls;
+pub mod eval_cache;
 pub mod solve_subproblem;
 pub mod sub_problem;
 
+pub use eval_cache::EvalCacheStats;
 pub use sub_problem::*;
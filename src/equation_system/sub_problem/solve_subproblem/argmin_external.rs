@@ -0,0 +1,127 @@
+use argmin::{
+    core::{
+        CostFunction, Error as ArgminError, Executor, Gradient, Jacobian, Operator,
+        observers::ObserverMode,
+    },
+    solver::{
+        gaussnewton::GaussNewtonLS,
+        linesearch::{BacktrackingLineSearch, MoreThuenteLineSearch, condition::ArmijoCondition},
+        quasinewton::LBFGS,
+    },
+};
+use nalgebra::{DMatrix, DVector};
+
+use super::external::{ExternalBlockProblem, ExternalSolver};
+use crate::{equation_system::opt_tools::MyObserver, prelude::*};
+
+/// The argmin solvers available through `ArgminExternalSolver`.
+#[derive(Clone, Debug)]
+pub enum ArgminBackend {
+    /// Gauss-Newton with a More-Thuente line search.
+    GaussNewton,
+    /// L-BFGS on the sum of squared residuals, with the given history length.
+    Lbfgs { memory: usize },
+}
+
+/// Adapter exposing the built-in argmin solvers through the `ExternalSolver` interface, e.g. as a
+/// reference when validating a new backend.
+#[derive(Clone, Debug)]
+pub struct ArgminExternalSolver {
+    pub backend: ArgminBackend,
+    pub max_iters: u64,
+}
+
+impl ArgminExternalSolver {
+    pub fn new(backend: ArgminBackend) -> Self {
+        Self {
+            backend,
+            max_iters: 10000,
+        }
+    }
+}
+
+impl ExternalSolver for ArgminExternalSolver {
+    fn name(&self) -> &str {
+        match self.backend {
+            ArgminBackend::GaussNewton => "argmin Gauss-Newton",
+            ArgminBackend::Lbfgs { .. } => "argmin L-BFGS",
+        }
+    }
+
+    fn solve(&self, problem: &ExternalBlockProblem) -> Result<DVector<f64>, EqSysError> {
+        let callbacks = CallbackProblem { problem };
+        let init = problem.initial_params.clone();
+        let max_iters = self.max_iters;
+
+        let best_param = match self.backend {
+            ArgminBackend::GaussNewton => {
+                let linesearch = MoreThuenteLineSearch::new().with_bounds(0.0, 1.0)?;
+                let mut executor = Executor::new(callbacks, GaussNewtonLS::new(linesearch))
+                    .configure(|state| state.param(init).max_iters(max_iters))
+                    .add_observer(MyObserver::new(), ObserverMode::Always);
+                if let Some(timeout) = problem.timeout {
+                    executor = executor.timeout(timeout);
+                }
+                executor.run()?.state.best_param
+            }
+            ArgminBackend::Lbfgs { memory } => {
+                let linesearch: BacktrackingLineSearch<DVector<f64>, DVector<f64>, _, _> =
+                    BacktrackingLineSearch::new(ArmijoCondition::new(1e-4f64)?).rho(0.5f64)?;
+                let mut executor = Executor::new(callbacks, LBFGS::new(linesearch, memory))
+                    .configure(|state| state.param(init).max_iters(max_iters))
+                    .add_observer(MyObserver::new(), ObserverMode::Always);
+                if let Some(timeout) = problem.timeout {
+                    executor = executor.timeout(timeout);
+                }
+                executor.run()?.state.best_param
+            }
+        };
+
+        best_param.ok_or_else(|| EqSysError::NoBestParams {
+            solver: self.name().to_string(),
+        })
+    }
+}
+
+/// argmin problem built from the callbacks of an `ExternalBlockProblem`.
+struct CallbackProblem<'a> {
+    problem: &'a ExternalBlockProblem<'a>,
+}
+
+impl Operator for CallbackProblem<'_> {
+    type Param = DVector<f64>;
+    type Output = DVector<f64>;
+
+    fn apply(&self, p: &Self::Param) -> Result<Self::Output, ArgminError> {
+        Ok((self.problem.residuals)(p)?)
+    }
+}
+
+impl Jacobian for CallbackProblem<'_> {
+    type Param = DVector<f64>;
+    type Jacobian = DMatrix<f64>;
+
+    fn jacobian(&self, p: &Self::Param) -> Result<Self::Jacobian, ArgminError> {
+        Ok((self.problem.jacobian)(p)?)
+    }
+}
+
+impl CostFunction for CallbackProblem<'_> {
+    type Param = DVector<f64>;
+    type Output = f64;
+
+    fn cost(&self, p: &Self::Param) -> Result<Self::Output, ArgminError> {
+        Ok((self.problem.residuals)(p)?.norm_squared())
+    }
+}
+
+impl Gradient for CallbackProblem<'_> {
+    type Param = DVector<f64>;
+    type Gradient = DVector<f64>;
+
+    fn gradient(&self, p: &Self::Param) -> Result<Self::Gradient, ArgminError> {
+        let r = (self.problem.residuals)(p)?;
+        let jac = (self.problem.jacobian)(p)?;
+        Ok(jac.transpose() * r * 2.0)
+    }
+}
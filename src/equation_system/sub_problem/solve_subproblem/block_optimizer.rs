@@ -0,0 +1,182 @@
+use std::time::Duration;
+
+use ad_trait::forward_ad::adfn::adfn;
+use nalgebra::{DMatrix, DVector};
+#[cfg(feature = "sparse")]
+use nalgebra_sparse::CscMatrix;
+
+use super::convergence::{BlockConvergence, DEFAULT_RESIDUAL_NORM_TOL};
+use crate::{
+    equation_system::logging::{solver_info, solver_span},
+    prelude::*,
+};
+
+/// A block least-squares problem as seen by a `BlockOptimizer`: objective outputs and their
+/// Jacobian as functions of the block's unknowns in opt space.
+///
+/// This is the crate's own optimization interface; it does not depend on any solver backend.
+pub trait BlockObjective {
+    fn block_idx(&self) -> usize;
+    /// Starting point (opt space), one entry per block unknown.
+    fn initial_params(&self) -> DVector<f64>;
+    /// Objective outputs to be driven to zero in a least-squares sense.
+    fn residuals(&self, p: &DVector<f64>) -> Result<DVector<f64>, EqSysError>;
+    /// Jacobian of `residuals` w.r.t. the opt-space params.
+    fn jacobian(&self, p: &DVector<f64>) -> Result<DMatrix<f64>, EqSysError>;
+    /// Jacobian with only its structural nonzeros stored. Defaults to the dense `jacobian` with its
+    /// exact zeros dropped.
+    #[cfg(feature = "sparse")]
+    fn sparse_jacobian(&self, p: &DVector<f64>) -> Result<CscMatrix<f64>, EqSysError> {
+        Ok(CscMatrix::from(&self.jacobian(p)?))
+    }
+    /// Whether the caller's convergence criteria are met at `p`.
+    fn converged(&self, p: &DVector<f64>) -> Result<bool, EqSysError>;
+    /// Wall-clock limit for a single optimizer run, if any.
+    fn timeout(&self) -> Option<Duration>;
+    /// Projects `p` onto the feasible set (e.g. box constraints on the unknowns). Optimizers should
+    /// project every iterate they accept.
+    fn project(&self, p: &DVector<f64>) -> DVector<f64> {
+        p.clone()
+    }
+    /// Records a non-fatal issue with the run in the solve's report. Does nothing by default.
+    fn warn(&self, _warning: SolveWarning) {}
+}
+
+/// Warns through `problem` if a run of `solver` ended with `termination` at `MaxIters` or `Stalled`
+/// while `residual_norm` is still at or above `DEFAULT_RESIDUAL_NORM_TOL`.
+pub(crate) fn warn_if_unconverged(
+    problem: &dyn BlockObjective,
+    solver: &str,
+    termination: BlockTermination,
+    residual_norm: f64,
+) {
+    let stopped_early = matches!(
+        termination,
+        BlockTermination::MaxIters | BlockTermination::Stalled
+    );
+    if stopped_early && (residual_norm.is_nan() || residual_norm >= DEFAULT_RESIDUAL_NORM_TOL) {
+        problem.warn(SolveWarning::UnconvergedBlock {
+            block_idx: problem.block_idx(),
+            solver: solver.to_string(),
+            termination,
+            residual_norm,
+        });
+    }
+}
+
+/// Why a `BlockOptimizer` run stopped.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BlockTermination {
+    /// Residuals reached zero or the convergence criteria were met.
+    Converged,
+    MaxIters,
+    Timeout,
+    /// No further progress could be made from the current point.
+    Stalled,
+    /// The backend did not report why it stopped (e.g. an `ExternalSolver`).
+    Unreported,
+}
+
+/// Result of a `BlockOptimizer` run.
+#[derive(Clone, Debug)]
+pub struct BlockOptimum {
+    /// Best params found (sub-problem opt space).
+    pub params: DVector<f64>,
+    /// Norm of the objective residuals at `params`.
+    pub residual_norm: f64,
+    pub iterations: usize,
+    pub termination: BlockTermination,
+}
+
+/// A block solver backend, decoupled from any particular optimization library.
+pub trait BlockOptimizer {
+    fn name(&self) -> &str;
+    fn optimize(&self, problem: &dyn BlockObjective) -> Result<BlockOptimum, EqSysError>;
+}
+
+impl<G64, U64, Gadfn, Uadfn, R, const N: usize> BlockObjective
+    for SubProblem<G64, U64, Gadfn, Uadfn, R, ResidNoOpGaussNewton, N>
+where
+    G64: GivenParamsFor<f64, N>,
+    U64: UnknownParamsFor<f64, N>,
+    Gadfn: GivenParamsFor<adfn<1>, N>,
+    Uadfn: UnknownParamsFor<adfn<1>, N>,
+    R: ResidTransHOF,
+{
+    fn block_idx(&self) -> usize {
+        self.block.block_idx
+    }
+
+    fn initial_params(&self) -> DVector<f64> {
+        self.subprob_initial_params_optspace()
+    }
+
+    fn residuals(&self, p: &DVector<f64>) -> Result<DVector<f64>, EqSysError> {
+        self.outputs_optspace(p)
+    }
+
+    fn jacobian(&self, p: &DVector<f64>) -> Result<DMatrix<f64>, EqSysError> {
+        self.jacobian_optspace(p)
+    }
+
+    #[cfg(feature = "sparse")]
+    fn sparse_jacobian(&self, p: &DVector<f64>) -> Result<CscMatrix<f64>, EqSysError> {
+        self.sparse_jacobian_optspace(p)
+    }
+
+    fn converged(&self, p: &DVector<f64>) -> Result<bool, EqSysError> {
+        self.convergence_met(p)
+    }
+
+    fn timeout(&self) -> Option<Duration> {
+        self.timeout
+    }
+
+    fn project(&self, p: &DVector<f64>) -> DVector<f64> {
+        self.project_subprob_params(p)
+    }
+
+    fn warn(&self, warning: SolveWarning) {
+        SubProblem::warn(self, warning);
+    }
+}
+
+impl<G64, U64, Gadfn, Uadfn, R, const N: usize>
+    SubProblem<G64, U64, Gadfn, Uadfn, R, ResidNoOpGaussNewton, N>
+where
+    G64: GivenParamsFor<f64, N>,
+    U64: UnknownParamsFor<f64, N>,
+    Gadfn: GivenParamsFor<adfn<1>, N>,
+    Uadfn: UnknownParamsFor<adfn<1>, N>,
+    R: ResidTransHOF,
+{
+    /// Solves this block with the given optimizer and patches the result into the initial params.
+    pub fn solve_with(&self, optimizer: &dyn BlockOptimizer) -> Result<U64, EqSysError> {
+        let _span = solver_span!("solver", solver = optimizer.name());
+        self.report_solver_started(optimizer.name());
+        self.print_pre_optimization_summary();
+
+        let optimum = optimizer.optimize(self)?;
+        self.set_last_run(optimum.iterations as u64, optimum.termination);
+
+        solver_info!(
+            self.verbosity,
+            "------- post {} (block {})-------",
+            optimizer.name(),
+            self.block.block_idx
+        );
+        solver_info!(
+            self.verbosity,
+            iterations = optimum.iterations,
+            residual_norm = optimum.residual_norm,
+            "    stop reason: {:?} at iteration {}; residual norm: {:.6e}; params (opt space): {:?}",
+            optimum.termination,
+            optimum.iterations,
+            optimum.residual_norm,
+            optimum.params.as_slice()
+        );
+        self.check_converged(&optimum.params)?;
+
+        Ok(self.params_with_subprob_optimizer_result(optimum.params.as_slice()))
+    }
+}
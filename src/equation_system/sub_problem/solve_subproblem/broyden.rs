@@ -0,0 +1,91 @@
+use ad_trait::forward_ad::adfn::adfn;
+use nalgebra::{DMatrix, DVector};
+
+use crate::prelude::*;
+
+/// Jacobian reuse for Gauss-Newton: instead of recomputing the AD Jacobian at every iterate, the
+/// last one is corrected with Broyden rank-one updates, and recomputed only when a step stops
+/// making good progress.
+///
+/// On smooth blocks this cuts the number of AD Jacobian evaluations several-fold; on strongly
+/// nonlinear ones the recomputation criterion kicks in and the solve behaves like plain
+/// Gauss-Newton.
+#[derive(Clone, Debug)]
+pub struct BroydenConfig {
+    /// Number of consecutive Broyden updates after which the AD Jacobian is recomputed anyway.
+    pub max_updates: usize,
+    /// The AD Jacobian is recomputed when the residual norm drops by less than this fraction since the last Jacobian.
+    pub min_reduction: f64,
+}
+
+impl Default for BroydenConfig {
+    fn default() -> Self {
+        Self {
+            max_updates: 4,
+            min_reduction: 0.1,
+        }
+    }
+}
+
+/// The point, residuals and (approximate) Jacobian of the last Jacobian request.
+#[derive(Clone, Debug)]
+pub(crate) struct BroydenState {
+    params: DVector<f64>,
+    residuals: DVector<f64>,
+    jacobian: DMatrix<f64>,
+    n_updates: usize,
+}
+
+impl BroydenState {
+    /// Rank-one secant update `J + (Δr - J Δx) Δxᵀ / (Δxᵀ Δx)` moving the state to `params`.
+    fn update(&mut self, params: DVector<f64>, residuals: DVector<f64>) {
+        let dx = &params - &self.params;
+        let dx_norm2 = dx.norm_squared();
+        if dx_norm2 > 0.0 {
+            let dr = &residuals - &self.residuals;
+            let correction = (dr - &self.jacobian * &dx) / dx_norm2;
+            self.jacobian += correction * dx.transpose();
+        }
+        self.params = params;
+        self.residuals = residuals;
+        self.n_updates += 1;
+    }
+}
+
+impl<G64, U64, Gadfn, Uadfn, R, const N: usize>
+    SubProblem<G64, U64, Gadfn, Uadfn, R, ResidNoOpGaussNewton, N>
+where
+    G64: GivenParamsFor<f64, N>,
+    U64: UnknownParamsFor<f64, N>,
+    Gadfn: GivenParamsFor<adfn<1>, N>,
+    Uadfn: UnknownParamsFor<adfn<1>, N>,
+    R: ResidTransHOF,
+{
+    /// The Jacobian Gauss-Newton uses at `p`: the AD Jacobian, or with `broyden` set, a Broyden
+    /// update of the previous one while the residual norm keeps dropping by at least
+    /// `min_reduction` (see `BroydenConfig`).
+    pub fn gauss_newton_jacobian(&self, p: &DVector<f64>) -> Result<DMatrix<f64>, EqSysError> {
+        let Some(cfg) = &self.broyden else {
+            return self.jacobian_optspace(p);
+        };
+
+        let residuals = self.outputs_optspace(p)?;
+        let mut state = self.broyden_state.lock().unwrap();
+        if let Some(prev) = state.as_mut() {
+            let progressed = residuals.norm() <= (1.0 - cfg.min_reduction) * prev.residuals.norm();
+            if prev.n_updates < cfg.max_updates && progressed {
+                prev.update(p.clone(), residuals);
+                return Ok(prev.jacobian.clone());
+            }
+        }
+
+        let jacobian = self.jacobian_optspace(p)?;
+        *state = Some(BroydenState {
+            params: p.clone(),
+            residuals,
+            jacobian: jacobian.clone(),
+            n_updates: 0,
+        });
+        Ok(jacobian)
+    }
+}
@@ -0,0 +1,224 @@
+use ad_trait::{differentiable_function::DifferentiableFunctionTrait, forward_ad::adfn::adfn};
+#[cfg(feature = "argmin")]
+use argmin::core::{
+    Error as ArgminError, KV, Problem, Solver, State, TerminationReason, TerminationStatus,
+};
+use nalgebra::DVector;
+
+use super::block_optimizer::BlockTermination;
+
+use crate::prelude::*;
+
+/// Raw residual norm below which a run that stopped early still counts as converged.
+///
+/// Only used when no `ConvergenceCriteria` are set, and only on square, unpenalized blocks.
+pub const DEFAULT_RESIDUAL_NORM_TOL: f64 = 1e-6;
+
+/// Per-block stopping criteria, checked against the *raw* (untransformed) residuals of the block
+/// after every solver iteration.
+///
+/// A block is converged once every criterion that is set holds. A run that hits its
+/// iteration cap or stalls short of them fails with `EqSysError::DidNotConverge`.
+/// With no criteria set, solvers run to their own termination conditions.
+#[derive(Clone, Debug, Default)]
+pub struct ConvergenceCriteria {
+    /// Stop once every raw residual in the block satisfies `|r| < max_abs_residual`.
+    pub max_abs_residual: Option<f64>,
+    /// Stop once the sum of squared raw residuals in the block is below `max_cost`.
+    pub max_cost: Option<f64>,
+    /// Stop once the opt-space gradient norm of the block objective is below `max_grad_norm`.
+    pub max_grad_norm: Option<f64>,
+}
+
+impl ConvergenceCriteria {
+    pub fn with_max_abs_residual(mut self, tol: f64) -> Self {
+        self.max_abs_residual = Some(tol);
+        self
+    }
+
+    pub fn with_max_cost(mut self, tol: f64) -> Self {
+        self.max_cost = Some(tol);
+        self
+    }
+
+    pub fn with_max_grad_norm(mut self, tol: f64) -> Self {
+        self.max_grad_norm = Some(tol);
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.max_abs_residual.is_none() && self.max_cost.is_none() && self.max_grad_norm.is_none()
+    }
+}
+
+/// Problems that can report whether a candidate (sub-problem, opt-space) param vector meets their convergence criteria.
+pub trait BlockConvergence {
+    fn convergence_met(&self, p: &DVector<f64>) -> Result<bool, EqSysError>;
+}
+
+impl<G64, U64, Gadfn, Uadfn, R, A, const N: usize> BlockConvergence
+    for SubProblem<G64, U64, Gadfn, Uadfn, R, A, N>
+where
+    G64: GivenParamsFor<f64, N>,
+    U64: UnknownParamsFor<f64, N>,
+    Gadfn: GivenParamsFor<adfn<1>, N>,
+    Uadfn: UnknownParamsFor<adfn<1>, N>,
+    R: ResidTransHOF,
+    A: ResidAggHOF,
+{
+    fn convergence_met(&self, p: &DVector<f64>) -> Result<bool, EqSysError> {
+        // A cancelled solve stops the solver as if it had converged, so it returns its best params so far.
+        if let Some(progress) = &self.progress {
+            let cost = || {
+                let p_full_opt = self.optspace_fullprob_input_from_subprob_input(p.as_slice());
+                let residuals = self
+                    .raw_residual_fn
+                    .call(&self.optspace_to_modspace(&p_full_opt), false);
+                residuals.iter().map(|r| r * r).sum()
+            };
+            if progress.tick(self.block.block_idx, cost) {
+                return Ok(true);
+            }
+        }
+
+        if self.convergence.is_empty() {
+            return Ok(false);
+        }
+        Ok(self.criteria_met(p))
+    }
+}
+
+impl<G64, U64, Gadfn, Uadfn, R, A, const N: usize> SubProblem<G64, U64, Gadfn, Uadfn, R, A, N>
+where
+    G64: GivenParamsFor<f64, N>,
+    U64: UnknownParamsFor<f64, N>,
+    Gadfn: GivenParamsFor<adfn<1>, N>,
+    Uadfn: UnknownParamsFor<adfn<1>, N>,
+    R: ResidTransHOF,
+    A: ResidAggHOF,
+{
+    /// Whether every `ConvergenceCriteria` that is set holds at sub-problem params `p`.
+    fn criteria_met(&self, p: &DVector<f64>) -> bool {
+        let criteria = &self.convergence;
+        let p_full_opt = self.optspace_fullprob_input_from_subprob_input(p.as_slice());
+        let p_model = self.optspace_to_modspace(&p_full_opt);
+        let residuals = self.raw_residual_fn.call(&p_model, false);
+
+        // Comparisons are written so that NaN residuals never count as converged.
+        if let Some(tol) = criteria.max_abs_residual {
+            if !residuals.iter().all(|r| r.abs() < tol) {
+                return false;
+            }
+        }
+
+        if let Some(tol) = criteria.max_cost {
+            let cost: f64 = residuals.iter().map(|r| r * r).sum();
+            if cost.is_nan() || cost >= tol {
+                return false;
+            }
+        }
+
+        if let Some(tol) = criteria.max_grad_norm {
+            let grad_norm = self.subprob_grad_norm(p);
+            if grad_norm.is_nan() || grad_norm >= tol {
+                return false;
+            }
+        }
+
+        true
+    }
+
+    /// Fails with `EqSysError::DidNotConverge` if the last solver run hit its iteration cap
+    /// or stalled short of convergence at its best params `p`.
+    ///
+    /// Converged means meeting the `ConvergenceCriteria`. With none set, it means a raw
+    /// residual norm below `DEFAULT_RESIDUAL_NORM_TOL` if `root_check` is on, and anything
+    /// otherwise: least-squares, penalized and full-problem objectives need not reach a root.
+    pub(crate) fn check_converged(&self, p: &DVector<f64>) -> Result<(), EqSysError> {
+        let run = self.last_run();
+        let Some(termination @ (BlockTermination::MaxIters | BlockTermination::Stalled)) =
+            run.termination
+        else {
+            return Ok(());
+        };
+
+        let p_full_opt = self.optspace_fullprob_input_from_subprob_input(p.as_slice());
+        let residuals = self
+            .raw_residual_fn
+            .call(&self.optspace_to_modspace(&p_full_opt), false);
+        let cost: f64 = residuals.iter().map(|r| r * r).sum();
+        let converged = if self.convergence.is_empty() {
+            !self.root_check || cost.sqrt() < DEFAULT_RESIDUAL_NORM_TOL
+        } else {
+            self.criteria_met(p)
+        };
+        if converged {
+            return Ok(());
+        }
+
+        Err(EqSysError::DidNotConverge {
+            block_idx: self.block.block_idx,
+            cost,
+            grad_norm: self.subprob_grad_norm(p),
+            iterations: run.iterations.unwrap_or(0),
+            termination,
+        })
+    }
+}
+
+/// Wraps an argmin solver so that it also terminates (with `SolverConverged`) as soon as the
+/// problem's `ConvergenceCriteria` are met.
+#[cfg(feature = "argmin")]
+pub struct ConvergenceCheck<S> {
+    inner: S,
+    converged: bool,
+}
+
+#[cfg(feature = "argmin")]
+impl<S> ConvergenceCheck<S> {
+    pub fn new(inner: S) -> Self {
+        Self {
+            inner,
+            converged: false,
+        }
+    }
+}
+
+#[cfg(feature = "argmin")]
+impl<O, I, S> Solver<O, I> for ConvergenceCheck<S>
+where
+    O: BlockConvergence,
+    I: State<Param = DVector<f64>>,
+    S: Solver<O, I>,
+{
+    const NAME: &'static str = <S as Solver<O, I>>::NAME;
+
+    fn init(&mut self, problem: &mut Problem<O>, state: I) -> Result<(I, Option<KV>), ArgminError> {
+        let (state, kv) = self.inner.init(problem, state)?;
+        if let Some(param) = state.get_param() {
+            self.converged =
+                problem.problem("convergence_check", |p| Ok(p.convergence_met(param)?))?;
+        }
+        Ok((state, kv))
+    }
+
+    fn next_iter(
+        &mut self,
+        problem: &mut Problem<O>,
+        state: I,
+    ) -> Result<(I, Option<KV>), ArgminError> {
+        let (state, kv) = self.inner.next_iter(problem, state)?;
+        if let Some(param) = state.get_param() {
+            self.converged =
+                problem.problem("convergence_check", |p| Ok(p.convergence_met(param)?))?;
+        }
+        Ok((state, kv))
+    }
+
+    fn terminate(&mut self, state: &I) -> TerminationStatus {
+        if self.converged {
+            return TerminationStatus::Terminated(TerminationReason::SolverConverged);
+        }
+        self.inner.terminate(state)
+    }
+}
@@ -0,0 +1,85 @@
+use std::fmt;
+use std::time::Duration;
+
+use crate::prelude::*;
+use ad_trait::forward_ad::adfn::adfn;
+use nalgebra::{DMatrix, DVector};
+
+use super::block_optimizer::{BlockObjective, BlockOptimizer, BlockOptimum, BlockTermination};
+
+/// A block of the equation system as seen by an `ExternalSolver`: raw residuals and their Jacobian
+/// as functions of the block's unknowns in opt space.
+pub struct ExternalBlockProblem<'a> {
+    /// Starting point (opt space), one entry per block unknown.
+    pub initial_params: DVector<f64>,
+    /// Block residuals at an opt-space point, one entry per block equation (plus any regularization penalties).
+    pub residuals: &'a dyn Fn(&DVector<f64>) -> Result<DVector<f64>, EqSysError>,
+    /// Jacobian of `residuals` w.r.t. the opt-space params.
+    pub jacobian: &'a dyn Fn(&DVector<f64>) -> Result<DMatrix<f64>, EqSysError>,
+    /// Wall-clock limit for this solve, if any.
+    pub timeout: Option<Duration>,
+}
+
+/// A pluggable block solver backend (e.g. bindings to nlopt, ipopt or ceres).
+///
+/// Implementors minimize the sum of squared `residuals` starting from `initial_params` and return
+/// the best opt-space params found.
+pub trait ExternalSolver: MaybeSendSync {
+    fn name(&self) -> &str;
+    fn solve(&self, problem: &ExternalBlockProblem) -> Result<DVector<f64>, EqSysError>;
+}
+
+impl fmt::Debug for dyn ExternalSolver {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "ExternalSolver({})", self.name())
+    }
+}
+
+/// Runs an `ExternalSolver` as a `BlockOptimizer`.
+pub struct ExternalOptimizer<'a>(pub &'a dyn ExternalSolver);
+
+impl BlockOptimizer for ExternalOptimizer<'_> {
+    fn name(&self) -> &str {
+        self.0.name()
+    }
+
+    fn optimize(&self, problem: &dyn BlockObjective) -> Result<BlockOptimum, EqSysError> {
+        let residuals = |x: &DVector<f64>| problem.residuals(x);
+        let jacobian = |x: &DVector<f64>| problem.jacobian(x);
+        let external_problem = ExternalBlockProblem {
+            initial_params: problem.initial_params(),
+            residuals: &residuals,
+            jacobian: &jacobian,
+            timeout: problem.timeout(),
+        };
+
+        let params = problem.project(&self.0.solve(&external_problem)?);
+        let termination = if problem.converged(&params)? {
+            BlockTermination::Converged
+        } else {
+            BlockTermination::Unreported
+        };
+
+        Ok(BlockOptimum {
+            residual_norm: problem.residuals(&params)?.norm(),
+            params,
+            iterations: 0,
+            termination,
+        })
+    }
+}
+
+impl<G64, U64, Gadfn, Uadfn, R, const N: usize>
+    SubProblem<G64, U64, Gadfn, Uadfn, R, ResidNoOpGaussNewton, N>
+where
+    G64: GivenParamsFor<f64, N>,
+    U64: UnknownParamsFor<f64, N>,
+    Gadfn: GivenParamsFor<adfn<1>, N>,
+    Uadfn: UnknownParamsFor<adfn<1>, N>,
+    R: ResidTransHOF,
+{
+    /// Solves this block with an external solver backend.
+    pub fn solve_external(&self, solver: &dyn ExternalSolver) -> Result<U64, EqSysError> {
+        self.solve_with(&ExternalOptimizer(solver))
+    }
+}
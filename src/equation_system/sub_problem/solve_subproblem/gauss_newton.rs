@@ -1,7 +1,14 @@
-use crate::prelude::{opt_tools::MyObserver, *};
+use super::convergence::ConvergenceCheck;
+use crate::{
+    equation_system::{
+        logging::{solver_debug, solver_span},
+        opt_tools::block_termination,
+    },
+    prelude::*,
+};
 use ad_trait::forward_ad::adfn::adfn;
 use argmin::{
-    core::{Executor, Jacobian},
+    core::{Executor, Jacobian, State},
     solver::{gaussnewton::GaussNewtonLS, linesearch::MoreThuenteLineSearch},
 };
 
@@ -15,6 +22,8 @@ where
     R: ResidTransHOF,
 {
     pub fn solve_gauss_newton(&self) -> Result<U64, EqSysError> {
+        let _span = solver_span!("solver", solver = "GaussNewton");
+        self.report_solver_started("GaussNewton");
         self.print_pre_optimization_summary();
 
         // let linesearch: BacktrackingLineSearch<Vec<f64>, Vec<f64>, _, _> =
@@ -22,41 +31,60 @@ where
 
         let linesearch = MoreThuenteLineSearch::new().with_bounds(0.0, 1.0)?;
         let solver = GaussNewtonLS::new(linesearch);
-        let max_iters = 10000;
+        let max_iters = self.max_iters_or(DEFAULT_MAX_ITERS);
 
         let optspace_params = self.subprob_initial_params_optspace().clone();
 
-        println!(
+        solver_debug!(
+            self.verbosity,
             "Sub-problem {} initial params (opt space): {:?}",
-            self.block.block_idx, optspace_params
-        );
-        println!(
-            "Sub-problem Jacobian at initial params: {}",
-            self.jacobian(&optspace_params)?
+            self.block.block_idx,
+            optspace_params
         );
+        if self.verbosity >= Verbosity::Debug {
+            let jacobian = self.jacobian(&optspace_params)?;
+            solver_debug!(
+                self.verbosity,
+                "Sub-problem Jacobian at initial params: {}",
+                jacobian
+            );
+        }
 
-        let observer = MyObserver::new();
-        let opt_result = Executor::new(self.clone(), solver)
+        let observer = self.observer("GaussNewton");
+        let mut executor = Executor::new(self.clone(), ConvergenceCheck::new(solver))
             .configure(|state| state.param(optspace_params).max_iters(max_iters))
             .add_observer(
                 observer.clone(),
                 argmin::core::observers::ObserverMode::Always,
-            )
-            .run()?;
+            );
+        if let Some(timeout) = self.timeout {
+            executor = executor.timeout(timeout);
+        }
+        let opt_result = executor.run();
+        self.finish_trace(&observer);
+        let opt_result = opt_result?;
+        self.set_last_run(
+            opt_result.state.get_iter(),
+            block_termination(opt_result.state.get_termination_status()),
+        );
 
         self.print_post_optimization_summary(&opt_result);
         // println!("Cost history: {:?}", observer.cost_history());
 
-        let best_params_optspace_subprob = opt_result
-            .state
-            .best_param
-            .as_ref()
-            .expect("must have best param");
-
-        let best_params_vec: Vec<f64> = best_params_optspace_subprob.as_slice().to_vec();
+        let best_params_optspace_subprob =
+            opt_result
+                .state
+                .best_param
+                .as_ref()
+                .ok_or_else(|| EqSysError::NoBestParams {
+                    solver: "GaussNewton".to_string(),
+                })?;
+        self.check_converged(best_params_optspace_subprob)?;
 
         Ok(self.modspace_to_params(&self.optspace_to_modspace(
-            &self.optspace_fullprob_input_from_subprob_input(&best_params_vec),
+            &self.optspace_fullprob_input_from_subprob_input(
+                best_params_optspace_subprob.as_slice(),
+            ),
         )))
     }
 }
@@ -0,0 +1,149 @@
+use ad_trait::{differentiable_function::DifferentiableFunctionTrait, forward_ad::adfn::adfn};
+use nalgebra::DVector;
+use rand::prelude::*;
+
+use crate::{equation_system::logging::solver_info, prelude::*};
+
+/// Configuration for the coarse grid-search pre-solver (in *optimization space*, e.g. log-space).
+///
+/// Candidate points lie in the box `prior ± half_width` along every block unknown. A full grid of
+/// `points_per_dim` points per unknown is used when it has at most `max_points` points; otherwise
+/// `max_points` points are drawn uniformly from the box.
+#[derive(Clone, Debug)]
+pub struct GridSearchConfig {
+    /// Half-width of the search box around the prior, per opt-space coordinate.
+    ///
+    /// In log-space, `ln(10)` covers 0.1x to 10x of the prior in exp-linked model space.
+    pub half_width: f64,
+    /// Grid points per unknown. Odd values put a grid point on the prior itself.
+    pub points_per_dim: usize,
+    /// Upper bound on the number of cost evaluations.
+    pub max_points: usize,
+    /// Seed for the random cloud used when the full grid is too large.
+    pub seed: u64,
+}
+
+impl Default for GridSearchConfig {
+    fn default() -> Self {
+        Self {
+            half_width: std::f64::consts::LN_10,
+            points_per_dim: 5,
+            max_points: 1000,
+            seed: 0,
+        }
+    }
+}
+
+impl GridSearchConfig {
+    /// Fails with `EqSysError::InvalidGridSearchHalfWidth` unless `half_width` is finite and non-negative.
+    pub fn validate(&self) -> Result<(), EqSysError> {
+        if self.half_width.is_finite() && self.half_width >= 0.0 {
+            Ok(())
+        } else {
+            Err(EqSysError::InvalidGridSearchHalfWidth {
+                half_width: self.half_width,
+            })
+        }
+    }
+
+    /// Opt-space offsets from the prior to evaluate, always starting with the prior itself.
+    fn offsets(&self, n_dims: usize) -> Vec<Vec<f64>> {
+        let mut offsets = vec![vec![0.0; n_dims]];
+
+        let n_per_dim = self.points_per_dim.max(1);
+        let grid_size = u32::try_from(n_dims)
+            .ok()
+            .and_then(|n| n_per_dim.checked_pow(n));
+
+        match grid_size {
+            Some(size) if size <= self.max_points => {
+                let ticks: Vec<f64> = if n_per_dim == 1 {
+                    vec![0.0]
+                } else {
+                    (0..n_per_dim)
+                        .map(|k| {
+                            -self.half_width
+                                + 2.0 * self.half_width * k as f64 / (n_per_dim - 1) as f64
+                        })
+                        .collect()
+                };
+                for flat_idx in 0..size {
+                    let mut rem = flat_idx;
+                    let point = (0..n_dims)
+                        .map(|_| {
+                            let tick = ticks[rem % n_per_dim];
+                            rem /= n_per_dim;
+                            tick
+                        })
+                        .collect();
+                    offsets.push(point);
+                }
+            }
+            _ => {
+                let mut rng = StdRng::seed_from_u64(self.seed);
+                for _ in 0..self.max_points {
+                    let point = (0..n_dims)
+                        .map(|_| rng.random_range(-self.half_width..=self.half_width))
+                        .collect();
+                    offsets.push(point);
+                }
+            }
+        }
+        offsets
+    }
+}
+
+impl<G64, U64, Gadfn, Uadfn, R, A, const N: usize> SubProblem<G64, U64, Gadfn, Uadfn, R, A, N>
+where
+    G64: GivenParamsFor<f64, N>,
+    U64: UnknownParamsFor<f64, N>,
+    Gadfn: GivenParamsFor<adfn<1>, N>,
+    Uadfn: UnknownParamsFor<adfn<1>, N>,
+    R: ResidTransHOF,
+    A: ResidAggHOF,
+{
+    /// Evaluates the block's sum of squared raw residuals on a coarse opt-space grid (or random
+    /// cloud) around the prior and returns the params at the cheapest point.
+    ///
+    /// This is not a solver in its own right; it is meant to hand Gauss-Newton a starting point
+    /// inside its basin of convergence. Non-finite costs are ignored, so the prior is returned if
+    /// nothing on the grid beats it. Fails if `cfg` doesn't pass `GridSearchConfig::validate`.
+    pub fn grid_search(&self, cfg: &GridSearchConfig) -> Result<U64, EqSysError> {
+        cfg.validate()?;
+        let origin = self.subprob_initial_params_optspace();
+
+        let mut best_point = origin.clone();
+        let mut best_cost = f64::INFINITY;
+
+        for offset in cfg.offsets(origin.len()) {
+            let point = &origin + DVector::from_vec(offset);
+            let cost = self.raw_cost(&point);
+            if cost < best_cost {
+                best_cost = cost;
+                best_point = point;
+            }
+        }
+
+        solver_info!(
+            self.verbosity,
+            cost = best_cost,
+            "------- grid search (block {}): best raw cost {:.6e} at opt-space offset {:?}",
+            self.block.block_idx,
+            best_cost,
+            (&best_point - &origin).as_slice()
+        );
+
+        Ok(self.params_with_subprob_optimizer_result(best_point.as_slice()))
+    }
+
+    /// Sum of squared raw residuals of the block at sub-problem opt-space params `p`.
+    fn raw_cost(&self, p: &DVector<f64>) -> f64 {
+        let p_full_opt = self.optspace_fullprob_input_from_subprob_input(p.as_slice());
+        let p_model = self.optspace_to_modspace(&p_full_opt);
+        self.raw_residual_fn
+            .call(&p_model, false)
+            .iter()
+            .map(|r| r * r)
+            .sum()
+    }
+}
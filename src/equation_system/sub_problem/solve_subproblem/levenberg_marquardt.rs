@@ -0,0 +1,115 @@
+use crate::equation_system::clock::Instant;
+
+use crate::prelude::*;
+use ad_trait::forward_ad::adfn::adfn;
+use nalgebra::DMatrix;
+
+use super::block_optimizer::{
+    BlockObjective, BlockOptimizer, BlockOptimum, BlockTermination, warn_if_unconverged,
+};
+
+/// Levenberg-Marquardt: damped Gauss-Newton steps `(JᵀJ + μ·diag(JᵀJ)) dx = -Jᵀr`.
+///
+/// The damping `μ` shrinks after every step that lowers the residual norm and grows after every
+/// rejected one, so the method moves between Gauss-Newton (small `μ`) and scaled gradient descent
+/// (large `μ`).
+#[derive(Clone, Debug)]
+pub struct LevenbergMarquardt {
+    pub max_iters: usize,
+    pub initial_damping: f64,
+}
+
+impl Default for LevenbergMarquardt {
+    fn default() -> Self {
+        Self {
+            max_iters: 200,
+            initial_damping: 1e-3,
+        }
+    }
+}
+
+impl BlockOptimizer for LevenbergMarquardt {
+    fn name(&self) -> &str {
+        "Levenberg-Marquardt"
+    }
+
+    fn optimize(&self, problem: &dyn BlockObjective) -> Result<BlockOptimum, EqSysError> {
+        let max_mu = 1e12;
+        let start = Instant::now();
+
+        let mut x = problem.project(&problem.initial_params());
+        let mut r = problem.residuals(&x)?;
+        let mut r_norm = r.norm();
+        let mut mu = self.initial_damping;
+        let mut iters = 0;
+        let mut termination = BlockTermination::MaxIters;
+
+        while iters < self.max_iters {
+            if r_norm == 0.0 || problem.converged(&x)? {
+                termination = BlockTermination::Converged;
+                break;
+            }
+            if mu >= max_mu {
+                termination = BlockTermination::Stalled;
+                break;
+            }
+            if problem.timeout().is_some_and(|t| start.elapsed() > t) {
+                termination = BlockTermination::Timeout;
+                break;
+            }
+            iters += 1;
+
+            let jac = problem.jacobian(&x)?;
+            let jtj = jac.transpose() * &jac;
+            let jtr = jac.transpose() * &r;
+            // Floor the diagonal so that unknowns the residuals don't (yet) depend on still
+            // get damped.
+            let diag = DMatrix::from_diagonal(&jtj.diagonal().map(|d| d.max(1e-12)));
+
+            let Some(step) = (&jtj + &diag * mu).cholesky().map(|c| -c.solve(&jtr)) else {
+                mu *= 10.0;
+                continue;
+            };
+
+            let x_new = problem.project(&(&x + step));
+            let r_new = problem.residuals(&x_new)?;
+            let r_new_norm = r_new.norm();
+            if r_new_norm.is_finite() && r_new_norm < r_norm {
+                x = x_new;
+                r = r_new;
+                r_norm = r_new_norm;
+                mu = (mu / 10.0).max(1e-12);
+            } else {
+                mu *= 10.0;
+            }
+        }
+        warn_if_unconverged(problem, self.name(), termination, r_norm);
+
+        Ok(BlockOptimum {
+            params: x,
+            residual_norm: r_norm,
+            iterations: iters,
+            termination,
+        })
+    }
+}
+
+impl<G64, U64, Gadfn, Uadfn, R, const N: usize>
+    SubProblem<G64, U64, Gadfn, Uadfn, R, ResidNoOpGaussNewton, N>
+where
+    G64: GivenParamsFor<f64, N>,
+    U64: UnknownParamsFor<f64, N>,
+    Gadfn: GivenParamsFor<adfn<1>, N>,
+    Uadfn: UnknownParamsFor<adfn<1>, N>,
+    R: ResidTransHOF,
+{
+    /// Solves the block with `LevenbergMarquardt`. As with `solve_min_norm_least_squares`, the
+    /// residual transform `R` should be the identity.
+    pub fn solve_levenberg_marquardt(&self) -> Result<U64, EqSysError> {
+        let default = LevenbergMarquardt::default();
+        self.solve_with(&LevenbergMarquardt {
+            max_iters: self.max_iters_or(default.max_iters as u64) as usize,
+            ..default
+        })
+    }
+}
@@ -0,0 +1,147 @@
+use crate::equation_system::clock::Instant;
+
+use crate::prelude::*;
+use ad_trait::forward_ad::adfn::adfn;
+use nalgebra::DVector;
+
+use super::block_optimizer::{
+    BlockObjective, BlockOptimizer, BlockOptimum, BlockTermination, warn_if_unconverged,
+};
+
+/// Gauss-Newton with pseudo-inverse steps `dx = -J⁺ r`, optionally weighted per residual.
+///
+/// Each step is the smallest opt-space update that zeroes the linearized residuals. When the block
+/// has more unknowns than equations, this picks (among all solutions) the one closest to the
+/// starting point, i.e. closest to the prior, which sits at the opt-space origin under the default
+/// link functions. Steps are halved until the residual norm decreases.
+#[derive(Clone, Debug)]
+pub struct PseudoInverseGaussNewton {
+    /// Minimize `sum_i w_i * r_i^2` instead of `sum_i r_i^2`. Residuals past the end of `weights`
+    /// (e.g. regularization penalties) are unweighted.
+    pub weights: Option<Vec<f64>>,
+    pub max_iters: usize,
+}
+
+impl Default for PseudoInverseGaussNewton {
+    fn default() -> Self {
+        Self {
+            weights: None,
+            max_iters: 100,
+        }
+    }
+}
+
+impl BlockOptimizer for PseudoInverseGaussNewton {
+    fn name(&self) -> &str {
+        "least squares"
+    }
+
+    fn optimize(&self, problem: &dyn BlockObjective) -> Result<BlockOptimum, EqSysError> {
+        let min_step_scale = 1e-6;
+        let pinv_eps = 1e-12;
+        let start = Instant::now();
+
+        let mut x = problem.project(&problem.initial_params());
+        let r_init = problem.residuals(&x)?;
+
+        // Scaling residuals and Jacobian rows by sqrt(w) turns the weighted problem into an
+        // ordinary least-squares one.
+        let sqrt_w = DVector::from_fn(r_init.len(), |i, _| match &self.weights {
+            Some(w) if i < w.len() => w[i].sqrt(),
+            _ => 1.0,
+        });
+
+        let mut r = r_init.component_mul(&sqrt_w);
+        let mut r_norm = r.norm();
+        let mut iters = 0;
+        let mut termination = BlockTermination::MaxIters;
+
+        while iters < self.max_iters {
+            if r_norm == 0.0 || problem.converged(&x)? {
+                termination = BlockTermination::Converged;
+                break;
+            }
+            if problem.timeout().is_some_and(|t| start.elapsed() > t) {
+                termination = BlockTermination::Timeout;
+                break;
+            }
+
+            let mut jac = problem.jacobian(&x)?;
+            for (i, mut row) in jac.row_iter_mut().enumerate() {
+                row *= sqrt_w[i];
+            }
+            let jac_pinv = jac
+                .pseudo_inverse(pinv_eps)
+                .map_err(EqSysError::PseudoInverseFailed)?;
+            let step = -(jac_pinv * &r);
+
+            let mut alpha = 1.0;
+            let mut accepted = false;
+            while alpha > min_step_scale {
+                let x_new = problem.project(&(&x + &step * alpha));
+                let r_new = problem.residuals(&x_new)?.component_mul(&sqrt_w);
+                let r_new_norm = r_new.norm();
+                if r_new_norm.is_finite() && r_new_norm < r_norm {
+                    x = x_new;
+                    r = r_new;
+                    r_norm = r_new_norm;
+                    accepted = true;
+                    break;
+                }
+                alpha *= 0.5;
+            }
+            iters += 1;
+
+            if !accepted {
+                termination = BlockTermination::Stalled;
+                break;
+            }
+        }
+        warn_if_unconverged(problem, self.name(), termination, r_norm);
+
+        Ok(BlockOptimum {
+            params: x,
+            residual_norm: r_norm,
+            iterations: iters,
+            termination,
+        })
+    }
+}
+
+impl<G64, U64, Gadfn, Uadfn, R, const N: usize>
+    SubProblem<G64, U64, Gadfn, Uadfn, R, ResidNoOpGaussNewton, N>
+where
+    G64: GivenParamsFor<f64, N>,
+    U64: UnknownParamsFor<f64, N>,
+    Gadfn: GivenParamsFor<adfn<1>, N>,
+    Uadfn: UnknownParamsFor<adfn<1>, N>,
+    R: ResidTransHOF,
+{
+    /// Solves the block as a minimum-norm least-squares problem with pseudo-inverse Gauss-Newton
+    /// steps (see `PseudoInverseGaussNewton`).
+    ///
+    /// The residual transform `R` should be the identity so that `J⁺ r` is a true Gauss-Newton step.
+    pub fn solve_min_norm_least_squares(&self) -> Result<U64, EqSysError> {
+        self.solve_weighted_least_squares(None)
+    }
+
+    /// Like `solve_min_norm_least_squares`, but minimizes `sum_i w_i * r_i^2` for the given
+    /// per-equation weights (in block equation order).
+    ///
+    /// For blocks with more equations than unknowns there is generally no exact solution; the
+    /// weights decide which equations are satisfied most closely.
+    pub fn solve_weighted_least_squares(&self, weights: Option<&[f64]>) -> Result<U64, EqSysError> {
+        debug_assert!(
+            weights.is_none_or(|w| w.len() == self.block.equation_idxs.len()),
+            "Expected {} residual weights for block {}",
+            self.block.equation_idxs.len(),
+            self.block.block_idx
+        );
+        let default = PseudoInverseGaussNewton::default();
+        self.solve_with(&PseudoInverseGaussNewton {
+            weights: weights.map(<[f64]>::to_vec),
+            max_iters: self.max_iters_or(default.max_iters as u64) as usize,
+            ..default
+        })
+    }
+}
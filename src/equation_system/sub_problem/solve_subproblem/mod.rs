@@ -1,12 +1,30 @@
+#[cfg(feature = "argmin")]
+pub mod argmin_external;
+pub mod block_optimizer;
+pub mod broyden;
+pub mod convergence;
+pub mod external;
+#[cfg(feature = "argmin")]
 pub mod gauss_newton;
+pub mod grid_search;
+#[cfg(feature = "argmin")]
 pub mod lbfgs;
+pub mod levenberg_marquardt;
+pub mod min_norm;
+pub mod restart;
 pub mod simulated_annealing;
+pub mod small_newton;
 pub mod solver_run_log_data;
+#[cfg(feature = "sparse")]
+pub mod sparse;
 
 use ad_trait::forward_ad::adfn::adfn;
-use argmin::core::{Operator, State};
+#[cfg(feature = "argmin")]
+use argmin::core::State;
 
-use crate::prelude::*;
+#[cfg(feature = "argmin")]
+use crate::equation_system::opt_tools::OptRes;
+use crate::{equation_system::logging::solver_info, prelude::*};
 
 impl<G64, U64, Gadfn, Uadfn, R, A, const N: usize> SubProblem<G64, U64, Gadfn, Uadfn, R, A, N>
 where
@@ -18,75 +36,100 @@ where
     A: ResidAggHOF,
 {
     fn print_pre_optimization_summary(&self) {
-        println!(
+        if self.verbosity == Verbosity::Quiet {
+            return;
+        }
+        let v = self.verbosity;
+        solver_info!(
+            v,
             "\n------- pre optimization (block {})-------",
             self.block.block_idx
         );
-        println!("Initial params (model space): {:#?}", self.initial_unknowns);
-        println!(
+        solver_info!(
+            v,
+            "Initial params (model space): {:#?}",
+            self.initial_unknowns
+        );
+        solver_info!(
+            v,
             "Initial unknowns (opt space): {:?}",
             self.subprob_initial_params_optspace()
         );
-        println!("initial cost: {:.4e}", self.initial_params_cost().unwrap());
+        let cost = self.initial_params_cost().unwrap();
+        solver_info!(v, cost = cost, "initial cost: {:.4e}", cost);
 
-        self.apply(&self.subprob_initial_params_optspace())
-            .map_or_else(
-                |err| println!("initial gradient computation error: {:?}", err),
-                |grad| println!("initial gradient: {:?}", grad.as_slice()),
-            );
+        match self.outputs_optspace(&self.subprob_initial_params_optspace()) {
+            Ok(grad) => solver_info!(v, "initial gradient: {:?}", grad.as_slice()),
+            Err(err) => solver_info!(v, "initial gradient computation error: {:?}", err),
+        }
     }
 
+    #[cfg(feature = "argmin")]
     fn print_post_optimization_summary<S, Gr, J>(
         &self,
         opt_res: &OptRes<S, G64, U64, Gadfn, Uadfn, R, A, N, Gr, J>,
     ) {
-        println!(
+        if self.verbosity == Verbosity::Quiet {
+            return;
+        }
+        let v = self.verbosity;
+        solver_info!(
+            v,
             "------- post optimization (block {})-------",
             self.block.block_idx
         );
-        println!("  solver: {}", tynm::type_name::<S>());
-        println!(
+        solver_info!(v, "  solver: {}", tynm::type_name::<S>());
+        solver_info!(
+            v,
+            iterations = opt_res.state.get_iter(),
             "    stop status: {:?} at iteration {}",
             opt_res.state.get_termination_status(),
             opt_res.state.get_iter()
         );
-        println!(
+        solver_info!(
+            v,
             "    stop reason: {:?}",
             opt_res.state.get_termination_status()
         );
-        println!(
+        solver_info!(
+            v,
+            cost = opt_res.state.best_cost,
             "Best cost: {:.6e} (prev: {:.6e})",
-            opt_res.state.best_cost, opt_res.state.prev_best_cost
+            opt_res.state.best_cost,
+            opt_res.state.prev_best_cost
         );
 
-        let best_params_optspace_subprob = opt_res
-            .state
-            .best_param
-            .as_ref()
-            .expect("must have best param");
-        println!(
+        let Some(best_params_optspace_subprob) = opt_res.state.best_param.as_ref() else {
+            solver_info!(v, "no best params reported");
+            return;
+        };
+        solver_info!(
+            v,
             "Best params (opt space): {:?}",
             best_params_optspace_subprob
         );
-        self.apply(&best_params_optspace_subprob).map_or_else(
-            |err| println!("gradient computation error: {:?}", err),
-            |grad| {
-                println!(
+        match self.outputs_optspace(best_params_optspace_subprob) {
+            Ok(grad) => {
+                let grad_norm = grad.iter().map(|x| x * x).sum::<f64>().sqrt();
+                solver_info!(
+                    v,
+                    grad_norm = grad_norm,
                     "gradient at best_params_opt_space: {:?};       norm: {:.6e}",
                     grad.as_slice(),
-                    grad.iter().map(|x| x * x).sum::<f64>().sqrt()
-                )
-            },
-        );
+                    grad_norm
+                );
+            }
+            Err(err) => solver_info!(v, "gradient computation error: {:?}", err),
+        }
 
-        let best_params_optspace_fullprob = self.optspace_fullprob_input_from_subprob_input(
-            &best_params_optspace_subprob.as_slice().to_vec(),
-        );
+        let best_params_optspace_fullprob = self
+            .optspace_fullprob_input_from_subprob_input(best_params_optspace_subprob.as_slice());
 
         let best_params_modspace_fullprob =
             self.optspace_to_modspace(&best_params_optspace_fullprob);
 
-        println!(
+        solver_info!(
+            v,
             "Best unknowns (POST): {:#?}",
             self.modspace_to_params(&best_params_modspace_fullprob)
         );
@@ -0,0 +1,69 @@
+use ad_trait::forward_ad::adfn::adfn;
+use nalgebra::DVector;
+use rand::prelude::*;
+
+use crate::prelude::*;
+
+/// Random restarts for block solvers: when a solver fails, retry from the prior perturbed in
+/// *optimization space* before escalating to simulated annealing.
+#[derive(Clone, Debug)]
+pub struct RestartPolicy {
+    /// Number of perturbed retries per failed block.
+    pub max_restarts: usize,
+    /// Half-width of the uniform perturbation applied to each opt-space coordinate.
+    ///
+    /// In log-space, `0.1` is roughly a ±10% multiplicative jitter in exp-linked model space.
+    pub perturbation_scale: f64,
+    /// Seed for the perturbation RNG, so restarts are reproducible.
+    pub seed: u64,
+}
+
+impl Default for RestartPolicy {
+    fn default() -> Self {
+        Self {
+            max_restarts: 3,
+            perturbation_scale: 0.1,
+            seed: 0,
+        }
+    }
+}
+
+impl RestartPolicy {
+    /// Fails with `EqSysError::InvalidPerturbationScale` unless `perturbation_scale` is finite and non-negative.
+    pub fn validate(&self) -> Result<(), EqSysError> {
+        check_perturbation_scale(self.perturbation_scale)
+    }
+}
+
+fn check_perturbation_scale(scale: f64) -> Result<(), EqSysError> {
+    if scale.is_finite() && scale >= 0.0 {
+        Ok(())
+    } else {
+        Err(EqSysError::InvalidPerturbationScale { scale })
+    }
+}
+
+impl<G64, U64, Gadfn, Uadfn, R, A, const N: usize> SubProblem<G64, U64, Gadfn, Uadfn, R, A, N>
+where
+    G64: GivenParamsFor<f64, N>,
+    U64: UnknownParamsFor<f64, N>,
+    Gadfn: GivenParamsFor<adfn<1>, N>,
+    Uadfn: UnknownParamsFor<adfn<1>, N>,
+    R: ResidTransHOF,
+    A: ResidAggHOF,
+{
+    /// Returns the sub-problem's initial params with every block unknown jittered uniformly by up
+    /// to `±scale` in opt space.
+    ///
+    /// Fails with `EqSysError::InvalidPerturbationScale` unless `scale` is finite and non-negative.
+    pub fn perturbed_initial_params(
+        &self,
+        scale: f64,
+        rng: &mut impl Rng,
+    ) -> Result<U64, EqSysError> {
+        check_perturbation_scale(scale)?;
+        let origin = self.subprob_initial_params_optspace();
+        let jitter = DVector::from_fn(origin.len(), |_, _| rng.random_range(-scale..=scale));
+        Ok(self.params_with_subprob_optimizer_result((origin + jitter).as_slice()))
+    }
+}
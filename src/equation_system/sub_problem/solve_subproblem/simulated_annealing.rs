@@ -1,6 +1,21 @@
-use crate::prelude::{opt_tools::MyObserver, *};
+#[cfg(feature = "argmin")]
+use super::convergence::ConvergenceCheck;
+#[cfg(feature = "argmin")]
+use crate::equation_system::{
+    logging::{solver_debug, solver_span},
+    opt_tools::block_termination,
+};
+use crate::prelude::*;
 use ad_trait::forward_ad::adfn::adfn;
-use argmin::{core::Executor, solver::simulatedannealing::SimulatedAnnealing};
+#[cfg(feature = "argmin")]
+use argmin::{
+    core::{Executor, State},
+    solver::simulatedannealing::SimulatedAnnealing,
+};
+#[cfg(feature = "argmin")]
+use rand::{SeedableRng, rngs::StdRng};
+#[cfg(feature = "argmin")]
+use std::sync::atomic::{AtomicU64, Ordering};
 
 /// Configuration for the annealing proposal (in *optimization space*, e.g. log-space).
 #[derive(Clone, Debug)]
@@ -34,6 +49,19 @@ pub struct SimulatedAnnealingConfig {
 
 impl Default for SimulatedAnnealingConfig {
     fn default() -> Self {
+        Self::log_space_default()
+    }
+}
+
+impl SimulatedAnnealingConfig {
+    /// A builder starting from `log_space_default`, whose `build` validates the config.
+    pub fn builder() -> SimulatedAnnealingConfigBuilder {
+        SimulatedAnnealingConfigBuilder::from(Self::log_space_default())
+    }
+
+    /// Tuned for unknowns with log-scaled links, where a step of `ln(10)` is a 10x jump in model
+    /// space: local moves of up to ~28% early on, and big jumps of around 10x, clamped to 100x.
+    pub fn log_space_default() -> Self {
         Self {
             init_temp: 100.0,
             small_step_init: 0.25,
@@ -48,8 +76,210 @@ impl Default for SimulatedAnnealingConfig {
             grad_drift_max: Some(1.0), // set > 0.0 to enable (and compile with feature "sa_grad")
         }
     }
+
+    /// For starting points that may be orders of magnitude off: hotter, with larger and more
+    /// frequent big jumps (around 100x in log space, clamped to 10^4x).
+    pub fn aggressive() -> Self {
+        Self {
+            init_temp: 1000.0,
+            small_step_init: 0.5,
+            small_step_min: 0.02,
+            big_step_init: 100f64.ln(),
+            big_step_min: 0.25,
+            p_big_init: 0.50,
+            p_big_min: 0.05,
+            max_abs_step: 1e4f64.ln(),
+            grad_drift_max: Some(1.0),
+        }
+    }
+
+    /// For starting points already near the solution: cooler, with small local moves and rare big
+    /// jumps of around 3x in log space, clamped to 10x.
+    pub fn conservative() -> Self {
+        Self {
+            init_temp: 10.0,
+            small_step_init: 0.10,
+            small_step_min: 0.005,
+            big_step_init: 3f64.ln(),
+            big_step_min: 0.05,
+            p_big_init: 0.10,
+            p_big_min: 0.01,
+            max_abs_step: std::f64::consts::LN_10,
+            grad_drift_max: Some(1.0),
+        }
+    }
+
+    /// Checks that the temperature and step scales are positive, each step's `*_min` is at most its
+    /// `*_init`, the big-jump probabilities are in `[0, 1]`, and `grad_drift_max` (if set) is
+    /// non-negative.
+    ///
+    /// Fails with `EqSysError::InvalidAnnealingConfig` naming the first offending field.
+    pub fn validate(&self) -> Result<(), EqSysError> {
+        let check = |field: &'static str, value: f64, ok: bool, requirement: &'static str| {
+            if ok {
+                Ok(())
+            } else {
+                Err(EqSysError::InvalidAnnealingConfig {
+                    field,
+                    value,
+                    requirement,
+                })
+            }
+        };
+        let positive = |x: f64| x.is_finite() && x > 0.0;
+        let probability = |x: f64| (0.0..=1.0).contains(&x);
+
+        check(
+            "init_temp",
+            self.init_temp,
+            positive(self.init_temp),
+            "finite and positive",
+        )?;
+        check(
+            "small_step_min",
+            self.small_step_min,
+            positive(self.small_step_min),
+            "finite and positive",
+        )?;
+        check(
+            "small_step_init",
+            self.small_step_init,
+            self.small_step_init.is_finite() && self.small_step_init >= self.small_step_min,
+            "finite and at least small_step_min",
+        )?;
+        check(
+            "big_step_min",
+            self.big_step_min,
+            positive(self.big_step_min),
+            "finite and positive",
+        )?;
+        check(
+            "big_step_init",
+            self.big_step_init,
+            self.big_step_init.is_finite() && self.big_step_init >= self.big_step_min,
+            "finite and at least big_step_min",
+        )?;
+        check(
+            "p_big_init",
+            self.p_big_init,
+            probability(self.p_big_init),
+            "in [0, 1]",
+        )?;
+        check(
+            "p_big_min",
+            self.p_big_min,
+            probability(self.p_big_min),
+            "in [0, 1]",
+        )?;
+        check(
+            "max_abs_step",
+            self.max_abs_step,
+            self.max_abs_step > 0.0,
+            "positive",
+        )?;
+        if let Some(drift) = self.grad_drift_max {
+            check(
+                "grad_drift_max",
+                drift,
+                drift.is_finite() && drift >= 0.0,
+                "finite and non-negative",
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Chainable construction of a `SimulatedAnnealingConfig`, validated by `build`.
+///
+/// Start from `SimulatedAnnealingConfig::builder()`, or from a preset with
+/// `SimulatedAnnealingConfigBuilder::from(SimulatedAnnealingConfig::aggressive())`.
+#[derive(Clone, Debug)]
+pub struct SimulatedAnnealingConfigBuilder {
+    config: SimulatedAnnealingConfig,
+}
+
+impl From<SimulatedAnnealingConfig> for SimulatedAnnealingConfigBuilder {
+    fn from(config: SimulatedAnnealingConfig) -> Self {
+        Self { config }
+    }
+}
+
+impl SimulatedAnnealingConfigBuilder {
+    pub fn with_init_temp(mut self, init_temp: f64) -> Self {
+        self.config.init_temp = init_temp;
+        self
+    }
+
+    /// Small-move half-width at `init_temp` and as the temperature goes to zero.
+    pub fn with_small_step(mut self, init: f64, min: f64) -> Self {
+        self.config.small_step_init = init;
+        self.config.small_step_min = min;
+        self
+    }
+
+    /// Big-jump scale at `init_temp` and as the temperature goes to zero.
+    pub fn with_big_step(mut self, init: f64, min: f64) -> Self {
+        self.config.big_step_init = init;
+        self.config.big_step_min = min;
+        self
+    }
+
+    /// Probability of a big jump at `init_temp` and as the temperature goes to zero.
+    pub fn with_p_big(mut self, init: f64, min: f64) -> Self {
+        self.config.p_big_init = init;
+        self.config.p_big_min = min;
+        self
+    }
+
+    pub fn with_max_abs_step(mut self, max_abs_step: f64) -> Self {
+        self.config.max_abs_step = max_abs_step;
+        self
+    }
+
+    pub fn with_grad_drift_max(mut self, grad_drift_max: Option<f64>) -> Self {
+        self.config.grad_drift_max = grad_drift_max;
+        self
+    }
+
+    /// The config, if it passes `SimulatedAnnealingConfig::validate`.
+    pub fn build(self) -> Result<SimulatedAnnealingConfig, EqSysError> {
+        self.config.validate()?;
+        Ok(self.config)
+    }
+}
+
+/// Proposals of one simulated-annealing run, and how many of them `SimulatedAnnealingConfig::max_abs_step` clamped.
+#[cfg(feature = "argmin")]
+#[derive(Debug, Default)]
+pub(crate) struct AnnealingStepCounts {
+    proposals: AtomicU64,
+    clamped: AtomicU64,
+}
+
+#[cfg(feature = "argmin")]
+impl AnnealingStepCounts {
+    pub(crate) fn record(&self, clamped: bool) {
+        self.proposals.fetch_add(1, Ordering::Relaxed);
+        if clamped {
+            self.clamped.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    fn reset(&self) {
+        self.proposals.store(0, Ordering::Relaxed);
+        self.clamped.store(0, Ordering::Relaxed);
+    }
+
+    /// `(clamped, proposals)` so far.
+    fn counts(&self) -> (u64, u64) {
+        (
+            self.clamped.load(Ordering::Relaxed),
+            self.proposals.load(Ordering::Relaxed),
+        )
+    }
 }
 
+#[cfg(feature = "argmin")]
 impl<G64, U64, Gadfn, Uadfn, R, A, const N: usize> SubProblem<G64, U64, Gadfn, Uadfn, R, A, N>
 where
     G64: GivenParamsFor<f64, N>,
@@ -60,6 +290,8 @@ where
     A: ResidAggFnToScalarGen,
 {
     pub fn solve_simulated_annealing(&self) -> Result<U64, EqSysError> {
+        let _span = solver_span!("solver", solver = "SimulatedAnnealing");
+        self.report_solver_started("SimulatedAnnealing");
         self.print_pre_optimization_summary();
 
         let optspace_params = self.subprob_initial_params_optspace().clone();
@@ -67,13 +299,13 @@ where
         let temp = self
             .sa_cfg
             .as_ref()
-            .expect("Simulated annealing config (sa_cfg) not set on annealing SubProblem")
+            .ok_or(EqSysError::AnnealingConfigMissing)?
             .init_temp;
 
-        // Set up simulated annealing solver
-        // An alternative random number generator (RNG) can be provided to `new_with_rng`:
-        // SimulatedAnnealing::new_with_rng(temp, Xoshiro256PlusPlus::try_from_os_rng()?)?
-        let solver = SimulatedAnnealing::new(temp)?
+        // Set up simulated annealing solver. Its acceptance RNG is seeded from the proposal RNG
+        // (see `with_rng_seed`) rather than from the OS, so that runs are reproducible.
+        let acceptance_rng = StdRng::from_rng(&mut *self.rng.lock().unwrap());
+        let solver = SimulatedAnnealing::new_with_rng(temp, acceptance_rng)?
             // Optional: Define temperature function (defaults to `SATempFunc::TemperatureFast`)
             // .with_temp_func(SATempFunc::Boltzmann)
             /////////////////////////
@@ -93,19 +325,23 @@ where
         // // Optional: Start reannealing after no new best solution has been found for 800 iterations
         // .with_reannealing_best(800);
 
-        println!(
+        solver_debug!(
+            self.verbosity,
             "Sub-problem {} initial params (opt space): {:?}",
-            self.block.block_idx, optspace_params
+            self.block.block_idx,
+            optspace_params
         );
+        let max_iters = self.max_iters_or(DEFAULT_MAX_ITERS);
 
-        let observer = MyObserver::new();
+        let observer = self.observer("SimulatedAnnealing");
+        self.annealing_steps.reset();
 
-        let opt_result = Executor::new(self.clone(), solver)
+        let mut executor = Executor::new(self.clone(), ConvergenceCheck::new(solver))
             .configure(|state| {
                 state
                     .param(optspace_params)
                     // Optional: Set maximum number of iterations (defaults to `std::u64::MAX`)
-                    .max_iters(10_000)
+                    .max_iters(max_iters)
                     // Optional: Set target cost function value (defaults to `std::f64::NEG_INFINITY`)
                     .target_cost(0.0)
             })
@@ -113,22 +349,47 @@ where
             .add_observer(
                 observer.clone(),
                 argmin::core::observers::ObserverMode::NewBest,
-            )
-            .run()?;
+            );
+        if let Some(timeout) = self.timeout {
+            executor = executor.timeout(timeout);
+        }
+        let opt_result = executor.run();
+        self.finish_trace(&observer);
+        let opt_result = opt_result?;
+        self.warn_if_steps_clamped();
+        self.set_last_run(
+            opt_result.state.get_iter(),
+            block_termination(opt_result.state.get_termination_status()),
+        );
 
         self.print_post_optimization_summary(&opt_result);
         // println!("Cost history: {:?}", observer.cost_history());
 
-        let best_params_optspace_subprob = opt_result
-            .state
-            .best_param
-            .as_ref()
-            .expect("must have best param");
-
-        let best_params_vec: Vec<f64> = best_params_optspace_subprob.as_slice().to_vec();
+        let best_params_optspace_subprob =
+            opt_result
+                .state
+                .best_param
+                .as_ref()
+                .ok_or_else(|| EqSysError::NoBestParams {
+                    solver: "SimulatedAnnealing".to_string(),
+                })?;
 
         Ok(self.modspace_to_params(&self.optspace_to_modspace(
-            &self.optspace_fullprob_input_from_subprob_input(&best_params_vec),
+            &self.optspace_fullprob_input_from_subprob_input(
+                best_params_optspace_subprob.as_slice(),
+            ),
         )))
     }
+
+    /// Warns if more than `ANNEALING_CLAMP_WARN_FRACTION` of the last run's proposals were clamped to `max_abs_step`.
+    fn warn_if_steps_clamped(&self) {
+        let (clamped, proposals) = self.annealing_steps.counts();
+        if proposals > 0 && clamped as f64 > ANNEALING_CLAMP_WARN_FRACTION * proposals as f64 {
+            self.warn(SolveWarning::AnnealingStepsClamped {
+                block_idx: self.block.block_idx,
+                clamped,
+                proposals,
+            });
+        }
+    }
 }
@@ -0,0 +1,139 @@
+use crate::equation_system::clock::Instant;
+
+use crate::prelude::*;
+use ad_trait::forward_ad::adfn::adfn;
+use nalgebra::{DMatrix, DVector};
+
+use super::block_optimizer::{BlockObjective, BlockOptimizer, BlockOptimum, BlockTermination};
+
+/// Damped Newton for 1×1 and 2×2 blocks using a closed-form Jacobian inverse.
+///
+/// The block must be square with no extra penalty outputs (i.e. no Tikhonov regularization). Steps
+/// are halved until the residual norm decreases. Returns an error if the Jacobian becomes singular
+/// or the residuals don't reach zero, so callers can fall back to the general solvers.
+#[derive(Clone, Debug)]
+pub struct SmallNewton {
+    pub max_iters: usize,
+    /// Residual norm below which the block counts as solved.
+    pub abs_tol: f64,
+}
+
+impl Default for SmallNewton {
+    fn default() -> Self {
+        Self {
+            max_iters: 50,
+            abs_tol: 1e-10,
+        }
+    }
+}
+
+impl BlockOptimizer for SmallNewton {
+    fn name(&self) -> &str {
+        "analytic Newton"
+    }
+
+    fn optimize(&self, problem: &dyn BlockObjective) -> Result<BlockOptimum, EqSysError> {
+        let min_step_scale = 1e-6;
+        let start = Instant::now();
+
+        let mut x = problem.project(&problem.initial_params());
+        let mut r = problem.residuals(&x)?;
+        let n_unks = x.len();
+        if n_unks > 2 || r.len() != n_unks {
+            return Err(EqSysError::SmallBlockUnsupported {
+                n_eqs: r.len(),
+                n_unks,
+            });
+        }
+        let mut r_norm = r.norm();
+
+        for iters in 0..self.max_iters {
+            if r_norm < self.abs_tol || problem.converged(&x)? {
+                return Ok(BlockOptimum {
+                    params: x,
+                    residual_norm: r_norm,
+                    iterations: iters,
+                    termination: BlockTermination::Converged,
+                });
+            }
+            if problem.timeout().is_some_and(|t| start.elapsed() > t) {
+                break;
+            }
+
+            let jac = problem.jacobian(&x)?;
+            let step = small_newton_step(&jac, &r).ok_or(EqSysError::SingularBlockJacobian {
+                block_idx: problem.block_idx(),
+            })?;
+
+            let mut alpha = 1.0;
+            let mut accepted = false;
+            while alpha > min_step_scale {
+                let x_new = problem.project(&(&x + &step * alpha));
+                let r_new = problem.residuals(&x_new)?;
+                let r_new_norm = r_new.norm();
+                if r_new_norm.is_finite() && r_new_norm < r_norm {
+                    x = x_new;
+                    r = r_new;
+                    r_norm = r_new_norm;
+                    accepted = true;
+                    break;
+                }
+                alpha *= 0.5;
+            }
+
+            if !accepted {
+                break;
+            }
+        }
+
+        Err(EqSysError::SmallNewtonDidNotConverge {
+            block_idx: problem.block_idx(),
+            residual_norm: r_norm,
+        })
+    }
+}
+
+impl<G64, U64, Gadfn, Uadfn, R, const N: usize>
+    SubProblem<G64, U64, Gadfn, Uadfn, R, ResidNoOpGaussNewton, N>
+where
+    G64: GivenParamsFor<f64, N>,
+    U64: UnknownParamsFor<f64, N>,
+    Gadfn: GivenParamsFor<adfn<1>, N>,
+    Uadfn: UnknownParamsFor<adfn<1>, N>,
+    R: ResidTransHOF,
+{
+    /// Solves a 1×1 or 2×2 block with `SmallNewton`, without going through an argmin executor. The
+    /// residual transform `R` should be the identity.
+    pub fn solve_small_newton(&self) -> Result<U64, EqSysError> {
+        let default = SmallNewton::default();
+        self.solve_with(&SmallNewton {
+            max_iters: self.max_iters_or(default.max_iters as u64) as usize,
+            ..default
+        })
+    }
+}
+
+/// Newton step `-J⁻¹ r` for a 1×1 or 2×2 system, or `None` if `J` is (numerically) singular.
+pub(crate) fn small_newton_step(jac: &DMatrix<f64>, r: &DVector<f64>) -> Option<DVector<f64>> {
+    match r.len() {
+        1 => {
+            let j = jac[(0, 0)];
+            (j != 0.0 && j.is_finite()).then(|| DVector::from_element(1, -r[0] / j))
+        }
+        2 => {
+            let (a, b, c, d) = (jac[(0, 0)], jac[(0, 1)], jac[(1, 0)], jac[(1, 1)]);
+            let det = a * d - b * c;
+            // Relative to the row magnitudes, so that a uniformly tiny Jacobian isn't
+            // mistaken for a singular one.
+            let scale = (a.abs() + b.abs()) * (c.abs() + d.abs());
+            if !det.is_finite() || det.abs() <= f64::EPSILON * scale {
+                return None;
+            }
+            Some(DVector::from_vec(vec![
+                -(d * r[0] - b * r[1]) / det,
+                -(a * r[1] - c * r[0]) / det,
+            ]))
+        }
+        _ => None,
+    }
+}
@@ -1,19 +1,10 @@
-use argmin::core::{TerminationReason, TerminationStatus};
-use nalgebra::DVector;
+use super::block_optimizer::BlockTermination;
 
-pub struct SolverRunPostOptLogData<U> {
-    pub termination_status: TerminationStatus,
-    pub termination_reason: TerminationReason,
-    pub best_cost: f64,
-    pub best_params: U,
-    pub opt_space_grad_at_best_params: DVector<f64>,
-    pub cost_history: Vec<f64>,
-}
-
-pub struct SolverRunLogData<U> {
-    pub solver_name: String,
-    pub best_cost_pre: f64,
-    pub input_params: U,
-    pub opt_space_grad_at_input: DVector<f64>,
-    pub post_run_data: Option<SolverRunPostOptLogData<U>>,
+/// What the most recent solver run on a sub-problem reported about itself, as recorded in `BlockReport`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SolverRunLogData {
+    /// Iterations of the run, if the solver reports them.
+    pub iterations: Option<u64>,
+    /// Why the run stopped, if the solver reports it.
+    pub termination: Option<BlockTermination>,
 }
@@ -1,17 +1,33 @@
-use std::rc::Rc;
 use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
 use ad_trait::{
     differentiable_function::ForwardAD, forward_ad::adfn::adfn, function_engine::FunctionEngine,
 };
-use argmin::core::{Error as ArgminError, Operator};
-use nalgebra::{DVector, Dyn, Matrix, VecStorage};
+use nalgebra::{DMatrix, DVector, Dyn, Matrix, VecStorage};
 use rand::SeedableRng;
 use rand::rngs::StdRng;
 
+use crate::equation_system::logging::solver_debug;
+use crate::equation_system::optimization_trace::TraceSink;
+use crate::equation_system::solve_options::{ProgressHook, TimeBudget};
+use crate::equation_system::sub_problem::eval_cache::EvalCache;
+use crate::equation_system::sub_problem::solve_subproblem::block_optimizer::BlockTermination;
+use crate::equation_system::sub_problem::solve_subproblem::broyden::{BroydenConfig, BroydenState};
+use crate::equation_system::sub_problem::solve_subproblem::convergence::ConvergenceCriteria;
+#[cfg(feature = "argmin")]
+use crate::equation_system::sub_problem::solve_subproblem::simulated_annealing::AnnealingStepCounts;
 use crate::equation_system::sub_problem::solve_subproblem::simulated_annealing::SimulatedAnnealingConfig;
+use crate::equation_system::sub_problem::solve_subproblem::solver_run_log_data::SolverRunLogData;
+#[cfg(feature = "sparse")]
+use crate::equation_system::sub_problem::solve_subproblem::sparse::JacobianSparsity;
+use crate::equation_system::warnings::{SolveWarning, Warnings};
 use crate::prelude::*;
 
+/// `SubProblem` named by the f64 versions of the givens and unknowns structs only (see `ParamFamily`).
+pub type SubProblemFor<G, U, R, A, const N: usize> =
+    SubProblem<G, U, AdfnFor<G>, AdfnFor<U>, R, A, N>;
+
 pub struct ToScalar;
 pub struct ToVector;
 
@@ -35,19 +51,62 @@ where
     R: ResidTransHOF,
     A: ResidAggHOF,
 {
-    pub loss_fn_engine: Rc<
+    pub loss_fn_engine: Shared<
         FunctionEngine<
             ObjectiveFunction<f64, G64, U64, R, A, N>,
             ObjectiveFunction<adfn<1>, Gadfn, Uadfn, R, A, N>,
             ForwardAD,
         >,
     >,
+    /// The `adfn<1>` objective of `loss_fn_engine`, for directional derivatives (see `sparse_jacobian_optspace`).
+    #[cfg(feature = "sparse")]
+    pub(crate) loss_adfn: Shared<ObjectiveFunction<adfn<1>, Gadfn, Uadfn, R, A, N>>,
     pub block: SolutionBlock,
+    /// Untransformed, unaggregated residuals of this block's equations, evaluated on model-space
+    /// params.
+    ///
+    /// Used for convergence checks and reporting; never differentiated.
+    pub raw_residual_fn:
+        ObjectiveFunction<f64, G64, U64, ResidTransIdentity, ResidNoOpGaussNewton, N>,
     pub param_scaler: Option<ParamScaler<f64, N>>,
+    /// Domains of the link functions, which every starting point must lie in.
+    pub scaling_domains: [LinkDomain; N],
     pub initial_unknowns: U64,
     pub residual_agg_fn_gen: A,
     pub rng: Arc<Mutex<StdRng>>,
     pub sa_cfg: Option<SimulatedAnnealingConfig>,
+    /// Proposals of the running simulated-annealing run and how many were clamped, shared between clones.
+    #[cfg(feature = "argmin")]
+    pub(crate) annealing_steps: Arc<AnnealingStepCounts>,
+    /// Optional wall-clock limit for a single solver run on this sub-problem.
+    pub timeout: Option<Duration>,
+    pub convergence: ConvergenceCriteria,
+    /// Whether a run that stops early with no `ConvergenceCriteria` set must still reach a root of
+    /// the block (see `check_converged`).
+    pub root_check: bool,
+    /// Optional iteration cap overriding each solver's default.
+    pub max_iters: Option<u64>,
+    pub verbosity: Verbosity,
+    /// Optional hard model-space bounds; sub-problem params are projected onto them before every evaluation.
+    pub box_constraints: Option<BoxConstraints<N>>,
+    /// Optional structural pattern of the block Jacobian, used by the sparse solvers.
+    #[cfg(feature = "sparse")]
+    pub jacobian_sparsity: Option<JacobianSparsity>,
+    /// Iteration count and termination of the most recent solver run, shared between clones
+    /// (solvers run on a clone of the sub-problem).
+    pub(crate) last_run: Arc<Mutex<SolverRunLogData>>,
+    /// Optional memo of objective evaluations, shared between clones (see `SolveOptions::memoize_evaluations`).
+    pub(crate) eval_cache: Option<Arc<EvalCache<N>>>,
+    /// Optional Jacobian reuse with Broyden updates in Gauss-Newton.
+    pub broyden: Option<BroydenConfig>,
+    /// Last Jacobian handed to Gauss-Newton, shared between clones.
+    pub(crate) broyden_state: Arc<Mutex<Option<BroydenState>>>,
+    /// Optional progress reporting and cancellation of the solver runs (see `SolveOptions::progress`).
+    pub(crate) progress: Option<ProgressHook>,
+    /// Optional sink for per-iteration traces of the argmin solver runs (see `SolveOptions::trace`).
+    pub(crate) trace: Option<TraceSink>,
+    /// Optional collector of non-fatal issues found by the solver runs (see `SolveReport::warnings`).
+    pub(crate) warnings: Option<Warnings>,
 }
 
 impl<G64, U64, Gadfn, Uadfn, R, A, const N: usize> SubProblem<G64, U64, Gadfn, Uadfn, R, A, N>
@@ -60,6 +119,19 @@ where
     A: ResidAggHOF,
 {
     /// Creates a new SubProblem for the given solution block.
+    ///
+    /// If `tikhonov_lambda` is set, the objective is augmented with `lambda * ||x - prior||^2` over
+    /// the block's unknowns in opt space (see `TikhonovRegularization`).
+    ///
+    /// If `constraints` is set, the objective also gets one augmented-Lagrangian penalty residual
+    /// per constraint among the unknowns, using the multipliers paired with it (missing ones count
+    /// as zero).
+    ///
+    /// If `inequalities` is set, the objective also gets one penalty residual per one-sided
+    /// residual `g <= 0` (see `InequalityResiduals`).
+    ///
+    /// Fails with `EqSysError::InvalidScaling` if an initial unknown is outside the domain of its
+    /// link function under `scaling`.
     pub fn new(
         super_prob_resid_fn: &ResidualFns<G64, U64, Gadfn, Uadfn>,
         solution_block: &SolutionBlock,
@@ -68,21 +140,39 @@ where
         initial_unknowns: &U64,
         residual_scaling: R,
         residual_agg_fn_gen: A,
-        use_scaling: bool,
-    ) -> Self {
+        scaling: &ParamScaling<N>,
+        tikhonov_lambda: Option<f64>,
+        constraints: Option<(&AugmentedLagrangian<U64, Uadfn>, &[f64])>,
+        inequalities: Option<&InequalityResiduals<G64, U64, Gadfn, Uadfn>>,
+    ) -> Result<Self, EqSysError> {
         // Filter the residual functions to only those relevant to this sub-problem
         let sub_prob_res_fns = super_prob_resid_fn.filter_res_fns_to_block(solution_block);
 
+        let param_scaler = ParamScaler::try_from_scaling(scaling, initial_unknowns)?;
+        let scaling_domains = scaling.link_domains(&initial_unknowns.to_arr());
+
+        let regularization = tikhonov_lambda.map(|lambda| {
+            let priors = scaling_domains.map(|domain| domain.prior);
+            let prior_opt = param_scaler
+                .as_ref()
+                .map_or(priors, |scaler| scaler.model_to_opt(priors));
+            TikhonovRegularization {
+                lambda,
+                unknown_idxs: solution_block.unknown_idxs.clone(),
+                prior_opt: solution_block
+                    .unknown_idxs
+                    .iter()
+                    .map(|&idx| prior_opt[idx])
+                    .collect(),
+            }
+        });
+
         let loss_f64 = ObjectiveFunction::new(
             givens_f64,
             &sub_prob_res_fns.f64(),
             residual_scaling.clone(),
             residual_agg_fn_gen.clone(),
-            if use_scaling {
-                Some(ParamScaler::new_link_fns_from_priors(initial_unknowns))
-            } else {
-                None
-            },
+            param_scaler.clone(),
         );
 
         let loss_adfn = ObjectiveFunction::new(
@@ -90,35 +180,86 @@ where
             &sub_prob_res_fns.adfn_1(),
             residual_scaling,
             residual_agg_fn_gen.clone(),
-            if use_scaling {
-                Some(ParamScaler::new_link_fns_from_priors(initial_unknowns))
-            } else {
-                None
-            },
+            // the link domains were checked with the f64 scaler above
+            ParamScaler::from_scaling(scaling, initial_unknowns),
         );
 
-        let loss_fn_engine = FunctionEngine::new(loss_f64, loss_adfn, ForwardAD::new());
+        let (loss_f64, loss_adfn) = match regularization {
+            Some(reg) => (
+                loss_f64.with_regularization(reg.clone()),
+                loss_adfn.with_regularization(reg),
+            ),
+            None => (loss_f64, loss_adfn),
+        };
 
-        let param_scaler = if use_scaling {
-            Some(ParamScaler::new_link_fns_from_priors(initial_unknowns))
-        } else {
-            None
+        let (loss_f64, loss_adfn) = match constraints {
+            Some((al, multipliers)) => (
+                loss_f64.with_constraint_penalty(al.penalty_f64(multipliers)),
+                loss_adfn.with_constraint_penalty(al.penalty_adfn(multipliers)),
+            ),
+            None => (loss_f64, loss_adfn),
+        };
+
+        let (loss_f64, loss_adfn) = match inequalities {
+            Some(ineqs) => (
+                loss_f64.with_inequality_penalty(ineqs.penalty_f64()),
+                loss_adfn.with_inequality_penalty(ineqs.penalty_adfn()),
+            ),
+            None => (loss_f64, loss_adfn),
         };
 
+        #[cfg(feature = "sparse")]
+        let loss_adfn_shared = Shared::new(loss_adfn.clone());
+        let loss_fn_engine = FunctionEngine::new(loss_f64, loss_adfn, ForwardAD::new());
+
+        // Penalties and regularization move the optimum off the root of the block's equations.
+        let penalized = tikhonov_lambda.is_some_and(|lambda| lambda > 0.0)
+            || constraints.is_some()
+            || inequalities.is_some();
+
+        let raw_residual_fn = ObjectiveFunction::new(
+            givens_f64,
+            &sub_prob_res_fns.f64(),
+            ResidTransIdentity::new(sub_prob_res_fns.f64().len()),
+            ResidNoOpGaussNewton::new_subprob(solution_block),
+            None,
+        );
+
         // // Extract only the active parameters from initial_unknowns
         // let full_params_opt_space = (param_scaler.model_to_opt)(initial_unknowns.to_arr());
 
-        SubProblem {
-            loss_fn_engine: Rc::new(loss_fn_engine),
+        Ok(SubProblem {
+            loss_fn_engine: Shared::new(loss_fn_engine),
+            #[cfg(feature = "sparse")]
+            loss_adfn: loss_adfn_shared,
             // equation_idxs: solution_block.equation_idxs.clone(),
             // unknown_idxs: solution_block.unknown_idxs.clone(),
             block: solution_block.clone(),
+            raw_residual_fn,
             param_scaler,
+            scaling_domains,
             residual_agg_fn_gen,
             initial_unknowns: initial_unknowns.clone(),
             rng: Arc::new(Mutex::new(StdRng::seed_from_u64(0))),
             sa_cfg: None,
-        }
+            #[cfg(feature = "argmin")]
+            annealing_steps: Arc::default(),
+            timeout: None,
+            convergence: ConvergenceCriteria::default(),
+            root_check: solution_block.is_square() && !penalized,
+            max_iters: None,
+            verbosity: Verbosity::default(),
+            box_constraints: None,
+            #[cfg(feature = "sparse")]
+            jacobian_sparsity: None,
+            last_run: Arc::default(),
+            eval_cache: None,
+            broyden: None,
+            broyden_state: Arc::new(Mutex::new(None)),
+            progress: None,
+            trace: None,
+            warnings: None,
+        })
     }
 
     pub fn with_simulated_annealing_config(mut self, sa_config: SimulatedAnnealingConfig) -> Self {
@@ -126,6 +267,147 @@ where
         self
     }
 
+    /// Sets a wall-clock limit on solver runs. When it is reached the solver stops and returns its best params so far.
+    pub fn with_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    pub fn with_convergence_criteria(mut self, convergence: ConvergenceCriteria) -> Self {
+        self.convergence = convergence;
+        self
+    }
+
+    pub fn with_box_constraints(mut self, box_constraints: Option<BoxConstraints<N>>) -> Self {
+        self.box_constraints = box_constraints;
+        self
+    }
+
+    #[cfg(feature = "sparse")]
+    pub fn with_jacobian_sparsity(mut self, jacobian_sparsity: Option<JacobianSparsity>) -> Self {
+        self.jacobian_sparsity = jacobian_sparsity;
+        self
+    }
+
+    /// Turns the root check of `check_converged` on or off, e.g. off for a full-problem refinement,
+    /// whose equations need not share a root.
+    pub fn with_root_check(mut self, root_check: bool) -> Self {
+        self.root_check = root_check;
+        self
+    }
+
+    pub fn with_max_iters(mut self, max_iters: Option<u64>) -> Self {
+        self.max_iters = max_iters;
+        self
+    }
+
+    pub fn with_broyden(mut self, broyden: Option<BroydenConfig>) -> Self {
+        self.broyden = broyden;
+        self
+    }
+
+    pub fn with_verbosity(mut self, verbosity: Verbosity) -> Self {
+        self.verbosity = verbosity;
+        self
+    }
+
+    /// Reseeds the simulated-annealing RNG, from which both the proposals and argmin's acceptance
+    /// RNG of every later run are drawn.
+    pub fn with_rng_seed(mut self, seed: u64) -> Self {
+        self.rng = Arc::new(Mutex::new(StdRng::seed_from_u64(seed)));
+        self
+    }
+
+    /// A copy of this sub-problem that starts from `initial_unknowns`, sharing its function engines
+    /// and param scaler instead of rebuilding them.
+    ///
+    /// Its solver runs are tracked separately from this one's.
+    ///
+    /// Fails with `EqSysError::InvalidScaling` if `initial_unknowns` is outside the domain of the
+    /// scaling, which stays centered where the sub-problem was built.
+    pub fn starting_from(&self, initial_unknowns: &U64) -> Result<Self, EqSysError> {
+        check_link_domains(&self.scaling_domains, &initial_unknowns.to_arr())?;
+        Ok(Self {
+            initial_unknowns: initial_unknowns.clone(),
+            last_run: Arc::default(),
+            broyden_state: Arc::new(Mutex::new(None)),
+            ..self.clone()
+        })
+    }
+
+    pub(crate) fn with_eval_cache(mut self, eval_cache: Option<EvalCache<N>>) -> Self {
+        self.eval_cache = eval_cache.map(Arc::new);
+        self
+    }
+
+    pub(crate) fn with_progress(mut self, progress: Option<ProgressHook>) -> Self {
+        self.progress = progress;
+        self
+    }
+
+    pub(crate) fn with_trace(mut self, trace: Option<TraceSink>) -> Self {
+        self.trace = trace;
+        self
+    }
+
+    pub(crate) fn with_warnings(mut self, warnings: Option<Warnings>) -> Self {
+        self.warnings = warnings;
+        self
+    }
+
+    /// Applies the per-run settings from `SolveOptions` (time limit, convergence criteria,
+    /// iteration cap, verbosity, RNG seed, evaluation memoization, Jacobian reuse, progress
+    /// reporting, tracing, warnings).
+    pub(crate) fn with_solve_options(self, options: &SolveOptions, budget: &TimeBudget) -> Self {
+        self.with_timeout(budget.block_timeout())
+            .with_convergence_criteria(options.convergence.clone())
+            .with_max_iters(options.max_iters)
+            .with_verbosity(options.verbosity)
+            .with_rng_seed(options.rng_seed)
+            .with_eval_cache(budget.eval_cache())
+            .with_broyden(options.broyden.clone())
+            .with_progress(budget.progress_hook())
+            .with_trace(budget.trace_sink())
+            .with_warnings(Some(budget.warnings()))
+    }
+
+    /// Tells the progress callback, if any, that a run of `solver` starts on this sub-problem.
+    pub(crate) fn report_solver_started(&self, solver: &str) {
+        if let Some(progress) = &self.progress {
+            progress.solver_started(self.block.block_idx, solver);
+        }
+    }
+
+    /// Records `warning` in the solve's report, if this sub-problem runs under `solve_system`.
+    pub(crate) fn warn(&self, warning: SolveWarning) {
+        if let Some(warnings) = &self.warnings {
+            warnings.push(warning);
+        }
+    }
+
+    /// Iteration count of the most recent solver run on this sub-problem, if the solver reported one.
+    pub fn last_iterations(&self) -> Option<u64> {
+        self.last_run().iterations
+    }
+
+    /// Iteration count and termination of the most recent solver run on this sub-problem, as far as
+    /// the solver reported them.
+    pub fn last_run(&self) -> SolverRunLogData {
+        *self.last_run.lock().unwrap()
+    }
+
+    pub(crate) fn set_last_run(&self, iterations: u64, termination: BlockTermination) {
+        *self.last_run.lock().unwrap() = SolverRunLogData {
+            iterations: Some(iterations),
+            termination: Some(termination),
+        };
+    }
+
+    /// The iteration cap for a solver whose own default is `default`.
+    pub(crate) fn max_iters_or(&self, default: u64) -> u64 {
+        self.max_iters.unwrap_or(default)
+    }
+
     /// Converts a full-problem parameter vector from optimization space to model space
     pub fn optspace_to_modspace(&self, opt_params: &[f64; N]) -> [f64; N] {
         if let Some(param_scaling) = &self.param_scaler {
@@ -178,12 +460,10 @@ where
 
     /// makes a vector of the initial parameters relevant to this sub-problem in opt space
     pub fn subprob_initial_params_optspace(&self) -> DVector<f64> {
+        let full = self.fullprob_initial_params_optspace();
         DVector::from_iterator(
             self.block.unknown_idxs.len(),
-            self.block
-                .unknown_idxs
-                .iter()
-                .map(|&idx| self.fullprob_initial_params_optspace()[idx]),
+            self.block.unknown_idxs.iter().map(|&idx| full[idx]),
         )
     }
 
@@ -193,7 +473,7 @@ where
     }
 
     /// Reconstructs unknown params struct by patching in optimized sub-problem parameters into initial parameter set.
-    pub fn params_with_subprob_optimizer_result(&self, p_opt: &Vec<f64>) -> U64 {
+    pub fn params_with_subprob_optimizer_result(&self, p_opt: &[f64]) -> U64 {
         let full_optspace = self.optspace_fullprob_input_from_subprob_input(p_opt);
 
         let full_modspace = self.optspace_to_modspace(&full_optspace);
@@ -202,10 +482,7 @@ where
     }
 
     /// The `argmin` optimizer uses only the number of active parameters for this sub-problem. Before handing inputs fromt the optimizer into the residual functions, we need to reconstruct the full opt space parameter vector. This function does that.
-    pub fn optspace_fullprob_input_from_subprob_input(
-        &self,
-        opt_space_inputs: &Vec<f64>,
-    ) -> [f64; N] {
+    pub fn optspace_fullprob_input_from_subprob_input(&self, opt_space_inputs: &[f64]) -> [f64; N] {
         debug_assert!(
             opt_space_inputs.len() == self.block.unknown_idxs.len(),
             "Parameter vector length ({}) for reconstruction did not match number subproblem unknowns ({})",
@@ -217,9 +494,88 @@ where
 
         // overwrite the intial opt space params with the inputs relevant to this sub-problem
         for (i, &idx) in self.block.unknown_idxs.iter().enumerate() {
-            full_params[idx] = opt_space_inputs[i].clone();
+            full_params[idx] = opt_space_inputs[i];
         }
-        full_params
+        self.project_onto_box_constraints(full_params)
+    }
+
+    /// Clamps this sub-problem's unknowns of a full opt-space parameter vector to the box
+    /// constraints (in model space).
+    ///
+    /// A bound on the edge of an unknown's link domain (e.g. a lower bound of 0 under a log link)
+    /// is nudged just inside the domain, so that the clamped value maps to a finite opt-space
+    /// value; only an unknown that still doesn't is left as it was.
+    fn project_onto_box_constraints(&self, full_opt: [f64; N]) -> [f64; N] {
+        let Some(bounds) = &self.box_constraints else {
+            return full_opt;
+        };
+        let mut full_model = self.optspace_to_modspace(&full_opt);
+        let mut clamped = false;
+        for &idx in &self.block.unknown_idxs {
+            if !bounds.contains(idx, full_model[idx]) {
+                full_model[idx] =
+                    self.scaling_domains[idx].nudged_inside(bounds.clamp(idx, full_model[idx]));
+                clamped = true;
+            }
+        }
+        if !clamped {
+            return full_opt;
+        }
+
+        let mut projected = self.modspace_to_optspace(&full_model);
+        for &idx in &self.block.unknown_idxs {
+            if !projected[idx].is_finite() {
+                projected[idx] = full_opt[idx];
+            }
+        }
+        projected
+    }
+
+    /// Projects sub-problem opt-space params onto the box constraints, if any.
+    pub fn project_subprob_params(&self, p: &DVector<f64>) -> DVector<f64> {
+        if self.box_constraints.is_none() {
+            return p.clone();
+        }
+        let full_opt = self.optspace_fullprob_input_from_subprob_input(p.as_slice());
+        DVector::from_vec(self.select_subprob_items(&full_opt))
+    }
+}
+
+impl<G, U, R, A, const N: usize> SubProblemFor<G, U, R, A, N>
+where
+    G: GivenParamsFor<f64, N> + ToAdParams,
+    U: UnknownParamsFor<f64, N> + ParamFamily,
+    AdfnFor<G>: GivenParamsFor<adfn<1>, N>,
+    AdfnFor<U>: UnknownParamsFor<adfn<1>, N>,
+    R: ResidTransHOF,
+    A: ResidAggHOF,
+{
+    /// Like `new`, deriving the `adfn<1>` givens from the f64 ones (see `ToAdParams`).
+    pub fn new_from_f64(
+        super_prob_resid_fn: &ResidualFnsFor<G, U>,
+        solution_block: &SolutionBlock,
+        givens_f64: &G,
+        initial_unknowns: &U,
+        residual_scaling: R,
+        residual_agg_fn_gen: A,
+        scaling: &ParamScaling<N>,
+        tikhonov_lambda: Option<f64>,
+        constraints: Option<(&AugmentedLagrangian<U, AdfnFor<U>>, &[f64])>,
+        inequalities: Option<&InequalityResiduals<G, U, AdfnFor<G>, AdfnFor<U>>>,
+    ) -> Result<Self, EqSysError> {
+        Self::new(
+            super_prob_resid_fn,
+            solution_block,
+            givens_f64,
+            &givens_f64.to_ad_params::<adfn<1>>(),
+            initial_unknowns,
+            residual_scaling,
+            residual_agg_fn_gen,
+            scaling,
+            tikhonov_lambda,
+            constraints,
+            inequalities,
+        )
     }
 }
 
@@ -232,23 +588,89 @@ where
     R: ResidTransHOF,
     A: ResidAggHOF,
 {
-    pub fn initial_params_cost(&self) -> Result<f64, ArgminError> {
+    /// Evaluates the objective outputs at sub-problem opt-space params `p`.
+    ///
+    /// For scalar aggregations this is a single entry holding the cost; for `ResidNoOpGaussNewton`
+    /// it has one entry per (transformed) residual, plus any penalty outputs.
+    pub fn outputs_optspace(&self, p: &DVector<f64>) -> Result<DVector<f64>, EqSysError> {
+        self.check_subprob_param_len(p)?;
+        let p_full = self.optspace_fullprob_input_from_subprob_input(p.as_slice());
+        Ok(DVector::from_vec(self.call_fullprob_optspace(&p_full)))
+    }
+
+    /// Jacobian of `outputs_optspace` w.r.t. the sub-problem opt-space params.
+    pub fn jacobian_optspace(&self, p: &DVector<f64>) -> Result<DMatrix<f64>, EqSysError> {
+        self.check_subprob_param_len(p)?;
+        let p_full = self.optspace_fullprob_input_from_subprob_input(p.as_slice());
+        let (_values, full_jacobian) = self.derivative_fullprob_optspace(&p_full);
+        Ok(self.select_subprob_jacobian(&full_jacobian))
+    }
+
+    /// Objective outputs at full-problem opt-space params, through the evaluation cache if there is one.
+    pub fn call_fullprob_optspace(&self, p_full: &[f64; N]) -> Vec<f64> {
+        match &self.eval_cache {
+            Some(cache) => cache.outputs(p_full, || self.loss_fn_engine.call(p_full)),
+            None => self.loss_fn_engine.call(p_full),
+        }
+    }
+
+    /// Objective outputs and their Jacobian w.r.t. full-problem opt-space params, through the
+    /// evaluation cache if there is one.
+    pub fn derivative_fullprob_optspace(
+        &self,
+        p_full: &[f64; N],
+    ) -> (Vec<f64>, Matrix<f64, Dyn, Dyn, VecStorage<f64, Dyn, Dyn>>) {
+        match &self.eval_cache {
+            Some(cache) => cache.derivative(p_full, || self.loss_fn_engine.derivative(p_full)),
+            None => self.loss_fn_engine.derivative(p_full),
+        }
+    }
+
+    /// Norm of the opt-space gradient of the block objective at sub-problem params `p`.
+    ///
+    /// The objective is the aggregated cost, or for `ResidNoOpGaussNewton` the sum of squared outputs.
+    pub fn subprob_grad_norm(&self, p: &DVector<f64>) -> f64 {
+        let p_full_opt = self.optspace_fullprob_input_from_subprob_input(p.as_slice());
+        let (values, full_jacobian) = self.derivative_fullprob_optspace(&p_full_opt);
+        self.residual_agg_fn_gen
+            .objective_gradient_f64(&values, &self.select_subprob_jacobian(&full_jacobian))
+            .norm()
+    }
+
+    pub(crate) fn check_subprob_param_len(&self, p: &DVector<f64>) -> Result<(), EqSysError> {
+        if p.len() != self.block.unknown_idxs.len() {
+            return Err(EqSysError::SubProblemParamLenMismatch {
+                n_params: p.len(),
+                n_unknowns: self.block.unknown_idxs.len(),
+            });
+        }
+        Ok(())
+    }
+
+    pub fn initial_params_cost(&self) -> Result<f64, EqSysError> {
         let init_params = self.subprob_initial_params_optspace();
-        let resids = self.apply(&init_params)?;
-        Ok(self
-            .residual_agg_fn_gen
-            .scalar_cost_f64(resids.as_slice().to_vec()))
+        let resids = self.outputs_optspace(&init_params)?;
+        Ok(self.residual_agg_fn_gen.scalar_cost_f64(resids.data.into()))
     }
 
+    /// Logs the loss, gradient and cost at the initial params at `Verbosity::Debug`.
     pub fn print_initial_loss(&self) {
+        if self.verbosity < Verbosity::Debug {
+            return;
+        }
         let subprob_params = self.subprob_initial_params_optspace();
         let full_params =
-            self.optspace_fullprob_input_from_subprob_input(&subprob_params.as_slice().to_vec());
-        let loss = self.loss_fn_engine.derivative(&full_params);
-        println!("Loss and gradient for first sub-problem: {:?}", loss);
+            self.optspace_fullprob_input_from_subprob_input(subprob_params.as_slice());
+        let loss = self.derivative_fullprob_optspace(&full_params);
+        solver_debug!(
+            self.verbosity,
+            "Loss and gradient for sub-problem {}: {:?}",
+            self.block.block_idx,
+            loss
+        );
         match self.initial_params_cost() {
-            Ok(cost) => println!("Initial cost for sub-problem: {}", cost),
-            Err(e) => println!("Error computing initial cost: {}", e),
+            Ok(cost) => solver_debug!(self.verbosity, "Initial cost for sub-problem: {}", cost),
+            Err(e) => solver_debug!(self.verbosity, "Error computing initial cost: {}", e),
         }
     }
 }
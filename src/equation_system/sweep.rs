@@ -0,0 +1,100 @@
+use ad_trait::{AD, forward_ad::adfn::adfn};
+use field_names_and_counts::FieldNames;
+use struct_to_array::StructToArray;
+
+use crate::{
+    equation_system::{EqSysSolutionPlan, EquationSystemBuilder},
+    prelude::*,
+};
+
+/// One step of a parameter sweep: the value the swept given was set to, and the solve at that value.
+#[derive(Clone, Debug)]
+pub struct SweepPoint<U> {
+    pub given_value: f64,
+    pub report: SolveReport<U>,
+}
+
+impl<U> SweepPoint<U> {
+    pub fn solution(&self) -> &U {
+        &self.report.solution
+    }
+}
+
+impl<G64, U64, Gadfn, Uadfn, const N: usize>
+    EquationSystemBuilder<G64, U64, Gadfn, Uadfn, EqSysSolutionPlan, N>
+where
+    G64: GivenParamsFor<f64, N> + FieldNames,
+    U64: UnknownParamsFor<f64, N>,
+    Gadfn: GivenParamsFor<adfn<1>, N>,
+    Uadfn: UnknownParamsFor<adfn<1>, N>,
+{
+    /// Sets the given named `field` to each of `values` in turn and solves the system,
+    /// warm-starting every step from the previous step's solution (the first from
+    /// `initial_unknowns`).
+    ///
+    /// Returns the path of solutions, e.g. for design charts like "required thrust vs. desired max
+    /// air speed". Small steps between consecutive values keep each solve close to its starting
+    /// point. The plan is reused throughout, and the original givens are restored afterwards, also
+    /// when a step fails.
+    pub fn sweep_given<const NG: usize>(
+        &mut self,
+        field: &str,
+        values: &[f64],
+        initial_unknowns: &U64,
+    ) -> Result<Vec<SweepPoint<U64>>, EqSysError>
+    where
+        G64: StructToArray<f64, NG>,
+        Gadfn: StructToArray<adfn<1>, NG>,
+    {
+        self.sweep_given_with_options(field, values, initial_unknowns, &SolveOptions::default())
+    }
+
+    /// Like `sweep_given`, but solving every step with `solve_system_with_options`.
+    pub fn sweep_given_with_options<const NG: usize>(
+        &mut self,
+        field: &str,
+        values: &[f64],
+        initial_unknowns: &U64,
+        options: &SolveOptions,
+    ) -> Result<Vec<SweepPoint<U64>>, EqSysError>
+    where
+        G64: StructToArray<f64, NG>,
+        Gadfn: StructToArray<adfn<1>, NG>,
+    {
+        let idx = G64::FIELDS
+            .iter()
+            .position(|&name| name == field)
+            .ok_or_else(|| EqSysError::UnknownParamField {
+                field: field.to_string(),
+            })?;
+        let original_givens = (self.givens_f64, self.givens_adfn);
+
+        let mut points = Vec::with_capacity(values.len());
+        let mut current_unknowns = *initial_unknowns;
+        let mut result = Ok(());
+        for &value in values {
+            let mut givens_arr = original_givens.0.to_arr();
+            givens_arr[idx] = value;
+            self.set_givens(
+                G64::from_arr(givens_arr),
+                Gadfn::from_arr(givens_arr.map(adfn::<1>::constant)),
+            );
+            match self.solve_system_with_options(&current_unknowns, options) {
+                Ok(report) => {
+                    current_unknowns = report.solution;
+                    points.push(SweepPoint {
+                        given_value: value,
+                        report,
+                    });
+                }
+                Err(e) => {
+                    result = Err(e);
+                    break;
+                }
+            }
+        }
+
+        self.set_givens(original_givens.0, original_givens.1);
+        result.map(|()| points)
+    }
+}
@@ -0,0 +1,118 @@
+use argmin::core::Gradient;
+use nalgebra::DVector;
+
+use crate::prelude::{ad_trait::AD, *};
+
+#[solver_params]
+#[derive(Clone, Copy, Debug)]
+struct Givens<T> {
+    a: T,
+    b: T,
+}
+
+#[solver_params]
+#[derive(Clone, Copy, Debug)]
+struct Unknowns<T> {
+    x: T,
+    y: T,
+}
+
+fn x_is_a<T: AD>(g: &Givens<T>, u: &Unknowns<T>) -> T {
+    u.x - g.a
+}
+
+fn x_is_b<T: AD>(g: &Givens<T>, u: &Unknowns<T>) -> T {
+    u.x - g.b
+}
+
+fn x_is_b_again<T: AD>(g: &Givens<T>, u: &Unknowns<T>) -> T {
+    u.x - g.b
+}
+
+/// Minimizes the squared residuals of `x = 2`, `x = 4` and `x = 4` (again), combined by `agg`, with L-BFGS.
+fn solve_x<A: ResidAggFnToScalarGen>(agg: A) -> f64 {
+    let res_fns = residual_fns_for_generic_params!(Givens, Unknowns; x_is_a, x_is_b, x_is_b_again);
+    let block = SolutionBlock {
+        block_idx: 0,
+        equation_idxs: vec![0, 1, 2],
+        unknown_idxs: vec![0],
+    };
+    let sub_problem = SubProblem::new_from_f64(
+        &res_fns,
+        &block,
+        &Givens { a: 2.0, b: 4.0 },
+        &Unknowns { x: 0.0, y: 0.0 },
+        ResidTransUnscaledL2 { n: 3 },
+        agg,
+        &ParamScaling::Unscaled,
+        None,
+        None,
+        None,
+    )
+    .unwrap();
+    sub_problem.solve_lbfgs().unwrap().x
+}
+
+#[test]
+fn sum_of_squares_is_the_least_squares_solution() {
+    let x = solve_x(ResidAggSum);
+
+    assert!((x - 10.0 / 3.0).abs() < 1e-4, "{x}");
+}
+
+#[test]
+fn weighted_sum_pulls_toward_the_heavier_equation() {
+    // 4 (x - 2)^2 + (x - 4)^2 + (x - 4)^2
+    let x = solve_x(ResidAggWeightedSum::new(vec![4.0, 1.0, 1.0]));
+
+    assert!((x - 8.0 / 3.0).abs() < 1e-4, "{x}");
+}
+
+#[test]
+fn p_norm_weighs_the_largest_residuals_more() {
+    // minimizes (x - 2)^4 + 2 (x - 4)^4, i.e. (x - 2)^3 = 2 (4 - x)^3
+    let x = solve_x(ResidAggPNorm::new(2.0));
+
+    let c = 2f64.cbrt();
+    assert!((x - (2.0 + 4.0 * c) / (1.0 + c)).abs() < 1e-3, "{x}");
+}
+
+#[test]
+fn errors_raised_inside_argmin_come_back_typed() {
+    let res_fns = residual_fns_for_generic_params!(Givens, Unknowns; x_is_a, x_is_b, x_is_b_again);
+    let block = SolutionBlock {
+        block_idx: 0,
+        equation_idxs: vec![0, 1, 2],
+        unknown_idxs: vec![0],
+    };
+    let sub_problem = SubProblem::new_from_f64(
+        &res_fns,
+        &block,
+        &Givens { a: 2.0, b: 4.0 },
+        &Unknowns { x: 0.0, y: 0.0 },
+        ResidTransUnscaledL2 { n: 3 },
+        ResidAggSum,
+        &ParamScaling::Unscaled,
+        None,
+        None,
+        None,
+    )
+    .unwrap();
+
+    // one param too many for the block's single unknown
+    let error: EqSysError = sub_problem
+        .gradient(&DVector::from_vec(vec![0.0, 0.0]))
+        .unwrap_err()
+        .into();
+
+    assert!(
+        matches!(
+            error,
+            EqSysError::SubProblemParamLenMismatch {
+                n_params: 2,
+                n_unknowns: 1
+            }
+        ),
+        "{error:?}"
+    );
+}
@@ -0,0 +1,96 @@
+#[cfg(feature = "argmin")]
+use crate::prelude::ad_trait::AD;
+use crate::{
+    equation_system::sub_problem::solve_subproblem::simulated_annealing::SimulatedAnnealingConfig,
+    prelude::*,
+};
+
+#[cfg(feature = "argmin")]
+#[solver_params]
+#[derive(Clone, Copy, Debug)]
+struct Givens<T> {
+    a: T,
+    b: T,
+}
+
+#[cfg(feature = "argmin")]
+#[solver_params]
+#[derive(Clone, Copy, Debug)]
+struct Unknowns<T> {
+    x: T,
+    y: T,
+}
+
+#[cfg(feature = "argmin")]
+fn x_is_a<T: AD>(g: &Givens<T>, u: &Unknowns<T>) -> T {
+    u.x - g.a
+}
+
+#[cfg(feature = "argmin")]
+fn y_is_b<T: AD>(g: &Givens<T>, u: &Unknowns<T>) -> T {
+    u.y - g.b
+}
+
+#[test]
+fn presets_are_valid() {
+    for config in [
+        SimulatedAnnealingConfig::log_space_default(),
+        SimulatedAnnealingConfig::aggressive(),
+        SimulatedAnnealingConfig::conservative(),
+    ] {
+        config.validate().unwrap();
+    }
+}
+
+#[test]
+fn builder_names_the_first_invalid_field() {
+    let result = SimulatedAnnealingConfig::builder()
+        .with_small_step(0.1, 0.2)
+        .with_p_big(1.5, 0.1)
+        .build();
+
+    assert!(
+        matches!(
+            result,
+            Err(EqSysError::InvalidAnnealingConfig {
+                field: "small_step_init",
+                ..
+            })
+        ),
+        "{result:?}"
+    );
+}
+
+#[cfg(feature = "argmin")]
+#[test]
+fn built_config_anneals_a_sub_problem() {
+    let config = SimulatedAnnealingConfig::builder()
+        .with_small_step(0.2, 0.01)
+        .with_max_abs_step(1.0)
+        .build()
+        .unwrap();
+    let res_fns = residual_fns_for_generic_params!(Givens, Unknowns; x_is_a, y_is_b);
+    let block = SolutionBlock {
+        block_idx: 0,
+        equation_idxs: vec![0],
+        unknown_idxs: vec![0],
+    };
+    let sub_problem = SubProblem::new_from_f64(
+        &res_fns,
+        &block,
+        &Givens { a: 2.0, b: 3.0 },
+        &Unknowns { x: 0.0, y: 0.0 },
+        ResidTransUnscaledL2 { n: 1 },
+        ResidAggSum,
+        &ParamScaling::Unscaled,
+        None,
+        None,
+        None,
+    )
+    .unwrap()
+    .with_simulated_annealing_config(config);
+
+    let x = sub_problem.solve_simulated_annealing().unwrap().x;
+
+    assert!((x - 2.0).abs() < 0.1, "{x}");
+}
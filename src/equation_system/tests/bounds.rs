@@ -0,0 +1,81 @@
+use crate::prelude::{ad_trait::AD, *};
+
+#[solver_params]
+#[derive(Clone, Copy, Debug)]
+struct Givens<T> {
+    a: T,
+    b: T,
+}
+
+#[solver_params]
+#[derive(Clone, Copy, Debug)]
+struct Unknowns<T> {
+    x: T,
+    y: T,
+}
+
+/// Roots at `x = a` and `x = -3 b`.
+fn two_roots<T: AD>(g: &Givens<T>, u: &Unknowns<T>) -> T {
+    (u.x - g.a) * (u.x + g.b * T::constant(3.0))
+}
+
+fn y_eq<T: AD>(g: &Givens<T>, u: &Unknowns<T>) -> T {
+    u.y - g.b
+}
+
+#[test]
+fn bounded_unknown_settles_on_the_root_inside_its_bounds() {
+    let initial = Unknowns { x: -1.5, y: 1.0 };
+    let eq_sys = EquationSystemBuilder::new_from_f64(
+        Givens { a: 2.0, b: 2.0 },
+        residual_fns_for_generic_params!(Givens, Unknowns; two_roots, y_eq),
+    )
+    .unwrap()
+    // a plain Newton step from -1.5 overshoots far past x = 2; the logit link can't leave (-30, -1)
+    .with_param_bounds(&Unknowns {
+        x: ParamBounds::new(-30.0, -1.5, -1.0),
+        y: ParamBounds::new(f64::NEG_INFINITY, 1.0, f64::INFINITY),
+    })
+    .unwrap()
+    .with_triangularization(&initial)
+    .unwrap();
+
+    let options = SolveOptions::default().with_verbosity(Verbosity::Quiet);
+    let report = eq_sys
+        .solve_system_with_options(&initial, &options)
+        .unwrap();
+
+    assert!((report.solution.x + 6.0).abs() < 1e-6, "{report:?}");
+    assert!((report.solution.y - 2.0).abs() < 1e-6, "{report:?}");
+}
+
+/// Roots at `x = a` and `x = -b`.
+fn positive_and_negative_root<T: AD>(g: &Givens<T>, u: &Unknowns<T>) -> T {
+    (u.x - g.a) * (u.x + g.b)
+}
+
+#[test]
+fn lower_bound_keeps_the_log_linked_unknown_positive() {
+    let initial = Unknowns { x: 0.2, y: 1.0 };
+    let eq_sys = EquationSystemBuilder::new_from_f64(
+        Givens { a: 2.0, b: 1.0 },
+        residual_fns_for_generic_params!(Givens, Unknowns; positive_and_negative_root, y_eq),
+    )
+    .unwrap()
+    // a plain Newton step from 0.2 lands at -3.4, in the basin of x = -1
+    .with_param_bounds(&Unknowns {
+        x: ParamBounds::new(0.0, 1.0, f64::INFINITY),
+        y: ParamBounds::new(f64::NEG_INFINITY, 1.0, f64::INFINITY),
+    })
+    .unwrap()
+    .with_triangularization(&initial)
+    .unwrap();
+
+    let options = SolveOptions::default().with_verbosity(Verbosity::Quiet);
+    let report = eq_sys
+        .solve_system_with_options(&initial, &options)
+        .unwrap();
+
+    assert!((report.solution.x - 2.0).abs() < 1e-6, "{report:?}");
+    assert!((report.solution.y - 1.0).abs() < 1e-6, "{report:?}");
+}
@@ -0,0 +1,62 @@
+use crate::{
+    equation_system::sub_problem::solve_subproblem::broyden::BroydenConfig,
+    prelude::{ad_trait::AD, *},
+};
+
+#[solver_params]
+#[derive(Clone, Copy, Debug)]
+struct Givens<T> {
+    a: T,
+    b: T,
+}
+
+#[solver_params]
+#[derive(Clone, Copy, Debug)]
+struct Unknowns<T> {
+    x: T,
+    y: T,
+}
+
+fn sum_eq<T: AD>(g: &Givens<T>, u: &Unknowns<T>) -> T {
+    u.x + u.y - g.a
+}
+
+fn cubic_eq<T: AD>(g: &Givens<T>, u: &Unknowns<T>) -> T {
+    u.x * u.x * u.x + u.y - g.b
+}
+
+#[test]
+fn broyden_updates_reach_the_same_solution() {
+    let initial = Unknowns { x: 1.0, y: 1.0 };
+    let eq_sys = EquationSystemBuilder::new_from_f64(
+        Givens { a: 3.0, b: 9.0 },
+        residual_fns_for_generic_params!(Givens, Unknowns; sum_eq, cubic_eq),
+    )
+    .unwrap()
+    .with_scaling_specs(&Unknowns {
+        x: ScalingSpec::Identity,
+        y: ScalingSpec::Identity,
+    })
+    .unwrap()
+    .with_triangularization(&initial)
+    .unwrap();
+    let solve = |options: SolveOptions| {
+        let options = options
+            .with_refinement(RefinementConfig::skipped())
+            .with_verbosity(Verbosity::Quiet);
+        eq_sys
+            .solve_system_with_options(&initial, &options)
+            .unwrap()
+    };
+
+    // x^3 - x - 6 = (x - 2)(x^2 + 2x + 3) has the one real root x = 2
+    let plain = solve(SolveOptions::default());
+    let broyden = solve(SolveOptions::default().with_broyden(BroydenConfig::default()));
+
+    assert_eq!(broyden.blocks.len(), 1);
+    assert_eq!(broyden.blocks[0].solver, BlockSolverKind::GaussNewton);
+    for report in [&plain, &broyden] {
+        assert!((report.solution.x - 2.0).abs() < 1e-6, "{report:?}");
+        assert!((report.solution.y - 1.0).abs() < 1e-6, "{report:?}");
+    }
+}
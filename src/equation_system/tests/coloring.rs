@@ -0,0 +1,98 @@
+use ad_trait::forward_ad::adfn::adfn;
+use nalgebra::DMatrix;
+
+use crate::prelude::{ad_trait::AD, *};
+
+#[solver_params]
+#[derive(Clone, Copy, Debug)]
+struct Givens<T> {
+    a: T,
+    b: T,
+}
+
+#[solver_params]
+#[derive(Clone, Copy, Debug)]
+struct Unknowns<T> {
+    x: T,
+    y: T,
+    z: T,
+}
+
+fn x_sq<T: AD>(g: &Givens<T>, u: &Unknowns<T>) -> T {
+    u.x * u.x - g.a
+}
+
+fn y_cubed<T: AD>(g: &Givens<T>, u: &Unknowns<T>) -> T {
+    u.y * u.y * u.y - g.b
+}
+
+fn y_plus_z<T: AD>(_g: &Givens<T>, u: &Unknowns<T>) -> T {
+    u.y + u.z
+}
+
+/// No two columns of one color share a row.
+fn assert_structurally_orthogonal(coloring: &ColumnColoring, row_cols: &[Vec<usize>]) {
+    for cols in row_cols {
+        for (i, &c1) in cols.iter().enumerate() {
+            for &c2 in &cols[i + 1..] {
+                assert_ne!(coloring.colors()[c1], coloring.colors()[c2]);
+            }
+        }
+    }
+}
+
+#[test]
+fn diagonal_pattern_needs_one_color() {
+    let coloring = ColumnColoring::from_incidence(&DMatrix::<f32>::identity(4, 4));
+
+    assert_eq!(coloring.n_colors(), 1);
+    assert_eq!(coloring.color_groups(), vec![vec![0, 1, 2, 3]]);
+}
+
+#[test]
+fn dense_row_needs_one_color_per_column() {
+    let coloring = ColumnColoring::from_incidence(&DMatrix::<f32>::from_element(1, 3, 1.0));
+
+    assert_eq!(coloring.n_colors(), 3);
+}
+
+#[test]
+fn tridiagonal_pattern_colors_densest_columns_first() {
+    let row_cols = vec![vec![0, 1], vec![0, 1, 2], vec![1, 2, 3], vec![2, 3]];
+    let coloring = ColumnColoring::from_row_cols(4, row_cols.clone());
+
+    // columns 1 and 2 appear in three rows, so they get the first two colors
+    assert_eq!(coloring.colors(), &[2, 0, 1, 2]);
+    assert_eq!(coloring.color_groups(), vec![vec![1], vec![2], vec![0, 3]]);
+    assert_structurally_orthogonal(&coloring, &row_cols);
+}
+
+#[test]
+fn nan_entries_count_as_structural_nonzeros() {
+    let incidence = DMatrix::from_row_slice(1, 2, &[f32::NAN, 1.0]);
+
+    assert_eq!(ColumnColoring::from_incidence(&incidence).n_colors(), 2);
+}
+
+#[test]
+fn colored_derivative_matches_the_dense_jacobian() {
+    let givens = Givens { a: 1.0, b: 1.0 }.to_ad_params::<adfn<1>>();
+    let res_fns = residual_fns_for_generic_params!(Givens, Unknowns; x_sq, y_cubed, y_plus_z);
+    let row_cols = vec![vec![0], vec![1], vec![1, 2]];
+    let coloring = ColumnColoring::from_row_cols(3, row_cols.clone());
+    // x and y share no row; z shares one with y
+    assert_eq!(coloring.n_colors(), 2);
+    assert_structurally_orthogonal(&coloring, &row_cols);
+
+    let (values, jacobian) = coloring.derivative::<_, Unknowns<adfn<1>>, 1, 3>(
+        &givens,
+        res_fns.adfn_1(),
+        &[2.0, 3.0, 5.0],
+    );
+
+    assert_eq!(values, vec![3.0, 26.0, 8.0]);
+    assert_eq!(
+        jacobian,
+        DMatrix::from_row_slice(3, 3, &[4.0, 0.0, 0.0, 0.0, 27.0, 0.0, 0.0, 1.0, 1.0])
+    );
+}
@@ -0,0 +1,55 @@
+use nalgebra::{Complex, DMatrix};
+
+use crate::prelude::*;
+
+#[solver_params]
+#[derive(Clone, Copy, Debug)]
+struct Givens<T> {
+    a: T,
+    b: T,
+}
+
+#[solver_params]
+#[derive(Clone, Copy, Debug)]
+struct Unknowns<T> {
+    x: T,
+    y: T,
+}
+
+fn x_sq(g: &Givens<f64>, u: &[Complex<f64>; 2]) -> Complex<f64> {
+    u[0] * u[0] - g.a
+}
+
+fn y_from_x(g: &Givens<f64>, u: &[Complex<f64>; 2]) -> Complex<f64> {
+    u[1] - u[0] * g.b
+}
+
+#[test]
+fn complex_step_residuals_are_solved() {
+    let initial = Unknowns { x: 1.0, y: 1.0 };
+    let eq_sys = EquationSystemBuilder::new_from_f64(
+        Givens { a: 4.0, b: 3.0 },
+        complex_step_residual_fns!(Givens, Unknowns; x_sq, y_from_x),
+    )
+    .unwrap()
+    .with_triangularization(&initial)
+    .unwrap();
+
+    let (values, jacobian) = eq_sys.full_derivative(&[2.0, 6.0]);
+    assert_eq!(values, vec![0.0, 0.0]);
+    assert_eq!(
+        jacobian,
+        DMatrix::from_row_slice(2, 2, &[4.0, 0.0, -3.0, 1.0])
+    );
+
+    let report = eq_sys
+        .solve_system_with_options(
+            &initial,
+            &SolveOptions::default().with_verbosity(Verbosity::Quiet),
+        )
+        .unwrap();
+
+    assert_eq!(report.blocks.len(), 2);
+    assert!((report.solution.x - 2.0).abs() < 1e-6, "{report:?}");
+    assert!((report.solution.y - 6.0).abs() < 1e-6, "{report:?}");
+}
@@ -0,0 +1,61 @@
+use crate::prelude::{ad_trait::AD, *};
+
+#[solver_params]
+#[derive(Clone, Copy, Debug)]
+struct Givens<T> {
+    a: T,
+    b: T,
+}
+
+#[solver_params]
+#[derive(Clone, Copy, Debug)]
+struct Unknowns<T> {
+    x: T,
+    y: T,
+}
+
+fn sum_eq<T: AD>(g: &Givens<T>, u: &Unknowns<T>) -> T {
+    u.x + u.y - g.a
+}
+
+fn product_eq<T: AD>(g: &Givens<T>, u: &Unknowns<T>) -> T {
+    u.x * u.y - g.b
+}
+
+#[test]
+fn block_conditioning_reaches_the_solve_report() {
+    let initial = Unknowns { x: 0.5, y: 3.0 };
+    let eq_sys = EquationSystemBuilder::new_from_f64(
+        Givens { a: 3.0, b: 2.0 },
+        residual_fns_for_generic_params!(Givens, Unknowns; sum_eq, product_eq),
+    )
+    .unwrap()
+    .with_scaling_specs(&Unknowns {
+        x: ScalingSpec::Identity,
+        y: ScalingSpec::Identity,
+    })
+    .unwrap()
+    .with_triangularization(&initial)
+    .unwrap()
+    // the Jacobian rows (1, 1) and (y, x) are parallel where x = y
+    .with_block_conditioning(&Unknowns { x: 1.0, y: 1.0 });
+
+    let options = SolveOptions::default().with_verbosity(Verbosity::Quiet);
+    let report = eq_sys
+        .solve_system_with_options(&initial, &options)
+        .unwrap();
+
+    assert_eq!(report.blocks.len(), 1);
+    let conditioning = report.blocks[0].conditioning.as_ref().unwrap();
+    assert!(conditioning.is_near_singular(), "{conditioning:?}");
+    assert_eq!((conditioning.rank, conditioning.full_rank), (1, 2));
+    // both roots, (1, 2) and (2, 1), are well-conditioned
+    assert!(report.residual_norm() < 1e-6, "{report:?}");
+    assert!(
+        !report
+            .warnings
+            .iter()
+            .any(|w| matches!(w, SolveWarning::NearSingularJacobian { .. })),
+        "{report:?}"
+    );
+}
@@ -0,0 +1,152 @@
+use ad_trait::forward_ad::adfn::adfn;
+
+use crate::prelude::{ad_trait::AD, *};
+
+#[solver_params]
+#[derive(Clone, Copy, Debug)]
+struct Givens<T> {
+    a: T,
+    b: T,
+}
+
+#[solver_params]
+#[derive(Clone, Copy, Debug)]
+struct Unknowns<T> {
+    x: T,
+    y: T,
+}
+
+fn x_eq<T: AD>(g: &Givens<T>, u: &Unknowns<T>) -> T {
+    u.x - g.a
+}
+
+fn y_eq<T: AD>(g: &Givens<T>, u: &Unknowns<T>) -> T {
+    u.y - g.b
+}
+
+fn sum_is<T: AD>(total: f64) -> impl Fn(&Unknowns<T>) -> T {
+    move |u| u.x + u.y - T::constant(total)
+}
+
+fn y_minus_x<T: AD>(u: &Unknowns<T>) -> T {
+    u.y - u.x
+}
+
+type Constraints = UnknownConstraints<Unknowns<f64>, Unknowns<adfn<1>>>;
+
+fn constraints(total: f64) -> Constraints {
+    UnknownConstraints::new()
+        .equality("sum", sum_is::<f64>(total), sum_is::<adfn<1>>(total))
+        .non_negative("y_above_x", y_minus_x::<f64>, y_minus_x::<adfn<1>>)
+}
+
+#[test]
+fn max_violation_ignores_satisfied_inequalities() {
+    let constraints = constraints(5.0);
+
+    assert_eq!(
+        constraints.values(&Unknowns { x: 1.0, y: 3.0 }),
+        vec![-1.0, 2.0]
+    );
+    assert_eq!(constraints.max_violation(&Unknowns { x: 1.0, y: 3.0 }), 1.0);
+    // y < x by 3, while the sum is only off by 2
+    assert_eq!(constraints.max_violation(&Unknowns { x: 3.0, y: 0.0 }), 3.0);
+}
+
+#[test]
+fn multiplier_update_is_first_order() {
+    let al = AugmentedLagrangian::new(constraints(5.0)).with_penalty(10.0);
+    assert_eq!(al.initial_multipliers(), vec![0.0, 0.0]);
+
+    // lambda + mu * c for the equality, max(0, lambda - mu * c) for c >= 0
+    let unknowns = Unknowns { x: 1.0, y: 3.0 };
+    assert_eq!(
+        al.updated_multipliers(&[1.0, 4.0], &unknowns),
+        vec![1.0 - 10.0, 0.0]
+    );
+    let unknowns = Unknowns { x: 1.5, y: 1.0 };
+    assert_eq!(
+        al.updated_multipliers(&[0.0, 1.0], &unknowns),
+        vec![-25.0, 6.0]
+    );
+    // missing multipliers count as zero
+    assert_eq!(al.updated_multipliers(&[], &unknowns), vec![-25.0, 5.0]);
+}
+
+#[test]
+fn penalty_residuals_shift_by_the_multipliers() {
+    let al = AugmentedLagrangian::new(constraints(5.0)).with_penalty(4.0);
+    let unknowns = Unknowns { x: 1.0, y: 3.0 };
+
+    // sqrt(mu) * (c + lambda / mu), and sqrt(mu) * max(0, lambda / mu - c)
+    assert_eq!(
+        al.penalty_f64(&[2.0, 12.0]).penalty_residuals(&unknowns),
+        vec![2.0 * (-1.0 + 0.5), 2.0 * (3.0 - 2.0)]
+    );
+    assert_eq!(
+        al.penalty_f64(&[]).penalty_residuals(&unknowns),
+        vec![-2.0, 0.0]
+    );
+}
+
+#[test]
+fn consistent_constraints_leave_the_solution_in_place() {
+    let initial = Unknowns { x: 1.0, y: 1.0 };
+    let eq_sys = EquationSystemBuilder::new_from_f64(
+        Givens { a: 2.0, b: 3.0 },
+        residual_fns_for_generic_params!(Givens, Unknowns; x_eq, y_eq),
+    )
+    .unwrap()
+    .with_augmented_lagrangian(AugmentedLagrangian::new(constraints(5.0)).with_tol(1e-6))
+    .with_triangularization(&initial)
+    .unwrap();
+
+    // the penalty keeps the raw residuals of a block off zero until the other block has moved
+    let options = SolveOptions::default()
+        .with_convergence_criteria(ConvergenceCriteria::default().with_max_grad_norm(1e-8))
+        .with_verbosity(Verbosity::Quiet);
+    let report = eq_sys
+        .solve_system_with_options(&initial, &options)
+        .unwrap();
+
+    assert!((report.solution.x - 2.0).abs() < 1e-4);
+    assert!((report.solution.y - 3.0).abs() < 1e-4);
+    assert!(
+        !report
+            .warnings
+            .iter()
+            .any(|w| matches!(w, SolveWarning::ConstraintsViolated { .. }))
+    );
+}
+
+#[test]
+fn conflicting_constraints_are_reported() {
+    let initial = Unknowns { x: 1.0, y: 1.0 };
+    let eq_sys = EquationSystemBuilder::new_from_f64(
+        Givens { a: 2.0, b: 3.0 },
+        residual_fns_for_generic_params!(Givens, Unknowns; x_eq, y_eq),
+    )
+    .unwrap()
+    // the equations put x + y at 5, and y above x
+    .with_augmented_lagrangian(AugmentedLagrangian::new(constraints(10.0)).with_max_outer_iters(2))
+    .with_triangularization(&initial)
+    .unwrap();
+
+    // accept every block as is, so that no block solve fails on the conflict
+    let options = SolveOptions::default()
+        .with_convergence_criteria(ConvergenceCriteria::default().with_max_abs_residual(10.0))
+        .with_refinement(RefinementConfig::skipped())
+        .with_verbosity(Verbosity::Quiet);
+    let report = eq_sys
+        .solve_system_with_options(&initial, &options)
+        .unwrap();
+
+    assert!(report.warnings.iter().any(|w| matches!(
+        w,
+        SolveWarning::ConstraintsViolated {
+            outer_iterations: 2,
+            max_violation,
+            tol,
+        } if max_violation > tol
+    )));
+}
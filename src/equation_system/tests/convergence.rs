@@ -0,0 +1,317 @@
+use crate::{
+    equation_system::sub_problem::solve_subproblem::block_optimizer::BlockTermination,
+    prelude::{ad_trait::AD, *},
+};
+
+#[solver_params]
+#[derive(Clone, Copy, Debug)]
+struct Givens<T> {
+    a: T,
+    b: T,
+}
+
+#[solver_params]
+#[derive(Clone, Copy, Debug)]
+struct Unknowns<T> {
+    x: T,
+    y: T,
+}
+
+fn x_eq<T: AD>(g: &Givens<T>, u: &Unknowns<T>) -> T {
+    u.x - g.a
+}
+
+fn a_minus_x<T: AD>(g: &Givens<T>, u: &Unknowns<T>) -> T {
+    g.a - u.x
+}
+
+fn y_eq<T: AD>(g: &Givens<T>, u: &Unknowns<T>) -> T {
+    u.y - g.b
+}
+
+fn x_cubed<T: AD>(g: &Givens<T>, u: &Unknowns<T>) -> T {
+    u.x * u.x * u.x - g.a
+}
+
+fn sum_eq<T: AD>(g: &Givens<T>, u: &Unknowns<T>) -> T {
+    u.x + u.y - g.a
+}
+
+fn y_from_x<T: AD>(g: &Givens<T>, u: &Unknowns<T>) -> T {
+    u.y - u.x * T::constant(2.0) + g.b
+}
+
+#[cfg(feature = "argmin")]
+fn y_squared_eq<T: AD>(g: &Givens<T>, u: &Unknowns<T>) -> T {
+    u.y * u.y - g.b * u.x
+}
+
+#[cfg(feature = "argmin")]
+#[test]
+fn capped_regularized_block_is_accepted() {
+    let initial = Unknowns { x: 1.0, y: 1.0 };
+    let eq_sys = EquationSystemBuilder::new_from_f64(
+        Givens { a: 2.0, b: 3.0 },
+        residual_fns_for_generic_params!(Givens, Unknowns; x_eq, y_eq),
+    )
+    .unwrap()
+    .with_tikhonov_regularization(1.0)
+    .unwrap()
+    .with_triangularization(&initial)
+    .unwrap();
+
+    // the penalty keeps the raw residuals off zero, however many iterations run
+    let options = SolveOptions::default()
+        .with_max_iters(1)
+        .with_refinement(RefinementConfig::skipped())
+        .with_verbosity(Verbosity::Quiet);
+    let report = eq_sys
+        .solve_system_with_options(&initial, &options)
+        .unwrap();
+
+    for block in &report.blocks {
+        assert!(block.fallbacks.is_empty(), "{block:?}");
+        assert_eq!(block.solver, BlockSolverKind::GaussNewton);
+    }
+    assert!(report.solution.x > 1.0 && report.solution.x < 2.0);
+}
+
+#[cfg(feature = "argmin")]
+#[test]
+fn capped_refinement_is_accepted() {
+    let initial = Unknowns { x: 1.0, y: 1.0 };
+    let eq_sys = EquationSystemBuilder::new_from_f64(
+        Givens { a: 5.0, b: 1.0 },
+        residual_fns_for_generic_params!(Givens, Unknowns; sum_eq, y_from_x),
+    )
+    .unwrap()
+    .with_scaling_specs(&Unknowns {
+        x: ScalingSpec::Identity,
+        y: ScalingSpec::Identity,
+    })
+    .unwrap()
+    // hides the coupling, so the blocks leave one equation off and refinement has to fix it
+    .with_triangularization_from_incidence(&[[true, false], [false, true]])
+    .unwrap();
+
+    let options = SolveOptions::default()
+        .with_max_iters(1)
+        .with_verbosity(Verbosity::Quiet);
+    let report = eq_sys
+        .solve_system_with_options(&initial, &options)
+        .unwrap();
+
+    assert_eq!(report.refinement_passes, 1);
+    let residual_norm = report.residuals.iter().map(|r| r * r).sum::<f64>().sqrt();
+    assert!(residual_norm < 6.0, "{residual_norm}");
+}
+
+#[test]
+fn gauss_newton_grad_norm_is_of_the_sum_of_squares() {
+    // the rows of the Jacobian cancel, but the gradients of the squared residuals add up
+    let res_fns = residual_fns_for_generic_params!(Givens, Unknowns; x_eq, a_minus_x);
+    let block = SolutionBlock {
+        block_idx: 0,
+        equation_idxs: vec![0, 1],
+        unknown_idxs: vec![0],
+    };
+    let givens = Givens { a: 1.0, b: 0.0 };
+    let initial = Unknowns { x: 2.0, y: 0.0 };
+    let scaling = ParamScaling::Unscaled;
+
+    let gauss_newton = SubProblem::new_from_f64(
+        &res_fns,
+        &block,
+        &givens,
+        &initial,
+        ResidTransIdentity::new(2),
+        ResidNoOpGaussNewton::new_subprob(&block),
+        &scaling,
+        None,
+        None,
+        None,
+    )
+    .unwrap();
+    let summed = SubProblem::new_from_f64(
+        &res_fns,
+        &block,
+        &givens,
+        &initial,
+        ResidTransUnscaledL2 { n: 2 },
+        ResidAggSum,
+        &scaling,
+        None,
+        None,
+        None,
+    )
+    .unwrap();
+
+    // d/dx of (x - 1)^2 + (1 - x)^2 at x = 2
+    let p = gauss_newton.subprob_initial_params_optspace();
+    assert!((gauss_newton.subprob_grad_norm(&p) - 4.0).abs() < 1e-12);
+    assert!((summed.subprob_grad_norm(&p) - 4.0).abs() < 1e-12);
+}
+
+#[cfg(feature = "argmin")]
+#[test]
+fn failed_refinement_keeps_the_solved_blocks() {
+    let initial = Unknowns { x: 1.0, y: 1.0 };
+    let eq_sys = EquationSystemBuilder::new_from_f64(
+        Givens { a: 5.0, b: 1.0 },
+        residual_fns_for_generic_params!(Givens, Unknowns; sum_eq, y_from_x),
+    )
+    .unwrap()
+    .with_scaling_specs(&Unknowns {
+        x: ScalingSpec::Identity,
+        y: ScalingSpec::Identity,
+    })
+    .unwrap()
+    .with_triangularization_from_incidence(&[[true, false], [false, true]])
+    .unwrap();
+
+    // one iteration solves each linear block, but can't fix the coupling the plan misses
+    let options = SolveOptions::default()
+        .with_max_iters(1)
+        .with_convergence_criteria(ConvergenceCriteria::default().with_max_abs_residual(1e-9))
+        .with_verbosity(Verbosity::Quiet);
+    let result = eq_sys.solve_system_with_options(&initial, &options);
+
+    let Err(EqSysError::PartialSolution(partial)) = result else {
+        panic!("expected a partial solution, got {result:?}");
+    };
+    assert_eq!(partial.failed_block, 2);
+    assert_eq!(partial.solved_blocks.len(), 2);
+    assert!((partial.best_params_so_far[0] - 4.0).abs() < 1e-9);
+    assert!(matches!(partial.error, EqSysError::DidNotConverge { .. }));
+}
+
+#[cfg(feature = "argmin")]
+#[test]
+fn failed_block_keeps_the_upstream_solution() {
+    let initial = Unknowns { x: 1.0, y: 1.0 };
+    let eq_sys = EquationSystemBuilder::new_from_f64(
+        Givens { a: 2.0, b: -1.0 },
+        residual_fns_for_generic_params!(Givens, Unknowns; x_eq, y_squared_eq),
+    )
+    .unwrap()
+    .with_scaling_specs(&Unknowns {
+        x: ScalingSpec::Identity,
+        y: ScalingSpec::Identity,
+    })
+    .unwrap()
+    .with_triangularization(&initial)
+    .unwrap();
+
+    // y^2 = -2 has no root, so y's block fails after x's was solved
+    let options = SolveOptions::default()
+        .with_max_iters(20)
+        .with_polish_failure(PolishFailurePolicy::Fail)
+        .with_verbosity(Verbosity::Quiet);
+    let result = eq_sys.solve_system_with_options(&initial, &options);
+
+    let Err(EqSysError::PartialSolution(partial)) = result else {
+        panic!("expected a partial solution, got {result:?}");
+    };
+    assert_eq!(partial.solved_blocks.len(), 1);
+    assert_ne!(partial.failed_block, partial.solved_blocks[0].block_idx);
+    assert!((partial.best_params_so_far[0] - 2.0).abs() < 1e-9);
+    assert_eq!(partial.best_params_so_far[1], 1.0);
+    assert!(matches!(
+        partial.error,
+        EqSysError::AnnealingPolishFailed { .. }
+    ));
+}
+
+#[test]
+fn unconverged_block_error_carries_its_final_state() {
+    let initial = Unknowns { x: 1.0, y: 1.0 };
+    let eq_sys = EquationSystemBuilder::new_from_f64(
+        Givens { a: 8.0, b: 3.0 },
+        residual_fns_for_generic_params!(Givens, Unknowns; x_cubed, y_eq),
+    )
+    .unwrap()
+    .with_scaling_specs(&Unknowns {
+        x: ScalingSpec::Identity,
+        y: ScalingSpec::Identity,
+    })
+    .unwrap()
+    .with_triangularization(&initial)
+    .unwrap();
+
+    // one iteration can't reach the root of x^3 = 8 from anywhere nearby
+    let options = SolveOptions::default()
+        .with_max_iters(1)
+        .with_polish_failure(PolishFailurePolicy::Fail)
+        .with_verbosity(Verbosity::Quiet);
+    let error = eq_sys
+        .solve_system_with_options(&initial, &options)
+        .unwrap_err();
+
+    // wrapped in a partial solution if y's block was solved first, and with the `argmin` feature
+    // in the failed polish of the simulated-annealing fallback
+    let error = match error {
+        EqSysError::PartialSolution(partial) => partial.error,
+        error => error,
+    };
+    #[cfg(feature = "argmin")]
+    let error = match error {
+        EqSysError::AnnealingPolishFailed { source, .. } => *source,
+        error => error,
+    };
+    let EqSysError::DidNotConverge {
+        cost,
+        grad_norm,
+        iterations,
+        termination,
+        ..
+    } = error
+    else {
+        panic!("expected DidNotConverge, got {error:?}");
+    };
+    assert_eq!(termination, BlockTermination::MaxIters);
+    assert_eq!(iterations, 1);
+    assert!(cost.is_finite() && cost > 0.0, "{cost}");
+    assert!(grad_norm.is_finite() && grad_norm > 0.0, "{grad_norm}");
+}
+
+#[test]
+fn loose_criteria_stop_the_block_early() {
+    let initial = Unknowns { x: 1.0, y: 1.0 };
+    let eq_sys = EquationSystemBuilder::new_from_f64(
+        Givens { a: 8.0, b: 3.0 },
+        residual_fns_for_generic_params!(Givens, Unknowns; x_cubed, y_eq),
+    )
+    .unwrap()
+    .with_scaling_specs(&Unknowns {
+        x: ScalingSpec::Identity,
+        y: ScalingSpec::Identity,
+    })
+    .unwrap()
+    .with_triangularization(&initial)
+    .unwrap();
+    let x_block = eq_sys
+        .solution_plan()
+        .blocks
+        .iter()
+        .find(|b| b.equation_idxs == [0])
+        .unwrap()
+        .block_idx;
+
+    let solve = |criteria: ConvergenceCriteria| {
+        let options = SolveOptions::default()
+            .with_convergence_criteria(criteria)
+            .with_refinement(RefinementConfig::skipped())
+            .with_verbosity(Verbosity::Quiet);
+        let report = eq_sys
+            .solve_system_with_options(&initial, &options)
+            .unwrap();
+        report.last_block_report(x_block).unwrap().clone()
+    };
+    let loose = solve(ConvergenceCriteria::default().with_max_abs_residual(0.5));
+    let tight = solve(ConvergenceCriteria::default().with_max_abs_residual(1e-10));
+
+    assert_eq!(loose.termination, Some(BlockTermination::Converged));
+    assert!(loose.residuals[0].abs() < 0.5, "{loose:?}");
+    assert!(tight.residuals[0].abs() < 1e-10, "{tight:?}");
+    assert!(loose.iterations.unwrap() < tight.iterations.unwrap());
+}
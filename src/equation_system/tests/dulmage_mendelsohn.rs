@@ -0,0 +1,119 @@
+use nalgebra::DMatrix;
+
+use crate::prelude::{ad_trait::AD, *};
+
+#[solver_params]
+#[derive(Clone, Copy, Debug)]
+struct Givens<T> {
+    a: T,
+}
+
+#[solver_params]
+#[derive(Clone, Copy, Debug)]
+struct Unknowns<T> {
+    x: T,
+    y: T,
+}
+
+fn x_eq<T: AD>(g: &Givens<T>, u: &Unknowns<T>) -> T {
+    u.x - g.a
+}
+
+fn x_eq_twice<T: AD>(g: &Givens<T>, u: &Unknowns<T>) -> T {
+    u.x * T::constant(2.0) - g.a * T::constant(2.0)
+}
+
+fn incidence(n_rows: usize, n_cols: usize, entries: &[(usize, usize)]) -> DMatrix<f32> {
+    let mut incidence = DMatrix::zeros(n_rows, n_cols);
+    for &(r, c) in entries {
+        incidence[(r, c)] = 1.0;
+    }
+    incidence
+}
+
+#[test]
+fn square_nonsingular_pattern_is_well_determined() {
+    // lower triangular, so the diagonal is a perfect matching
+    let dm = dulmage_mendelsohn(&incidence(3, 3, &[(0, 0), (1, 0), (1, 1), (2, 1), (2, 2)]));
+
+    assert!(dm.is_well_determined());
+    assert_eq!(dm.square_equations, vec![0, 1, 2]);
+    assert_eq!(dm.square_unknowns, vec![0, 1, 2]);
+}
+
+#[test]
+fn equations_competing_for_one_unknown_are_overdetermined() {
+    // e0: x0, x1; e1: x1; e2: x1
+    let dm = dulmage_mendelsohn(&incidence(3, 2, &[(0, 0), (0, 1), (1, 1), (2, 1)]));
+
+    assert_eq!(dm.over_equations, vec![1, 2]);
+    assert_eq!(dm.over_unknowns, vec![1]);
+    assert_eq!(dm.square_equations, vec![0]);
+    assert_eq!(dm.square_unknowns, vec![0]);
+    assert!(dm.under_equations.is_empty());
+    assert!(dm.under_unknowns.is_empty());
+}
+
+#[test]
+fn unknowns_sharing_one_equation_are_underdetermined() {
+    // e0: x0, x1; e1: x2
+    let dm = dulmage_mendelsohn(&incidence(2, 3, &[(0, 0), (0, 1), (1, 2)]));
+
+    assert_eq!(dm.under_equations, vec![0]);
+    assert_eq!(dm.under_unknowns, vec![0, 1]);
+    assert_eq!(dm.square_equations, vec![1]);
+    assert_eq!(dm.square_unknowns, vec![2]);
+    assert!(dm.over_equations.is_empty());
+    assert!(dm.over_unknowns.is_empty());
+}
+
+#[test]
+fn structurally_singular_square_pattern_splits_both_ways() {
+    // e0 and e1 both only involve x0, and x1 appears nowhere
+    let dm = dulmage_mendelsohn(&incidence(2, 2, &[(0, 0), (1, 0)]));
+
+    assert_eq!(dm.over_equations, vec![0, 1]);
+    assert_eq!(dm.over_unknowns, vec![0]);
+    assert!(dm.under_equations.is_empty());
+    assert_eq!(dm.under_unknowns, vec![1]);
+    assert!(!dm.is_well_determined());
+}
+
+#[test]
+fn diagnosis_names_the_ill_posed_parts() {
+    let eq_sys = EquationSystemBuilder::new_from_f64(
+        Givens { a: 2.0 },
+        residual_fns_for_generic_params!(Givens, Unknowns; x_eq, x_eq_twice),
+    )
+    .unwrap();
+
+    let diagnosis =
+        eq_sys.diagnose_structure(&Unknowns { x: 1.0, y: 1.0 }, &StructureSampling::default());
+
+    assert_eq!(
+        diagnosis,
+        StructuralDiagnosis {
+            undetermined_unknowns: vec!["y"],
+            underdetermined_equations: vec![],
+            over_constraining_equations: vec!["x_eq", "x_eq_twice"],
+            overdetermined_unknowns: vec!["x"],
+        }
+    );
+    assert!(!diagnosis.is_well_posed());
+    assert_eq!(diagnosis.n_missing_equations(), 1);
+    assert_eq!(diagnosis.n_redundant_equations(), 1);
+}
+
+#[test]
+fn hand_built_diagnosis_counts_saturate() {
+    // not reachable from a decomposition, but the fields are public
+    let diagnosis = StructuralDiagnosis {
+        undetermined_unknowns: vec![],
+        underdetermined_equations: vec!["e0"],
+        over_constraining_equations: vec![],
+        overdetermined_unknowns: vec!["x"],
+    };
+
+    assert_eq!(diagnosis.n_missing_equations(), 0);
+    assert_eq!(diagnosis.n_redundant_equations(), 0);
+}
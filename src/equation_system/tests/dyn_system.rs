@@ -0,0 +1,59 @@
+use ad_trait::forward_ad::adfn::adfn;
+use nalgebra::DVector;
+
+use crate::prelude::{ad_trait::AD, *};
+
+fn x_sq<T: AD>(u: &[T]) -> T {
+    u[0] * u[0] - T::constant(4.0)
+}
+
+fn y_from_x<T: AD>(u: &[T]) -> T {
+    u[1] - u[0] - T::constant(1.0)
+}
+
+fn z_from_y<T: AD>(u: &[T]) -> T {
+    u[2] * u[1] - T::constant(6.0)
+}
+
+fn builder() -> DynEquationSystemBuilder<EqSysStateInit> {
+    let residual_fns = vec![
+        DynResidualFn::new("z_from_y", z_from_y::<f64>, z_from_y::<adfn<1>>),
+        DynResidualFn::new("x_sq", x_sq::<f64>, x_sq::<adfn<1>>),
+        DynResidualFn::new("y_from_x", y_from_x::<f64>, y_from_x::<adfn<1>>),
+    ];
+    let unknown_names = ["x", "y", "z"].map(String::from).to_vec();
+    DynEquationSystemBuilder::new(residual_fns, unknown_names)
+}
+
+#[test]
+fn runtime_sized_system_is_solved_block_by_block() {
+    let initial = DVector::from_element(3, 1.0);
+    let eq_sys = builder().with_triangularization(&initial).unwrap();
+
+    let solution = eq_sys
+        .solve_system_with_options(
+            &initial,
+            &SolveOptions::default().with_verbosity(Verbosity::Quiet),
+        )
+        .unwrap();
+
+    // x, then y from x, then z from y
+    assert_eq!(eq_sys.solution_plan().blocks.len(), 3);
+    assert!((solution[0] - 2.0).abs() < 1e-6, "{solution}");
+    assert!((solution[1] - 3.0).abs() < 1e-6, "{solution}");
+    assert!((solution[2] - 2.0).abs() < 1e-6, "{solution}");
+    assert!(eq_sys.residuals_at(&solution).unwrap().norm() < 1e-6);
+}
+
+#[test]
+fn unknowns_of_the_wrong_length_are_rejected() {
+    let result = builder().with_triangularization(&DVector::from_element(2, 1.0));
+
+    assert!(matches!(
+        result,
+        Err(EqSysError::UnknownsLenMismatch {
+            expected: 3,
+            got: 2
+        })
+    ));
+}
@@ -0,0 +1,110 @@
+use std::sync::Arc;
+
+use nalgebra::DVector;
+
+use crate::prelude::{
+    ad_trait::AD,
+    solve_subproblem::external::{ExternalBlockProblem, ExternalSolver},
+    *,
+};
+
+#[solver_params]
+#[derive(Clone, Copy, Debug)]
+struct Givens<T> {
+    a: T,
+    b: T,
+}
+
+#[solver_params]
+#[derive(Clone, Copy, Debug)]
+struct Unknowns<T> {
+    x: T,
+    y: T,
+}
+
+fn x_sq<T: AD>(g: &Givens<T>, u: &Unknowns<T>) -> T {
+    u.x * u.x - g.a
+}
+
+fn y_from_x<T: AD>(g: &Givens<T>, u: &Unknowns<T>) -> T {
+    u.y - u.x * g.b
+}
+
+/// Plain Newton iterations on the block's square Jacobian.
+struct Newton;
+
+impl ExternalSolver for Newton {
+    fn name(&self) -> &str {
+        "newton"
+    }
+
+    fn solve(&self, problem: &ExternalBlockProblem) -> Result<DVector<f64>, EqSysError> {
+        let mut x = problem.initial_params.clone();
+        for _ in 0..50 {
+            let r = (problem.residuals)(&x)?;
+            let step = (problem.jacobian)(&x)?
+                .lu()
+                .solve(&r)
+                .ok_or(EqSysError::PseudoInverseFailed("singular block Jacobian"))?;
+            x -= step;
+        }
+        Ok(x)
+    }
+}
+
+struct Failing;
+
+impl ExternalSolver for Failing {
+    fn name(&self) -> &str {
+        "failing"
+    }
+
+    fn solve(&self, _problem: &ExternalBlockProblem) -> Result<DVector<f64>, EqSysError> {
+        Err(EqSysError::NoBestParams {
+            solver: "failing".to_string(),
+        })
+    }
+}
+
+fn solve(solver: Arc<dyn ExternalSolver>) -> SolveReport<Unknowns<f64>> {
+    let initial = Unknowns { x: 1.0, y: 1.0 };
+    let eq_sys = EquationSystemBuilder::new_from_f64(
+        Givens { a: 4.0, b: 3.0 },
+        residual_fns_for_generic_params!(Givens, Unknowns; x_sq, y_from_x),
+    )
+    .unwrap()
+    .with_triangularization(&initial)
+    .unwrap();
+    let options = SolveOptions::default()
+        .with_external_solver(solver)
+        .with_verbosity(Verbosity::Quiet);
+    eq_sys
+        .solve_system_with_options(&initial, &options)
+        .unwrap()
+}
+
+#[test]
+fn external_solver_solves_every_block() {
+    let report = solve(Arc::new(Newton));
+
+    assert_eq!(report.blocks.len(), 2);
+    for block in &report.blocks {
+        assert_eq!(block.solver, BlockSolverKind::External);
+        assert!(block.fallbacks.is_empty());
+    }
+    assert!((report.solution.x - 2.0).abs() < 1e-8);
+    assert!((report.solution.y - 6.0).abs() < 1e-8);
+}
+
+#[test]
+fn failing_external_solver_falls_back_to_the_default_chain() {
+    let report = solve(Arc::new(Failing));
+
+    for block in &report.blocks {
+        assert_ne!(block.solver, BlockSolverKind::External);
+        assert_eq!(block.fallbacks.len(), 1);
+        assert_eq!(block.fallbacks[0].solver, BlockSolverKind::External);
+    }
+    assert!((report.solution.x - 2.0).abs() < 1e-6);
+    assert!((report.solution.y - 6.0).abs() < 1e-6);
+}
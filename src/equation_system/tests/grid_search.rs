@@ -0,0 +1,76 @@
+use crate::prelude::{ad_trait::AD, *};
+
+#[solver_params]
+#[derive(Clone, Copy, Debug)]
+struct Givens<T> {
+    a: T,
+    b: T,
+}
+
+#[solver_params]
+#[derive(Clone, Copy, Debug)]
+struct Unknowns<T> {
+    x: T,
+    y: T,
+}
+
+/// Roots at `x = a` and `x = -b / 2`.
+fn two_roots<T: AD>(g: &Givens<T>, u: &Unknowns<T>) -> T {
+    (u.x - g.a) * (u.x + g.b * T::constant(0.5))
+}
+
+fn y_eq<T: AD>(g: &Givens<T>, u: &Unknowns<T>) -> T {
+    u.y - g.b
+}
+
+fn solve(grid_search: Option<GridSearchConfig>) -> Result<SolveReport<Unknowns<f64>>, EqSysError> {
+    let initial = Unknowns { x: 0.0, y: 0.0 };
+    let eq_sys = EquationSystemBuilder::new_from_f64(
+        Givens { a: 6.0, b: 3.0 },
+        residual_fns_for_generic_params!(Givens, Unknowns; two_roots, y_eq),
+    )
+    .unwrap()
+    .with_scaling_specs(&Unknowns {
+        x: ScalingSpec::Identity,
+        y: ScalingSpec::Identity,
+    })
+    .unwrap()
+    .with_triangularization(&initial)
+    .unwrap();
+
+    let mut options = SolveOptions::default().with_verbosity(Verbosity::Quiet);
+    options.grid_search = grid_search;
+    eq_sys.solve_system_with_options(&initial, &options)
+}
+
+#[test]
+fn grid_search_starts_the_block_in_the_cheapest_basin() {
+    // the ticks are -6, 0 and 6, and x = 6 is a root, while the local solve from 0 heads for -1.5
+    let grid = GridSearchConfig {
+        half_width: 6.0,
+        points_per_dim: 3,
+        ..GridSearchConfig::default()
+    };
+
+    let local = solve(None).unwrap();
+    let searched = solve(Some(grid)).unwrap();
+
+    assert!((local.solution.x + 1.5).abs() < 1e-6, "{local:?}");
+    assert!((searched.solution.x - 6.0).abs() < 1e-6, "{searched:?}");
+    assert!((searched.solution.y - 3.0).abs() < 1e-6, "{searched:?}");
+}
+
+#[test]
+fn grid_search_with_a_negative_half_width_is_rejected() {
+    let grid = GridSearchConfig {
+        half_width: -1.0,
+        ..GridSearchConfig::default()
+    };
+
+    let result = solve(Some(grid));
+
+    assert!(matches!(
+        result,
+        Err(EqSysError::InvalidGridSearchHalfWidth { half_width }) if half_width == -1.0
+    ));
+}
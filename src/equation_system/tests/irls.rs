@@ -0,0 +1,83 @@
+use crate::prelude::{ad_trait::AD, *};
+
+#[solver_params]
+#[derive(Clone, Copy, Debug)]
+struct Givens<T> {
+    a: T,
+    b: T,
+}
+
+#[solver_params]
+#[derive(Clone, Copy, Debug)]
+struct Unknowns<T> {
+    x: T,
+    y: T,
+}
+
+fn x_is_a<T: AD>(g: &Givens<T>, u: &Unknowns<T>) -> T {
+    u.x - g.a
+}
+
+fn x_above_a<T: AD>(g: &Givens<T>, u: &Unknowns<T>) -> T {
+    u.x - g.a - T::constant(0.1)
+}
+
+fn x_below_a<T: AD>(g: &Givens<T>, u: &Unknowns<T>) -> T {
+    u.x - g.a + T::constant(0.1)
+}
+
+/// A measurement of `x` far off the others.
+fn x_is_b<T: AD>(g: &Givens<T>, u: &Unknowns<T>) -> T {
+    u.x - g.b
+}
+
+fn y_is_x<T: AD>(_g: &Givens<T>, u: &Unknowns<T>) -> T {
+    u.y - u.x
+}
+
+#[test]
+fn irls_discounts_the_outlier_that_least_squares_averages_in() {
+    let initial = Unknowns { x: 1.0, y: 1.0 };
+    let eq_sys = EquationSystemBuilder::new_from_f64(
+        Givens { a: 1.0, b: 10.0 },
+        residual_fns_for_generic_params!(Givens, Unknowns; x_is_a, x_above_a, x_below_a, x_is_b, y_is_x),
+    )
+    .unwrap()
+    .with_scaling_specs(&Unknowns {
+        x: ScalingSpec::Identity,
+        y: ScalingSpec::Identity,
+    })
+    .unwrap()
+    .with_triangularization(&initial)
+    .unwrap();
+    let x_block = eq_sys
+        .solution_plan()
+        .blocks
+        .iter()
+        .find(|b| b.unknown_idxs == [0])
+        .unwrap()
+        .clone();
+
+    let least_squares = eq_sys
+        .solve_system_with_options(
+            &initial,
+            &SolveOptions::default().with_verbosity(Verbosity::Quiet),
+        )
+        .unwrap();
+    let cfg = IrlsConfig {
+        loss: RobustLoss::Huber { k: 1.345 },
+        max_outer_iters: 100,
+        tol: 1e-10,
+    };
+    let robust = eq_sys
+        .solve_sub_problem_irls(&x_block, &initial, &cfg)
+        .unwrap();
+
+    // the mean of 1, 1.1, 0.9 and 10
+    assert!(
+        (least_squares.solution.x - 3.25).abs() < 1e-6,
+        "{least_squares:?}"
+    );
+    // Huber: the three inliers balance the outlier's capped pull, 3 (x - 1) = k
+    assert!((robust.x - (1.0 + 1.345 / 3.0)).abs() < 1e-6, "{robust:?}");
+}
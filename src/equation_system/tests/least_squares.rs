@@ -0,0 +1,214 @@
+use crate::prelude::{ad_trait::AD, *};
+
+#[solver_params]
+#[derive(Clone, Copy, Debug)]
+struct Givens<T> {
+    a: T,
+    b: T,
+}
+
+#[solver_params]
+#[derive(Clone, Copy, Debug)]
+struct Unknowns<T> {
+    x: T,
+    y: T,
+}
+
+const IDENTITY: Unknowns<ScalingSpec> = Unknowns {
+    x: ScalingSpec::Identity,
+    y: ScalingSpec::Identity,
+};
+
+fn sum_eq<T: AD>(g: &Givens<T>, u: &Unknowns<T>) -> T {
+    u.x + u.y - g.a
+}
+
+fn x_is_a<T: AD>(g: &Givens<T>, u: &Unknowns<T>) -> T {
+    u.x - g.a
+}
+
+fn x_is_b<T: AD>(g: &Givens<T>, u: &Unknowns<T>) -> T {
+    u.x - g.b
+}
+
+fn y_is_x<T: AD>(_g: &Givens<T>, u: &Unknowns<T>) -> T {
+    u.y - u.x
+}
+
+/// One-sided: `x >= b`.
+fn x_at_least_b<T: AD>(g: &Givens<T>, u: &Unknowns<T>) -> T {
+    g.b - u.x
+}
+
+fn quiet() -> SolveOptions {
+    SolveOptions::default().with_verbosity(Verbosity::Quiet)
+}
+
+#[test]
+fn underdetermined_system_takes_the_minimum_norm_step() {
+    let initial = Unknowns { x: 1.0, y: 1.0 };
+    let eq_sys = EquationSystemBuilder::new_from_f64(
+        Givens { a: 4.0, b: 0.0 },
+        residual_fns_for_generic_params!(Givens, Unknowns; sum_eq),
+    )
+    .unwrap()
+    .with_scaling_specs(&IDENTITY)
+    .unwrap()
+    .with_triangularization(&initial)
+    .unwrap();
+
+    let report = eq_sys
+        .solve_system_with_options(&initial, &quiet())
+        .unwrap();
+
+    // the closest point to (1, 1) on x + y = 4
+    assert_eq!(report.blocks.len(), 1);
+    assert_eq!(
+        report.blocks[0].solver,
+        BlockSolverKind::WeightedLeastSquares
+    );
+    assert!((report.solution.x - 2.0).abs() < 1e-8);
+    assert!((report.solution.y - 2.0).abs() < 1e-8);
+}
+
+#[test]
+fn overdetermined_system_settles_on_the_weighted_compromise() {
+    let initial = Unknowns { x: 1.0, y: 1.0 };
+    let eq_sys = EquationSystemBuilder::new_from_f64(
+        Givens { a: 1.0, b: 5.0 },
+        residual_fns_for_generic_params!(Givens, Unknowns; x_is_a, x_is_b, y_is_x),
+    )
+    .unwrap()
+    .with_scaling_specs(&IDENTITY)
+    .unwrap()
+    .with_least_squares_weights(vec![1.0, 3.0, 1.0])
+    .unwrap()
+    .with_triangularization(&initial)
+    .unwrap();
+
+    let report = eq_sys
+        .solve_system_with_options(&initial, &quiet())
+        .unwrap();
+
+    // minimizes (x - 1)^2 + 3 (x - 5)^2, and y follows x exactly
+    assert!((report.solution.x - 4.0).abs() < 1e-8);
+    assert!((report.solution.y - 4.0).abs() < 1e-8);
+    assert_eq!(report.refinement_passes, 0);
+}
+
+#[test]
+fn overdetermined_part_is_solved_before_the_blocks_that_use_it() {
+    let initial = Unknowns { x: 1.0, y: 1.0 };
+    let eq_sys = EquationSystemBuilder::new_from_f64(
+        Givens { a: 1.0, b: 5.0 },
+        residual_fns_for_generic_params!(Givens, Unknowns; y_is_x, x_is_a, x_is_b),
+    )
+    .unwrap()
+    .with_scaling_specs(&IDENTITY)
+    .unwrap()
+    .with_triangularization(&initial)
+    .unwrap();
+
+    let report = eq_sys
+        .solve_system_with_options(&initial, &quiet())
+        .unwrap();
+
+    // x = 1 and x = 5 compete for x; y = x is a square block downstream of them
+    let blocks = &eq_sys.solution_plan().blocks;
+    assert_eq!(blocks.len(), 2);
+    assert_eq!(blocks[0].equation_idxs.len(), 2);
+    assert_eq!(blocks[0].unknown_idxs, vec![0]);
+    assert_eq!(blocks[1].equation_idxs, vec![0]);
+    assert_eq!(
+        report.blocks[0].solver,
+        BlockSolverKind::WeightedLeastSquares
+    );
+    assert_ne!(
+        report.blocks[1].solver,
+        BlockSolverKind::WeightedLeastSquares
+    );
+    assert!((report.solution.x - 3.0).abs() < 1e-8);
+    assert!((report.solution.y - 3.0).abs() < 1e-6);
+}
+
+#[test]
+fn residual_weights_scale_the_residuals_before_squaring() {
+    let initial = Unknowns { x: 1.0, y: 1.0 };
+    let eq_sys = EquationSystemBuilder::new_from_f64(
+        Givens { a: 1.0, b: 5.0 },
+        residual_fns_for_generic_params!(Givens, Unknowns; x_is_a, x_is_b, y_is_x),
+    )
+    .unwrap()
+    .with_scaling_specs(&IDENTITY)
+    .unwrap()
+    .with_residual_weights(&[1.0, 3.0, 1.0])
+    .unwrap()
+    .with_triangularization(&initial)
+    .unwrap();
+
+    let report = eq_sys
+        .solve_system_with_options(&initial, &quiet())
+        .unwrap();
+
+    // minimizes (x - 1)^2 + (3 (x - 5))^2, where least-squares weights would give 4
+    assert!((report.solution.x - 4.6).abs() < 1e-8);
+    assert!((report.solution.y - 4.6).abs() < 1e-6);
+    // the weights only scale the solver's residuals, not the reported raw ones
+    assert!((report.residuals[1] + 0.4).abs() < 1e-8, "{report:?}");
+}
+
+#[test]
+fn inequality_pushes_the_free_direction_of_an_underdetermined_system() {
+    let initial = Unknowns { x: 1.0, y: 1.0 };
+    let eq_sys = EquationSystemBuilder::new_from_f64(
+        Givens { a: 4.0, b: 3.0 },
+        residual_fns_for_generic_params!(Givens, Unknowns; sum_eq),
+    )
+    .unwrap()
+    .with_scaling_specs(&IDENTITY)
+    .unwrap()
+    .with_inequality_residuals(InequalityResiduals::new(
+        residual_fns_for_generic_params!(Givens, Unknowns; x_at_least_b),
+    ))
+    .with_triangularization(&initial)
+    .unwrap();
+
+    let report = eq_sys
+        .solve_system_with_options(&initial, &quiet())
+        .unwrap();
+
+    // without the inequality, the minimum-norm step would stop at (2, 2)
+    assert!((report.solution.x - 3.0).abs() < 1e-6, "{report:?}");
+    assert!((report.solution.y - 1.0).abs() < 1e-6, "{report:?}");
+    assert_eq!(report.residual_names, vec!["sum_eq"]);
+}
+
+#[test]
+fn auto_scaling_balances_residuals_in_different_units() {
+    let initial = Unknowns { x: 1.0, y: 1.0 };
+    let builder = || {
+        EquationSystemBuilder::new_from_f64(
+            Givens { a: 2.0, b: 4.0 },
+            residual_fns_for_generic_params!(Givens, Unknowns; y_is_x, x_is_a, x_is_b * 1000.0),
+        )
+        .unwrap()
+        .with_scaling_specs(&IDENTITY)
+        .unwrap()
+    };
+    let solve = |eq_sys: EquationSystemFor<Givens<f64>, Unknowns<f64>, EqSysStateInit, 2>| {
+        eq_sys
+            .with_triangularization(&initial)
+            .unwrap()
+            .solve_system_with_options(&initial, &quiet())
+            .unwrap()
+    };
+
+    let unscaled = solve(builder());
+    let scaled = solve(builder().with_auto_residual_scaling(&initial).unwrap());
+
+    // unscaled, the equation in the smaller unit wins outright
+    assert!((unscaled.solution.x - 4.0).abs() < 1e-4, "{unscaled:?}");
+    // magnitudes 1 and 3000 at the start leave (x - 2)^2 + ((x - 4) / 3)^2
+    assert!((scaled.solution.x - 2.2).abs() < 1e-6, "{scaled:?}");
+    assert!((scaled.solution.y - 2.2).abs() < 1e-6, "{scaled:?}");
+}
@@ -0,0 +1,54 @@
+use crate::prelude::{ad_trait::AD, *};
+
+#[solver_params]
+#[derive(Clone, Copy, Debug)]
+struct Givens<T> {
+    a: T,
+    b: T,
+}
+
+#[solver_params]
+#[derive(Clone, Copy, Debug)]
+struct Unknowns<T> {
+    x: T,
+    y: T,
+}
+
+fn x_cubed<T: AD>(g: &Givens<T>, u: &Unknowns<T>) -> T {
+    u.x * u.x * u.x - g.a
+}
+
+fn y_eq<T: AD>(g: &Givens<T>, u: &Unknowns<T>) -> T {
+    u.y - g.b
+}
+
+fn solve(options: SolveOptions) -> SolveReport<Unknowns<f64>> {
+    let initial = Unknowns { x: 1.0, y: 1.0 };
+    EquationSystemBuilder::new_from_f64(
+        Givens { a: 8.0, b: 3.0 },
+        residual_fns_for_generic_params!(Givens, Unknowns; x_cubed, y_eq),
+    )
+    .unwrap()
+    .with_triangularization(&initial)
+    .unwrap()
+    .solve_system_with_options(&initial, &options.with_verbosity(Verbosity::Quiet))
+    .unwrap()
+}
+
+#[test]
+fn memoized_solve_reports_its_cache_use() {
+    let report = solve(SolveOptions::default().with_memoize_evaluations(64));
+
+    let cache = report.eval_cache.unwrap();
+    assert!(cache.misses > 0, "{cache:?}");
+    assert!((0.0..=1.0).contains(&cache.hit_rate()));
+    assert!((report.solution.x - 2.0).abs() < 1e-6, "{report:?}");
+    assert!((report.solution.y - 3.0).abs() < 1e-6, "{report:?}");
+}
+
+#[test]
+fn solve_without_memoization_has_no_cache_stats() {
+    let report = solve(SolveOptions::default());
+
+    assert!(report.eval_cache.is_none());
+}
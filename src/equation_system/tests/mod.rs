@@ -1 +1,48 @@
+#[cfg(feature = "argmin")]
+mod aggregation;
+mod annealing;
+mod bounds;
+#[cfg(feature = "argmin")]
+mod broyden;
+mod coloring;
+mod complex_step;
+mod conditioning;
+mod constraints;
+mod convergence;
+mod dulmage_mendelsohn;
+mod dyn_system;
+mod external;
+mod grid_search;
+#[cfg(feature = "argmin")]
+mod irls;
+mod least_squares;
+mod memoization;
+mod monte_carlo;
+mod optimization_trace;
+#[cfg(feature = "parallel")]
+mod parallel;
+mod param_maps;
 mod param_scaling;
+mod priors;
+mod progress;
+mod refinement;
+mod residual_fns;
+mod resolve;
+mod restart;
+mod role_swap;
+mod scaling_specs;
+mod sensitivity;
+mod small_newton;
+mod solve_block;
+mod solved;
+mod solvers;
+#[cfg(feature = "sparse")]
+mod sparse;
+mod structure;
+mod sweep;
+mod sweeps;
+mod time_budget;
+mod validation;
+mod verification;
+mod warnings;
+mod wide_tangents;
@@ -0,0 +1,80 @@
+use crate::prelude::{ad_trait::AD, *};
+
+#[solver_params]
+#[derive(Clone, Copy, Debug)]
+struct Givens<T> {
+    a: T,
+    b: T,
+}
+
+#[solver_params]
+#[derive(Clone, Copy, Debug)]
+struct Unknowns<T> {
+    x: T,
+    y: T,
+}
+
+fn x_sq<T: AD>(g: &Givens<T>, u: &Unknowns<T>) -> T {
+    u.x * u.x - g.a
+}
+
+fn y_eq<T: AD>(g: &Givens<T>, u: &Unknowns<T>) -> T {
+    u.y - g.b
+}
+
+#[test]
+fn infeasible_samples_count_as_failures() {
+    let initial = Unknowns { x: 1.0, y: 1.0 };
+    let mut eq_sys = EquationSystemBuilder::new_from_f64(
+        Givens { a: 1.0, b: 3.0 },
+        residual_fns_for_generic_params!(Givens, Unknowns; x_sq, y_eq),
+    )
+    .unwrap()
+    .with_triangularization(&initial)
+    .unwrap();
+
+    // alternates between a = 4, where x = 2, and a = -4, where x^2 = a has no root
+    let mut n_drawn = 0;
+    let solve_options = SolveOptions::default()
+        .with_max_iters(100)
+        .with_verbosity(Verbosity::Quiet);
+    let result = eq_sys
+        .solve_monte_carlo_with_options::<2>(
+            4,
+            |nominal, _rng| {
+                n_drawn += 1;
+                let sign = if n_drawn % 2 == 1 { 1.0 } else { -1.0 };
+                Givens {
+                    a: sign * 4.0,
+                    ..*nominal
+                }
+            },
+            &initial,
+            &MonteCarloOptions::default(),
+            &solve_options,
+        )
+        .unwrap();
+
+    assert!(
+        (result.nominal.x - 1.0).abs() < 1e-6,
+        "{:?}",
+        result.nominal
+    );
+    assert_eq!(result.n_runs(), 4);
+    assert_eq!(result.failure_rate(), 0.5);
+    assert!(result.failures.iter().all(|f| f.givens.a == -4.0));
+    let distributions = result.distributions::<2>();
+    assert!(
+        (distributions[0].mean - 2.0).abs() < 1e-6,
+        "{distributions:?}"
+    );
+    assert!(
+        (distributions[1].mean - 3.0).abs() < 1e-6,
+        "{distributions:?}"
+    );
+    // the nominal givens are back in place
+    let report = eq_sys
+        .solve_system_with_options(&initial, &solve_options)
+        .unwrap();
+    assert!((report.solution.x - 1.0).abs() < 1e-6, "{report:?}");
+}
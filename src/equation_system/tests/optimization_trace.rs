@@ -0,0 +1,144 @@
+use std::time::Duration;
+
+#[cfg(feature = "argmin")]
+use crate::prelude::ad_trait::AD;
+use crate::prelude::*;
+
+#[cfg(feature = "argmin")]
+#[solver_params]
+#[derive(Clone, Copy, Debug)]
+struct Givens<T> {
+    a: T,
+    b: T,
+}
+
+#[cfg(feature = "argmin")]
+#[solver_params]
+#[derive(Clone, Copy, Debug)]
+struct Unknowns<T> {
+    x: T,
+    y: T,
+}
+
+#[cfg(feature = "argmin")]
+fn x_cubed<T: AD>(g: &Givens<T>, u: &Unknowns<T>) -> T {
+    u.x * u.x * u.x - g.a
+}
+
+#[cfg(feature = "argmin")]
+fn y_eq<T: AD>(g: &Givens<T>, u: &Unknowns<T>) -> T {
+    u.y - g.b
+}
+
+/// Solves `x^3 = 8`, `y = 3` from `(1, 1)` with every block solver run traced.
+#[cfg(feature = "argmin")]
+fn traced_solve() -> SolveReport<Unknowns<f64>> {
+    let initial = Unknowns { x: 1.0, y: 1.0 };
+    EquationSystemBuilder::new_from_f64(
+        Givens { a: 8.0, b: 3.0 },
+        residual_fns_for_generic_params!(Givens, Unknowns; x_cubed, y_eq),
+    )
+    .unwrap()
+    .with_triangularization(&initial)
+    .unwrap()
+    .solve_system_with_options(
+        &initial,
+        &SolveOptions::default()
+            .with_trace(TraceConfig::all())
+            .with_verbosity(Verbosity::Quiet),
+    )
+    .unwrap()
+}
+
+fn trace(solver: &str) -> OptimizationTrace {
+    OptimizationTrace {
+        block_idx: 1,
+        solver: solver.to_string(),
+        iterations: vec![IterationRecord {
+            iter: 0,
+            cost: 0.5,
+            params: None,
+            grad_norm: None,
+            step_size: Some(f64::NAN),
+            elapsed: Duration::ZERO,
+        }],
+    }
+}
+
+#[test]
+fn csv_quotes_solver_names_with_separators() {
+    let csv = trace("GaussNewton<f64, \"line search\">").to_csv();
+
+    assert_eq!(
+        csv.lines().nth(1),
+        Some("0,1,\"GaussNewton<f64, \"\"line search\"\">\",0,0.0,0.5,,NaN")
+    );
+}
+
+#[test]
+fn csv_leaves_plain_solver_names_unquoted() {
+    let csv = trace("lbfgs").to_csv();
+
+    assert_eq!(csv.lines().nth(1), Some("0,1,lbfgs,0,0.0,0.5,,NaN"));
+}
+
+#[test]
+fn json_escapes_control_characters() {
+    let json = trace("a\"b\\c\nd\u{1}").to_json();
+
+    assert!(json.contains(r#""solver": "a\"b\\c\nd\u0001""#), "{json}");
+    assert!(json.contains(r#""step_size": null"#));
+}
+
+#[cfg(feature = "argmin")]
+#[test]
+fn traced_solve_records_every_iteration() {
+    let report = traced_solve();
+
+    assert!(!report.traces.is_empty());
+    for trace in &report.traces {
+        let n_iters = trace.iterations.len();
+        assert!(n_iters > 0, "{trace:?}");
+        // both blocks are 1x1
+        assert!(trace.params().iter().all(|p| p.len() == 1), "{trace:?}");
+        assert_eq!(trace.grad_norms().len(), n_iters);
+        assert_eq!(trace.step_sizes().len(), n_iters - 1);
+        assert!(trace.timestamps().windows(2).all(|t| t[0] <= t[1]));
+    }
+    // x^3 = 8 takes a few steps, each of which lowers the cost
+    let longest = report
+        .traces
+        .iter()
+        .max_by_key(|t| t.iterations.len())
+        .unwrap();
+    let costs = longest.costs();
+    assert!(costs.len() > 1, "{longest:?}");
+    assert!(costs.last().unwrap() < costs.first().unwrap(), "{costs:?}");
+}
+
+#[cfg(feature = "argmin")]
+#[test]
+fn traced_solve_exports_one_row_per_iteration() {
+    let report = traced_solve();
+    let n_iters: usize = report.traces.iter().map(|t| t.iterations.len()).sum();
+
+    let csv = report.convergence_csv();
+    let mut lines = csv.lines();
+    assert_eq!(
+        lines.next(),
+        Some("run,block_idx,solver,iter,elapsed_s,cost,grad_norm,step_size,param_0")
+    );
+    let rows: Vec<&str> = lines.collect();
+    assert_eq!(rows.len(), n_iters);
+    // every recorded iteration has its cost, grad norm and param filled in
+    for row in &rows {
+        let fields: Vec<&str> = row.split(',').collect();
+        assert_eq!(fields.len(), 9, "{row}");
+        assert!(!fields[5].is_empty() && !fields[6].is_empty() && !fields[8].is_empty());
+    }
+
+    let json = report.convergence_json();
+    assert_eq!(json.matches("\"block_idx\"").count(), report.traces.len());
+    assert_eq!(json.matches("\"iter\"").count(), n_iters);
+    assert!(json.starts_with('[') && json.ends_with(']'));
+}
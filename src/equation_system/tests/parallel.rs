@@ -0,0 +1,60 @@
+use crate::prelude::{ad_trait::AD, *};
+
+#[solver_params]
+#[derive(Clone, Copy, Debug)]
+struct Givens<T> {
+    a: T,
+    b: T,
+}
+
+#[solver_params]
+#[derive(Clone, Copy, Debug)]
+struct Unknowns<T> {
+    x: T,
+    y: T,
+}
+
+const WORKER_WARNING: &str = "evaluated on a rayon worker thread";
+
+fn x_eq<T: AD>(g: &Givens<T>, u: &Unknowns<T>) -> T {
+    if rayon::current_thread_index().is_some() {
+        warn_from_residual(WORKER_WARNING);
+    }
+    u.x - g.a
+}
+
+fn y_eq<T: AD>(g: &Givens<T>, u: &Unknowns<T>) -> T {
+    if rayon::current_thread_index().is_some() {
+        warn_from_residual(WORKER_WARNING);
+    }
+    u.y - g.b
+}
+
+#[test]
+fn warnings_from_worker_threads_reach_the_report() {
+    let givens = Givens { a: 2.0, b: 3.0 };
+    let initial = Unknowns { x: 1.0, y: 1.0 };
+    let eq_sys = EquationSystemBuilder::new_from_f64(
+        givens,
+        residual_fns_for_generic_params!(Givens, Unknowns; x_eq, y_eq),
+    )
+    .unwrap()
+    .with_triangularization(&initial)
+    .unwrap();
+    // the two blocks are independent, so they share a level and are solved on the pool
+    assert_eq!(eq_sys.block_levels(), vec![vec![0, 1]]);
+
+    let options = SolveOptions::default()
+        .with_parallel_blocks(true)
+        .with_verbosity(Verbosity::Quiet);
+    let report = eq_sys
+        .solve_system_with_options(&initial, &options)
+        .unwrap();
+
+    assert!((report.solution.x - 2.0).abs() < 1e-6);
+    assert!((report.solution.y - 3.0).abs() < 1e-6);
+    assert!(report.warnings.iter().any(|w| matches!(
+        w,
+        SolveWarning::Residual { message, .. } if message == WORKER_WARNING
+    )));
+}
@@ -0,0 +1,72 @@
+use std::collections::HashMap;
+
+use crate::prelude::{ad_trait::AD, *};
+
+#[solver_params]
+#[derive(Clone, Copy, Debug)]
+struct Givens<T> {
+    a: T,
+    b: T,
+}
+
+#[solver_params]
+#[derive(Clone, Copy, Debug)]
+struct Unknowns<T> {
+    x: T,
+    y: T,
+}
+
+fn x_eq<T: AD>(g: &Givens<T>, u: &Unknowns<T>) -> T {
+    u.x - g.a
+}
+
+fn y_from_x<T: AD>(g: &Givens<T>, u: &Unknowns<T>) -> T {
+    u.y - u.x * g.b
+}
+
+#[test]
+fn system_set_up_and_read_out_by_field_name() {
+    // as read from e.g. a config file
+    let givens: Givens<f64> = params_from_map(&HashMap::from([("a", 2.0), ("b", 3.0)])).unwrap();
+    let initial: Unknowns<f64> = params_from_map(&HashMap::from([
+        ("x".to_string(), 1.0),
+        ("y".to_string(), 1.0),
+    ]))
+    .unwrap();
+    let initial = params_updated_from_map(&initial, &HashMap::from([("y", 5.0)])).unwrap();
+    assert_eq!(initial.y, 5.0);
+
+    let report = EquationSystemBuilder::new_from_f64(
+        givens,
+        residual_fns_for_generic_params!(Givens, Unknowns; x_eq, y_from_x),
+    )
+    .unwrap()
+    .with_triangularization(&initial)
+    .unwrap()
+    .solve_system_with_options(
+        &initial,
+        &SolveOptions::default().with_verbosity(Verbosity::Quiet),
+    )
+    .unwrap();
+
+    let solution = params_to_map(&report.solution);
+    assert_eq!(solution.len(), 2);
+    assert!((solution["x"] - 2.0).abs() < 1e-6, "{solution:?}");
+    assert!((solution["y"] - 6.0).abs() < 1e-6, "{solution:?}");
+}
+
+#[test]
+fn maps_with_missing_or_unknown_fields_are_rejected() {
+    let missing = params_from_map::<Givens<f64>, _, 2>(&HashMap::from([("a", 2.0)]));
+    assert!(
+        matches!(missing, Err(EqSysError::MissingParamField { field: "b" })),
+        "{missing:?}"
+    );
+
+    let unknown =
+        params_from_map::<Givens<f64>, _, 2>(&HashMap::from([("a", 2.0), ("b", 3.0), ("c", 4.0)]));
+    assert!(
+        matches!(unknown, Err(EqSysError::UnknownParamField { .. })),
+        "{unknown:?}"
+    );
+}
@@ -0,0 +1,66 @@
+use crate::prelude::{ad_trait::AD, *};
+
+#[solver_params]
+#[derive(Clone, Copy, Debug)]
+struct Givens<T> {
+    a: T,
+    b: T,
+}
+
+#[solver_params]
+#[derive(Clone, Copy, Debug)]
+struct Unknowns<T> {
+    x: T,
+    y: T,
+}
+
+fn x_eq<T: AD>(g: &Givens<T>, u: &Unknowns<T>) -> T {
+    u.x - g.a
+}
+
+fn y_eq<T: AD>(g: &Givens<T>, u: &Unknowns<T>) -> T {
+    u.y - g.b
+}
+
+fn builder() -> EquationSystemFor<Givens<f64>, Unknowns<f64>, EqSysStateInit, 2> {
+    EquationSystemBuilder::new_from_f64(
+        Givens { a: 3.0, b: 5.0 },
+        residual_fns_for_generic_params!(Givens, Unknowns; x_eq, y_eq),
+    )
+    .unwrap()
+    .with_scaling_specs(&Unknowns {
+        x: ScalingSpec::Identity,
+        y: ScalingSpec::Identity,
+    })
+    .unwrap()
+}
+
+fn solve(
+    eq_sys: &EquationSystemFor<Givens<f64>, Unknowns<f64>, EqSysSolutionPlan, 2>,
+    initial: &Unknowns<f64>,
+) -> Unknowns<f64> {
+    // refinement would re-solve the whole system around the blocks' solutions as priors
+    let options = SolveOptions::default()
+        .with_refinement(RefinementConfig::skipped())
+        .with_verbosity(Verbosity::Quiet);
+    eq_sys
+        .solve_system_with_options(initial, &options)
+        .unwrap()
+        .solution
+}
+
+#[test]
+fn tikhonov_regularization_pulls_toward_the_initial_unknowns() {
+    let initial = Unknowns { x: 1.0, y: 1.0 };
+    let eq_sys = builder()
+        .with_tikhonov_regularization(1.0)
+        .unwrap()
+        .with_triangularization(&initial)
+        .unwrap();
+
+    // minimizes (x - 3)^2 + (x - 1)^2, and (y - 5)^2 + (y - 1)^2
+    let solution = solve(&eq_sys, &initial);
+
+    assert!((solution.x - 2.0).abs() < 1e-6, "{solution:?}");
+    assert!((solution.y - 3.0).abs() < 1e-6, "{solution:?}");
+}
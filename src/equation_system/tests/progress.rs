@@ -0,0 +1,90 @@
+use std::ops::ControlFlow;
+use std::sync::{Arc, Mutex};
+
+use crate::prelude::{ad_trait::AD, *};
+
+#[solver_params]
+#[derive(Clone, Copy, Debug)]
+struct Givens<T> {
+    a: T,
+    b: T,
+}
+
+#[solver_params]
+#[derive(Clone, Copy, Debug)]
+struct Unknowns<T> {
+    x: T,
+    y: T,
+}
+
+fn x_cubed<T: AD>(g: &Givens<T>, u: &Unknowns<T>) -> T {
+    u.x * u.x * u.x - g.a
+}
+
+fn y_eq<T: AD>(g: &Givens<T>, u: &Unknowns<T>) -> T {
+    u.y - g.b
+}
+
+fn eq_sys() -> EquationSystemFor<Givens<f64>, Unknowns<f64>, EqSysSolutionPlan, 2> {
+    EquationSystemBuilder::new_from_f64(
+        Givens { a: 8.0, b: 3.0 },
+        residual_fns_for_generic_params!(Givens, Unknowns; x_cubed, y_eq),
+    )
+    .unwrap()
+    .with_triangularization(&Unknowns { x: 1.0, y: 1.0 })
+    .unwrap()
+}
+
+#[test]
+fn every_block_and_iteration_is_reported() {
+    let events = Arc::new(Mutex::new(vec![]));
+    let recorded = events.clone();
+    let options = SolveOptions::default()
+        .with_verbosity(Verbosity::Quiet)
+        .with_progress_every_iters(1)
+        .with_progress(move |progress| {
+            recorded.lock().unwrap().push(progress);
+            ControlFlow::Continue(())
+        });
+
+    let report = eq_sys()
+        .solve_system_with_options(&Unknowns { x: 1.0, y: 1.0 }, &options)
+        .unwrap();
+
+    assert!(!report.cancelled);
+    let events = events.lock().unwrap();
+    for block_idx in 0..2 {
+        let started = events.iter().position(|e| {
+            matches!(e, SolveProgress::BlockStarted { block_idx: idx, n_blocks: 2 } if *idx == block_idx)
+        });
+        let finished = events.iter().position(|e| {
+            matches!(e, SolveProgress::BlockFinished { block_idx: idx, n_blocks: 2, .. } if *idx == block_idx)
+        });
+        assert!(started.unwrap() < finished.unwrap(), "{events:?}");
+    }
+    // x^3 = 8 from x = 1 takes more than one iteration
+    assert!(
+        events
+            .iter()
+            .any(|e| matches!(e, SolveProgress::Iteration { .. })),
+        "{events:?}"
+    );
+}
+
+#[test]
+fn breaking_after_the_first_block_cancels_the_solve() {
+    let options = SolveOptions::default()
+        .with_verbosity(Verbosity::Quiet)
+        .with_progress(|progress| match progress {
+            SolveProgress::BlockFinished { .. } => ControlFlow::Break(()),
+            _ => ControlFlow::Continue(()),
+        });
+
+    let report = eq_sys()
+        .solve_system_with_options(&Unknowns { x: 1.0, y: 1.0 }, &options)
+        .unwrap();
+
+    assert!(report.cancelled);
+    assert_eq!(report.blocks.len(), 1);
+    assert_eq!(report.refinement_passes, 0);
+}
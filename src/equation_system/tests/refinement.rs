@@ -0,0 +1,162 @@
+use crate::prelude::{ad_trait::AD, *};
+
+#[solver_params]
+#[derive(Clone, Copy, Debug)]
+struct Givens<T> {
+    a: T,
+    b: T,
+}
+
+#[solver_params]
+#[derive(Clone, Copy, Debug)]
+struct Unknowns<T> {
+    x: T,
+    y: T,
+}
+
+fn sum_eq<T: AD>(g: &Givens<T>, u: &Unknowns<T>) -> T {
+    u.x + u.y - g.a
+}
+
+fn y_from_x<T: AD>(g: &Givens<T>, u: &Unknowns<T>) -> T {
+    u.y - u.x * T::constant(2.0) + g.b
+}
+
+fn sum_is_b<T: AD>(g: &Givens<T>, u: &Unknowns<T>) -> T {
+    u.x + u.y - g.b
+}
+
+/// Solves `x + y = 5`, `y = 2 x - 1` with blocks that each miss the coupling to the other unknown,
+/// so the blocks leave one equation off by 6 from `(1, 1)` and only refinement can fix it.
+fn solve(initial: Unknowns<f64>, refinement: RefinementConfig) -> SolveReport<Unknowns<f64>> {
+    let eq_sys = EquationSystemBuilder::new_from_f64(
+        Givens { a: 5.0, b: 1.0 },
+        residual_fns_for_generic_params!(Givens, Unknowns; sum_eq, y_from_x),
+    )
+    .unwrap()
+    .with_scaling_specs(&Unknowns {
+        x: ScalingSpec::Identity,
+        y: ScalingSpec::Identity,
+    })
+    .unwrap()
+    .with_triangularization_from_incidence(&[[true, false], [false, true]])
+    .unwrap();
+
+    let options = SolveOptions::default()
+        .with_refinement(refinement)
+        .with_verbosity(Verbosity::Quiet);
+    eq_sys
+        .solve_system_with_options(&initial, &options)
+        .unwrap()
+}
+
+#[test]
+fn skipped_refinement_returns_the_block_solution() {
+    let report = solve(Unknowns { x: 1.0, y: 1.0 }, RefinementConfig::skipped());
+
+    // whichever block runs first, x ends up at 4 and the other equation is left off by 6
+    assert_eq!(report.refinement_passes, 0);
+    assert!((report.solution.x - 4.0).abs() < 1e-9, "{report:?}");
+    assert!((report.residual_norm() - 6.0).abs() < 1e-9, "{report:?}");
+}
+
+#[test]
+fn refinement_passes_run_until_the_residual_tolerance() {
+    let refinement = RefinementConfig::default()
+        .with_solver(RefinementSolver::LevenbergMarquardt)
+        .with_passes(5)
+        .with_residual_tol(1e-8);
+
+    let report = solve(Unknowns { x: 1.0, y: 1.0 }, refinement);
+
+    assert!((1..=5).contains(&report.refinement_passes), "{report:?}");
+    assert!(
+        report.residuals.iter().all(|r| r.abs() < 1e-8),
+        "{report:?}"
+    );
+    assert!((report.solution.x - 2.0).abs() < 1e-6, "{report:?}");
+    assert!((report.solution.y - 3.0).abs() < 1e-6, "{report:?}");
+}
+
+#[test]
+fn refinement_does_not_start_within_the_residual_tolerance() {
+    let refinement = RefinementConfig::default()
+        .with_solver(RefinementSolver::LevenbergMarquardt)
+        .with_passes(5)
+        .with_residual_tol(1e-8);
+
+    // the blocks leave the solution where it is, so every residual is already zero
+    let report = solve(Unknowns { x: 2.0, y: 3.0 }, refinement);
+
+    assert_eq!(report.refinement_passes, 0);
+    assert!((report.solution.x - 2.0).abs() < 1e-12, "{report:?}");
+    assert!((report.solution.y - 3.0).abs() < 1e-12, "{report:?}");
+}
+
+#[cfg(feature = "argmin")]
+#[test]
+fn smooth_max_refinement_minimizes_the_worst_residual() {
+    // x + y = 2 and 3 (x + y - 4) = 0 can't both hold, and the blocks don't see the conflict
+    let solve = |solver: RefinementSolver| {
+        let eq_sys = EquationSystemBuilder::new_from_f64(
+            Givens { a: 2.0, b: 4.0 },
+            residual_fns_for_generic_params!(Givens, Unknowns; sum_eq, sum_is_b * 3.0),
+        )
+        .unwrap()
+        .with_scaling_specs(&Unknowns {
+            x: ScalingSpec::Identity,
+            y: ScalingSpec::Identity,
+        })
+        .unwrap()
+        .with_triangularization_from_incidence(&[[true, false], [false, true]])
+        .unwrap();
+        let options = SolveOptions::default()
+            .with_refinement(RefinementConfig::default().with_solver(solver))
+            .with_verbosity(Verbosity::Quiet);
+        eq_sys
+            .solve_system_with_options(&Unknowns { x: 1.0, y: 1.0 }, &options)
+            .unwrap()
+    };
+
+    let sum = solve(RefinementSolver::Lbfgs);
+    let worst_case = solve(RefinementSolver::SmoothMaxLbfgs);
+
+    // least squares settles at x + y = 3.8 with residuals 1.8 and -0.6; the worst case is
+    // minimized at x + y = 3.5, where both are off by 1.5
+    assert!((sum.max_abs_residual() - 1.8).abs() < 0.01, "{sum:?}");
+    assert!(
+        (worst_case.max_abs_residual() - 1.5).abs() < 0.01,
+        "{worst_case:?}"
+    );
+}
+
+#[test]
+fn equations_left_in_conflict_are_named() {
+    let initial = Unknowns { x: 1.0, y: 1.0 };
+    let eq_sys = EquationSystemBuilder::new_from_f64(
+        Givens { a: 2.0, b: 4.0 },
+        residual_fns_for_generic_params!(Givens, Unknowns; sum_eq, sum_is_b),
+    )
+    .unwrap()
+    .with_scaling_specs(&Unknowns {
+        x: ScalingSpec::Identity,
+        y: ScalingSpec::Identity,
+    })
+    .unwrap()
+    .with_triangularization_from_incidence(&[[true, false], [false, true]])
+    .unwrap();
+
+    let options = SolveOptions::default()
+        .with_refinement(RefinementConfig::default().with_conflict_tol(1e-3))
+        .with_verbosity(Verbosity::Quiet);
+    let report = eq_sys
+        .solve_system_with_options(&initial, &options)
+        .unwrap();
+
+    // wherever x + y ends up, the residuals differ by 2, which no step along (1, 1) removes
+    assert_eq!(report.conflicts.len(), 1, "{report:?}");
+    let conflict = &report.conflicts[0];
+    assert_eq!(conflict.fn_names, vec!["sum_eq", "sum_is_b"]);
+    assert_eq!(conflict.unknown_names, vec!["x", "y"]);
+    assert!((conflict.misfit - 2f64.sqrt()).abs() < 1e-9, "{conflict:?}");
+}
@@ -0,0 +1,226 @@
+use ad_trait::forward_ad::adfn::adfn;
+
+use crate::prelude::{ad_trait::AD, *};
+
+#[solver_params]
+#[derive(Clone, Copy, Debug)]
+struct Givens<T> {
+    a: T,
+    b: T,
+}
+
+#[solver_params]
+#[derive(Clone, Copy, Debug)]
+struct Unknowns<T> {
+    x: T,
+    y: T,
+}
+
+/// `x` is the mean of the measurements in `table`.
+fn x_measured<T: AD>(table: Vec<f64>) -> impl Fn(&Givens<T>, &Unknowns<T>) -> T {
+    move |_g, u| u.x - T::constant(table.iter().sum::<f64>() / table.len() as f64)
+}
+
+fn x_eq<T: AD>(g: &Givens<T>, u: &Unknowns<T>) -> T {
+    u.x - g.a
+}
+
+fn y_from_x<T: AD>(g: &Givens<T>, u: &Unknowns<T>) -> T {
+    u.y - u.x * g.b
+}
+
+#[test]
+fn capturing_closures_are_solved_like_fns() {
+    let table = vec![1.5, 2.5, 3.5];
+    let res_fns = ResidualFnsFor::<Givens<f64>, Unknowns<f64>>::default()
+        .with_residual(
+            "x_measured",
+            x_measured::<f64>(table.clone()),
+            x_measured::<adfn<1>>(table),
+        )
+        .with_residual("y_from_x", y_from_x::<f64>, y_from_x::<adfn<1>>);
+    let initial = Unknowns { x: 1.0, y: 1.0 };
+    let eq_sys = EquationSystemBuilder::new_from_f64(Givens { a: 2.0, b: 3.0 }, res_fns)
+        .unwrap()
+        .with_triangularization(&initial)
+        .unwrap();
+
+    let options = SolveOptions::default().with_verbosity(Verbosity::Quiet);
+    let report = eq_sys
+        .solve_system_with_options(&initial, &options)
+        .unwrap();
+
+    assert!((report.solution.x - 2.5).abs() < 1e-6, "{report:?}");
+    assert!((report.solution.y - 7.5).abs() < 1e-6, "{report:?}");
+}
+
+#[test]
+fn named_and_scaled_entries_are_solved() {
+    let initial = Unknowns { x: 1.0, y: 1.0 };
+    let eq_sys = EquationSystemBuilder::new_from_f64(
+        Givens { a: 2.0, b: 3.0 },
+        residual_fns_for_generic_params!(Givens, Unknowns;
+            x_eq as "x off a" * 1.0 / 4.0,
+            y_from_x,
+        ),
+    )
+    .unwrap()
+    .with_triangularization(&initial)
+    .unwrap();
+
+    // the factor applies to the residual itself; the name stays the function's
+    let at_initial = eq_sys.residuals_by_name(&initial);
+    assert_eq!(at_initial["x_eq"], -0.25);
+    assert_eq!(at_initial["y_from_x"], -2.0);
+    assert!(
+        eq_sys.per_fn_residuals_string(&initial).contains("x off a"),
+        "{}",
+        eq_sys.per_fn_residuals_string(&initial)
+    );
+
+    let options = SolveOptions::default().with_verbosity(Verbosity::Quiet);
+    let report = eq_sys
+        .solve_system_with_options(&initial, &options)
+        .unwrap();
+
+    assert_eq!(report.residual_names, vec!["x_eq", "y_from_x"]);
+    assert!((report.solution.x - 2.0).abs() < 1e-6, "{report:?}");
+    assert!((report.solution.y - 6.0).abs() < 1e-6, "{report:?}");
+}
+
+#[test]
+fn residual_metadata_labels_the_solved_residuals() {
+    let res_fns = residual_fns_for_generic_params!(Givens, Unknowns;
+        y_from_x as "jump apex" { units: "m", tag: "jump" },
+    )
+    .with_residual(
+        "x_measured",
+        x_measured::<f64>(vec![2.0]),
+        x_measured::<adfn<1>>(vec![2.0]),
+    )
+    .with_meta(
+        "x_measured",
+        ResidualMeta::default().with_description("x off the measurements"),
+    )
+    .unwrap();
+    let initial = Unknowns { x: 1.0, y: 1.0 };
+    let eq_sys = EquationSystemBuilder::new_from_f64(Givens { a: 0.0, b: 3.0 }, res_fns)
+        .unwrap()
+        .with_triangularization(&initial)
+        .unwrap();
+
+    let options = SolveOptions::default().with_verbosity(Verbosity::Quiet);
+    let report = eq_sys
+        .solve_system_with_options(&initial, &options)
+        .unwrap();
+
+    // lookups and the report keep the function names; printing uses the metadata
+    assert_eq!(report.residual_names, vec!["y_from_x", "x_measured"]);
+    let printed = eq_sys.per_fn_residuals_string(&report.solution);
+    assert!(printed.contains("jump apex #jump: "), "{printed}");
+    assert!(printed.contains(" m\n"), "{printed}");
+    assert!(printed.contains("(x off the measurements)"), "{printed}");
+    assert!((report.solution.y - 6.0).abs() < 1e-6, "{report:?}");
+}
+
+#[test]
+fn metadata_for_an_unknown_residual_is_rejected() {
+    let result = residual_fns_for_generic_params!(Givens, Unknowns; y_from_x)
+        .with_meta("x_measured", ResidualMeta::default().with_units("m"));
+
+    assert!(matches!(
+        result,
+        Err(EqSysError::UnknownResidualName { fn_name }) if fn_name == "x_measured"
+    ));
+}
+
+#[test]
+fn added_residuals_are_solved_with_the_rest() {
+    let initial = Unknowns { x: 1.0, y: 1.0 };
+    let more = ResidualFnsFor::<Givens<f64>, Unknowns<f64>>::default().with_residual(
+        "x_measured",
+        x_measured::<f64>(vec![4.0]),
+        x_measured::<adfn<1>>(vec![4.0]),
+    );
+    let eq_sys = EquationSystemBuilder::new_from_f64(
+        Givens { a: 0.0, b: 3.0 },
+        residual_fns_for_generic_params!(Givens, Unknowns; y_from_x),
+    )
+    .unwrap()
+    .add_residuals(more)
+    .unwrap()
+    .with_triangularization(&initial)
+    .unwrap();
+
+    let options = SolveOptions::default().with_verbosity(Verbosity::Quiet);
+    let report = eq_sys
+        .solve_system_with_options(&initial, &options)
+        .unwrap();
+
+    assert_eq!(report.residual_names, vec!["y_from_x", "x_measured"]);
+    assert!((report.solution.x - 4.0).abs() < 1e-6, "{report:?}");
+    assert!((report.solution.y - 12.0).abs() < 1e-6, "{report:?}");
+}
+
+#[test]
+fn adding_a_residual_name_twice_is_rejected() {
+    let result = EquationSystemBuilder::new_from_f64(
+        Givens { a: 0.0, b: 3.0 },
+        residual_fns_for_generic_params!(Givens, Unknowns; y_from_x),
+    )
+    .unwrap()
+    .add_residuals(residual_fns_for_generic_params!(Givens, Unknowns; y_from_x));
+
+    assert!(matches!(
+        result,
+        Err(EqSysError::DuplicateResidualName {
+            fn_name: "y_from_x"
+        })
+    ));
+}
+
+/// `unknown - target`, for a residual assembled at runtime.
+fn pinned<T: AD>(unknown: usize, target: f64) -> impl Fn(&Givens<T>, &Unknowns<T>) -> T {
+    move |_g, u| [u.x, u.y][unknown] - T::constant(target)
+}
+
+#[test]
+fn pushed_residuals_are_solved() {
+    let mut res_fns = ResidualFnsFor::<Givens<f64>, Unknowns<f64>>::default();
+    for (name, unknown, target) in [("x_pinned", 0, 2.5), ("y_pinned", 1, -1.0)] {
+        res_fns
+            .push(
+                name,
+                pinned::<f64>(unknown, target),
+                pinned::<adfn<1>>(unknown, target),
+            )
+            .unwrap();
+    }
+    let duplicate = res_fns.push("y_pinned", pinned::<f64>(1, 0.0), pinned::<adfn<1>>(1, 0.0));
+    assert!(matches!(
+        duplicate,
+        Err(EqSysError::DuplicateResidualName {
+            fn_name: "y_pinned"
+        })
+    ));
+
+    let initial = Unknowns { x: 1.0, y: 1.0 };
+    let eq_sys = EquationSystemBuilder::new_from_f64(Givens { a: 0.0, b: 0.0 }, res_fns)
+        .unwrap()
+        .with_scaling_specs(&Unknowns {
+            x: ScalingSpec::Identity,
+            y: ScalingSpec::Identity,
+        })
+        .unwrap()
+        .with_triangularization(&initial)
+        .unwrap();
+
+    let options = SolveOptions::default().with_verbosity(Verbosity::Quiet);
+    let report = eq_sys
+        .solve_system_with_options(&initial, &options)
+        .unwrap();
+
+    assert_eq!(report.residual_names, vec!["x_pinned", "y_pinned"]);
+    assert!((report.solution.x - 2.5).abs() < 1e-6, "{report:?}");
+    assert!((report.solution.y + 1.0).abs() < 1e-6, "{report:?}");
+}
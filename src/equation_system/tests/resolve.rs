@@ -0,0 +1,219 @@
+use std::ops::ControlFlow;
+use std::sync::{Arc, Mutex};
+
+use ad_trait::forward_ad::adfn::adfn;
+
+use crate::prelude::{ad_trait::AD, *};
+
+#[solver_params]
+#[derive(Clone, Copy, Debug)]
+struct Givens<T> {
+    a: T,
+    b: T,
+}
+
+#[solver_params]
+#[derive(Clone, Copy, Debug)]
+struct Unknowns<T> {
+    x: T,
+    y: T,
+}
+
+fn x_eq<T: AD>(g: &Givens<T>, u: &Unknowns<T>) -> T {
+    u.x - g.a
+}
+
+fn y_eq<T: AD>(g: &Givens<T>, u: &Unknowns<T>) -> T {
+    u.y - g.b
+}
+
+fn y_from_x<T: AD>(g: &Givens<T>, u: &Unknowns<T>) -> T {
+    u.y - u.x - g.b
+}
+
+#[cfg(feature = "argmin")]
+fn y_squared_eq<T: AD>(g: &Givens<T>, u: &Unknowns<T>) -> T {
+    u.y * u.y - g.b * u.x
+}
+
+fn independent_system() -> EquationSystemFor<Givens<f64>, Unknowns<f64>, EqSysSolutionPlan, 2> {
+    EquationSystemBuilder::new_from_f64(
+        Givens { a: 2.0, b: 3.0 },
+        residual_fns_for_generic_params!(Givens, Unknowns; x_eq, y_eq),
+    )
+    .unwrap()
+    .with_triangularization(&Unknowns { x: 2.0, y: 3.0 })
+    .unwrap()
+}
+
+fn resolve(
+    eq_sys: &mut EquationSystemFor<Givens<f64>, Unknowns<f64>, EqSysSolutionPlan, 2>,
+    givens: Givens<f64>,
+    prev_solution: &Unknowns<f64>,
+    options: &SolveOptions,
+) -> Result<SolveReport<Unknowns<f64>>, EqSysError> {
+    let givens_adfn = givens.to_ad_params::<adfn<1>>();
+    eq_sys.resolve_changed_givens_with_options(givens, givens_adfn, prev_solution, options)
+}
+
+#[test]
+fn new_givens_are_solved_with_the_same_plan() {
+    let prev_solution = Unknowns { x: 2.0, y: 3.0 };
+    let mut eq_sys = independent_system();
+    let n_blocks = eq_sys.solution_plan().blocks.len();
+
+    let givens = Givens { a: 4.0, b: 5.0 };
+    let report = eq_sys
+        .resolve_with_givens_with_options(
+            givens,
+            givens.to_ad_params::<adfn<1>>(),
+            &prev_solution,
+            &SolveOptions::default().with_verbosity(Verbosity::Quiet),
+        )
+        .unwrap();
+
+    assert_eq!(eq_sys.solution_plan().blocks.len(), n_blocks);
+    assert_eq!(report.blocks.len(), n_blocks);
+    assert!((report.solution.x - 4.0).abs() < 1e-6, "{report:?}");
+    assert!((report.solution.y - 5.0).abs() < 1e-6, "{report:?}");
+}
+
+#[cfg(feature = "argmin")]
+#[test]
+fn failed_changed_block_keeps_the_re_solved_ones() {
+    let prev_solution = Unknowns { x: 1.0, y: 2.0 };
+    let mut eq_sys = EquationSystemBuilder::new_from_f64(
+        Givens { a: 1.0, b: 4.0 },
+        residual_fns_for_generic_params!(Givens, Unknowns; x_eq, y_squared_eq),
+    )
+    .unwrap()
+    .with_scaling_specs(&Unknowns {
+        x: ScalingSpec::Identity,
+        y: ScalingSpec::Identity,
+    })
+    .unwrap()
+    .with_triangularization(&prev_solution)
+    .unwrap();
+
+    // y^2 = -2 has no root, so every solver on y's block runs out of iterations short of one
+    let options = SolveOptions::default()
+        .with_max_iters(1)
+        .with_polish_failure(PolishFailurePolicy::Fail)
+        .with_verbosity(Verbosity::Quiet);
+    let result = resolve(
+        &mut eq_sys,
+        Givens { a: 2.0, b: -1.0 },
+        &prev_solution,
+        &options,
+    );
+
+    let Err(EqSysError::PartialSolution(partial)) = result else {
+        panic!("expected a partial solution, got {result:?}");
+    };
+    assert_eq!(partial.failed_block, 1);
+    assert_eq!(partial.solved_blocks.len(), 1);
+    assert!((partial.best_params_so_far[0] - 2.0).abs() < 1e-9);
+    assert_eq!(partial.best_params_so_far[1], 2.0);
+}
+
+#[test]
+fn changed_blocks_report_progress() {
+    let prev_solution = Unknowns { x: 2.0, y: 3.0 };
+    let mut eq_sys = independent_system();
+
+    let events = Arc::new(Mutex::new(vec![]));
+    let recorded = events.clone();
+    let options = SolveOptions::default()
+        .with_verbosity(Verbosity::Quiet)
+        .with_progress(move |progress| {
+            recorded.lock().unwrap().push(progress);
+            ControlFlow::Continue(())
+        });
+    // only x's block is affected
+    let report = resolve(
+        &mut eq_sys,
+        Givens { a: 4.0, b: 3.0 },
+        &prev_solution,
+        &options,
+    )
+    .unwrap();
+
+    assert_eq!(report.blocks.len(), 1);
+    let block_idx = report.blocks[0].block_idx;
+    let events = events.lock().unwrap();
+    let started: Vec<_> = events
+        .iter()
+        .filter(|e| matches!(e, SolveProgress::BlockStarted { .. }))
+        .collect();
+    assert_eq!(
+        started,
+        vec![&SolveProgress::BlockStarted {
+            block_idx,
+            n_blocks: 2
+        }]
+    );
+    assert!(matches!(
+        events.last(),
+        Some(SolveProgress::BlockFinished { block_idx: idx, n_blocks: 2, .. }) if *idx == block_idx
+    ));
+}
+
+#[test]
+fn changed_blocks_stop_when_cancelled() {
+    let prev_solution = Unknowns { x: 2.0, y: 3.0 };
+    let mut eq_sys = independent_system();
+
+    let options = SolveOptions::default()
+        .with_verbosity(Verbosity::Quiet)
+        .with_progress(|progress| match progress {
+            SolveProgress::BlockFinished { .. } => ControlFlow::Break(()),
+            _ => ControlFlow::Continue(()),
+        });
+    let report = resolve(
+        &mut eq_sys,
+        Givens { a: 4.0, b: 5.0 },
+        &prev_solution,
+        &options,
+    )
+    .unwrap();
+
+    assert!(report.cancelled);
+    assert_eq!(report.blocks.len(), 1);
+}
+
+#[test]
+fn changed_givens_re_solve_the_affected_blocks_and_their_dependents() {
+    let prev_solution = Unknowns { x: 2.0, y: 5.0 };
+    let mut eq_sys = EquationSystemBuilder::new_from_f64(
+        Givens { a: 2.0, b: 3.0 },
+        residual_fns_for_generic_params!(Givens, Unknowns; x_eq, y_from_x),
+    )
+    .unwrap()
+    .with_triangularization(&prev_solution)
+    .unwrap();
+    let options = SolveOptions::default().with_verbosity(Verbosity::Quiet);
+
+    // only y's equation reads b, and nothing depends on y
+    let report = resolve(
+        &mut eq_sys,
+        Givens { a: 2.0, b: 4.0 },
+        &prev_solution,
+        &options,
+    )
+    .unwrap();
+    assert_eq!(report.blocks.len(), 1);
+    assert_eq!(report.solution.x, 2.0);
+    assert!((report.solution.y - 6.0).abs() < 1e-6, "{report:?}");
+
+    // a moves x, so y's block is re-solved too
+    let report = resolve(
+        &mut eq_sys,
+        Givens { a: 3.0, b: 4.0 },
+        &report.solution,
+        &options,
+    )
+    .unwrap();
+    assert_eq!(report.blocks.len(), 2);
+    assert!((report.solution.x - 3.0).abs() < 1e-6, "{report:?}");
+    assert!((report.solution.y - 7.0).abs() < 1e-6, "{report:?}");
+}
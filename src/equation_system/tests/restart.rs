@@ -0,0 +1,85 @@
+use crate::prelude::{ad_trait::AD, *};
+
+#[solver_params]
+#[derive(Clone, Copy, Debug)]
+struct Givens<T> {
+    a: T,
+    b: T,
+}
+
+#[solver_params]
+#[derive(Clone, Copy, Debug)]
+struct Unknowns<T> {
+    x: T,
+    y: T,
+}
+
+fn x_sq<T: AD>(g: &Givens<T>, u: &Unknowns<T>) -> T {
+    u.x * u.x - g.a
+}
+
+fn y_eq<T: AD>(g: &Givens<T>, u: &Unknowns<T>) -> T {
+    u.y - g.b
+}
+
+fn solve(policy: RestartPolicy) -> Result<SolveReport<Unknowns<f64>>, EqSysError> {
+    // x^2 is flat at x = 0, so the default solver can't take a single step from there
+    let initial = Unknowns { x: 0.0, y: 0.0 };
+    let eq_sys = EquationSystemBuilder::new_from_f64(
+        Givens { a: 4.0, b: 3.0 },
+        residual_fns_for_generic_params!(Givens, Unknowns; x_sq, y_eq),
+    )
+    .unwrap()
+    .with_scaling_specs(&Unknowns {
+        x: ScalingSpec::Identity,
+        y: ScalingSpec::Identity,
+    })
+    .unwrap()
+    .with_triangularization(&initial)
+    .unwrap();
+
+    let options = SolveOptions::default()
+        .with_restart_policy(policy)
+        .with_verbosity(Verbosity::Quiet);
+    eq_sys.solve_system_with_options(&initial, &options)
+}
+
+#[test]
+fn restart_from_a_perturbed_start_solves_a_stuck_block() {
+    let policy = RestartPolicy {
+        perturbation_scale: 0.5,
+        ..RestartPolicy::default()
+    };
+
+    let report = solve(policy).unwrap();
+
+    let x_block = report
+        .blocks
+        .iter()
+        .find(|b| b.solver == BlockSolverKind::Restart)
+        .expect("a block solved by a restart");
+    assert!(
+        x_block
+            .fallbacks
+            .iter()
+            .any(|f| f.solver != BlockSolverKind::Restart),
+        "{x_block:?}"
+    );
+    assert!((report.solution.x.abs() - 2.0).abs() < 1e-6, "{report:?}");
+    assert!((report.solution.y - 3.0).abs() < 1e-6, "{report:?}");
+}
+
+#[test]
+fn restart_policy_with_a_negative_scale_is_rejected() {
+    let policy = RestartPolicy {
+        perturbation_scale: -0.1,
+        ..RestartPolicy::default()
+    };
+
+    let result = solve(policy);
+
+    assert!(matches!(
+        result,
+        Err(EqSysError::InvalidPerturbationScale { scale }) if scale == -0.1
+    ));
+}
@@ -0,0 +1,66 @@
+use crate::prelude::{ad_trait::AD, *};
+
+#[solver_params]
+#[derive(Clone, Copy, Debug)]
+struct Givens<T> {
+    a: T,
+    b: T,
+}
+
+#[solver_params]
+#[derive(Clone, Copy, Debug)]
+struct Unknowns<T> {
+    x: T,
+    y: T,
+}
+
+fn x_eq<T: AD>(g: &Givens<T>, u: &Unknowns<T>) -> T {
+    u.x - g.a * g.b
+}
+
+fn y_eq<T: AD>(_g: &Givens<T>, u: &Unknowns<T>) -> T {
+    u.y - u.x * u.x
+}
+
+fn builder() -> EquationSystemFor<Givens<f64>, Unknowns<f64>, EqSysStateInit, 2> {
+    EquationSystemBuilder::new_from_f64(
+        Givens { a: 1.0, b: 2.0 },
+        residual_fns_for_generic_params!(Givens, Unknowns; x_eq, y_eq),
+    )
+    .unwrap()
+}
+
+#[test]
+fn promoted_given_is_solved_for_with_the_unknown_fixed() {
+    // which `a` makes x = 8, and what is y there?
+    let swapped = builder()
+        .with_swapped_roles::<2>(&["a"], &["x"], &Unknowns { x: 8.0, y: 1.0 })
+        .unwrap();
+    assert_eq!(swapped.system.unknown_names(), ["y", "a"]);
+
+    let solution = swapped
+        .system
+        .with_triangularization(&swapped.initial_unknowns)
+        .unwrap()
+        .solve_system_with_options(
+            &swapped.initial_unknowns,
+            &SolveOptions::default().with_verbosity(Verbosity::Quiet),
+        )
+        .unwrap();
+    let (givens, unknowns) = swapped.split_solution(&solution).unwrap();
+
+    assert!((givens.a - 4.0).abs() < 1e-6, "{givens:?}");
+    assert_eq!(givens.b, 2.0);
+    assert_eq!(unknowns.x, 8.0);
+    assert!((unknowns.y - 64.0).abs() < 1e-6, "{unknowns:?}");
+}
+
+#[test]
+fn promoting_a_missing_given_is_rejected() {
+    let result = builder().with_swapped_roles::<2>(&["c"], &["x"], &Unknowns { x: 8.0, y: 1.0 });
+
+    assert!(matches!(
+        result,
+        Err(EqSysError::UnknownParamField { field }) if field == "c"
+    ));
+}
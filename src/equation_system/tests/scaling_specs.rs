@@ -0,0 +1,181 @@
+use crate::prelude::{ad_trait::AD, *};
+
+#[solver_params]
+#[derive(Clone, Copy, Debug)]
+struct Givens<T> {
+    a: T,
+    b: T,
+}
+
+#[solver_params]
+#[derive(Clone, Copy, Debug)]
+struct Unknowns<T> {
+    x: T,
+    y: T,
+}
+
+fn x_eq<T: AD>(g: &Givens<T>, u: &Unknowns<T>) -> T {
+    u.x - g.a
+}
+
+fn y_eq<T: AD>(g: &Givens<T>, u: &Unknowns<T>) -> T {
+    u.y - g.b
+}
+
+fn solve(
+    givens: Givens<f64>,
+    specs: &Unknowns<ScalingSpec>,
+    initial: &Unknowns<f64>,
+) -> SolveReport<Unknowns<f64>> {
+    let eq_sys = EquationSystemBuilder::new_from_f64(
+        givens,
+        residual_fns_for_generic_params!(Givens, Unknowns; x_eq, y_eq),
+    )
+    .unwrap()
+    .with_scaling_specs(specs)
+    .unwrap()
+    .with_triangularization(initial)
+    .unwrap();
+
+    let options = SolveOptions::default().with_verbosity(Verbosity::Quiet);
+    eq_sys.solve_system_with_options(initial, &options).unwrap()
+}
+
+#[test]
+fn per_field_links_let_each_unknown_reach_its_sign() {
+    // x has to change sign, which the default log link can't do
+    let report = solve(
+        Givens { a: -3.0, b: -5.0 },
+        &Unknowns {
+            x: ScalingSpec::Asinh,
+            y: ScalingSpec::LogNegative,
+        },
+        &Unknowns { x: 2.0, y: -1.0 },
+    );
+
+    assert!((report.solution.x + 3.0).abs() < 1e-6, "{report:?}");
+    assert!((report.solution.y + 5.0).abs() < 1e-6, "{report:?}");
+}
+
+#[test]
+fn logit_link_solves_inside_its_bounds() {
+    let report = solve(
+        Givens { a: 0.9, b: 3.0 },
+        &Unknowns {
+            x: ScalingSpec::Logit { lb: 0.0, ub: 1.0 },
+            y: ScalingSpec::Identity,
+        },
+        &Unknowns { x: 0.2, y: 1.0 },
+    );
+
+    assert!((report.solution.x - 0.9).abs() < 1e-6, "{report:?}");
+    assert!((report.solution.y - 3.0).abs() < 1e-6, "{report:?}");
+}
+
+#[test]
+fn asinh_links_cross_zero_in_either_direction() {
+    let report = solve(
+        Givens { a: 50.0, b: -4.0 },
+        &Unknowns {
+            x: ScalingSpec::Asinh,
+            y: ScalingSpec::AsinhScaled { scale: 2.0 },
+        },
+        &Unknowns { x: -0.5, y: 1.0 },
+    );
+
+    assert!((report.solution.x - 50.0).abs() < 1e-6, "{report:?}");
+    assert!((report.solution.y + 4.0).abs() < 1e-6, "{report:?}");
+}
+
+#[test]
+fn identity_and_affine_links_solve_unscaled_unknowns() {
+    let report = solve(
+        Givens { a: 130.0, b: -2.0 },
+        &Unknowns {
+            x: ScalingSpec::Affine { scale: 10.0 },
+            y: ScalingSpec::Identity,
+        },
+        &Unknowns { x: 100.0, y: 0.5 },
+    );
+
+    assert!((report.solution.x - 130.0).abs() < 1e-6, "{report:?}");
+    assert!((report.solution.y + 2.0).abs() < 1e-6, "{report:?}");
+}
+
+#[test]
+fn zero_initial_unknowns_are_scaled_without_fake_priors() {
+    let initial = Unknowns { x: 0.0, y: 0.0 };
+    let eq_sys = EquationSystemBuilder::new_from_f64(
+        Givens { a: -3.0, b: 2.0 },
+        residual_fns_for_generic_params!(Givens, Unknowns; x_eq, y_eq),
+    )
+    .unwrap()
+    .with_zero_prior_scale(0.5)
+    .unwrap()
+    .with_triangularization(&initial)
+    .unwrap();
+
+    let options = SolveOptions::default().with_verbosity(Verbosity::Quiet);
+    let report = eq_sys
+        .solve_system_with_options(&initial, &options)
+        .unwrap();
+
+    assert!((report.solution.x + 3.0).abs() < 1e-6, "{report:?}");
+    assert!((report.solution.y - 2.0).abs() < 1e-6, "{report:?}");
+}
+
+#[test]
+fn warm_start_keeps_the_scaling_priors() {
+    let priors = Unknowns { x: 1.0, y: 1.0 };
+    let eq_sys = EquationSystemBuilder::new_from_f64(
+        Givens { a: 2.0, b: 3.0 },
+        residual_fns_for_generic_params!(Givens, Unknowns; x_eq, y_eq),
+    )
+    .unwrap()
+    .with_scaling_priors(&priors)
+    .unwrap()
+    .with_triangularization(&priors)
+    .unwrap();
+
+    let options = SolveOptions::default().with_verbosity(Verbosity::Quiet);
+    for initial in [priors, Unknowns { x: 5.0, y: 0.5 }] {
+        let report = eq_sys
+            .solve_system_with_options(&initial, &options)
+            .unwrap();
+
+        assert!((report.solution.x - 2.0).abs() < 1e-6, "{report:?}");
+        assert!((report.solution.y - 3.0).abs() < 1e-6, "{report:?}");
+    }
+}
+
+#[test]
+fn initial_guess_outside_the_link_domain_fails_the_solve() {
+    let eq_sys = EquationSystemBuilder::new_from_f64(
+        Givens { a: 2.0, b: 3.0 },
+        residual_fns_for_generic_params!(Givens, Unknowns; x_eq, y_eq),
+    )
+    .unwrap()
+    .with_scaling_specs(&Unknowns {
+        x: ScalingSpec::LogPositive,
+        y: ScalingSpec::Identity,
+    })
+    .unwrap()
+    .with_scaling_priors(&Unknowns { x: 4.0, y: 1.0 })
+    .unwrap()
+    .with_triangularization(&Unknowns { x: 4.0, y: 1.0 })
+    .unwrap();
+
+    // a log link can't map x = -1, so the solve must fail rather than run on NaNs
+    let result = eq_sys.solve_system_with_options(
+        &Unknowns { x: -1.0, y: 1.0 },
+        &SolveOptions::default().with_verbosity(Verbosity::Quiet),
+    );
+
+    let Err(EqSysError::InvalidInitialGuess { violations }) = result else {
+        panic!("expected an invalid initial guess, got {result:?}");
+    };
+    assert!(matches!(
+        violations[..],
+        [InitialGuessViolation::OutsideScalingDomain { unknown: "x", value, .. }] if value == -1.0
+    ));
+}
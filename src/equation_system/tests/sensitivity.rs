@@ -0,0 +1,163 @@
+use ad_trait::forward_ad::adfn::adfn;
+
+use crate::prelude::{ad_trait::AD, *};
+
+#[solver_params]
+#[derive(Clone, Copy, Debug)]
+struct Givens<T> {
+    a: T,
+    b: T,
+}
+
+#[solver_params]
+#[derive(Clone, Copy, Debug)]
+struct Unknowns<T> {
+    x: T,
+    y: T,
+}
+
+fn x_eq<T: AD>(g: &Givens<T>, u: &Unknowns<T>) -> T {
+    u.x - g.a * g.b
+}
+
+fn y_eq<T: AD>(_g: &Givens<T>, u: &Unknowns<T>) -> T {
+    u.y - u.x * u.x
+}
+
+fn sum_eq<T: AD>(g: &Givens<T>, u: &Unknowns<T>) -> T {
+    u.x + u.y - g.a
+}
+
+fn sum_eq_twice<T: AD>(g: &Givens<T>, u: &Unknowns<T>) -> T {
+    (u.x + u.y - g.a) * T::constant(2.0)
+}
+
+#[test]
+fn sensitivities_follow_the_chain_rule() {
+    let eq_sys = EquationSystemBuilder::new_from_f64(
+        Givens { a: 2.0, b: 3.0 },
+        residual_fns_for_generic_params!(Givens, Unknowns; x_eq, y_eq),
+    )
+    .unwrap();
+
+    // x = a b and y = x^2, so dx = (b, a) and dy = 2 x dx
+    let sens = eq_sys
+        .sensitivities::<2>(&Unknowns { x: 6.0, y: 36.0 })
+        .unwrap();
+
+    assert_eq!(sens.unknown_names, vec!["x", "y"]);
+    assert_eq!(sens.given_names, vec!["a", "b"]);
+    let expected = [
+        ("x", "a", 3.0),
+        ("x", "b", 2.0),
+        ("y", "a", 36.0),
+        ("y", "b", 24.0),
+    ];
+    for (unknown, given, value) in expected {
+        let actual = sens.get(unknown, given).unwrap();
+        assert!(
+            (actual - value).abs() < 1e-9,
+            "d{unknown}/d{given} = {actual}, expected {value}"
+        );
+    }
+    assert_eq!(sens.get("z", "a"), None);
+
+    let change = sens.predicted_change(&[0.1, 0.0]);
+    assert!((change[0] - 0.3).abs() < 1e-9);
+    assert!((change[1] - 3.6).abs() < 1e-9);
+}
+
+#[test]
+fn dependent_equations_make_sensitivities_singular() {
+    let eq_sys = EquationSystemBuilder::new_from_f64(
+        Givens { a: 2.0, b: 3.0 },
+        residual_fns_for_generic_params!(Givens, Unknowns; sum_eq, sum_eq_twice),
+    )
+    .unwrap();
+
+    let result = eq_sys.sensitivities::<2>(&Unknowns { x: 1.0, y: 1.0 });
+
+    assert!(matches!(
+        result,
+        Err(EqSysError::SingularSensitivityJacobian {
+            rank: 1,
+            n_unknowns: 2,
+        })
+    ));
+}
+
+#[test]
+fn sensitivities_at_the_solution_predict_the_re_solve() {
+    let initial = Unknowns { x: 1.0, y: 1.0 };
+    let options = SolveOptions::default().with_verbosity(Verbosity::Quiet);
+    let mut eq_sys = EquationSystemBuilder::new_from_f64(
+        Givens { a: 2.0, b: 3.0 },
+        residual_fns_for_generic_params!(Givens, Unknowns; x_eq, y_eq),
+    )
+    .unwrap()
+    .with_triangularization(&initial)
+    .unwrap();
+    let solution = eq_sys
+        .solve_system_with_options(&initial, &options)
+        .unwrap()
+        .solution;
+
+    let predicted = eq_sys
+        .sensitivities::<2>(&solution)
+        .unwrap()
+        .predicted_change(&[0.001, 0.0]);
+    let givens = Givens { a: 2.001, b: 3.0 };
+    let moved = eq_sys
+        .resolve_with_givens_with_options(
+            givens,
+            givens.to_ad_params::<adfn<1>>(),
+            &solution,
+            &options,
+        )
+        .unwrap()
+        .solution;
+
+    // first order in the change of a, so off by about (b da)^2 in y
+    assert!(
+        (moved.x - solution.x - predicted[0]).abs() < 1e-6,
+        "{predicted:?}"
+    );
+    assert!(
+        (moved.y - solution.y - predicted[1]).abs() < 1e-4,
+        "{predicted:?}"
+    );
+}
+
+#[test]
+fn solve_report_carries_the_propagated_intervals() {
+    let initial = Unknowns { x: 1.0, y: 1.0 };
+    let eq_sys = EquationSystemBuilder::new_from_f64(
+        Givens { a: 2.0, b: 3.0 },
+        residual_fns_for_generic_params!(Givens, Unknowns; x_eq, y_eq),
+    )
+    .unwrap()
+    .with_triangularization(&initial)
+    .unwrap();
+
+    let uncertainties = GivenUncertainties::new()
+        .with_std_dev("a", 0.1)
+        .with_tolerance("b", 0.3);
+    let report = eq_sys
+        .solve_system_with_uncertainty::<2>(
+            &initial,
+            &SolveOptions::default().with_verbosity(Verbosity::Quiet),
+            &uncertainties,
+        )
+        .unwrap();
+
+    // x = a b has std dev sqrt((b 0.1)^2 + (a 0.3 / sqrt(3))^2), and y = x^2 has 2 x times that
+    let uncertainty = report.uncertainty.unwrap();
+    let x_std_dev = 0.21f64.sqrt();
+    let x = uncertainty.get("x").unwrap();
+    let y = uncertainty.get("y").unwrap();
+    assert!((x.value - 6.0).abs() < 1e-6, "{x:?}");
+    assert!((x.std_dev - x_std_dev).abs() < 1e-6, "{x:?}");
+    assert!((y.std_dev - 12.0 * x_std_dev).abs() < 1e-5, "{y:?}");
+    assert!((x.lower - (x.value - 1.96 * x.std_dev)).abs() < 1e-12);
+    assert_eq!(uncertainty.least_robust(1)[0].name, "y");
+}
@@ -0,0 +1,55 @@
+use crate::prelude::{ad_trait::AD, *};
+
+#[solver_params]
+#[derive(Clone, Copy, Debug)]
+struct Givens<T> {
+    a: T,
+    b: T,
+}
+
+#[solver_params]
+#[derive(Clone, Copy, Debug)]
+struct Unknowns<T> {
+    x: T,
+    y: T,
+}
+
+fn sum_eq<T: AD>(g: &Givens<T>, u: &Unknowns<T>) -> T {
+    u.x + u.y - g.a
+}
+
+fn product_eq<T: AD>(g: &Givens<T>, u: &Unknowns<T>) -> T {
+    u.x * u.y - g.b
+}
+
+#[test]
+fn coupled_2x2_block_is_solved_by_analytic_newton() {
+    let initial = Unknowns { x: 0.5, y: 3.0 };
+    let eq_sys = EquationSystemBuilder::new_from_f64(
+        Givens { a: 3.0, b: 2.0 },
+        residual_fns_for_generic_params!(Givens, Unknowns; sum_eq, product_eq),
+    )
+    .unwrap()
+    .with_scaling_specs(&Unknowns {
+        x: ScalingSpec::Identity,
+        y: ScalingSpec::Identity,
+    })
+    .unwrap()
+    .with_triangularization(&initial)
+    .unwrap();
+
+    let options = SolveOptions::default()
+        .with_analytic_small_blocks(true)
+        .with_refinement(RefinementConfig::skipped())
+        .with_verbosity(Verbosity::Quiet);
+    let report = eq_sys
+        .solve_system_with_options(&initial, &options)
+        .unwrap();
+
+    // x + y = 3 and x y = 2 couple both unknowns into a single block
+    assert_eq!(report.blocks.len(), 1);
+    assert_eq!(report.blocks[0].solver, BlockSolverKind::SmallNewton);
+    assert!(report.blocks[0].fallbacks.is_empty(), "{report:?}");
+    assert!((report.solution.x - 1.0).abs() < 1e-6, "{report:?}");
+    assert!((report.solution.y - 2.0).abs() < 1e-6, "{report:?}");
+}
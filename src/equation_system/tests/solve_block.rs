@@ -0,0 +1,99 @@
+use crate::prelude::{ad_trait::AD, *};
+
+#[solver_params]
+#[derive(Clone, Copy, Debug)]
+struct Givens<T> {
+    a: T,
+    b: T,
+}
+
+#[solver_params]
+#[derive(Clone, Copy, Debug)]
+struct Unknowns<T> {
+    x: T,
+    y: T,
+}
+
+fn x_sq<T: AD>(g: &Givens<T>, u: &Unknowns<T>) -> T {
+    u.x * u.x - g.a
+}
+
+fn y_from_x<T: AD>(g: &Givens<T>, u: &Unknowns<T>) -> T {
+    u.y - u.x * g.b
+}
+
+fn system() -> EquationSystemFor<Givens<f64>, Unknowns<f64>, EqSysSolutionPlan, 2> {
+    EquationSystemBuilder::new_from_f64(
+        Givens { a: 4.0, b: 3.0 },
+        residual_fns_for_generic_params!(Givens, Unknowns; x_sq, y_from_x),
+    )
+    .unwrap()
+    .with_scaling_specs(&Unknowns {
+        x: ScalingSpec::Identity,
+        y: ScalingSpec::Identity,
+    })
+    .unwrap()
+    .with_triangularization(&Unknowns { x: 1.0, y: 1.0 })
+    .unwrap()
+}
+
+fn y_block(eq_sys: &EquationSystemFor<Givens<f64>, Unknowns<f64>, EqSysSolutionPlan, 2>) -> usize {
+    eq_sys
+        .solution_plan()
+        .blocks
+        .iter()
+        .find(|b| b.unknown_idxs == [1])
+        .unwrap()
+        .block_idx
+}
+
+#[test]
+fn solve_block_only_updates_that_block() {
+    let eq_sys = system();
+    let block_idx = y_block(&eq_sys);
+
+    // x is already solved; y is stale
+    let (solution, report) = eq_sys
+        .solve_block(
+            block_idx,
+            &Unknowns { x: 2.0, y: 0.0 },
+            Some(BlockSolverKind::LevenbergMarquardt),
+        )
+        .unwrap();
+
+    assert_eq!(report.block_idx, block_idx);
+    assert_eq!(report.solver, BlockSolverKind::LevenbergMarquardt);
+    assert_eq!(solution.x, 2.0);
+    assert!((solution.y - 6.0).abs() < 1e-6, "{solution:?}");
+}
+
+#[test]
+fn solve_block_without_an_external_solver_is_unavailable() {
+    let eq_sys = system();
+
+    let result = eq_sys.solve_block(
+        y_block(&eq_sys),
+        &Unknowns { x: 2.0, y: 0.0 },
+        Some(BlockSolverKind::External),
+    );
+
+    assert!(matches!(
+        result,
+        Err(EqSysError::BlockSolverUnavailable {
+            solver: BlockSolverKind::External
+        })
+    ));
+}
+
+#[test]
+fn solve_block_past_the_plan_is_rejected() {
+    let result = system().solve_block(2, &Unknowns { x: 2.0, y: 0.0 }, None);
+
+    assert!(matches!(
+        result,
+        Err(EqSysError::BlockIdxOutOfRange {
+            block_idx: 2,
+            n_blocks: 2
+        })
+    ));
+}
@@ -0,0 +1,89 @@
+use crate::prelude::{ad_trait::AD, *};
+
+#[solver_params]
+#[derive(Clone, Copy, Debug)]
+struct Givens<T> {
+    a: T,
+    b: T,
+}
+
+#[solver_params]
+#[derive(Clone, Copy, Debug)]
+struct Unknowns<T> {
+    x: T,
+    y: T,
+}
+
+fn x_eq<T: AD>(g: &Givens<T>, u: &Unknowns<T>) -> T {
+    u.x - g.a
+}
+
+fn y_from_x<T: AD>(g: &Givens<T>, u: &Unknowns<T>) -> T {
+    u.y - u.x * g.b
+}
+
+#[test]
+fn solved_state_keeps_the_results() {
+    let initial = Unknowns { x: 1.0, y: 1.0 };
+    let options = SolveOptions::default().with_verbosity(Verbosity::Quiet);
+    let solved = EquationSystemBuilder::new_from_f64(
+        Givens { a: 2.0, b: 3.0 },
+        residual_fns_for_generic_params!(Givens, Unknowns; x_eq, y_from_x),
+    )
+    .unwrap()
+    .with_triangularization(&initial)
+    .unwrap()
+    .solve_with_options(&initial, &options)
+    .unwrap();
+
+    assert!((solved.solution().x - 2.0).abs() < 1e-6);
+    assert!((solved.solution().y - 6.0).abs() < 1e-6);
+    assert_eq!(solved.residuals().len(), 2);
+    assert!(solved.residual("y_from_x").unwrap().abs() < 1e-6);
+    assert_eq!(solved.residual("z_eq"), None);
+    assert_eq!(solved.block_reports().len(), 2);
+    // d(y_from_x)/dx = -b
+    assert!((solved.jacobian()[(1, 0)] + 3.0).abs() < 1e-9);
+    assert_eq!(solved.jacobian()[(0, 1)], 0.0);
+
+    let solution = *solved.solution();
+    let planned = solved.into_planned();
+    assert_eq!(planned.solution_plan().blocks.len(), 2);
+    let report = planned
+        .solve_system_with_options(&solution, &options)
+        .unwrap();
+    assert!((report.solution.y - 6.0).abs() < 1e-6, "{report:?}");
+}
+
+#[test]
+fn residuals_are_keyed_by_function_name() {
+    let initial = Unknowns { x: 1.0, y: 1.0 };
+    let eq_sys = EquationSystemBuilder::new_from_f64(
+        Givens { a: 2.0, b: 3.0 },
+        residual_fns_for_generic_params!(Givens, Unknowns; x_eq, y_from_x),
+    )
+    .unwrap()
+    .with_triangularization(&initial)
+    .unwrap();
+
+    let at_initial = eq_sys.residuals_by_name(&initial);
+    assert_eq!(
+        at_initial.keys().copied().collect::<Vec<_>>(),
+        ["x_eq", "y_from_x"]
+    );
+    assert_eq!(at_initial["x_eq"], -1.0);
+    assert_eq!(at_initial["y_from_x"], -2.0);
+
+    let report = eq_sys
+        .solve_system_with_options(
+            &initial,
+            &SolveOptions::default().with_verbosity(Verbosity::Quiet),
+        )
+        .unwrap();
+    let at_solution = eq_sys.residuals_by_name(&report.solution);
+    assert!(at_solution["y_from_x"].abs() < 1e-6, "{at_solution:?}");
+    assert_eq!(
+        at_solution.values().copied().collect::<Vec<_>>(),
+        report.residuals
+    );
+}
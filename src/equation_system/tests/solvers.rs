@@ -0,0 +1,397 @@
+use std::time::Duration;
+
+use nalgebra::{DMatrix, DVector};
+
+use crate::{
+    equation_system::sub_problem::solve_subproblem::{
+        block_optimizer::{BlockObjective, BlockOptimizer, BlockTermination},
+        levenberg_marquardt::LevenbergMarquardt,
+        min_norm::PseudoInverseGaussNewton,
+    },
+    prelude::{ad_trait::AD, *},
+};
+
+/// Residuals `A x - b`.
+pub(super) struct Linear {
+    pub a: DMatrix<f64>,
+    pub b: DVector<f64>,
+    pub x0: DVector<f64>,
+}
+
+#[solver_params]
+#[derive(Clone, Copy, Debug)]
+struct Givens<T> {
+    a: T,
+    b: T,
+}
+
+#[solver_params]
+#[derive(Clone, Copy, Debug)]
+struct Unknowns<T> {
+    x: T,
+    y: T,
+}
+
+fn x_cubed<T: AD>(g: &Givens<T>, u: &Unknowns<T>) -> T {
+    u.x * u.x * u.x - g.a
+}
+
+fn y_eq<T: AD>(g: &Givens<T>, u: &Unknowns<T>) -> T {
+    u.y - g.b
+}
+
+#[cfg(feature = "argmin")]
+fn y_squared_plus_b<T: AD>(g: &Givens<T>, u: &Unknowns<T>) -> T {
+    u.y * u.y + g.b
+}
+
+/// Rosenbrock's function as residuals `(10 (x1 - x0^2), 1 - x0)`, with its minimum at `(1, 1)`.
+pub(super) struct Rosenbrock;
+
+impl BlockObjective for Linear {
+    fn block_idx(&self) -> usize {
+        0
+    }
+
+    fn initial_params(&self) -> DVector<f64> {
+        self.x0.clone()
+    }
+
+    fn residuals(&self, p: &DVector<f64>) -> Result<DVector<f64>, EqSysError> {
+        Ok(&self.a * p - &self.b)
+    }
+
+    fn jacobian(&self, _p: &DVector<f64>) -> Result<DMatrix<f64>, EqSysError> {
+        Ok(self.a.clone())
+    }
+
+    fn converged(&self, p: &DVector<f64>) -> Result<bool, EqSysError> {
+        Ok(self.residuals(p)?.norm() < 1e-10)
+    }
+
+    fn timeout(&self) -> Option<Duration> {
+        None
+    }
+}
+
+impl BlockObjective for Rosenbrock {
+    fn block_idx(&self) -> usize {
+        0
+    }
+
+    fn initial_params(&self) -> DVector<f64> {
+        DVector::from_vec(vec![-1.2, 1.0])
+    }
+
+    fn residuals(&self, p: &DVector<f64>) -> Result<DVector<f64>, EqSysError> {
+        Ok(DVector::from_vec(vec![
+            10.0 * (p[1] - p[0] * p[0]),
+            1.0 - p[0],
+        ]))
+    }
+
+    fn jacobian(&self, p: &DVector<f64>) -> Result<DMatrix<f64>, EqSysError> {
+        Ok(DMatrix::from_row_slice(
+            2,
+            2,
+            &[-20.0 * p[0], 10.0, -1.0, 0.0],
+        ))
+    }
+
+    fn converged(&self, p: &DVector<f64>) -> Result<bool, EqSysError> {
+        Ok(self.residuals(p)?.norm() < 1e-10)
+    }
+
+    fn timeout(&self) -> Option<Duration> {
+        None
+    }
+}
+
+fn assert_close(actual: &DVector<f64>, expected: &[f64]) {
+    assert_eq!(actual.len(), expected.len());
+    for (a, e) in actual.iter().zip(expected) {
+        assert!(
+            (a - e).abs() < 1e-8,
+            "got {actual:?}, expected {expected:?}"
+        );
+    }
+}
+
+#[test]
+fn levenberg_marquardt_solves_square_linear_system() {
+    let problem = Linear {
+        a: DMatrix::from_row_slice(2, 2, &[1.0, 1.0, 1.0, -1.0]),
+        b: DVector::from_vec(vec![3.0, 1.0]),
+        x0: DVector::zeros(2),
+    };
+
+    let optimum = LevenbergMarquardt::default().optimize(&problem).unwrap();
+
+    assert_eq!(optimum.termination, BlockTermination::Converged);
+    assert_close(&optimum.params, &[2.0, 1.0]);
+}
+
+#[test]
+fn levenberg_marquardt_solves_rosenbrock() {
+    let optimum = LevenbergMarquardt::default().optimize(&Rosenbrock).unwrap();
+
+    assert_eq!(optimum.termination, BlockTermination::Converged);
+    assert_close(&optimum.params, &[1.0, 1.0]);
+    assert!(optimum.residual_norm < 1e-10);
+}
+
+#[test]
+fn levenberg_marquardt_stops_at_max_iters() {
+    let optimum = LevenbergMarquardt {
+        max_iters: 1,
+        ..Default::default()
+    }
+    .optimize(&Rosenbrock)
+    .unwrap();
+
+    assert_eq!(optimum.termination, BlockTermination::MaxIters);
+    assert_eq!(optimum.iterations, 1);
+}
+
+#[test]
+fn min_norm_picks_the_solution_closest_to_the_start() {
+    // every point of x + y = 2 solves the block; the nearest to (1, -1) is (2, 0)
+    let problem = Linear {
+        a: DMatrix::from_row_slice(1, 2, &[1.0, 1.0]),
+        b: DVector::from_vec(vec![2.0]),
+        x0: DVector::from_vec(vec![1.0, -1.0]),
+    };
+
+    let optimum = PseudoInverseGaussNewton::default()
+        .optimize(&problem)
+        .unwrap();
+
+    assert_eq!(optimum.termination, BlockTermination::Converged);
+    assert_close(&optimum.params, &[2.0, 0.0]);
+}
+
+#[test]
+fn min_norm_from_the_origin_is_the_minimum_norm_solution() {
+    let problem = Linear {
+        a: DMatrix::from_row_slice(1, 3, &[1.0, 2.0, 2.0]),
+        b: DVector::from_vec(vec![9.0]),
+        x0: DVector::zeros(3),
+    };
+
+    let optimum = PseudoInverseGaussNewton::default()
+        .optimize(&problem)
+        .unwrap();
+
+    // A⁺ b = Aᵀ b / |A|^2
+    assert_close(&optimum.params, &[1.0, 2.0, 2.0]);
+}
+
+#[test]
+fn weighted_least_squares_favors_heavier_equations() {
+    // x = 0 with weight 1 and x = 3 with weight 2: the weighted mean is 2
+    let problem = Linear {
+        a: DMatrix::from_row_slice(2, 1, &[1.0, 1.0]),
+        b: DVector::from_vec(vec![0.0, 3.0]),
+        x0: DVector::zeros(1),
+    };
+
+    let optimum = PseudoInverseGaussNewton {
+        weights: Some(vec![1.0, 2.0]),
+        ..Default::default()
+    }
+    .optimize(&problem)
+    .unwrap();
+
+    assert_close(&optimum.params, &[2.0]);
+    assert!((optimum.residual_norm - 6f64.sqrt()).abs() < 1e-8);
+}
+
+#[test]
+fn default_block_solver_reports_its_run() {
+    let initial = Unknowns { x: 1.0, y: 1.0 };
+    let eq_sys = EquationSystemBuilder::new_from_f64(
+        Givens { a: 8.0, b: 3.0 },
+        residual_fns_for_generic_params!(Givens, Unknowns; x_cubed, y_eq),
+    )
+    .unwrap()
+    .with_scaling_specs(&Unknowns {
+        x: ScalingSpec::Identity,
+        y: ScalingSpec::Identity,
+    })
+    .unwrap()
+    .with_triangularization(&initial)
+    .unwrap();
+
+    let options = SolveOptions::default()
+        .with_refinement(RefinementConfig::skipped())
+        .with_verbosity(Verbosity::Quiet);
+    let report = eq_sys
+        .solve_system_with_options(&initial, &options)
+        .unwrap();
+
+    let default_solver = if cfg!(feature = "argmin") {
+        BlockSolverKind::GaussNewton
+    } else {
+        BlockSolverKind::LevenbergMarquardt
+    };
+    assert_eq!(report.blocks.len(), 2);
+    for block in &report.blocks {
+        assert_eq!(block.solver, default_solver);
+        assert!(block.fallbacks.is_empty(), "{block:?}");
+        assert!(block.iterations.is_some(), "{block:?}");
+        assert!(
+            !matches!(
+                block.termination,
+                None | Some(BlockTermination::Timeout | BlockTermination::Unreported)
+            ),
+            "{block:?}"
+        );
+    }
+    assert!((report.solution.x - 2.0).abs() < 1e-6, "{report:?}");
+    assert!((report.solution.y - 3.0).abs() < 1e-6, "{report:?}");
+}
+
+/// Solves `x^3 = 8`, `y = 3` from `(1, 1)` with one iteration per solver run, which solves y's
+/// linear block but leaves x's short of its root.
+#[cfg(feature = "argmin")]
+fn solve_capped(
+    polish_failure: PolishFailurePolicy,
+) -> Result<SolveReport<Unknowns<f64>>, EqSysError> {
+    let initial = Unknowns { x: 1.0, y: 1.0 };
+    let eq_sys = EquationSystemBuilder::new_from_f64(
+        Givens { a: 8.0, b: 3.0 },
+        residual_fns_for_generic_params!(Givens, Unknowns; x_cubed, y_eq),
+    )
+    .unwrap()
+    .with_scaling_specs(&Unknowns {
+        x: ScalingSpec::Identity,
+        y: ScalingSpec::Identity,
+    })
+    .unwrap()
+    .with_triangularization(&initial)
+    .unwrap();
+
+    let options = SolveOptions::default()
+        .with_max_iters(1)
+        .with_max_extra_sweeps(0)
+        .with_polish_failure(polish_failure)
+        .with_refinement(RefinementConfig::skipped())
+        .with_verbosity(Verbosity::Quiet);
+    eq_sys.solve_system_with_options(&initial, &options)
+}
+
+#[cfg(feature = "argmin")]
+#[test]
+fn block_report_records_the_fallback_path() {
+    let report = solve_capped(PolishFailurePolicy::AcceptAnnealed).unwrap();
+
+    let x_block = report
+        .blocks
+        .iter()
+        .find(|b| b.solver == BlockSolverKind::SimulatedAnnealing)
+        .expect("a block that fell back to simulated annealing");
+    assert_eq!(x_block.fallbacks.len(), 1, "{x_block:?}");
+    assert_eq!(x_block.fallbacks[0].solver, BlockSolverKind::GaussNewton);
+    assert!(
+        x_block.fallbacks[0]
+            .error
+            .contains("did not meet its convergence criteria (MaxIters"),
+        "{x_block:?}"
+    );
+    assert_eq!(x_block.termination, Some(BlockTermination::MaxIters));
+    assert_eq!(x_block.iterations, Some(1));
+    let last = report.last_block_report(x_block.block_idx).unwrap();
+    assert_eq!(last.solver, BlockSolverKind::SimulatedAnnealing);
+
+    let y_block = report
+        .blocks
+        .iter()
+        .find(|b| b.block_idx != x_block.block_idx)
+        .unwrap();
+    assert_eq!(y_block.solver, BlockSolverKind::GaussNewton);
+    assert!(y_block.fallbacks.is_empty(), "{y_block:?}");
+    assert!((report.solution.y - 3.0).abs() < 1e-6, "{report:?}");
+}
+
+#[cfg(feature = "argmin")]
+#[test]
+fn failed_polish_keeps_the_annealed_solution_with_a_warning() {
+    let report = solve_capped(PolishFailurePolicy::AcceptAnnealed).unwrap();
+
+    let x_block = report
+        .blocks
+        .iter()
+        .find(|b| b.solver == BlockSolverKind::SimulatedAnnealing)
+        .unwrap();
+    assert!(
+        report.warnings.iter().any(|w| matches!(
+            w,
+            SolveWarning::AnnealingPolishFailed { block_idx, .. } if *block_idx == x_block.block_idx
+        )),
+        "{:?}",
+        report.warnings
+    );
+}
+
+#[cfg(feature = "argmin")]
+#[test]
+fn failed_polish_fails_the_block_if_asked_to() {
+    let error = solve_capped(PolishFailurePolicy::Fail).unwrap_err();
+
+    // a partial solution if y's block happened to be solved first
+    let error = match error {
+        EqSysError::PartialSolution(partial) => partial.error,
+        error => error,
+    };
+    let EqSysError::AnnealingPolishFailed { source, .. } = &error else {
+        panic!("expected a failed polish, got {error:?}");
+    };
+    assert!(
+        matches!(**source, EqSysError::DidNotConverge { .. }),
+        "{error:?}"
+    );
+}
+
+/// The annealed `y` of a solve where `y^2 = -3` has no root, so every local solver on y's block
+/// fails and simulated annealing decides the result.
+#[cfg(feature = "argmin")]
+fn annealed_y(rng_seed: u64) -> f64 {
+    let initial = Unknowns { x: 1.0, y: 1.0 };
+    let report = EquationSystemBuilder::new_from_f64(
+        Givens { a: 8.0, b: 3.0 },
+        residual_fns_for_generic_params!(Givens, Unknowns; x_cubed, y_squared_plus_b),
+    )
+    .unwrap()
+    .with_scaling_specs(&Unknowns {
+        x: ScalingSpec::Identity,
+        y: ScalingSpec::Identity,
+    })
+    .unwrap()
+    .with_triangularization(&initial)
+    .unwrap()
+    .solve_system_with_options(
+        &initial,
+        &SolveOptions::default()
+            .with_max_iters(50)
+            .with_max_extra_sweeps(0)
+            .with_rng_seed(rng_seed)
+            .with_refinement(RefinementConfig::skipped())
+            .with_verbosity(Verbosity::Quiet),
+    )
+    .unwrap();
+    assert!(
+        report
+            .blocks
+            .iter()
+            .any(|b| b.solver == BlockSolverKind::SimulatedAnnealing),
+        "{report:?}"
+    );
+    report.solution.y
+}
+
+#[cfg(feature = "argmin")]
+#[test]
+fn seeded_solves_are_reproducible() {
+    assert_eq!(annealed_y(7).to_bits(), annealed_y(7).to_bits());
+    assert_ne!(annealed_y(7).to_bits(), annealed_y(8).to_bits());
+}
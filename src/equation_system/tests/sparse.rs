@@ -0,0 +1,158 @@
+use nalgebra::{DMatrix, DVector};
+
+use super::solvers::{Linear, Rosenbrock};
+use crate::{
+    equation_system::sub_problem::solve_subproblem::{
+        block_optimizer::{BlockOptimizer, BlockTermination},
+        sparse::{JacobianSparsity, SparseLevenbergMarquardt},
+    },
+    prelude::{ad_trait::AD, *},
+};
+
+#[solver_params]
+#[derive(Clone, Copy, Debug)]
+struct Givens<T> {
+    a: T,
+    b: T,
+}
+
+#[solver_params]
+#[derive(Clone, Copy, Debug)]
+struct Unknowns<T> {
+    x: T,
+    y: T,
+}
+
+fn sum_eq<T: AD>(g: &Givens<T>, u: &Unknowns<T>) -> T {
+    u.x + u.y - g.a
+}
+
+fn diff_eq<T: AD>(g: &Givens<T>, u: &Unknowns<T>) -> T {
+    u.x - u.y - g.b
+}
+
+/// Block of equations 1..4 and unknowns 0..3 of a 5x4 system, with `jac` as its Jacobian.
+fn block_sparsity(jac: &DMatrix<f64>) -> JacobianSparsity {
+    let mut incidence = DMatrix::<f32>::zeros(5, 4);
+    for r in 0..3 {
+        for c in 0..3 {
+            incidence[(r + 1, c)] = if jac[(r, c)] != 0.0 { 1.0 } else { 0.0 };
+        }
+    }
+    // outside the block, so it must not show up in the pattern
+    incidence[(0, 0)] = 1.0;
+    let block = SolutionBlock {
+        block_idx: 0,
+        equation_idxs: vec![3, 1, 2],
+        unknown_idxs: vec![0, 1, 2],
+    };
+    JacobianSparsity::from_incidence(&incidence, &block)
+}
+
+/// `jac * sum of the unit vectors of cols`, counting the calls.
+fn directional<'a>(
+    jac: &'a DMatrix<f64>,
+    passes: &'a mut usize,
+) -> impl FnMut(&[usize]) -> Vec<f64> + 'a {
+    move |cols| {
+        *passes += 1;
+        let mut seed = DVector::zeros(jac.ncols());
+        for &c in cols {
+            seed[c] = 1.0;
+        }
+        (jac * seed).as_slice().to_vec()
+    }
+}
+
+#[test]
+fn colored_assembly_recovers_the_jacobian_in_one_pass_per_color() {
+    // columns 0 and 2 share no row
+    let jac = DMatrix::from_row_slice(3, 3, &[2.0, 1.0, 0.0, 0.0, 3.0, 4.0, 0.0, 5.0, 0.0]);
+    let sparsity = block_sparsity(&jac);
+
+    let mut passes = 0;
+    let assembled = sparsity.assemble_jacobian(3, directional(&jac, &mut passes));
+
+    assert_eq!(DMatrix::from(&assembled), jac);
+    assert_eq!(assembled.nnz(), 5);
+    assert_eq!(passes, 2);
+}
+
+#[test]
+fn dense_extra_rows_get_one_pass_per_column() {
+    let jac = DMatrix::from_row_slice(
+        4,
+        3,
+        &[2.0, 1.0, 0.0, 0.0, 3.0, 4.0, 0.0, 5.0, 0.0, 1.0, 1.0, 1.0],
+    );
+    let sparsity = block_sparsity(&jac.rows(0, 3).into_owned());
+
+    let mut passes = 0;
+    let assembled = sparsity.assemble_jacobian(4, directional(&jac, &mut passes));
+
+    assert_eq!(DMatrix::from(&assembled), jac);
+    assert_eq!(passes, 3);
+}
+
+#[test]
+fn sparse_levenberg_marquardt_solves_square_linear_system() {
+    let problem = Linear {
+        a: DMatrix::from_row_slice(3, 3, &[2.0, 1.0, 0.0, 0.0, 3.0, 4.0, 0.0, 5.0, 0.0]),
+        b: DVector::from_vec(vec![4.0, 10.0, 5.0]),
+        x0: DVector::zeros(3),
+    };
+
+    let optimum = SparseLevenbergMarquardt::default()
+        .optimize(&problem)
+        .unwrap();
+
+    assert_eq!(optimum.termination, BlockTermination::Converged);
+    for (x, expected) in optimum.params.iter().zip([1.5, 1.0, 1.75]) {
+        assert!((x - expected).abs() < 1e-8, "got {:?}", optimum.params);
+    }
+}
+
+#[test]
+fn sparse_levenberg_marquardt_matches_dense_on_rosenbrock() {
+    let optimum = SparseLevenbergMarquardt::default()
+        .optimize(&Rosenbrock)
+        .unwrap();
+
+    assert_eq!(optimum.termination, BlockTermination::Converged);
+    assert!((optimum.params[0] - 1.0).abs() < 1e-8);
+    assert!((optimum.params[1] - 1.0).abs() < 1e-8);
+}
+
+#[test]
+fn large_enough_blocks_are_solved_sparsely() {
+    let initial = Unknowns { x: 0.0, y: 0.0 };
+    let eq_sys = EquationSystemBuilder::new_from_f64(
+        Givens { a: 5.0, b: 1.0 },
+        residual_fns_for_generic_params!(Givens, Unknowns; sum_eq, diff_eq),
+    )
+    .unwrap()
+    .with_scaling_specs(&Unknowns {
+        x: ScalingSpec::Identity,
+        y: ScalingSpec::Identity,
+    })
+    .unwrap()
+    .with_triangularization(&initial)
+    .unwrap();
+
+    let options = SolveOptions::default()
+        .with_sparse_min_block_size(2)
+        .with_refinement(RefinementConfig::skipped())
+        .with_verbosity(Verbosity::Quiet);
+    let report = eq_sys
+        .solve_system_with_options(&initial, &options)
+        .unwrap();
+
+    assert_eq!(report.blocks.len(), 1);
+    assert_eq!(
+        report.blocks[0].solver,
+        BlockSolverKind::SparseLevenbergMarquardt
+    );
+    assert!(report.blocks[0].fallbacks.is_empty(), "{report:?}");
+    assert!((report.solution.x - 3.0).abs() < 1e-6, "{report:?}");
+    assert!((report.solution.y - 2.0).abs() < 1e-6, "{report:?}");
+}
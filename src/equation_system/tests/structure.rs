@@ -0,0 +1,210 @@
+use crate::prelude::{ad_trait::AD, *};
+
+#[solver_params]
+#[derive(Clone, Copy, Debug)]
+struct Givens<T> {
+    a: T,
+    b: T,
+}
+
+#[solver_params]
+#[derive(Clone, Copy, Debug)]
+struct Unknowns<T> {
+    x: T,
+    y: T,
+}
+
+fn x_eq<T: AD>(g: &Givens<T>, u: &Unknowns<T>) -> T {
+    u.x - g.a
+}
+
+/// Only depends on `x` while `x > 0`.
+fn y_from_clamped_x<T: AD>(g: &Givens<T>, u: &Unknowns<T>) -> T {
+    let clamped = if u.x > T::zero() { u.x } else { T::zero() };
+    u.y - clamped * g.b
+}
+
+/// Couples `x` and `y` only through round-off sized terms.
+fn x_with_noise<T: AD>(g: &Givens<T>, u: &Unknowns<T>) -> T {
+    u.x + u.y * T::constant(1e-15) - g.a
+}
+
+fn y_with_noise<T: AD>(g: &Givens<T>, u: &Unknowns<T>) -> T {
+    u.y + u.x * T::constant(1e-15) - g.b
+}
+
+fn builder() -> EquationSystemFor<Givens<f64>, Unknowns<f64>, EqSysStateInit, 2> {
+    EquationSystemBuilder::new_from_f64(
+        Givens { a: 2.0, b: 3.0 },
+        residual_fns_for_generic_params!(Givens, Unknowns; x_eq, y_from_clamped_x),
+    )
+    .unwrap()
+    .with_scaling_specs(&Unknowns {
+        x: ScalingSpec::Identity,
+        y: ScalingSpec::Identity,
+    })
+    .unwrap()
+}
+
+/// One forward pass without refinement, so the result shows whether the plan got the order right.
+fn single_pass() -> SolveOptions {
+    SolveOptions::default()
+        .with_refinement(RefinementConfig::skipped())
+        .with_verbosity(Verbosity::Quiet)
+}
+
+#[test]
+fn explicit_incidence_keeps_a_dependency_the_jacobian_misses() {
+    // at x = -1 the clamp hides y's dependency on x
+    let initial = Unknowns { x: -1.0, y: 0.0 };
+    let eq_sys = builder()
+        .with_triangularization_from_incidence(&[[true, false], [true, true]])
+        .unwrap();
+
+    let report = eq_sys
+        .solve_system_with_options(&initial, &single_pass())
+        .unwrap();
+
+    assert_eq!(eq_sys.solution_plan().blocks[0].unknown_idxs, vec![0]);
+    assert!((report.solution.x - 2.0).abs() < 1e-6, "{report:?}");
+    assert!((report.solution.y - 6.0).abs() < 1e-6, "{report:?}");
+}
+
+#[test]
+fn incidence_needs_one_row_per_equation() {
+    let result = builder().with_triangularization_from_incidence(&[[true, false]]);
+
+    assert!(matches!(
+        result,
+        Err(EqSysError::IncidenceRowsMismatch {
+            n_eqs: 2,
+            n_rows: 1
+        })
+    ));
+}
+
+#[test]
+fn sampled_structure_finds_a_dependency_that_vanishes_at_the_start() {
+    // the clamp's derivative is zero at x = 0 itself, but not at the copies perturbed upwards
+    let initial = Unknowns { x: 0.0, y: 0.0 };
+    let sampling = StructureSampling {
+        n_extra_points: 20,
+        ..StructureSampling::default()
+    };
+    let eq_sys = builder()
+        .with_triangularization_sampled(&initial, &sampling)
+        .unwrap();
+
+    let single_point = eq_sys.replan_at_sampled(&initial, &StructureSampling::single_point());
+    assert!(!single_point.has_same_structure(&eq_sys.replan_at_sampled(&initial, &sampling)));
+    let report = eq_sys
+        .solve_system_with_options(&initial, &single_pass())
+        .unwrap();
+
+    assert_eq!(eq_sys.solution_plan().blocks[0].unknown_idxs, vec![0]);
+    assert!((report.solution.x - 2.0).abs() < 1e-6, "{report:?}");
+    assert!((report.solution.y - 6.0).abs() < 1e-6, "{report:?}");
+}
+
+#[test]
+fn replanning_at_the_solution_picks_up_a_dependency_that_became_active() {
+    let initial = Unknowns { x: -1.0, y: 0.0 };
+    let eq_sys = builder()
+        .with_triangularization_sampled(&initial, &StructureSampling::single_point())
+        .unwrap();
+    let first = eq_sys
+        .solve_system_with_options(
+            &initial,
+            &SolveOptions::default().with_verbosity(Verbosity::Quiet),
+        )
+        .unwrap();
+
+    // x > 0 at the solution, where the clamp passes x through
+    let replanned = eq_sys.replan_at(&first.solution);
+    assert!(!replanned.has_same_structure(
+        &eq_sys.replan_at_sampled(&initial, &StructureSampling::single_point())
+    ));
+    let eq_sys = eq_sys.with_solution_plan(replanned);
+    let report = eq_sys
+        .solve_system_with_options(&initial, &single_pass())
+        .unwrap();
+
+    assert_eq!(eq_sys.solution_plan().blocks[0].unknown_idxs, vec![0]);
+    assert!((report.solution.x - 2.0).abs() < 1e-6, "{report:?}");
+    assert!((report.solution.y - 6.0).abs() < 1e-6, "{report:?}");
+}
+
+#[test]
+fn merged_blocks_are_solved_jointly() {
+    let initial = Unknowns { x: 1.0, y: 1.0 };
+    let eq_sys = builder()
+        .with_triangularization(&initial)
+        .unwrap()
+        .with_merged_blocks(&[0, 1])
+        .unwrap();
+
+    let report = eq_sys
+        .solve_system_with_options(&initial, &single_pass())
+        .unwrap();
+
+    assert_eq!(report.blocks.len(), 1);
+    assert_eq!(eq_sys.solution_plan().blocks[0].unknown_idxs.len(), 2);
+    assert!((report.solution.x - 2.0).abs() < 1e-6, "{report:?}");
+    assert!((report.solution.y - 6.0).abs() < 1e-6, "{report:?}");
+}
+
+#[test]
+fn block_order_against_the_dependencies_is_rejected() {
+    let eq_sys = builder()
+        .with_triangularization(&Unknowns { x: 1.0, y: 1.0 })
+        .unwrap();
+    let x_block = eq_sys
+        .solution_plan()
+        .blocks
+        .iter()
+        .position(|b| b.unknown_idxs == [0])
+        .unwrap();
+
+    // y's block uses x, so it can't come first
+    let result = eq_sys.with_block_order(&[1 - x_block, x_block]);
+
+    assert!(matches!(
+        result,
+        Err(EqSysError::InvalidPlanOverride { .. })
+    ));
+}
+
+#[test]
+fn zero_threshold_drops_round_off_couplings() {
+    let initial = Unknowns { x: 1.0, y: 1.0 };
+    let builder = || {
+        EquationSystemBuilder::new_from_f64(
+            Givens { a: 2.0, b: 3.0 },
+            residual_fns_for_generic_params!(Givens, Unknowns; x_with_noise, y_with_noise),
+        )
+        .unwrap()
+    };
+    let thresholded = StructureSampling {
+        zero_threshold: ZeroThreshold {
+            abs: 0.0,
+            rel: 1e-12,
+        },
+        ..StructureSampling::single_point()
+    };
+
+    let coupled = builder()
+        .with_triangularization_sampled(&initial, &StructureSampling::single_point())
+        .unwrap();
+    let eq_sys = builder()
+        .with_triangularization_sampled(&initial, &thresholded)
+        .unwrap();
+    let report = eq_sys
+        .solve_system_with_options(&initial, &single_pass())
+        .unwrap();
+
+    assert_eq!(coupled.solution_plan().blocks.len(), 1);
+    assert_eq!(eq_sys.solution_plan().blocks.len(), 2);
+    assert_eq!(report.blocks.len(), 2);
+    assert!((report.solution.x - 2.0).abs() < 1e-6, "{report:?}");
+    assert!((report.solution.y - 3.0).abs() < 1e-6, "{report:?}");
+}
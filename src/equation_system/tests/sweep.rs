@@ -0,0 +1,73 @@
+use crate::prelude::{ad_trait::AD, *};
+
+#[solver_params]
+#[derive(Clone, Copy, Debug)]
+struct Givens<T> {
+    a: T,
+    b: T,
+}
+
+#[solver_params]
+#[derive(Clone, Copy, Debug)]
+struct Unknowns<T> {
+    x: T,
+    y: T,
+}
+
+fn x_sq<T: AD>(g: &Givens<T>, u: &Unknowns<T>) -> T {
+    u.x * u.x - g.a
+}
+
+fn y_from_x<T: AD>(g: &Givens<T>, u: &Unknowns<T>) -> T {
+    u.y - u.x * g.b
+}
+
+fn system() -> EquationSystemFor<Givens<f64>, Unknowns<f64>, EqSysSolutionPlan, 2> {
+    EquationSystemBuilder::new_from_f64(
+        Givens { a: 1.0, b: 3.0 },
+        residual_fns_for_generic_params!(Givens, Unknowns; x_sq, y_from_x),
+    )
+    .unwrap()
+    .with_triangularization(&Unknowns { x: 1.0, y: 3.0 })
+    .unwrap()
+}
+
+#[test]
+fn sweep_follows_the_solution_path() {
+    let mut eq_sys = system();
+    let options = SolveOptions::default().with_verbosity(Verbosity::Quiet);
+
+    let points = eq_sys
+        .sweep_given_with_options::<2>(
+            "a",
+            &[4.0, 9.0, 16.0],
+            &Unknowns { x: 1.0, y: 3.0 },
+            &options,
+        )
+        .unwrap();
+
+    let path: Vec<_> = points
+        .iter()
+        .map(|p| (p.given_value, p.solution().x))
+        .collect();
+    for ((a, x), expected_x) in path.iter().zip([2.0, 3.0, 4.0]) {
+        assert!((x - expected_x).abs() < 1e-6, "a = {a}: {path:?}");
+    }
+    assert!((points[2].solution().y - 12.0).abs() < 1e-6);
+
+    // the original givens are back in place
+    let report = eq_sys
+        .solve_system_with_options(&Unknowns { x: 1.5, y: 3.0 }, &options)
+        .unwrap();
+    assert!((report.solution.x - 1.0).abs() < 1e-6, "{report:?}");
+}
+
+#[test]
+fn sweeping_a_missing_given_is_rejected() {
+    let result = system().sweep_given::<2>("c", &[1.0], &Unknowns { x: 1.0, y: 3.0 });
+
+    assert!(matches!(
+        result,
+        Err(EqSysError::UnknownParamField { field }) if field == "c"
+    ));
+}
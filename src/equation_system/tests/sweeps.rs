@@ -0,0 +1,63 @@
+use crate::prelude::{ad_trait::AD, *};
+
+#[solver_params]
+#[derive(Clone, Copy, Debug)]
+struct Givens<T> {
+    a: T,
+    b: T,
+}
+
+#[solver_params]
+#[derive(Clone, Copy, Debug)]
+struct Unknowns<T> {
+    x: T,
+    y: T,
+}
+
+fn x_from_y<T: AD>(g: &Givens<T>, u: &Unknowns<T>) -> T {
+    u.x + u.y * T::constant(0.5) - g.a
+}
+
+fn y_from_x<T: AD>(g: &Givens<T>, u: &Unknowns<T>) -> T {
+    u.y - u.x * T::constant(0.5) - g.b
+}
+
+/// Solves `x = 5 - y / 2`, `y = 1 + x / 2` with blocks that each miss the coupling to the other
+/// unknown; every sweep shrinks the error by a factor of 4 towards `(3.6, 2.8)`.
+fn solve(max_extra_sweeps: usize) -> SolveReport<Unknowns<f64>> {
+    let initial = Unknowns { x: 0.0, y: 0.0 };
+    let eq_sys = EquationSystemBuilder::new_from_f64(
+        Givens { a: 5.0, b: 1.0 },
+        residual_fns_for_generic_params!(Givens, Unknowns; x_from_y, y_from_x),
+    )
+    .unwrap()
+    .with_scaling_specs(&Unknowns {
+        x: ScalingSpec::Identity,
+        y: ScalingSpec::Identity,
+    })
+    .unwrap()
+    .with_triangularization_from_incidence(&[[true, false], [false, true]])
+    .unwrap();
+
+    let options = SolveOptions::default()
+        .with_max_extra_sweeps(max_extra_sweeps)
+        .with_refinement(RefinementConfig::skipped())
+        .with_verbosity(Verbosity::Quiet);
+    eq_sys
+        .solve_system_with_options(&initial, &options)
+        .unwrap()
+}
+
+#[test]
+fn extra_sweeps_fix_the_couplings_the_blocks_miss() {
+    let single = solve(0);
+    let swept = solve(20);
+
+    assert!(single.blocks.iter().all(|b| b.sweep == 0), "{single:?}");
+    assert!(single.residual_norm() > 0.1, "{single:?}");
+
+    assert!(swept.blocks.iter().any(|b| b.sweep > 0), "{swept:?}");
+    assert!(swept.residual_norm() < 1e-6, "{swept:?}");
+    assert!((swept.solution.x - 3.6).abs() < 1e-6, "{swept:?}");
+    assert!((swept.solution.y - 2.8).abs() < 1e-6, "{swept:?}");
+}
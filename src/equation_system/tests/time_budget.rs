@@ -0,0 +1,69 @@
+use std::time::Duration;
+
+use crate::prelude::{ad_trait::AD, *};
+
+#[solver_params]
+#[derive(Clone, Copy, Debug)]
+struct Givens<T> {
+    a: T,
+    b: T,
+}
+
+#[solver_params]
+#[derive(Clone, Copy, Debug)]
+struct Unknowns<T> {
+    x: T,
+    y: T,
+}
+
+fn x_eq<T: AD>(g: &Givens<T>, u: &Unknowns<T>) -> T {
+    u.x - g.a
+}
+
+fn y_from_x<T: AD>(g: &Givens<T>, u: &Unknowns<T>) -> T {
+    u.y - u.x * g.b
+}
+
+fn chain() -> EquationSystemFor<Givens<f64>, Unknowns<f64>, EqSysSolutionPlan, 2> {
+    EquationSystemBuilder::new_from_f64(
+        Givens { a: 2.0, b: 3.0 },
+        residual_fns_for_generic_params!(Givens, Unknowns; x_eq, y_from_x),
+    )
+    .unwrap()
+    .with_triangularization(&Unknowns { x: 1.0, y: 1.0 })
+    .unwrap()
+}
+
+#[test]
+fn exhausted_budget_returns_the_initial_unknowns() {
+    let initial = Unknowns { x: 1.0, y: 1.0 };
+    let options = SolveOptions::default()
+        .with_max_total_time(Duration::ZERO)
+        .with_verbosity(Verbosity::Quiet);
+
+    let report = chain()
+        .solve_system_with_options(&initial, &options)
+        .unwrap();
+
+    assert!(report.time_budget_exhausted);
+    assert!(report.blocks.is_empty());
+    assert_eq!(report.refinement_passes, 0);
+    assert_eq!((report.solution.x, report.solution.y), (1.0, 1.0));
+}
+
+#[test]
+fn generous_budget_solves_the_whole_plan() {
+    let options = SolveOptions::default()
+        .with_max_total_time(Duration::from_secs(60))
+        .with_max_block_time(Duration::from_secs(60))
+        .with_verbosity(Verbosity::Quiet);
+
+    let report = chain()
+        .solve_system_with_options(&Unknowns { x: 1.0, y: 1.0 }, &options)
+        .unwrap();
+
+    assert!(!report.time_budget_exhausted);
+    assert_eq!(report.blocks.len(), 2);
+    assert!((report.solution.x - 2.0).abs() < 1e-4);
+    assert!((report.solution.y - 6.0).abs() < 1e-4);
+}
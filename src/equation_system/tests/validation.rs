@@ -0,0 +1,406 @@
+use nalgebra::ComplexField;
+
+use crate::prelude::{ad_trait::AD, *};
+
+#[solver_params]
+#[derive(Clone, Copy, Debug)]
+struct Givens<T> {
+    a: T,
+    b: T,
+}
+
+#[solver_params]
+#[derive(Clone, Copy, Debug)]
+struct Unknowns<T> {
+    x: T,
+    y: T,
+}
+
+fn x_eq<T: AD>(g: &Givens<T>, u: &Unknowns<T>) -> T {
+    u.x - g.a
+}
+
+fn y_eq<T: AD>(g: &Givens<T>, u: &Unknowns<T>) -> T {
+    g.b / u.y - T::constant(1.0)
+}
+
+fn y_from_sqrt_x<T: AD>(g: &Givens<T>, u: &Unknowns<T>) -> T {
+    u.y - ComplexField::sqrt(u.x) * g.b
+}
+
+fn sum_eq<T: AD>(g: &Givens<T>, u: &Unknowns<T>) -> T {
+    u.x + u.y - g.a
+}
+
+fn sum_eq_twice<T: AD>(g: &Givens<T>, u: &Unknowns<T>) -> T {
+    (u.x + u.y - g.a) * T::constant(2.0)
+}
+
+fn builder() -> EquationSystemFor<Givens<f64>, Unknowns<f64>, EqSysStateInit, 2> {
+    EquationSystemBuilder::new_from_f64(
+        Givens { a: 2.0, b: 3.0 },
+        residual_fns_for_generic_params!(Givens, Unknowns; x_eq, y_eq),
+    )
+    .unwrap()
+}
+
+#[test]
+fn prior_outside_its_link_domain_is_named() {
+    let specs = Unknowns {
+        x: ScalingSpec::Identity,
+        y: ScalingSpec::LogPositive,
+    };
+
+    let result = builder()
+        .with_scaling_specs(&specs)
+        .unwrap()
+        .with_scaling_priors(&Unknowns { x: -1.0, y: -1.0 });
+
+    assert!(matches!(
+        result,
+        Err(EqSysError::InvalidParamScaling {
+            field_name: "y",
+            prior,
+            ..
+        }) if prior == -1.0
+    ));
+}
+
+#[test]
+fn scaling_specs_recheck_earlier_priors() {
+    let result = builder()
+        .with_scaling_priors(&Unknowns { x: 0.0, y: 1.0 })
+        .unwrap()
+        .with_scaling_specs(&Unknowns {
+            x: ScalingSpec::LogPositive,
+            y: ScalingSpec::Identity,
+        });
+
+    assert!(matches!(
+        result,
+        Err(EqSysError::InvalidParamScaling {
+            field_name: "x",
+            ..
+        })
+    ));
+}
+
+#[test]
+fn invalid_param_bounds_are_named() {
+    let bounds = Unknowns {
+        x: ParamBounds::new(0.0, 1.0, 10.0),
+        y: ParamBounds::new(2.0, 1.0, 10.0),
+    };
+
+    assert!(!bounds.y.is_valid());
+    assert!(matches!(
+        builder().with_param_bounds(&bounds),
+        Err(EqSysError::InvalidParamBounds { field: "y", .. })
+    ));
+}
+
+#[test]
+fn logit_specs_need_finite_bounds() {
+    let half_open = ScalingSpec::Logit {
+        lb: 0.0,
+        ub: f64::INFINITY,
+    };
+    assert!(!half_open.is_valid());
+    assert!(
+        !ScalingSpec::Logit {
+            lb: f64::NAN,
+            ub: 1.0
+        }
+        .is_valid()
+    );
+    assert!(ScalingSpec::Logit { lb: 0.0, ub: 1.0 }.is_valid());
+
+    let result = builder().with_scaling_specs(&Unknowns {
+        x: ScalingSpec::Identity,
+        y: half_open,
+    });
+
+    assert!(matches!(
+        result,
+        Err(EqSysError::InvalidScalingSpec { field: "y", .. })
+    ));
+}
+
+#[test]
+fn prior_unknowns_come_from_the_bounds() {
+    let bounds = Unknowns {
+        x: ParamBounds::new(0.0, 2.0, 10.0),
+        y: ParamBounds::new(f64::NEG_INFINITY, 0.5, 1.0),
+    };
+    assert!(builder().prior_unknowns().is_none());
+
+    let priors = builder()
+        .with_param_bounds(&bounds)
+        .unwrap()
+        .prior_unknowns()
+        .unwrap();
+
+    assert_eq!((priors.x, priors.y), (2.0, 0.5));
+    let from_bounds: Unknowns<f64> = unknowns_from_priors(&bounds);
+    assert_eq!((from_bounds.x, from_bounds.y), (2.0, 0.5));
+}
+
+#[test]
+fn scaling_priors_take_precedence_over_bounds() {
+    let bounds = Unknowns {
+        x: ParamBounds::new(0.0, 2.0, 10.0),
+        y: ParamBounds::new(0.0, 0.5, 1.0),
+    };
+
+    let priors = builder()
+        .with_param_bounds(&bounds)
+        .unwrap()
+        .with_scaling_priors(&Unknowns { x: 4.0, y: 0.25 })
+        .unwrap()
+        .prior_unknowns()
+        .unwrap();
+
+    assert_eq!((priors.x, priors.y), (4.0, 0.25));
+}
+
+#[test]
+fn system_solves_from_its_prior_unknowns() {
+    let eq_sys = builder()
+        .with_param_bounds(&Unknowns {
+            x: ParamBounds::new(0.0, 1.0, 10.0),
+            y: ParamBounds::new(0.5, 2.0, 10.0),
+        })
+        .unwrap();
+    let initial = eq_sys.prior_unknowns().unwrap();
+    let eq_sys = eq_sys.with_triangularization(&initial).unwrap();
+
+    let report = eq_sys
+        .solve_system_with_options(
+            &eq_sys.prior_unknowns().unwrap(),
+            &SolveOptions::default().with_verbosity(Verbosity::Quiet),
+        )
+        .unwrap();
+
+    assert!((report.solution.x - 2.0).abs() < 1e-6, "{report:?}");
+    assert!((report.solution.y - 3.0).abs() < 1e-6, "{report:?}");
+}
+
+#[test]
+fn good_initial_guess_has_no_violations() {
+    assert!(
+        builder()
+            .initial_guess_violations(&Unknowns { x: 1.0, y: 1.0 })
+            .is_empty()
+    );
+}
+
+#[test]
+fn non_finite_unknown_hides_residual_violations() {
+    let violations = builder().initial_guess_violations(&Unknowns {
+        x: f64::NAN,
+        y: 0.0,
+    });
+
+    // y = 0 would make `y_eq` infinite, but the NaN is the cause to report
+    assert_eq!(violations.len(), 1);
+    assert!(matches!(
+        violations[0],
+        InitialGuessViolation::NonFiniteUnknown { unknown: "x", value } if value.is_nan()
+    ));
+}
+
+#[test]
+fn non_finite_residual_is_named() {
+    let violations = builder().initial_guess_violations(&Unknowns { x: 1.0, y: 0.0 });
+
+    assert_eq!(
+        violations,
+        vec![InitialGuessViolation::NonFiniteResidual {
+            fn_name: "y_eq",
+            value: f64::INFINITY,
+        }]
+    );
+}
+
+#[test]
+fn guess_outside_scaling_domain_and_box_is_rejected_before_solving() {
+    let eq_sys = builder()
+        .with_param_bounds(&Unknowns {
+            x: ParamBounds::new(0.0, 2.0, 10.0),
+            y: ParamBounds::new(1.0, 3.0, 10.0),
+        })
+        .unwrap();
+    let initial = Unknowns { x: 20.0, y: 3.0 };
+
+    assert_eq!(
+        eq_sys.initial_guess_violations(&initial),
+        vec![
+            InitialGuessViolation::OutsideScalingDomain {
+                unknown: "x",
+                value: 20.0,
+                lb: 0.0,
+                ub: 10.0,
+            },
+            InitialGuessViolation::OutsideBoxConstraints {
+                unknown: "x",
+                value: 20.0,
+                lower: 0.0,
+                upper: 10.0,
+            },
+        ]
+    );
+    assert!(matches!(
+        eq_sys.check_initial_guess(&initial),
+        Err(EqSysError::InvalidInitialGuess { violations }) if violations.len() == 2
+    ));
+}
+
+#[test]
+fn solve_names_what_is_wrong_with_the_guess() {
+    let eq_sys = builder()
+        .with_triangularization(&Unknowns { x: 1.0, y: 1.0 })
+        .unwrap();
+    let options = SolveOptions::default().with_verbosity(Verbosity::Quiet);
+
+    let result = eq_sys.solve_system_with_options(
+        &Unknowns {
+            x: f64::NAN,
+            y: 1.0,
+        },
+        &options,
+    );
+    let Err(EqSysError::InvalidInitialGuess { violations }) = result else {
+        panic!("expected an invalid initial guess, got {result:?}");
+    };
+    assert!(
+        matches!(
+            violations.as_slice(),
+            [InitialGuessViolation::NonFiniteUnknown { unknown: "x", value }] if value.is_nan()
+        ),
+        "{violations:?}"
+    );
+
+    // b / y blows up at y = 0
+    let result = eq_sys.solve_system_with_options(&Unknowns { x: 1.0, y: 0.0 }, &options);
+    let Err(EqSysError::InvalidInitialGuess { violations }) = result else {
+        panic!("expected an invalid initial guess, got {result:?}");
+    };
+    assert!(
+        violations.iter().any(|v| matches!(
+            v,
+            InitialGuessViolation::NonFiniteResidual {
+                fn_name: "y_eq",
+                ..
+            }
+        )),
+        "{violations:?}"
+    );
+}
+
+#[test]
+fn validated_system_solves() {
+    let initial = Unknowns { x: 1.0, y: 1.0 };
+    let eq_sys = builder();
+    assert_eq!(eq_sys.validate(&initial), vec![]);
+
+    let report = eq_sys
+        .with_triangularization(&initial)
+        .unwrap()
+        .solve_system_with_options(
+            &initial,
+            &SolveOptions::default().with_verbosity(Verbosity::Quiet),
+        )
+        .unwrap();
+
+    assert!((report.solution.x - 2.0).abs() < 1e-6, "{report:?}");
+    assert!((report.solution.y - 3.0).abs() < 1e-6, "{report:?}");
+}
+
+#[test]
+fn validation_flags_an_infinite_residual_at_the_initial_guess() {
+    let issues = builder().validate(&Unknowns { x: 1.0, y: 0.0 });
+
+    assert!(
+        issues.iter().any(|issue| issue.is_error()
+            && matches!(
+                issue,
+                ValidationIssue::NonFiniteResidual {
+                    fn_name: "y_eq",
+                    ..
+                }
+            )),
+        "{issues:?}"
+    );
+}
+
+#[test]
+fn infinite_derivative_at_the_initial_guess_names_its_residual() {
+    let eq_sys = EquationSystemBuilder::new_from_f64(
+        Givens { a: 2.0, b: 3.0 },
+        residual_fns_for_generic_params!(Givens, Unknowns; x_eq, y_from_sqrt_x),
+    )
+    .unwrap()
+    .with_scaling_specs(&Unknowns {
+        x: ScalingSpec::Identity,
+        y: ScalingSpec::Identity,
+    })
+    .unwrap()
+    .with_triangularization(&Unknowns { x: 1.0, y: 1.0 })
+    .unwrap();
+
+    // the residual is finite at x = 0, but sqrt's slope there isn't
+    let result = eq_sys.solve_system_with_options(
+        &Unknowns { x: 0.0, y: 1.0 },
+        &SolveOptions::default().with_verbosity(Verbosity::Quiet),
+    );
+
+    let Err(EqSysError::NonFiniteResidual { fn_name, unknowns }) = result else {
+        panic!("expected a non-finite residual, got {result:?}");
+    };
+    assert_eq!(fn_name, "y_from_sqrt_x");
+    // y's column may come out NaN too, as 0 * inf
+    assert!(unknowns.contains(&"x"), "{unknowns:?}");
+}
+
+#[test]
+fn redundant_equation_is_named_and_replacing_it_makes_the_system_solvable() {
+    let initial = Unknowns { x: 1.0, y: 1.0 };
+    let givens = Givens { a: 5.0, b: 3.0 };
+    let redundant = EquationSystemBuilder::new_from_f64(
+        givens,
+        residual_fns_for_generic_params!(Givens, Unknowns; sum_eq, sum_eq_twice),
+    )
+    .unwrap();
+
+    let issues = redundant.validate(&initial);
+    let dependent: Vec<_> = issues
+        .iter()
+        .filter_map(|issue| match issue {
+            ValidationIssue::DependentEquations {
+                fn_name_a,
+                fn_name_b,
+                ..
+            } => Some((*fn_name_a, *fn_name_b)),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(dependent, vec![("sum_eq", "sum_eq_twice")]);
+
+    // swapping the duplicate for an independent equation leaves nothing to warn about
+    let eq_sys = EquationSystemBuilder::new_from_f64(
+        givens,
+        residual_fns_for_generic_params!(Givens, Unknowns; sum_eq, y_eq),
+    )
+    .unwrap();
+    assert_eq!(eq_sys.validate(&initial), vec![]);
+    let report = eq_sys
+        .with_triangularization(&initial)
+        .unwrap()
+        .solve_system_with_options(
+            &initial,
+            &SolveOptions::default().with_verbosity(Verbosity::Quiet),
+        )
+        .unwrap();
+    assert!((report.solution.x - 2.0).abs() < 1e-6, "{report:?}");
+    assert!((report.solution.y - 3.0).abs() < 1e-6, "{report:?}");
+}
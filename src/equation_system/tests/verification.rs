@@ -0,0 +1,62 @@
+use crate::prelude::{ad_trait::AD, *};
+
+#[solver_params]
+#[derive(Clone, Copy, Debug)]
+struct Givens<T> {
+    a: T,
+    b: T,
+}
+
+#[solver_params]
+#[derive(Clone, Copy, Debug)]
+struct Unknowns<T> {
+    x: T,
+    y: T,
+}
+
+fn x_eq<T: AD>(g: &Givens<T>, u: &Unknowns<T>) -> T {
+    u.x - g.a
+}
+
+fn y_from_x<T: AD>(g: &Givens<T>, u: &Unknowns<T>) -> T {
+    u.y - u.x * g.b
+}
+
+#[test]
+fn solved_system_verifies_and_a_perturbed_one_names_its_offender() {
+    let initial = Unknowns { x: 1.0, y: 1.0 };
+    let eq_sys = EquationSystemBuilder::new_from_f64(
+        Givens { a: 2.0, b: 3.0 },
+        residual_fns_for_generic_params!(Givens, Unknowns; x_eq, y_from_x),
+    )
+    .unwrap()
+    .with_triangularization(&initial)
+    .unwrap();
+    let report = eq_sys
+        .solve_system_with_options(
+            &initial,
+            &SolveOptions::default().with_verbosity(Verbosity::Quiet),
+        )
+        .unwrap();
+    let tolerances = ResidualTolerances::new(1e-6);
+
+    assert!(
+        eq_sys
+            .verify_solution(&report.solution, &tolerances)
+            .unwrap()
+            .passed()
+    );
+
+    let perturbed = Unknowns {
+        y: report.solution.y + 0.5,
+        ..report.solution
+    };
+    let verification = eq_sys.verify_solution(&perturbed, &tolerances).unwrap();
+    assert!(!verification.passed());
+    let failures: Vec<_> = verification.failures().iter().map(|c| c.fn_name).collect();
+    assert_eq!(failures, vec!["y_from_x"]);
+    assert_eq!(verification.worst_offenders(1)[0].fn_name, "y_from_x");
+
+    let loose = tolerances.with_tolerance("y_from_x", 1.0);
+    assert!(eq_sys.verify_solution(&perturbed, &loose).unwrap().passed());
+}
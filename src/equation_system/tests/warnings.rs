@@ -0,0 +1,92 @@
+use crate::prelude::{ad_trait::AD, *};
+
+#[solver_params]
+#[derive(Clone, Copy, Debug)]
+struct Givens<T> {
+    a: T,
+    b: T,
+}
+
+#[solver_params]
+#[derive(Clone, Copy, Debug)]
+struct Unknowns<T> {
+    x: T,
+    y: T,
+}
+
+const CLAMP_WARNING: &str = "clamped a negative normal force to zero";
+
+fn x_eq<T: AD>(g: &Givens<T>, u: &Unknowns<T>) -> T {
+    u.x - g.a
+}
+
+/// `y` is `b` times the normal force `x`, which can't pull.
+fn y_from_normal_force<T: AD>(g: &Givens<T>, u: &Unknowns<T>) -> T {
+    let normal_force = if u.x < T::zero() {
+        warn_from_residual(CLAMP_WARNING);
+        T::zero()
+    } else {
+        u.x
+    };
+    u.y - g.b * normal_force
+}
+
+fn solve(a: f64) -> SolveReport<Unknowns<f64>> {
+    let initial = Unknowns { x: 1.0, y: 1.0 };
+    EquationSystemBuilder::new_from_f64(
+        Givens { a, b: 3.0 },
+        residual_fns_for_generic_params!(Givens, Unknowns; x_eq, y_from_normal_force),
+    )
+    .unwrap()
+    .with_scaling_specs(&Unknowns {
+        x: ScalingSpec::Identity,
+        y: ScalingSpec::Identity,
+    })
+    .unwrap()
+    .with_triangularization(&initial)
+    .unwrap()
+    .solve_system_with_options(
+        &initial,
+        &SolveOptions::default()
+            .with_refinement(RefinementConfig::skipped())
+            .with_verbosity(Verbosity::Quiet),
+    )
+    .unwrap()
+}
+
+#[test]
+fn residual_warnings_are_counted_in_the_report() {
+    let report = solve(-1.0);
+
+    assert!((report.solution.x + 1.0).abs() < 1e-6, "{report:?}");
+    assert!(report.solution.y.abs() < 1e-6, "{report:?}");
+    let clamps: Vec<usize> = report
+        .warnings
+        .iter()
+        .filter_map(|w| match w {
+            SolveWarning::Residual {
+                message,
+                occurrences,
+            } if message == CLAMP_WARNING => Some(*occurrences),
+            _ => None,
+        })
+        .collect();
+    // every evaluation at x = -1 clamps, but they all land in one warning
+    assert_eq!(clamps.len(), 1, "{:?}", report.warnings);
+    assert!(clamps[0] >= 1);
+}
+
+#[test]
+fn solves_that_never_clamp_have_no_residual_warnings() {
+    let report = solve(2.0);
+
+    assert!((report.solution.y - 6.0).abs() < 1e-6, "{report:?}");
+    assert!(
+        !report
+            .warnings
+            .iter()
+            .any(|w| matches!(w, SolveWarning::Residual { .. })),
+        "{:?}",
+        report.warnings
+    );
+}
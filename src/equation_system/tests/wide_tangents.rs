@@ -0,0 +1,89 @@
+use nalgebra::DMatrix;
+
+use crate::prelude::{ad_trait::AD, *};
+
+#[solver_params]
+#[derive(Clone, Copy, Debug)]
+struct Givens<T> {
+    a: T,
+    b: T,
+}
+
+#[solver_params]
+#[derive(Clone, Copy, Debug)]
+struct Unknowns<T> {
+    x: T,
+    y: T,
+    z: T,
+}
+
+fn x_sq<T: AD>(g: &Givens<T>, u: &Unknowns<T>) -> T {
+    u.x * u.x - g.a
+}
+
+fn y_cubed<T: AD>(g: &Givens<T>, u: &Unknowns<T>) -> T {
+    u.y * u.y * u.y - g.b
+}
+
+fn y_plus_z<T: AD>(_g: &Givens<T>, u: &Unknowns<T>) -> T {
+    u.y + u.z
+}
+
+fn builder() -> EquationSystemFor<Givens<f64>, Unknowns<f64>, EqSysStateInit, 3> {
+    EquationSystemBuilder::new_from_f64(
+        Givens { a: 1.0, b: 1.0 },
+        residual_fns_for_generic_params!(Givens, Unknowns; x_sq, y_cubed * 2.0, y_plus_z),
+    )
+    .unwrap()
+}
+
+#[test]
+fn wide_tangents_match_the_one_column_jacobian() {
+    let unknowns = [2.0, 3.0, 5.0];
+    let wide = builder()
+        .with_wide_tangents(wide_residual_fns!(Givens, Unknowns, 3; x_sq, y_cubed * 2.0, y_plus_z))
+        .unwrap();
+
+    let (values, jacobian) = wide.full_derivative(&unknowns);
+
+    assert_eq!(values, vec![3.0, 52.0, 8.0]);
+    assert_eq!(
+        jacobian,
+        DMatrix::from_row_slice(3, 3, &[4.0, 0.0, 0.0, 0.0, 54.0, 0.0, 0.0, 1.0, 1.0])
+    );
+    assert_eq!((values, jacobian), builder().full_derivative(&unknowns));
+}
+
+#[test]
+fn colored_wide_tangents_match_the_one_column_jacobian() {
+    let initial = Unknowns {
+        x: 2.0,
+        y: 3.0,
+        z: 5.0,
+    };
+    // two colors fit in a single adfn<2> pass
+    let wide = builder()
+        .with_wide_tangents(wide_residual_fns!(Givens, Unknowns, 2; x_sq, y_cubed * 2.0, y_plus_z))
+        .unwrap()
+        .with_triangularization(&initial)
+        .unwrap()
+        .with_jacobian_coloring();
+    let narrow = builder().with_triangularization(&initial).unwrap();
+
+    let unknowns = [2.0, 3.0, 5.0];
+    assert_eq!(
+        wide.full_derivative(&unknowns),
+        narrow.full_derivative(&unknowns)
+    );
+}
+
+#[test]
+fn wide_tangents_need_one_fn_per_equation() {
+    let result =
+        builder().with_wide_tangents(wide_residual_fns!(Givens, Unknowns, 3; x_sq, y_plus_z));
+
+    assert!(matches!(
+        result,
+        Err(EqSysError::WideTangentFnsLenMismatch { n_eqs: 3, n_fns: 2 })
+    ));
+}
@@ -0,0 +1,238 @@
+use std::fmt;
+
+use ad_trait::forward_ad::adfn::adfn;
+use field_names_and_counts::FieldNames;
+use struct_to_array::StructToArray;
+
+use crate::{
+    equation_system::{EqSysSolutionPlan, EquationSystemBuilder},
+    prelude::*,
+};
+
+/// How uncertain one given is.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum GivenUncertainty {
+    /// Standard deviation of the given.
+    StdDev(f64),
+    /// The given lies anywhere within `±half_width` of its nominal value, treated as uniform
+    /// (standard deviation `half_width / √3`).
+    Tolerance(f64),
+}
+
+impl GivenUncertainty {
+    pub fn std_dev(&self) -> f64 {
+        match *self {
+            GivenUncertainty::StdDev(std_dev) => std_dev,
+            GivenUncertainty::Tolerance(half_width) => half_width / 3f64.sqrt(),
+        }
+    }
+}
+
+/// Per-given uncertainties for `EquationSystemBuilder::propagate_uncertainty`. Givens without an
+/// entry are taken as exact, and the givens are assumed independent.
+#[derive(Clone, Debug)]
+pub struct GivenUncertainties {
+    /// `(given field name, uncertainty)` pairs.
+    pub givens: Vec<(String, GivenUncertainty)>,
+    /// Half-width of the reported intervals in standard deviations; 1.96 (the default) gives 95%
+    /// intervals for normally distributed unknowns.
+    pub z: f64,
+}
+
+impl Default for GivenUncertainties {
+    fn default() -> Self {
+        Self {
+            givens: vec![],
+            z: 1.96,
+        }
+    }
+}
+
+impl GivenUncertainties {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_std_dev(mut self, field: &str, std_dev: f64) -> Self {
+        self.givens
+            .push((field.to_string(), GivenUncertainty::StdDev(std_dev)));
+        self
+    }
+
+    /// The given named `field` lies within `±half_width` of its value, e.g. a designer's "jump_height is 2.0 ± 0.1".
+    pub fn with_tolerance(mut self, field: &str, half_width: f64) -> Self {
+        self.givens
+            .push((field.to_string(), GivenUncertainty::Tolerance(half_width)));
+        self
+    }
+
+    pub fn with_z(mut self, z: f64) -> Self {
+        self.z = z;
+        self
+    }
+}
+
+/// A solved unknown with the interval the given uncertainties put it in.
+#[derive(Clone, Debug, PartialEq)]
+pub struct UnknownInterval {
+    pub name: &'static str,
+    pub value: f64,
+    pub std_dev: f64,
+    pub lower: f64,
+    pub upper: f64,
+}
+
+impl UnknownInterval {
+    /// Standard deviation relative to `|value|`: small for robust unknowns, large for knife-edge
+    /// ones (infinite at a zero value with nonzero spread).
+    pub fn relative_std_dev(&self) -> f64 {
+        if self.std_dev == 0.0 {
+            0.0
+        } else {
+            self.std_dev / self.value.abs()
+        }
+    }
+}
+
+/// Confidence intervals on every unknown from first-order propagation of the given uncertainties
+/// through the sensitivities, in `StructToArray` order.
+#[derive(Clone, Debug, PartialEq)]
+pub struct UncertaintyReport {
+    /// Half-width of the intervals in standard deviations.
+    pub z: f64,
+    pub unknowns: Vec<UnknownInterval>,
+}
+
+impl UncertaintyReport {
+    pub fn get(&self, unknown: &str) -> Option<&UnknownInterval> {
+        self.unknowns.iter().find(|u| u.name == unknown)
+    }
+
+    /// The `n` unknowns with the largest `relative_std_dev`, most sensitive first.
+    pub fn least_robust(&self, n: usize) -> Vec<&UnknownInterval> {
+        let mut unknowns: Vec<&UnknownInterval> = self.unknowns.iter().collect();
+        unknowns.sort_by(|a, b| b.relative_std_dev().total_cmp(&a.relative_std_dev()));
+        unknowns.truncate(n);
+        unknowns
+    }
+}
+
+impl fmt::Display for UncertaintyReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Unknown intervals (±{} std devs):", self.z)?;
+        for u in &self.unknowns {
+            writeln!(
+                f,
+                "  {}: {:.6e} in [{:.6e}, {:.6e}] (std dev {:.3e}, {:.1}%)",
+                u.name,
+                u.value,
+                u.lower,
+                u.upper,
+                u.std_dev,
+                100.0 * u.relative_std_dev()
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl Sensitivities {
+    /// Standard deviation of each unknown (in `StructToArray` order) given independent givens with
+    /// standard deviations `given_std_devs`, to first order: `σ_u² = Σ_g (∂u/∂g)² σ_g²`.
+    pub fn propagate_std_devs(&self, given_std_devs: &[f64]) -> Vec<f64> {
+        (0..self.matrix.nrows())
+            .map(|u| {
+                self.matrix
+                    .row(u)
+                    .iter()
+                    .zip(given_std_devs)
+                    .map(|(s, sd)| (s * sd).powi(2))
+                    .sum::<f64>()
+                    .sqrt()
+            })
+            .collect()
+    }
+}
+
+impl<G64, U64, Gadfn, Uadfn, S, const N: usize> EquationSystemBuilder<G64, U64, Gadfn, Uadfn, S, N>
+where
+    G64: GivenParamsFor<f64, N> + FieldNames,
+    U64: UnknownParamsFor<f64, N>,
+    Gadfn: GivenParamsFor<adfn<1>, N>,
+    Uadfn: UnknownParamsFor<adfn<1>, N>,
+{
+    /// Confidence intervals on the unknowns at `at_solution`, propagating `uncertainties` on the
+    /// givens through `sensitivities` to first order.
+    ///
+    /// Wide intervals flag knife-edge unknowns that small changes of the givens move a lot. The
+    /// linearization holds for uncertainties small enough that the solution moves smoothly; near a
+    /// fold or a branch switch of a residual function, check with re-solves at perturbed givens.
+    /// Fails with `EqSysError::UnknownParamField` if an uncertainty names no given.
+    pub fn propagate_uncertainty<const NG: usize>(
+        &self,
+        at_solution: &U64,
+        uncertainties: &GivenUncertainties,
+    ) -> Result<UncertaintyReport, EqSysError>
+    where
+        G64: StructToArray<f64, NG>,
+        Gadfn: StructToArray<adfn<1>, NG>,
+    {
+        let mut given_std_devs = [0.0; NG];
+        for (field, uncertainty) in &uncertainties.givens {
+            let idx = G64::FIELDS
+                .iter()
+                .position(|&name| name == field.as_str())
+                .ok_or_else(|| EqSysError::UnknownParamField {
+                    field: field.clone(),
+                })?;
+            given_std_devs[idx] = uncertainty.std_dev();
+        }
+
+        let sensitivities = self.sensitivities::<NG>(at_solution)?;
+        let std_devs = sensitivities.propagate_std_devs(&given_std_devs);
+        let unknowns = self
+            .unknown_field_names
+            .iter()
+            .zip(at_solution.to_arr())
+            .zip(std_devs)
+            .map(|((&name, value), std_dev)| UnknownInterval {
+                name,
+                value,
+                std_dev,
+                lower: value - uncertainties.z * std_dev,
+                upper: value + uncertainties.z * std_dev,
+            })
+            .collect();
+        Ok(UncertaintyReport {
+            z: uncertainties.z,
+            unknowns,
+        })
+    }
+}
+
+impl<G64, U64, Gadfn, Uadfn, const N: usize>
+    EquationSystemBuilder<G64, U64, Gadfn, Uadfn, EqSysSolutionPlan, N>
+where
+    G64: GivenParamsFor<f64, N> + FieldNames,
+    U64: UnknownParamsFor<f64, N>,
+    Gadfn: GivenParamsFor<adfn<1>, N>,
+    Uadfn: UnknownParamsFor<adfn<1>, N>,
+{
+    /// Like `solve_system_with_options`, then fills the report's `uncertainty` with the intervals
+    /// `uncertainties` put on the solution (see `propagate_uncertainty`).
+    pub fn solve_system_with_uncertainty<const NG: usize>(
+        &self,
+        initial_unknowns: &U64,
+        options: &SolveOptions,
+        uncertainties: &GivenUncertainties,
+    ) -> Result<SolveReport<U64>, EqSysError>
+    where
+        G64: StructToArray<f64, NG>,
+        Gadfn: StructToArray<adfn<1>, NG>,
+    {
+        let mut report = self.solve_system_with_options(initial_unknowns, options)?;
+        report.uncertainty =
+            Some(self.propagate_uncertainty::<NG>(&report.solution, uncertainties)?);
+        Ok(report)
+    }
+}
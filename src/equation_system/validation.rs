@@ -0,0 +1,460 @@
+use std::fmt;
+
+use ad_trait::forward_ad::adfn::adfn;
+
+use crate::{
+    equation_system::{EqSysStateInit, EquationSystemBuilder, to_binary_matrix},
+    prelude::*,
+};
+
+/// Two equations count as linearly dependent in `EquationSystemBuilder::validate` when the cosine
+/// of the angle between their Jacobian rows is within this of ±1.
+pub const DEPENDENT_ROWS_TOL: f64 = 1e-9;
+
+/// A problem found by `EquationSystemBuilder::validate` before triangularization.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ValidationIssue {
+    /// More or fewer equations than unknowns.
+    ///
+    /// Not an error by itself: the system is then solved in a least-squares / minimum-norm sense.
+    /// `diagnosis` names the parts that are under- and overdetermined.
+    NonSquare {
+        n_eqs: usize,
+        n_unknowns: usize,
+        diagnosis: StructuralDiagnosis,
+    },
+    /// As many equations as unknowns, but the sparsity pattern has no perfect matching of equations
+    /// to unknowns: some unknowns are undetermined while other equations are redundant, so the
+    /// Jacobian is singular everywhere.
+    StructurallySingular {
+        diagnosis: StructuralDiagnosis,
+    },
+    DuplicateFnName {
+        fn_name: &'static str,
+    },
+    NonFiniteResidual {
+        fn_name: &'static str,
+        value: f64,
+    },
+    NonFiniteDerivative {
+        fn_name: &'static str,
+        unknown: &'static str,
+        value: f64,
+    },
+    /// The equation's Jacobian row is zero at the initial guess.
+    EquationUsesNoUnknown {
+        fn_name: &'static str,
+    },
+    /// The unknown's Jacobian column is zero at the initial guess.
+    UnknownInNoEquation {
+        unknown: &'static str,
+    },
+    /// The two equations' Jacobian rows are parallel at the initial guess (`cosine` is the cosine
+    /// of the angle between them), so they constrain the unknowns the same way to first order: a
+    /// duplicated or redundant constraint that makes any block containing both singular.
+    DependentEquations {
+        fn_name_a: &'static str,
+        fn_name_b: &'static str,
+        cosine: f64,
+    },
+    /// The initial value lies outside the domain of the unknown's link function (see `ParamScaling`).
+    InitialValueOutsideScaling {
+        unknown: &'static str,
+        value: f64,
+        reason: &'static str,
+    },
+}
+
+impl ValidationIssue {
+    /// True for issues that will make solving fail or produce a wrong plan; false for warnings.
+    pub fn is_error(&self) -> bool {
+        !matches!(
+            self,
+            ValidationIssue::NonSquare { .. } | ValidationIssue::DependentEquations { .. }
+        )
+    }
+}
+
+impl fmt::Display for ValidationIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ValidationIssue::NonSquare {
+                n_eqs,
+                n_unknowns,
+                diagnosis,
+            } => write!(
+                f,
+                "{n_eqs} equations for {n_unknowns} unknowns; the system will be solved in a least-squares / minimum-norm sense. If it should be square: {diagnosis}"
+            ),
+            ValidationIssue::StructurallySingular { diagnosis } => {
+                write!(f, "the system is structurally singular: {diagnosis}")
+            }
+            ValidationIssue::DuplicateFnName { fn_name } => write!(
+                f,
+                "residual function `{fn_name}` is registered more than once; remove the duplicate or rename one of them"
+            ),
+            ValidationIssue::NonFiniteResidual { fn_name, value } => write!(
+                f,
+                "`{fn_name}` is {value} at the initial guess; move the initial guess into the function's domain"
+            ),
+            ValidationIssue::NonFiniteDerivative {
+                fn_name,
+                unknown,
+                value,
+            } => write!(
+                f,
+                "d `{fn_name}` / d `{unknown}` is {value} at the initial guess; the plan would treat this as a dependency. Move the initial guess away from the singularity"
+            ),
+            ValidationIssue::EquationUsesNoUnknown { fn_name } => write!(
+                f,
+                "`{fn_name}` doesn't depend on any unknown at the initial guess; it can't be solved for anything. If the dependency is hidden by a clamp or branch, use `with_triangularization_from_incidence`"
+            ),
+            ValidationIssue::UnknownInNoEquation { unknown } => write!(
+                f,
+                "no equation depends on `{unknown}` at the initial guess; it will keep its initial value. Add a residual that pins it down"
+            ),
+            ValidationIssue::DependentEquations {
+                fn_name_a,
+                fn_name_b,
+                cosine,
+            } => write!(
+                f,
+                "`{fn_name_a}` and `{fn_name_b}` have parallel Jacobian rows at the initial guess (cosine {cosine:.9}); they look redundant and will make a block containing both singular. Remove one, or check that they aren't the same constraint"
+            ),
+            ValidationIssue::InitialValueOutsideScaling {
+                unknown,
+                value,
+                reason,
+            } => write!(
+                f,
+                "initial value {value} of `{unknown}` {reason}; change the initial guess or the unknown's scaling"
+            ),
+        }
+    }
+}
+
+/// A problem with the initial unknowns found by `EquationSystemBuilder::initial_guess_violations`,
+/// named by unknown or residual function.
+#[derive(Clone, Debug, PartialEq)]
+pub enum InitialGuessViolation {
+    NonFiniteUnknown {
+        unknown: &'static str,
+        value: f64,
+    },
+    /// The initial value lies outside the open domain `(lb, ub)` of the unknown's link function
+    /// (see `ParamScaling::link_domain`), e.g. zero or of the wrong sign under a log link.
+    OutsideScalingDomain {
+        unknown: &'static str,
+        value: f64,
+        lb: f64,
+        ub: f64,
+    },
+    /// The initial value lies outside the unknown's box constraints (see
+    /// `EquationSystemBuilder::with_box_constraints` and `with_param_bounds`).
+    OutsideBoxConstraints {
+        unknown: &'static str,
+        value: f64,
+        lower: f64,
+        upper: f64,
+    },
+    /// The residual function is NaN or infinite at the initial guess. Only checked when every initial value is finite.
+    NonFiniteResidual {
+        fn_name: &'static str,
+        value: f64,
+    },
+}
+
+impl fmt::Display for InitialGuessViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InitialGuessViolation::NonFiniteUnknown { unknown, value } => {
+                write!(f, "`{unknown}` is {value}")
+            }
+            InitialGuessViolation::OutsideScalingDomain {
+                unknown,
+                value,
+                lb,
+                ub,
+            } => write!(
+                f,
+                "`{unknown}` = {value} is outside its scaling's domain ({lb}, {ub})"
+            ),
+            InitialGuessViolation::OutsideBoxConstraints {
+                unknown,
+                value,
+                lower,
+                upper,
+            } => write!(
+                f,
+                "`{unknown}` = {value} is outside its box constraints [{lower}, {upper}]"
+            ),
+            InitialGuessViolation::NonFiniteResidual { fn_name, value } => {
+                write!(f, "residual `{fn_name}` is {value}")
+            }
+        }
+    }
+}
+
+impl<G64, U64, Gadfn, Uadfn, S, const N: usize> EquationSystemBuilder<G64, U64, Gadfn, Uadfn, S, N>
+where
+    G64: GivenParamsFor<f64, N>,
+    U64: UnknownParamsFor<f64, N>,
+    Gadfn: GivenParamsFor<adfn<1>, N>,
+    Uadfn: UnknownParamsFor<adfn<1>, N>,
+{
+    /// Sanity checks of `initial_unknowns` before solving: every value finite, inside its link
+    /// function's domain under the builder's scaling (so nonzero, and on the right side of zero,
+    /// where a log link requires it), and inside its box constraints, and every residual finite
+    /// there.
+    ///
+    /// Returns every violation found, in that order; empty if the guess is fine.
+    pub fn initial_guess_violations(&self, initial_unknowns: &U64) -> Vec<InitialGuessViolation> {
+        let unknowns = initial_unknowns.to_arr();
+        let mut violations = vec![];
+        for (&unknown, &value) in self.unknown_field_names.iter().zip(unknowns.iter()) {
+            if !value.is_finite() {
+                violations.push(InitialGuessViolation::NonFiniteUnknown { unknown, value });
+            }
+        }
+        let all_finite = violations.is_empty();
+
+        let domains = self
+            .param_scaling
+            .link_domains(&self.scaling_center(initial_unknowns).to_arr());
+        for ((&unknown, &value), domain) in self
+            .unknown_field_names
+            .iter()
+            .zip(unknowns.iter())
+            .zip(domains.iter())
+        {
+            if value.is_finite() && !domain.contains(value) {
+                violations.push(InitialGuessViolation::OutsideScalingDomain {
+                    unknown,
+                    value,
+                    lb: domain.lb,
+                    ub: domain.ub,
+                });
+            }
+        }
+
+        if let Some(BoxConstraints { lower, upper }) = &self.box_constraints {
+            for (idx, (&unknown, &value)) in self
+                .unknown_field_names
+                .iter()
+                .zip(unknowns.iter())
+                .enumerate()
+            {
+                if value < lower[idx] || value > upper[idx] {
+                    violations.push(InitialGuessViolation::OutsideBoxConstraints {
+                        unknown,
+                        value,
+                        lower: lower[idx],
+                        upper: upper[idx],
+                    });
+                }
+            }
+        }
+
+        // with a non-finite unknown every residual that uses it is non-finite too; don't bury the cause
+        if all_finite {
+            let residuals = self.raw_res_fn_engine.call(&initial_unknowns.to_vec());
+            for (&fn_name, &value) in self.raw_res_fns.fn_names().iter().zip(residuals.iter()) {
+                if !value.is_finite() {
+                    violations.push(InitialGuessViolation::NonFiniteResidual { fn_name, value });
+                }
+            }
+        }
+        violations
+    }
+
+    /// Fails with `EqSysError::InvalidInitialGuess` listing every `initial_guess_violations` of
+    /// `initial_unknowns`, if there are any.
+    pub fn check_initial_guess(&self, initial_unknowns: &U64) -> Result<(), EqSysError> {
+        let violations = self.initial_guess_violations(initial_unknowns);
+        if violations.is_empty() {
+            Ok(())
+        } else {
+            Err(EqSysError::InvalidInitialGuess { violations })
+        }
+    }
+
+    /// Runs a Dulmage–Mendelsohn decomposition of the sparsity pattern at `unknowns` (sampled like
+    /// `with_triangularization_sampled`), and names the unknowns that are structurally undetermined
+    /// and the equations that over-constrain the rest.
+    ///
+    /// Works for square systems too, where a non-empty diagnosis means the system is structurally singular.
+    pub fn diagnose_structure(
+        &self,
+        unknowns: &U64,
+        sampling: &StructureSampling,
+    ) -> StructuralDiagnosis {
+        let binary_matrix = to_binary_matrix(
+            self.structure_jacobian(unknowns, sampling),
+            &sampling.zero_threshold,
+        );
+        StructuralDiagnosis::from_decomposition(
+            &dulmage_mendelsohn(&binary_matrix),
+            self.raw_res_fns.fn_names(),
+            self.unknown_field_names,
+        )
+    }
+
+    /// Pairs of equations whose Jacobian rows at `unknowns` are parallel to within
+    /// `DEPENDENT_ROWS_TOL`, as `(fn_name_a, fn_name_b, cosine)` in registration order.
+    ///
+    /// Rows that are zero or not finite are skipped.
+    pub fn dependent_equation_pairs(
+        &self,
+        unknowns: &U64,
+    ) -> Vec<(&'static str, &'static str, f64)> {
+        let fn_names = self.raw_res_fns.fn_names();
+        let (_residuals, jacobian) = self.full_derivative(&unknowns.to_arr());
+        let norms: Vec<f64> = (0..jacobian.nrows())
+            .map(|e| jacobian.row(e).norm())
+            .collect();
+        let usable = |e: usize| norms[e].is_finite() && norms[e] > 0.0;
+
+        let mut pairs = vec![];
+        for a in (0..jacobian.nrows()).filter(|&a| usable(a)) {
+            for b in (a + 1..jacobian.nrows()).filter(|&b| usable(b)) {
+                let cosine = jacobian.row(a).dot(&jacobian.row(b)) / (norms[a] * norms[b]);
+                if 1.0 - cosine.abs() <= DEPENDENT_ROWS_TOL {
+                    pairs.push((fn_names[a], fn_names[b], cosine));
+                }
+            }
+        }
+        pairs
+    }
+}
+
+/// Why `value` is outside the domain of unknown `idx`'s link function under `scaling`, if it is.
+fn scaling_domain_problem<const N: usize>(
+    scaling: &ParamScaling<N>,
+    idx: usize,
+    value: f64,
+) -> Option<&'static str> {
+    if !value.is_finite() {
+        return Some("is not finite");
+    }
+    match scaling {
+        ParamScaling::Unscaled | ParamScaling::FromInitialUnknowns { .. } => None,
+        ParamScaling::FromBounds(bounds) => {
+            let ParamBounds { lb, ub, .. } = bounds[idx];
+            if lb.is_finite() && value <= lb {
+                Some("is at or below its lower bound")
+            } else if ub.is_finite() && value >= ub {
+                Some("is at or above its upper bound")
+            } else {
+                None
+            }
+        }
+        ParamScaling::PerField(specs) => match specs[idx] {
+            ScalingSpec::LogPositive if value <= 0.0 => {
+                Some("is not positive, as `ScalingSpec::LogPositive` requires")
+            }
+            ScalingSpec::LogNegative if value >= 0.0 => {
+                Some("is not negative, as `ScalingSpec::LogNegative` requires")
+            }
+            ScalingSpec::Logit { lb, ub } if value <= lb || value >= ub => {
+                Some("is outside the `ScalingSpec::Logit` interval")
+            }
+            _ => None,
+        },
+    }
+}
+
+impl<G64, U64, Gadfn, Uadfn, const N: usize>
+    EquationSystemBuilder<G64, U64, Gadfn, Uadfn, EqSysStateInit, N>
+where
+    G64: GivenParamsFor<f64, N>,
+    U64: UnknownParamsFor<f64, N>,
+    Gadfn: GivenParamsFor<adfn<1>, N>,
+    Uadfn: UnknownParamsFor<adfn<1>, N>,
+{
+    /// Pre-flight checks of the system at `initial_unknowns`, before any triangularization is attempted.
+    ///
+    /// Checks the equation count against the unknown count, duplicate residual names, finiteness of
+    /// the residuals and the Jacobian, equations and unknowns that don't appear in the Jacobian's
+    /// sparsity pattern, linearly dependent pairs of equations, and whether the initial values lie
+    /// in the domain of the unknowns' scaling. An empty result means nothing was found; see
+    /// `ValidationIssue::is_error` to tell errors from warnings.
+    pub fn validate(&self, initial_unknowns: &U64) -> Vec<ValidationIssue> {
+        let fn_names = self.raw_res_fns.fn_names();
+        let mut issues = vec![];
+
+        let diagnosis = self.diagnose_structure(initial_unknowns, &StructureSampling::default());
+        if fn_names.len() != N {
+            issues.push(ValidationIssue::NonSquare {
+                n_eqs: fn_names.len(),
+                n_unknowns: N,
+                diagnosis,
+            });
+        } else if !diagnosis.is_well_posed() {
+            issues.push(ValidationIssue::StructurallySingular { diagnosis });
+        }
+
+        for (i, &fn_name) in fn_names.iter().enumerate() {
+            // report each duplicated name once, at its second occurrence
+            let n_earlier = fn_names[..i]
+                .iter()
+                .filter(|&&name| name == fn_name)
+                .count();
+            if n_earlier == 1 {
+                issues.push(ValidationIssue::DuplicateFnName { fn_name });
+            }
+        }
+
+        let unknowns = initial_unknowns.to_arr();
+        let (residuals, jacobian) = self.full_derivative(&unknowns);
+        for (&fn_name, &value) in fn_names.iter().zip(residuals.iter()) {
+            if !value.is_finite() {
+                issues.push(ValidationIssue::NonFiniteResidual { fn_name, value });
+            }
+        }
+        for (e, &fn_name) in fn_names.iter().enumerate() {
+            let row = jacobian.row(e);
+            for (u, &value) in row.iter().enumerate() {
+                if !value.is_finite() {
+                    issues.push(ValidationIssue::NonFiniteDerivative {
+                        fn_name,
+                        unknown: self.unknown_field_names[u],
+                        value,
+                    });
+                }
+            }
+            if row.iter().all(|&d| d == 0.0) {
+                issues.push(ValidationIssue::EquationUsesNoUnknown { fn_name });
+            }
+        }
+        for (u, &unknown) in self.unknown_field_names.iter().enumerate() {
+            if jacobian.column(u).iter().all(|&d| d == 0.0) {
+                issues.push(ValidationIssue::UnknownInNoEquation { unknown });
+            }
+        }
+
+        for (fn_name_a, fn_name_b, cosine) in self.dependent_equation_pairs(initial_unknowns) {
+            issues.push(ValidationIssue::DependentEquations {
+                fn_name_a,
+                fn_name_b,
+                cosine,
+            });
+        }
+
+        for (idx, (&unknown, &value)) in self
+            .unknown_field_names
+            .iter()
+            .zip(unknowns.iter())
+            .enumerate()
+        {
+            if let Some(reason) = scaling_domain_problem(&self.param_scaling, idx, value) {
+                issues.push(ValidationIssue::InitialValueOutsideScaling {
+                    unknown,
+                    value,
+                    reason,
+                });
+            }
+        }
+
+        issues
+    }
+}
@@ -0,0 +1,149 @@
+use std::fmt;
+
+use ad_trait::forward_ad::adfn::adfn;
+use struct_to_array::StructToVec;
+
+use crate::{equation_system::EquationSystemBuilder, prelude::*};
+
+/// Per-equation tolerances on `|r|` for `EquationSystemBuilder::verify_solution`: `default` for
+/// every equation without an override.
+#[derive(Clone, Debug)]
+pub struct ResidualTolerances {
+    pub default: f64,
+    /// `(residual function name, tolerance)` pairs.
+    pub overrides: Vec<(String, f64)>,
+}
+
+impl ResidualTolerances {
+    pub fn new(default: f64) -> Self {
+        Self {
+            default,
+            overrides: vec![],
+        }
+    }
+
+    /// Sets the tolerance of the residual function named `fn_name`, e.g. a looser one for an equation in large units.
+    pub fn with_tolerance(mut self, fn_name: &str, tolerance: f64) -> Self {
+        self.overrides.push((fn_name.to_string(), tolerance));
+        self
+    }
+}
+
+/// One equation's residual at a verified solution, against its tolerance.
+#[derive(Clone, Debug, PartialEq)]
+pub struct EquationCheck {
+    pub fn_name: &'static str,
+    pub residual: f64,
+    pub tolerance: f64,
+}
+
+impl EquationCheck {
+    /// `|r| <= tolerance`; false for non-finite residuals.
+    pub fn passed(&self) -> bool {
+        self.residual.abs() <= self.tolerance
+    }
+
+    /// `|r| / tolerance`: how many tolerances the equation is off by (infinite for non-finite residuals).
+    pub fn violation_ratio(&self) -> f64 {
+        if self.residual.is_finite() {
+            self.residual.abs() / self.tolerance
+        } else {
+            f64::INFINITY
+        }
+    }
+}
+
+/// Result of `EquationSystemBuilder::verify_solution`: one check per equation, in registration order.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SolutionVerification {
+    pub equations: Vec<EquationCheck>,
+}
+
+impl SolutionVerification {
+    /// True if every equation is within its tolerance.
+    pub fn passed(&self) -> bool {
+        self.equations.iter().all(EquationCheck::passed)
+    }
+
+    pub fn failures(&self) -> Vec<&EquationCheck> {
+        self.equations
+            .iter()
+            .filter(|check| !check.passed())
+            .collect()
+    }
+
+    /// The `n` equations with the largest `violation_ratio`, worst first, whether they passed or not.
+    pub fn worst_offenders(&self, n: usize) -> Vec<&EquationCheck> {
+        let mut checks: Vec<&EquationCheck> = self.equations.iter().collect();
+        checks.sort_by(|a, b| b.violation_ratio().total_cmp(&a.violation_ratio()));
+        checks.truncate(n);
+        checks
+    }
+}
+
+impl fmt::Display for SolutionVerification {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let n_failed = self.failures().len();
+        writeln!(
+            f,
+            "{}: {} of {} equations within tolerance",
+            if n_failed == 0 { "PASSED" } else { "FAILED" },
+            self.equations.len() - n_failed,
+            self.equations.len()
+        )?;
+        for check in &self.equations {
+            writeln!(
+                f,
+                "  [{}] {}: {:.6e} (tolerance {:.1e})",
+                if check.passed() { "ok" } else { "FAIL" },
+                check.fn_name,
+                check.residual,
+                check.tolerance
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl<G64, U64, Gadfn, Uadfn, S, const N: usize> EquationSystemBuilder<G64, U64, Gadfn, Uadfn, S, N>
+where
+    G64: GivenParamsFor<f64, N>,
+    U64: UnknownParamsFor<f64, N>,
+    Gadfn: GivenParamsFor<adfn<1>, N>,
+    Uadfn: UnknownParamsFor<adfn<1>, N>,
+{
+    /// Re-evaluates every raw residual at `solution` (e.g. `SolveReport::solution`) and checks it
+    /// against its tolerance in `tolerances`.
+    ///
+    /// Fails with `EqSysError::UnknownResidualName` if a tolerance override names no residual function.
+    pub fn verify_solution(
+        &self,
+        solution: &U64,
+        tolerances: &ResidualTolerances,
+    ) -> Result<SolutionVerification, EqSysError> {
+        let fn_names = self.raw_res_fns.fn_names();
+        let mut fn_tolerances = vec![tolerances.default; fn_names.len()];
+        for (fn_name, tolerance) in &tolerances.overrides {
+            let idx = fn_names
+                .iter()
+                .position(|name| *name == fn_name.as_str())
+                .ok_or_else(|| EqSysError::UnknownResidualName {
+                    fn_name: fn_name.clone(),
+                })?;
+            fn_tolerances[idx] = *tolerance;
+        }
+
+        let residuals = self.raw_res_fn_engine.call(&solution.to_vec());
+        let equations = fn_names
+            .iter()
+            .zip(residuals)
+            .zip(fn_tolerances)
+            .map(|((&fn_name, residual), tolerance)| EquationCheck {
+                fn_name,
+                residual,
+                tolerance,
+            })
+            .collect();
+        Ok(SolutionVerification { equations })
+    }
+}
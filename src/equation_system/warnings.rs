@@ -0,0 +1,200 @@
+//! Non-fatal issues noticed while solving, collected per `solve_system` call into `SolveReport::warnings`.
+
+use std::cell::RefCell;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+
+use crate::equation_system::solution_plan::BlockConditioning;
+use crate::equation_system::sub_problem::solve_subproblem::block_optimizer::BlockTermination;
+
+/// Fraction of a simulated-annealing run's proposals clamped to
+/// `SimulatedAnnealingConfig::max_abs_step` above which the run gets a
+/// `SolveWarning::AnnealingStepsClamped`.
+pub const ANNEALING_CLAMP_WARN_FRACTION: f64 = 0.05;
+
+/// Distance of a solved unknown from a finite bound of its link function's domain, relative to the
+/// prior's distance from it, below which the unknown counts as pinned at the bound.
+pub const PINNED_REL_TOL: f64 = 1e-6;
+
+/// A non-fatal issue with a solve: the solution was accepted, but may be less trustworthy than its residuals suggest.
+#[derive(Clone, Debug, PartialEq)]
+pub enum SolveWarning {
+    /// Raised by a residual function through `warn_from_residual`, e.g. a negative normal force it
+    /// clamped to zero.
+    ///
+    /// Repeats of the same message are counted instead of listed.
+    Residual { message: String, occurrences: usize },
+    /// The block's Jacobian is near-singular at its accepted solution, so the solution may be
+    /// poorly determined.
+    ///
+    /// Only checked for builders with `EquationSystemBuilder::with_block_conditioning`.
+    NearSingularJacobian { conditioning: BlockConditioning },
+    /// A solved unknown sits at a finite bound of its link function's domain (e.g. the default log
+    /// scaling's floor at 1% of the prior), where the opt-space coordinate runs off to infinity.
+    ///
+    /// The true solution probably lies beyond the bound; widen the scaling or center it elsewhere
+    /// (see `EquationSystemBuilder::with_scaling_priors`).
+    PinnedAtScalingBound {
+        block_idx: usize,
+        unknown: &'static str,
+        value: f64,
+        bound: f64,
+    },
+    /// More than `ANNEALING_CLAMP_WARN_FRACTION` of a simulated-annealing run's proposals were
+    /// clamped to `SimulatedAnnealingConfig::max_abs_step`, so the search was effectively capped in
+    /// reach.
+    AnnealingStepsClamped {
+        block_idx: usize,
+        clamped: u64,
+        proposals: u64,
+    },
+    /// A block solver hit its iteration cap or stalled with the block's residual norm still at or
+    /// above `DEFAULT_RESIDUAL_NORM_TOL`, e.g. at the least-squares solution of an overdetermined
+    /// block or short of a root it couldn't reach.
+    UnconvergedBlock {
+        block_idx: usize,
+        solver: String,
+        termination: BlockTermination,
+        residual_norm: f64,
+    },
+    /// The Gauss-Newton polish after a successful simulated-annealing run on the block failed, and
+    /// the annealed solution was kept (see `SolveOptions::polish_failure`).
+    AnnealingPolishFailed { block_idx: usize, error: String },
+    /// The augmented-Lagrangian outer loop (see `AugmentedLagrangian`) stopped after
+    /// `outer_iterations` solves with a constraint still violated by more than `tol`.
+    ConstraintsViolated {
+        max_violation: f64,
+        tol: f64,
+        outer_iterations: usize,
+    },
+}
+
+impl fmt::Display for SolveWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SolveWarning::Residual {
+                message,
+                occurrences,
+            } => write!(f, "{message} ({occurrences}x)"),
+            SolveWarning::NearSingularJacobian { conditioning } => write!(
+                f,
+                "block {} is near-singular at its solution: {}",
+                conditioning.block_idx, conditioning
+            ),
+            SolveWarning::PinnedAtScalingBound {
+                block_idx,
+                unknown,
+                value,
+                bound,
+            } => write!(
+                f,
+                "block {block_idx}: `{unknown}` = {value:.6e} is pinned at its scaling bound {bound:.6e}"
+            ),
+            SolveWarning::AnnealingStepsClamped {
+                block_idx,
+                clamped,
+                proposals,
+            } => write!(
+                f,
+                "block {block_idx}: simulated annealing clamped {clamped} of {proposals} proposals to max_abs_step"
+            ),
+            SolveWarning::UnconvergedBlock {
+                block_idx,
+                solver,
+                termination,
+                residual_norm,
+            } => write!(
+                f,
+                "block {block_idx}: {solver} stopped ({termination:?}) with residual norm {residual_norm:.6e}"
+            ),
+            SolveWarning::AnnealingPolishFailed { block_idx, error } => write!(
+                f,
+                "block {block_idx}: Gauss-Newton polish after simulated annealing failed ({error}); kept the annealed solution"
+            ),
+            SolveWarning::ConstraintsViolated {
+                max_violation,
+                tol,
+                outer_iterations,
+            } => write!(
+                f,
+                "constraints still violated by {max_violation:.6e} (tol {tol:.6e}) after {outer_iterations} augmented-Lagrangian iterations"
+            ),
+        }
+    }
+}
+
+/// Collects the warnings of one `solve_system` call, shared between its sub-problems and (with the
+/// `parallel` feature) threads.
+#[derive(Clone, Default)]
+pub(crate) struct Warnings(Arc<Mutex<Vec<SolveWarning>>>);
+
+thread_local! {
+    /// Where `warn_from_residual` calls on this thread go.
+    static CURRENT: RefCell<Option<Warnings>> = const { RefCell::new(None) };
+}
+
+impl Warnings {
+    /// Records `warning`, unless the same one was already recorded. A `Residual` warning whose
+    /// message was seen before adds to that one's occurrences.
+    pub(crate) fn push(&self, warning: SolveWarning) {
+        let mut warnings = self.0.lock().unwrap();
+        if let SolveWarning::Residual {
+            message,
+            occurrences,
+        } = &warning
+        {
+            let seen = warnings.iter_mut().find_map(|w| match w {
+                SolveWarning::Residual {
+                    message: seen_message,
+                    occurrences,
+                } if seen_message == message => Some(occurrences),
+                _ => None,
+            });
+            if let Some(seen) = seen {
+                *seen += occurrences;
+                return;
+            }
+        }
+        if !warnings.contains(&warning) {
+            warnings.push(warning);
+        }
+    }
+
+    /// The warnings collected so far, in the order they were first raised.
+    pub(crate) fn warnings(&self) -> Vec<SolveWarning> {
+        self.0.lock().unwrap().clone()
+    }
+
+    /// Routes `warn_from_residual` calls on this thread here until the returned guard drops.
+    pub(crate) fn collect_on_this_thread(&self) -> CollectingWarnings {
+        let previous = CURRENT.with_borrow_mut(|current| current.replace(self.clone()));
+        CollectingWarnings { previous }
+    }
+}
+
+/// Guard of `Warnings::collect_on_this_thread`; restores whatever collected before.
+pub(crate) struct CollectingWarnings {
+    previous: Option<Warnings>,
+}
+
+impl Drop for CollectingWarnings {
+    fn drop(&mut self) {
+        CURRENT.with_borrow_mut(|current| *current = self.previous.take());
+    }
+}
+
+/// Reports a non-fatal issue from inside a residual function, such as an input it had to clamp, as
+/// a `SolveWarning::Residual` of the solve that is evaluating it. Does nothing outside a solve.
+///
+/// Residual functions are evaluated many times per solve (and once per AD type), so pass a fixed
+/// message rather than one with the offending value: repeats are counted, not listed.
+pub fn warn_from_residual(message: impl Into<String>) {
+    CURRENT.with_borrow(|current| {
+        if let Some(warnings) = current {
+            warnings.push(SolveWarning::Residual {
+                message: message.into(),
+                occurrences: 1,
+            });
+        }
+    });
+}
@@ -1,15 +1,275 @@
 use thiserror::Error;
 
+use crate::equation_system::sub_problem::solve_subproblem::block_optimizer::BlockTermination;
+
 #[derive(Error, Debug)]
 pub enum EqSysError {
     #[error("Number of equations!=unknowns; {n_eqs} equations, {n_unks} unknowns")]
     NumEquationsNumUnknownsMismatch { n_eqs: usize, n_unks: usize },
 
+    /// An error argmin raised itself; `EqSysError`s raised inside the argmin problem impls come
+    /// back as themselves instead (see the `From` impl).
+    #[cfg(feature = "argmin")]
     #[error("Argmin error: {0}")]
-    ArgminError(#[from] argmin::core::Error),
+    ArgminError(#[source] argmin::core::Error),
+
+    #[cfg(feature = "plots")]
+    #[error("Plotting failed: {0}")]
+    PlotFailed(String),
 
     #[error("No best individual found in optimization result")]
     NoBestPsoIndividual,
+
+    #[error("{solver} finished without reporting any best params")]
+    NoBestParams { solver: String },
+
+    #[error(
+        "Parameter vector length ({n_params}) did not match number of sub-problem unknowns ({n_unknowns})"
+    )]
+    SubProblemParamLenMismatch { n_params: usize, n_unknowns: usize },
+
+    #[error(
+        "Expected the block objective to aggregate to a scalar for its gradient, but it has {n_outputs} outputs"
+    )]
+    NonScalarGradientOutput { n_outputs: usize },
+
+    #[error("Simulated annealing needs a `SimulatedAnnealingConfig` on the sub-problem")]
+    AnnealingConfigMissing,
+
+    #[error("Invalid simulated annealing config: `{field}` = {value} must be {requirement}")]
+    InvalidAnnealingConfig {
+        field: &'static str,
+        value: f64,
+        requirement: &'static str,
+    },
+
+    #[error("Expected {expected} unknowns, got {got}")]
+    UnknownsLenMismatch { expected: usize, got: usize },
+
+    #[error("Expected one field name per unknown; {n_unknowns} unknowns, {n_names} names")]
+    UnknownFieldNamesLenMismatch { n_unknowns: usize, n_names: usize },
+
+    #[error("Pseudo-inverse of block Jacobian failed: {0}")]
+    PseudoInverseFailed(&'static str),
+
+    #[error(
+        "Expected one wide-tangent residual function per equation; {n_eqs} equations, {n_fns} functions"
+    )]
+    WideTangentFnsLenMismatch { n_eqs: usize, n_fns: usize },
+
+    #[error("Expected one incidence row per equation; {n_eqs} equations, {n_rows} rows")]
+    IncidenceRowsMismatch { n_eqs: usize, n_rows: usize },
+
+    #[error("Expected one residual weight per equation; {n_eqs} equations, {n_weights} weights")]
+    ResidualWeightsLenMismatch { n_eqs: usize, n_weights: usize },
+
+    #[error("No given or unknown field named `{field}`")]
+    UnknownParamField { field: String },
+
+    #[error("No value for param field `{field}`")]
+    MissingParamField { field: &'static str },
+
+    #[error("Param field `{field}` is listed more than once")]
+    DuplicateParamField { field: String },
+
+    #[error("No residual function named `{fn_name}`")]
+    UnknownResidualName { fn_name: String },
+
+    #[error("Residual function `{fn_name}` is already registered")]
+    DuplicateResidualName { fn_name: &'static str },
+
+    #[error(
+        "Residual `{fn_name}` is not finite, or has non-finite derivatives with respect to {unknowns:?}; move the point into the function's domain"
+    )]
+    NonFiniteResidual {
+        fn_name: &'static str,
+        unknowns: Vec<&'static str>,
+    },
+
+    #[error(
+        "Invalid initial guess: {}",
+        .violations.iter().map(|v| v.to_string()).collect::<Vec<_>>().join("; ")
+    )]
+    InvalidInitialGuess {
+        violations: Vec<crate::equation_system::validation::InitialGuessViolation>,
+    },
+
+    #[error(
+        "Residual `{fn_name}` is differentiated by complex step in the unknowns only, so its derivatives with respect to the givens are unavailable"
+    )]
+    GivensDerivativesUnavailable { fn_name: &'static str },
+
+    #[error("Residual weight for `{fn_name}` must be finite and positive, got {weight}")]
+    InvalidResidualWeight { fn_name: &'static str, weight: f64 },
+
+    #[error("Residual magnitude for `{fn_name}` must be finite and positive, got {magnitude}")]
+    InvalidResidualMagnitude {
+        fn_name: &'static str,
+        magnitude: f64,
+    },
+
+    #[error(
+        "Invalid box constraint for `{field}`: lower bound {lower} must not exceed upper bound {upper}"
+    )]
+    InvalidBoxConstraint {
+        field: &'static str,
+        lower: f64,
+        upper: f64,
+    },
+
+    #[error(
+        "Invalid bounds for `{field}`: expected lb < prior < ub with a finite prior, got lb={lb}, prior={prior}, ub={ub}"
+    )]
+    InvalidParamBounds {
+        field: &'static str,
+        lb: f64,
+        prior: f64,
+        ub: f64,
+    },
+
+    #[error("Invalid scaling for `{field}`: {spec:?}")]
+    InvalidScalingSpec {
+        field: &'static str,
+        spec: crate::equation_system::param_scaling::ScalingSpec,
+    },
+
+    #[error(
+        "Invalid scaling for unknown {unknown_idx}{}: value {value} and prior {prior} must lie in its link function's domain ({lb}, {ub})",
+        .field.map(|f| format!(" `{f}`")).unwrap_or_default()
+    )]
+    InvalidScaling {
+        unknown_idx: usize,
+        field: Option<&'static str>,
+        value: f64,
+        prior: f64,
+        lb: f64,
+        ub: f64,
+    },
+
+    #[error(
+        "Degenerate scaling for `{field_name}`: prior {prior} must lie strictly inside its link function's domain ({lb}, {ub}), i.e. be finite and on the side of zero the link expects"
+    )]
+    InvalidParamScaling {
+        field_name: &'static str,
+        prior: f64,
+        lb: f64,
+        ub: f64,
+    },
+
+    #[error("Tikhonov regularization weight must be finite and non-negative, got {lambda}")]
+    InvalidTikhonovLambda { lambda: f64 },
+
+    #[error("Scale for zero-prior unknowns must be finite and positive, got {scale}")]
+    InvalidZeroPriorScale { scale: f64 },
+
+    #[error("Grid search half-width must be finite and non-negative, got {half_width}")]
+    InvalidGridSearchHalfWidth { half_width: f64 },
+
+    #[error("Restart perturbation scale must be finite and non-negative, got {scale}")]
+    InvalidPerturbationScale { scale: f64 },
+
+    #[error(
+        "Analytic Newton solver only handles square 1x1 and 2x2 blocks; got {n_eqs} equations, {n_unks} unknowns"
+    )]
+    SmallBlockUnsupported { n_eqs: usize, n_unks: usize },
+
+    #[error("No block {block_idx} in the solution plan; it has {n_blocks} blocks")]
+    BlockIdxOutOfRange { block_idx: usize, n_blocks: usize },
+
+    #[error("Invalid solution plan override: {reason}")]
+    InvalidPlanOverride { reason: String },
+
+    #[error("{solver:?} block solver is not available in this build or with these options")]
+    BlockSolverUnavailable {
+        solver: crate::equation_system::solve_report::BlockSolverKind,
+    },
+
+    #[error("All {attempts} restarts failed on block {block_idx}")]
+    RestartsFailed { block_idx: usize, attempts: usize },
+
+    #[error("Gauss-Newton polish after simulated annealing failed on block {block_idx}: {source}")]
+    AnnealingPolishFailed {
+        block_idx: usize,
+        #[source]
+        source: Box<EqSysError>,
+    },
+
+    #[error(
+        "Block {} failed after {} successful block solves: {}",
+        .0.failed_block,
+        .0.solved_blocks.len(),
+        .0.error
+    )]
+    PartialSolution(Box<crate::equation_system::solve_report::PartialSolution>),
+
+    #[error("Jacobian of block {block_idx} is singular")]
+    SingularBlockJacobian { block_idx: usize },
+
+    #[error(
+        "Jacobian with respect to the unknowns has rank {rank} < {n_unknowns}; the unknowns aren't locally determined by the givens"
+    )]
+    SingularSensitivityJacobian { rank: usize, n_unknowns: usize },
+
+    #[error(
+        "Block {block_idx} did not meet its convergence criteria ({termination:?} after {iterations} iterations); cost {cost:.6e}, gradient norm {grad_norm:.6e}"
+    )]
+    DidNotConverge {
+        block_idx: usize,
+        /// Sum of squared raw residuals of the block at the best params found.
+        cost: f64,
+        /// Opt-space gradient norm of the block objective there.
+        grad_norm: f64,
+        iterations: u64,
+        termination: BlockTermination,
+    },
+
+    #[error(
+        "Analytic Newton solver did not converge on block {block_idx}; residual norm {residual_norm:.6e}"
+    )]
+    SmallNewtonDidNotConverge {
+        block_idx: usize,
+        residual_norm: f64,
+    },
+
+    #[error("Solve ended with residual norm {residual_norm:.6e} and residuals beyond {tol:.1e}")]
+    ResidualsBeyondTol { residual_norm: f64, tol: f64 },
+}
+
+impl EqSysError {
+    /// Fills in the unknown's field name of errors that only know its index, e.g. `InvalidScaling`
+    /// from `SubProblem::new`.
+    pub fn with_unknown_field_names(self, names: &[&'static str]) -> Self {
+        match self {
+            EqSysError::InvalidScaling {
+                unknown_idx,
+                field: None,
+                value,
+                prior,
+                lb,
+                ub,
+            } => EqSysError::InvalidScaling {
+                unknown_idx,
+                field: names.get(unknown_idx).copied(),
+                value,
+                prior,
+                lb,
+                ub,
+            },
+            other => other,
+        }
+    }
+}
+
+#[cfg(feature = "argmin")]
+impl From<argmin::core::Error> for EqSysError {
+    /// Unwraps the `EqSysError`s that the argmin problem impls of `SubProblem` raise, so that
+    /// callers can match on them.
+    fn from(e: argmin::core::Error) -> Self {
+        match e.downcast::<EqSysError>() {
+            Ok(e) => e,
+            Err(e) => EqSysError::ArgminError(e),
+        }
+    }
 }
 
 #[derive(Error, Debug)]
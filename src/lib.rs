@@ -1,29 +1,57 @@
 // mod system_decomposition;
+// lets the param-struct derives' `::system_solver` paths resolve in the crate's own tests
+#[cfg(test)]
+extern crate self as system_solver;
+
 pub mod equation_system;
 pub mod error;
 
 pub mod prelude {
     pub use crate::{
+        complex_step_residual_fns,
         equation_system::{
-            EqSysSolutionPlan, EqSysStateInit, EquationSystemBuilder,
+            EqSysSolutionPlan, EqSysStateInit, EquationSystemBuilder, EquationSystemFor,
+            box_constraints::*,
+            coloring::*,
+            conflicts::*,
+            constraints::*,
+            derivative_check::*,
+            dulmage_mendelsohn::*,
+            dyn_system::*,
+            inequality::*,
+            irls::*,
+            monte_carlo::*,
             objective::*,
-            opt_tools::{self, *},
+            optimization_trace::*,
             param_scaling::*,
             param_traits::*,
             residuals::*,
             residuals::{aggregation_hof::*, transformation_hof::*},
+            role_swap::*,
+            sensitivity::*,
+            shared::{MaybeSendSync, Shared},
             solution_plan::*,
+            solve_options::*,
+            solve_report::*,
+            solved::*,
             sub_problem::*,
+            sweep::*,
+            uncertainty::*,
+            validation::*,
+            verification::*,
+            warnings::*,
         },
         error::*,
-        residual_fns, residual_fns_for_generic_params,
+        residual_fns, residual_fns_for_generic_params, wide_residual_fns,
     };
 
     pub use ad_trait;
     pub use field_names_and_counts;
     pub use nalgebra;
     pub use struct_to_array;
+    pub use system_solver_derive::{AdConvert, SolverParams, solver_params};
 }
 
 pub use field_names_and_counts::FieldNames;
 pub use struct_to_array::StructToArray;
+pub use system_solver_derive::{AdConvert, SolverParams, solver_params};
@@ -0,0 +1,218 @@
+//! Derive macros for `system_solver` param structs.
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{
+    Data, DeriveInput, Fields, GenericArgument, Ident, PathArguments, Type, parse_macro_input,
+};
+
+/// Generates `to_ad::<T>()` (on the `f64` version, also exposed through the `ToAdParams` and
+/// `ParamFamily` traits) and `to_f64()` (on any `T: AD` version, also exposed through
+/// `ToF64Params`) for a struct with named fields that is generic over a single scalar type
+/// parameter.
+///
+/// Fields of the parameter type are converted directly; fields whose type has the parameter as a
+/// generic argument (e.g. nested param structs) are converted with their own `to_ad`/`to_f64`; all
+/// other fields are cloned.
+#[proc_macro_derive(AdConvert)]
+pub fn derive_ad_convert(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match expand_ad_convert(&input) {
+        Ok(tokens) => tokens.into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+/// Everything a param struct needs besides `StructToArray` and `FieldNames`: the `AdConvert`
+/// conversions, and the `GivenParams` and `UnknownParams` marker impls (for every scalar type the
+/// struct is `Clone + Copy + Debug + MaybeSendSync` over), so that the same struct can serve as
+/// givens or unknowns.
+///
+/// `StructToArray` and `FieldNames` come from their own crates' derives; `#[solver_params]` adds
+/// those and this one in a single attribute.
+#[proc_macro_derive(SolverParams)]
+pub fn derive_solver_params(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match expand_solver_params(&input) {
+        Ok(tokens) => tokens.into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+/// Derives `StructToArray`, `FieldNames` and `SolverParams` on a param struct, so that defining one
+/// takes a single line on top of the usual `#[derive(Clone, Copy, Debug)]`:
+///
+/// ```ignore
+/// #[solver_params]
+/// #[derive(Clone, Copy, Debug)]
+/// struct MyUnknowns<T> {
+///     drag_coeff: T,
+///     thrust_max: T,
+/// }
+/// ```
+#[proc_macro_attribute]
+pub fn solver_params(attr: TokenStream, item: TokenStream) -> TokenStream {
+    if !attr.is_empty() {
+        return syn::Error::new(
+            proc_macro2::Span::call_site(),
+            "#[solver_params] takes no arguments",
+        )
+        .to_compile_error()
+        .into();
+    }
+    let item = TokenStream2::from(item);
+    quote! {
+        #[derive(
+            ::system_solver::StructToArray,
+            ::system_solver::FieldNames,
+            ::system_solver::SolverParams
+        )]
+        #item
+    }
+    .into()
+}
+
+fn expand_solver_params(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let name = &input.ident;
+    let param = single_type_param(input, "SolverParams")?;
+    let ad_convert = expand_ad_convert_as(input, "SolverParams")?;
+    let param_traits = quote!(::system_solver::equation_system::param_traits);
+    let bounds = quote!(
+        ::core::clone::Clone
+            + ::core::marker::Copy
+            + ::core::fmt::Debug
+            + ::system_solver::equation_system::shared::MaybeSendSync
+    );
+
+    Ok(quote! {
+        #ad_convert
+
+        impl<#param> #param_traits::GivenParams for #name<#param> where #name<#param>: #bounds {}
+
+        impl<#param> #param_traits::UnknownParams for #name<#param> where #name<#param>: #bounds {}
+    })
+}
+
+fn expand_ad_convert(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    expand_ad_convert_as(input, "AdConvert")
+}
+
+/// The `AdConvert` expansion, with errors naming `derive` (the derive the user wrote).
+fn expand_ad_convert_as(input: &DeriveInput, derive: &str) -> syn::Result<TokenStream2> {
+    let name = &input.ident;
+    let param = single_type_param(input, derive)?;
+
+    let Data::Struct(data) = &input.data else {
+        return Err(syn::Error::new_spanned(
+            name,
+            format!("{derive} can only be derived for structs"),
+        ));
+    };
+    let Fields::Named(fields) = &data.fields else {
+        return Err(syn::Error::new_spanned(
+            name,
+            format!("{derive} requires a struct with named fields"),
+        ));
+    };
+
+    let ad = quote!(::system_solver::prelude::ad_trait::AD);
+    let target = Ident::new("__AdConvertT", proc_macro2::Span::call_site());
+
+    let mut to_ad_fields = vec![];
+    let mut to_f64_fields = vec![];
+    for field in &fields.named {
+        let ident = field.ident.as_ref().expect("named field");
+        let (to_ad, to_f64) = match field_kind(&field.ty, param) {
+            FieldKind::Scalar => (
+                quote!(<#target as #ad>::constant(self.#ident)),
+                quote!(::core::convert::Into::<f64>::into(self.#ident)),
+            ),
+            FieldKind::Nested => (
+                quote!(self.#ident.to_ad::<#target>()),
+                quote!(self.#ident.to_f64()),
+            ),
+            FieldKind::Other => (
+                quote!(::core::clone::Clone::clone(&self.#ident)),
+                quote!(::core::clone::Clone::clone(&self.#ident)),
+            ),
+        };
+        to_ad_fields.push(quote!(#ident: #to_ad));
+        to_f64_fields.push(quote!(#ident: #to_f64));
+    }
+
+    Ok(quote! {
+        impl #name<f64> {
+            /// Converts this f64 param struct to any AD type.
+            pub fn to_ad<#target: #ad>(&self) -> #name<#target> {
+                #name { #(#to_ad_fields),* }
+            }
+        }
+
+        impl ::system_solver::equation_system::param_traits::ParamFamily for #name<f64> {
+            type For<#target: #ad> = #name<#target>;
+        }
+
+        impl ::system_solver::equation_system::param_traits::ToAdParams for #name<f64> {
+            fn to_ad_params<#target: #ad>(&self) -> #name<#target> {
+                self.to_ad::<#target>()
+            }
+        }
+
+        impl<#param: #ad> #name<#param> {
+            /// Converts this param struct back to f64 (dropping any derivative information).
+            pub fn to_f64(&self) -> #name<f64> {
+                #name { #(#to_f64_fields),* }
+            }
+        }
+
+        impl<#param: #ad> ::system_solver::equation_system::param_traits::ToF64Params for #name<#param> {
+            type F64 = #name<f64>;
+            fn to_f64_params(&self) -> #name<f64> {
+                self.to_f64()
+            }
+        }
+    })
+}
+
+fn single_type_param<'a>(input: &'a DeriveInput, derive: &str) -> syn::Result<&'a Ident> {
+    let mut params = input.generics.type_params();
+    match (params.next(), params.next()) {
+        (Some(param), None) => Ok(&param.ident),
+        _ => Err(syn::Error::new_spanned(
+            &input.generics,
+            format!("{derive} requires exactly one type parameter (the scalar type)"),
+        )),
+    }
+}
+
+enum FieldKind {
+    /// The field's type is the scalar type parameter itself.
+    Scalar,
+    /// The field's type takes the scalar type parameter as a generic argument.
+    Nested,
+    Other,
+}
+
+fn field_kind(ty: &Type, param: &Ident) -> FieldKind {
+    let Type::Path(type_path) = ty else {
+        return FieldKind::Other;
+    };
+    if type_path.qself.is_none() && type_path.path.is_ident(param) {
+        return FieldKind::Scalar;
+    }
+    let Some(last) = type_path.path.segments.last() else {
+        return FieldKind::Other;
+    };
+    let PathArguments::AngleBracketed(args) = &last.arguments else {
+        return FieldKind::Other;
+    };
+    let takes_param = args.args.iter().any(|arg| {
+        matches!(arg, GenericArgument::Type(Type::Path(p)) if p.qself.is_none() && p.path.is_ident(param))
+    });
+    if takes_param {
+        FieldKind::Nested
+    } else {
+        FieldKind::Other
+    }
+}